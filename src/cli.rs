@@ -0,0 +1,2520 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use itertools::Itertools;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use crate::config::{Config, ConfigSource};
+use crate::db::{read_csv_epics, JiraDatabase, SPLIT_INDEX_FILE};
+use crate::io_utils::get_editor;
+use crate::models::{DBState, Epic, IdMatch, ItemRef, MergeMode, Story, UNASSIGNED};
+use crate::query::parse_story_query;
+use crate::ui::{
+    format_duration, get_completion_percent, get_epic_completion, write_epic_detail,
+    write_story_detail, DisplayConfig, SortMode, TableWidth,
+};
+use crate::watch::{run_export_on_change, ExportFormat};
+
+// Output may be piped, so detail tables use a fixed width instead of the terminal's
+const OPEN_WIDTH: usize = 100;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum OutputFormat {
+    Table,
+    Json,
+}
+
+// How chatty the commands are. Quiet keeps only the essential result, such as a new id.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Default)]
+pub enum Verbosity {
+    Quiet,
+    #[default]
+    Normal,
+    Verbose,
+}
+
+// Where an export goes: stdout by default, or a file given with --output
+#[derive(Debug, PartialEq, Eq, Default)]
+pub struct ExportOutput {
+    pub path: Option<PathBuf>,
+    pub force: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    Burndown {
+        epic_id: String,
+        format: OutputFormat,
+    },
+    CreateEpic {
+        name: String,
+        description: String,
+    },
+    Gc {
+        dry_run: bool,
+    },
+    // Reports integrity problems without changing anything. --strict fails when
+    // there are any, for CI.
+    Check {
+        strict: bool,
+        format: OutputFormat,
+    },
+    Archive {
+        path: PathBuf,
+    },
+    Due {
+        start: NaiveDate,
+        end: NaiveDate,
+        format: OutputFormat,
+    },
+    ExportIcs {
+        output: ExportOutput,
+    },
+    ExportNdjson {
+        output: ExportOutput,
+    },
+    // Epics and stories in one CSV, which import-csv reads back
+    ExportCsv {
+        output: ExportOutput,
+    },
+    ImportCsv {
+        path: PathBuf,
+        mode: MergeMode,
+    },
+    // One file per epic plus an index, into a directory that only gets replaced
+    // with --force
+    ExportSplit {
+        dir: PathBuf,
+        force: bool,
+    },
+    // --all (or --wide) prints every field whole instead of fitting OPEN_WIDTH
+    Open {
+        id: String,
+        all: bool,
+    },
+    // Stories matching a query such as "status=open assignee=ana"
+    Query {
+        query: String,
+        format: OutputFormat,
+    },
+    Report {
+        format: OutputFormat,
+    },
+    Stats {
+        format: OutputFormat,
+    },
+    Metrics {
+        format: OutputFormat,
+    },
+    // Runs until interrupted
+    ExportOnChange {
+        format: ExportFormat,
+        output: PathBuf,
+    },
+    // Answered by main with write_info, without opening the database
+    Info,
+    // The story commands take their epic as --epic <id>, where a unique prefix of
+    // the id is enough
+    StoryCreate {
+        epic: String,
+        name: String,
+        description: String,
+    },
+    // Puts back the newest snapshot_history entry at or before the time
+    Restore {
+        at: DateTime<Utc>,
+    },
+    // Stories flagged for refinement, grouped by epic
+    Refinement {
+        format: OutputFormat,
+    },
+    // Epics sorted by id, only those owned by --owner when given
+    EpicList {
+        owner: Option<String>,
+        format: OutputFormat,
+    },
+    // Sets the epic's status from its stories, for epics last changed before that was
+    // automatic
+    EpicRecompute {
+        epic: String,
+    },
+    // Places the epic in the manual order shown by the [s] manual sort, counting from 1
+    EpicReorder {
+        epic: String,
+        position: usize,
+    },
+    // Every epic's stories without --epic
+    StoryList {
+        epic: Option<String>,
+        format: OutputFormat,
+    },
+    StoryMove {
+        story_id: String,
+        epic: String,
+    },
+    // Like EpicReorder, among the stories of the story's epic
+    StoryReorder {
+        story_id: String,
+        position: usize,
+    },
+    // Story points for the burndown, None clears them
+    StoryEstimate {
+        story_id: String,
+        estimate: Option<u32>,
+    },
+}
+
+// Flags given before the subcommand, which also apply to the TUI
+#[derive(Debug, PartialEq, Eq, Default)]
+pub struct GlobalArgs {
+    pub verbosity: Verbosity,
+    // `-c key=value` config overrides, in the order given
+    pub config: Vec<(String, String)>,
+    // Lines replayed into the TUI before reading from the keyboard
+    pub script: Option<PathBuf>,
+    // Skip the bell and banner for overdue stories
+    pub no_notify: bool,
+    // Print pages one after another instead of clearing the screen
+    pub no_clear: bool,
+    // Run the shell commands of watch rules that fire, not just show them
+    pub run_rule_actions: bool,
+}
+
+// Strips the global flags in front of the subcommand: --quiet/-q, --verbose/-v,
+// -c/--config key=value, --user <name>, --db-path <file>, --script <file>, --no-notify,
+// --no-clear and --run-rule-actions
+pub fn parse_global_args(args: &[String]) -> Result<(GlobalArgs, Vec<String>)> {
+    let mut quiet = false;
+    let mut verbose = false;
+    let mut config = Vec::new();
+    let mut script = None;
+    let mut no_notify = false;
+    let mut no_clear = false;
+    let mut run_rule_actions = false;
+    let mut rest = args.iter().peekable();
+    while let Some(&arg) = rest.peek() {
+        match arg.as_str() {
+            "--quiet" | "-q" => quiet = true,
+            "--verbose" | "-v" => verbose = true,
+            "--no-notify" => no_notify = true,
+            "--no-clear" => no_clear = true,
+            "--run-rule-actions" => run_rule_actions = true,
+            "--config" | "-c" => {
+                rest.next();
+                let pair = rest
+                    .peek()
+                    .ok_or_else(|| anyhow!("{} needs a key=value pair.", arg))?;
+                let (key, value) = pair
+                    .split_once('=')
+                    .ok_or_else(|| anyhow!("Expected key=value after {}, got '{}'.", arg, pair))?;
+                config.push((key.trim().to_owned(), value.to_owned()));
+            }
+            // Shorthand for -c user=<name>
+            "--user" => {
+                rest.next();
+                let user = rest.peek().ok_or_else(|| anyhow!("--user needs a name."))?;
+                config.push(("user".to_owned(), user.to_string()));
+            }
+            // Shorthand for -c db_path=<file>
+            "--db-path" => {
+                rest.next();
+                let path = rest
+                    .peek()
+                    .ok_or_else(|| anyhow!("--db-path needs a file path."))?;
+                config.push(("db_path".to_owned(), path.to_string()));
+            }
+            "--script" => {
+                rest.next();
+                let path = rest
+                    .peek()
+                    .ok_or_else(|| anyhow!("--script needs a file path."))?;
+                script = Some(PathBuf::from(path));
+            }
+            _ => break,
+        }
+        rest.next();
+    }
+
+    let verbosity = match (quiet, verbose) {
+        (true, true) => return Err(anyhow!("--quiet and --verbose can't be used together.")),
+        (true, false) => Verbosity::Quiet,
+        (false, true) => Verbosity::Verbose,
+        (false, false) => Verbosity::Normal,
+    };
+    Ok((
+        GlobalArgs {
+            verbosity,
+            config,
+            script,
+            no_notify,
+            no_clear,
+            run_rule_actions,
+        },
+        rest.cloned().collect(),
+    ))
+}
+
+// Returns None when no subcommand was given so the caller can start the TUI
+pub fn parse_args(args: &[String]) -> Result<Option<Command>> {
+    let mut args = args.iter();
+
+    let command = match args.next() {
+        Some(command) => command,
+        None => return Ok(None),
+    };
+
+    match command.as_str() {
+        "burndown" => {
+            let epic_id = args
+                .next()
+                .ok_or_else(|| anyhow!("burndown needs an epic id."))?;
+            let mut format = OutputFormat::Table;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--format" => format = parse_format(args.next())?,
+                    other => return Err(anyhow!("Unknown option '{}' for burndown.", other)),
+                }
+            }
+            Ok(Some(Command::Burndown {
+                epic_id: epic_id.clone(),
+                format,
+            }))
+        }
+        "create-epic" => {
+            let name = args
+                .next()
+                .ok_or_else(|| anyhow!("create-epic needs a name."))?;
+            let description = args.next().cloned().unwrap_or_default();
+            if let Some(arg) = args.next() {
+                return Err(anyhow!("Unknown option '{}' for create-epic.", arg));
+            }
+            Ok(Some(Command::CreateEpic {
+                name: name.clone(),
+                description,
+            }))
+        }
+        "gc" => {
+            let mut dry_run = false;
+            for arg in args {
+                match arg.as_str() {
+                    "--dry-run" => dry_run = true,
+                    other => return Err(anyhow!("Unknown option '{}' for gc.", other)),
+                }
+            }
+            Ok(Some(Command::Gc { dry_run }))
+        }
+        "check" => {
+            let mut strict = false;
+            let mut format = OutputFormat::Table;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--strict" => strict = true,
+                    "--format" => format = parse_format(args.next())?,
+                    other => return Err(anyhow!("Unknown option '{}' for check.", other)),
+                }
+            }
+            Ok(Some(Command::Check { strict, format }))
+        }
+        "archive" => {
+            let path = args
+                .next()
+                .ok_or_else(|| anyhow!("archive needs a file path."))?;
+            if let Some(arg) = args.next() {
+                return Err(anyhow!("Unknown option '{}' for archive.", arg));
+            }
+            Ok(Some(Command::Archive {
+                path: PathBuf::from(path),
+            }))
+        }
+        "restore" => {
+            let value = args
+                .next()
+                .ok_or_else(|| anyhow!("restore needs a time."))?;
+            if let Some(arg) = args.next() {
+                return Err(anyhow!("Unknown option '{}' for restore.", arg));
+            }
+            let at = DateTime::parse_from_rfc3339(value).map_err(|_| {
+                anyhow!(
+                    "Invalid time '{}'. Expected RFC 3339, e.g. 2024-05-01T09:00:00Z.",
+                    value
+                )
+            })?;
+            Ok(Some(Command::Restore {
+                at: at.with_timezone(&Utc),
+            }))
+        }
+        "refinement" => {
+            let mut format = OutputFormat::Table;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--format" => format = parse_format(args.next())?,
+                    other => return Err(anyhow!("Unknown option '{}' for refinement.", other)),
+                }
+            }
+            Ok(Some(Command::Refinement { format }))
+        }
+        "export-split" => {
+            let dir = args
+                .next()
+                .ok_or_else(|| anyhow!("export-split needs a directory."))?;
+            let mut force = false;
+            for arg in args {
+                match arg.as_str() {
+                    "--force" => force = true,
+                    other => return Err(anyhow!("Unknown option '{}' for export-split.", other)),
+                }
+            }
+            Ok(Some(Command::ExportSplit {
+                dir: PathBuf::from(dir),
+                force,
+            }))
+        }
+        "due" => {
+            let start = parse_date(args.next(), "due needs a start and an end date.")?;
+            let end = parse_date(args.next(), "due needs an end date.")?;
+            let mut format = OutputFormat::Table;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--format" => format = parse_format(args.next())?,
+                    other => return Err(anyhow!("Unknown option '{}' for due.", other)),
+                }
+            }
+            Ok(Some(Command::Due { start, end, format }))
+        }
+        "export-ics" => Ok(Some(Command::ExportIcs {
+            output: parse_export_output(command, args)?,
+        })),
+        "export-ndjson" => Ok(Some(Command::ExportNdjson {
+            output: parse_export_output(command, args)?,
+        })),
+        "export-csv" => Ok(Some(Command::ExportCsv {
+            output: parse_export_output(command, args)?,
+        })),
+        "import-csv" => {
+            let path = args
+                .next()
+                .ok_or_else(|| anyhow!("import-csv needs a file path."))?;
+            // Importing the same file twice shouldn't duplicate anything
+            let mut mode = MergeMode::SkipExisting;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--mode" => {
+                        mode = args
+                            .next()
+                            .ok_or_else(|| anyhow!("--mode needs a merge mode."))?
+                            .parse()?
+                    }
+                    other => return Err(anyhow!("Unknown option '{}' for import-csv.", other)),
+                }
+            }
+            Ok(Some(Command::ImportCsv {
+                path: PathBuf::from(path),
+                mode,
+            }))
+        }
+        "--export-on-change" => {
+            let format = args
+                .next()
+                .ok_or_else(|| anyhow!("--export-on-change needs a format and a file path."))?
+                .parse()?;
+            let output = args
+                .next()
+                .ok_or_else(|| anyhow!("--export-on-change needs a file path."))?;
+            if let Some(arg) = args.next() {
+                return Err(anyhow!("Unknown option '{}' for --export-on-change.", arg));
+            }
+            Ok(Some(Command::ExportOnChange {
+                format,
+                output: PathBuf::from(output),
+            }))
+        }
+        "open" => {
+            let id = args
+                .next()
+                .ok_or_else(|| anyhow!("open needs an epic or story id."))?;
+            let mut all = false;
+            for arg in args {
+                match arg.as_str() {
+                    "--all" | "--wide" => all = true,
+                    other => return Err(anyhow!("Unknown option '{}' for open.", other)),
+                }
+            }
+            Ok(Some(Command::Open {
+                id: id.clone(),
+                all,
+            }))
+        }
+        "query" => {
+            let mut terms = Vec::new();
+            let mut format = OutputFormat::Table;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--format" => format = parse_format(args.next())?,
+                    term => terms.push(term),
+                }
+            }
+            let query = terms.join(" ");
+            // Fail on a bad query before the database is read
+            let _ = parse_story_query(&query)?;
+            Ok(Some(Command::Query { query, format }))
+        }
+        "report" => {
+            let mut format = OutputFormat::Table;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--format" => format = parse_format(args.next())?,
+                    other => return Err(anyhow!("Unknown option '{}' for report.", other)),
+                }
+            }
+            Ok(Some(Command::Report { format }))
+        }
+        "stats" => {
+            let mut format = OutputFormat::Table;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--format" => format = parse_format(args.next())?,
+                    other => return Err(anyhow!("Unknown option '{}' for stats.", other)),
+                }
+            }
+            Ok(Some(Command::Stats { format }))
+        }
+        "metrics" => {
+            let mut format = OutputFormat::Table;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--format" => format = parse_format(args.next())?,
+                    other => return Err(anyhow!("Unknown option '{}' for metrics.", other)),
+                }
+            }
+            Ok(Some(Command::Metrics { format }))
+        }
+        "info" => {
+            if let Some(arg) = args.next() {
+                return Err(anyhow!("Unknown option '{}' for info.", arg));
+            }
+            Ok(Some(Command::Info))
+        }
+        "epic" => {
+            match args.next().map(String::as_str) {
+                Some("list") => {}
+                Some("recompute") => {
+                    let epic = args
+                        .next()
+                        .ok_or_else(|| anyhow!("epic recompute needs an epic id."))?;
+                    if let Some(arg) = args.next() {
+                        return Err(anyhow!("Unknown option '{}' for epic recompute.", arg));
+                    }
+                    return Ok(Some(Command::EpicRecompute { epic: epic.clone() }));
+                }
+                Some("reorder") => {
+                    let epic = args
+                        .next()
+                        .ok_or_else(|| anyhow!("epic reorder needs an epic id."))?;
+                    let position = parse_position(args.next(), "epic reorder")?;
+                    if let Some(arg) = args.next() {
+                        return Err(anyhow!("Unknown option '{}' for epic reorder.", arg));
+                    }
+                    return Ok(Some(Command::EpicReorder {
+                        epic: epic.clone(),
+                        position,
+                    }));
+                }
+                Some(other) => {
+                    return Err(anyhow!(
+                        "Unknown epic command '{}'. Expected list, recompute or reorder.",
+                        other
+                    ))
+                }
+                None => return Err(anyhow!("epic needs list, recompute or reorder.")),
+            }
+            let mut owner = None;
+            let mut format = OutputFormat::Table;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--owner" => {
+                        let name = args
+                            .next()
+                            .ok_or_else(|| anyhow!("--owner needs a name."))?;
+                        owner = Some(name.clone());
+                    }
+                    "--format" => format = parse_format(args.next())?,
+                    other => return Err(anyhow!("Unknown option '{}' for epic list.", other)),
+                }
+            }
+            Ok(Some(Command::EpicList { owner, format }))
+        }
+        "story" => Ok(Some(parse_story_command(args)?)),
+        other => Err(anyhow!("Unknown command '{}'.", other)),
+    }
+}
+
+// story create <name> [description] --epic <id>, story list [--epic <id>] [--format],
+// story move <story id> --epic <id>, story reorder <story id> <position> and
+// story estimate <story id> <points|none>. Options may come in any order.
+fn parse_story_command<'a>(mut args: impl Iterator<Item = &'a String>) -> Result<Command> {
+    let action = args
+        .next()
+        .ok_or_else(|| anyhow!("story needs create, list, move, reorder or estimate."))?;
+    let mut epic = None;
+    let mut format = OutputFormat::Table;
+    let mut values = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--epic" => {
+                let epic_id = args
+                    .next()
+                    .ok_or_else(|| anyhow!("--epic needs an epic id."))?;
+                epic = Some(epic_id.clone());
+            }
+            "--format" if action == "list" => format = parse_format(args.next())?,
+            other if other.starts_with("--") => {
+                return Err(anyhow!("Unknown option '{}' for story {}.", other, action))
+            }
+            value => values.push(value.to_owned()),
+        }
+    }
+    let require_epic =
+        |epic: Option<String>| epic.ok_or_else(|| anyhow!("story {} needs --epic <id>.", action));
+
+    match (action.as_str(), values.as_slice()) {
+        ("create", [name]) => Ok(Command::StoryCreate {
+            epic: require_epic(epic)?,
+            name: name.clone(),
+            description: "".to_owned(),
+        }),
+        ("create", [name, description]) => Ok(Command::StoryCreate {
+            epic: require_epic(epic)?,
+            name: name.clone(),
+            description: description.clone(),
+        }),
+        ("create", []) => Err(anyhow!("story create needs a name.")),
+        ("list", []) => Ok(Command::StoryList { epic, format }),
+        ("move", [story_id]) => Ok(Command::StoryMove {
+            story_id: story_id.clone(),
+            epic: require_epic(epic)?,
+        }),
+        ("move", []) => Err(anyhow!("story move needs a story id.")),
+        ("reorder", [story_id, position]) => Ok(Command::StoryReorder {
+            story_id: story_id.clone(),
+            position: parse_position(Some(position), "story reorder")?,
+        }),
+        ("reorder", []) => Err(anyhow!("story reorder needs a story id.")),
+        ("reorder", [_]) => Err(anyhow!("story reorder needs a position.")),
+        ("estimate", [story_id, points]) => Ok(Command::StoryEstimate {
+            story_id: story_id.clone(),
+            estimate: parse_estimate(points)?,
+        }),
+        ("estimate", []) => Err(anyhow!("story estimate needs a story id.")),
+        ("estimate", [_]) => Err(anyhow!("story estimate needs points or none.")),
+        ("create" | "list" | "move" | "reorder" | "estimate", [.., extra]) => Err(anyhow!(
+            "Unexpected argument '{}' for story {}.",
+            extra,
+            action
+        )),
+        (other, _) => Err(anyhow!(
+            "Unknown story command '{}'. Expected create, list, move, reorder or estimate.",
+            other
+        )),
+    }
+}
+
+// Every story command resolves its --epic here, so prefixes work the same everywhere
+fn resolve_epic_id(db: &JiraDatabase, prefix: &str) -> Result<String> {
+    let db_state = db.read_db()?;
+    match IdMatch::from_ids(prefix, db_state.epics.keys()) {
+        IdMatch::Unique(epic_id) => Ok(epic_id),
+        IdMatch::Ambiguous(count) => Err(anyhow!(
+            "Epic id '{}' is ambiguous, it matches {} epics.",
+            prefix,
+            count
+        )),
+        IdMatch::None => Err(anyhow!("No epic id starts with '{}'.", prefix)),
+    }
+}
+
+// Options shared by every export subcommand
+fn parse_export_output<'a>(
+    command: &str,
+    mut args: impl Iterator<Item = &'a String>,
+) -> Result<ExportOutput> {
+    let mut output = ExportOutput::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| anyhow!("--output needs a file path."))?;
+                output.path = Some(PathBuf::from(path));
+            }
+            "--force" => output.force = true,
+            other => return Err(anyhow!("Unknown option '{}' for {}.", other, command)),
+        }
+    }
+    Ok(output)
+}
+
+// Runs an exporter against stdout, or against the --output file when one was given.
+// Existing files are only replaced with --force.
+fn write_export(
+    output: &ExportOutput,
+    verbosity: Verbosity,
+    out: &mut dyn Write,
+    export: impl FnOnce(&mut dyn Write) -> Result<()>,
+) -> Result<()> {
+    let Some(path) = &output.path else {
+        return export(out);
+    };
+
+    if path.exists() && !output.force {
+        return Err(anyhow!(
+            "{} already exists. Use --force to overwrite it.",
+            path.display()
+        ));
+    }
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}.", parent.display()))?;
+    }
+
+    let file =
+        File::create(path).with_context(|| format!("Failed to create {}.", path.display()))?;
+    let mut writer = BufWriter::new(file);
+    export(&mut writer)?;
+    writer.flush()?;
+
+    if verbosity >= Verbosity::Normal {
+        writeln!(out, "Wrote {}.", path.display())?;
+    }
+    Ok(())
+}
+
+// One line of the completion report
+struct ReportRow {
+    epic_id: String,
+    name: String,
+    done: usize,
+    total: usize,
+}
+
+// Least complete first, so the epics that need attention lead the report
+fn get_report_rows(db_state: &DBState) -> Vec<ReportRow> {
+    db_state
+        .epics
+        .iter()
+        .map(|(epic_id, epic)| {
+            let (done, total) = get_epic_completion(db_state, epic);
+            ReportRow {
+                epic_id: epic_id.clone(),
+                name: epic.name.clone(),
+                done,
+                total,
+            }
+        })
+        .sorted_by(|a, b| {
+            // Compared as fractions without floats, an empty epic counting as 0 of 1
+            (a.done * b.total.max(1))
+                .cmp(&(b.done * a.total.max(1)))
+                .then_with(|| a.name.cmp(&b.name))
+                .then_with(|| a.epic_id.cmp(&b.epic_id))
+        })
+        .collect()
+}
+
+// Dates are given as YYYY-MM-DD
+fn parse_date(value: Option<&String>, missing: &str) -> Result<NaiveDate> {
+    let value = value.ok_or_else(|| anyhow!("{}", missing))?;
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| anyhow!("Invalid date '{}'. Expected YYYY-MM-DD.", value))
+}
+
+// Positions are given counting from 1, as shown in the listing
+fn parse_position(value: Option<&String>, command: &str) -> Result<usize> {
+    let value = value.ok_or_else(|| anyhow!("{} needs a position.", command))?;
+    match value.parse::<usize>() {
+        Ok(position) if position > 0 => Ok(position),
+        _ => Err(anyhow!(
+            "Invalid position '{}'. Expected a number from 1.",
+            value
+        )),
+    }
+}
+
+// "none" clears the estimate
+fn parse_estimate(value: &str) -> Result<Option<u32>> {
+    match value {
+        "none" => Ok(None),
+        value => value.parse::<u32>().map(Some).map_err(|_| {
+            anyhow!(
+                "Invalid estimate '{}'. Expected a number of points or none.",
+                value
+            )
+        }),
+    }
+}
+
+fn parse_format(value: Option<&String>) -> Result<OutputFormat> {
+    match value.map(|value| value.as_str()) {
+        Some("table") => Ok(OutputFormat::Table),
+        Some("json") => Ok(OutputFormat::Json),
+        Some(other) => Err(anyhow!(
+            "Unknown format '{}'. Expected table or json.",
+            other
+        )),
+        None => Err(anyhow!("--format needs a value.")),
+    }
+}
+
+// Where the database and configuration were resolved to. Only checks whether the
+// database file exists, so it also works before the first write.
+pub fn write_info(
+    out: &mut dyn Write,
+    config: &Config,
+    sources: &[ConfigSource],
+    db: &JiraDatabase,
+) -> Result<()> {
+    writeln!(out, "database")?;
+    match db.database.file_path() {
+        Some(path) if path.exists() => writeln!(out, "  path: {}", path.display())?,
+        Some(path) => writeln!(out, "  path: {} (not created yet)", path.display())?,
+        None => writeln!(out, "  path: not stored in a single file")?,
+    }
+    writeln!(out, "  backend: {}", db.database.backend_name())?;
+    let capabilities = db.database.capabilities();
+    let names = [
+        (capabilities.writable, "writable"),
+        (capabilities.transactional, "transactional"),
+        (capabilities.searchable, "full-text search"),
+        (capabilities.streaming, "streaming"),
+    ]
+    .iter()
+    .filter(|(enabled, _)| *enabled)
+    .map(|(_, name)| *name)
+    .join(", ");
+    writeln!(
+        out,
+        "  capabilities: {}",
+        if names.is_empty() { "none" } else { &names }
+    )?;
+
+    writeln!(out, "environment")?;
+    writeln!(out, "  editor: {}", get_editor())?;
+
+    writeln!(out, "config sources, later ones win")?;
+    for source in sources {
+        let keys = source.layer.keys().join(", ");
+        writeln!(
+            out,
+            "  {}: {}",
+            source.name,
+            if keys.is_empty() {
+                "nothing set"
+            } else {
+                &keys
+            }
+        )?;
+    }
+
+    writeln!(out, "effective config")?;
+    if let serde_json::Value::Object(values) = serde_json::to_value(config)? {
+        for (key, value) in values {
+            match value {
+                serde_json::Value::Null => writeln!(out, "  {} = unset", key)?,
+                value => writeln!(out, "  {} = {}", key, value)?,
+            }
+        }
+    }
+    Ok(())
+}
+
+// Informational lines (summaries, confirmations) are left out in quiet mode,
+// which keeps only the result a script would want, one item per line.
+pub fn run_command(
+    command: Command,
+    db: &JiraDatabase,
+    display: &DisplayConfig,
+    verbosity: Verbosity,
+    out: &mut dyn Write,
+) -> Result<()> {
+    let indent = if verbosity == Verbosity::Quiet {
+        ""
+    } else {
+        "  "
+    };
+
+    match command {
+        Command::Burndown { epic_id, format } => {
+            let points = db.burndown(&epic_id)?;
+            match format {
+                OutputFormat::Json => writeln!(out, "{}", serde_json::to_string_pretty(&points)?)?,
+                OutputFormat::Table => {
+                    for point in points {
+                        writeln!(out, "{} | {:>5}", point.date, point.remaining)?;
+                    }
+                }
+            }
+        }
+        Command::CreateEpic { name, description } => {
+            let (epic_id, epic) = db.create_epic_returning(Epic::new(name, description))?;
+            match verbosity {
+                Verbosity::Quiet => writeln!(out, "{}", epic_id)?,
+                Verbosity::Normal => writeln!(out, "Created epic {}.", epic_id)?,
+                Verbosity::Verbose => writeln!(out, "Created epic {}: {}.", epic_id, epic.name)?,
+            }
+        }
+        Command::Gc { dry_run: true } => {
+            let story_ids = db.find_orphan_stories()?;
+            if verbosity >= Verbosity::Normal {
+                writeln!(out, "Would remove {} orphan stories.", story_ids.len())?;
+            }
+            for story_id in story_ids {
+                writeln!(out, "{}{}", indent, story_id)?;
+            }
+        }
+        Command::Gc { dry_run: false } => {
+            let report = db.gc()?;
+            if verbosity >= Verbosity::Normal {
+                writeln!(out, "Removed {} orphan stories.", report.story_ids.len())?;
+            }
+            for story_id in report.story_ids {
+                writeln!(out, "{}{}", indent, story_id)?;
+            }
+        }
+        Command::Check { strict, format } => {
+            let problems = db.check_integrity()?;
+            match format {
+                OutputFormat::Json => {
+                    writeln!(out, "{}", serde_json::to_string_pretty(&problems)?)?;
+                }
+                OutputFormat::Table => {
+                    for problem in &problems {
+                        writeln!(
+                            out,
+                            "{} | {} | {}",
+                            problem.kind, problem.id, problem.message
+                        )?;
+                    }
+                    if problems.is_empty() && verbosity >= Verbosity::Normal {
+                        writeln!(out, "No problems found.")?;
+                    }
+                }
+            }
+            // main turns the error into a non-zero exit
+            if strict && !problems.is_empty() {
+                return Err(anyhow!("Found {} integrity problems.", problems.len()));
+            }
+        }
+        Command::Archive { path } => {
+            let count = db.archive_closed_epics(&path)?;
+            match verbosity {
+                Verbosity::Quiet => writeln!(out, "{}", count)?,
+                _ => writeln!(
+                    out,
+                    "Archived {} closed epics to {}.",
+                    count,
+                    path.display()
+                )?,
+            }
+        }
+        Command::Due { start, end, format } => {
+            let due = db.stories_due_between(start, end)?;
+            match format {
+                OutputFormat::Json => {
+                    let json = due
+                        .iter()
+                        .map(|(epic_id, story_id, story)| {
+                            serde_json::json!({
+                                "epic_id": epic_id,
+                                "story_id": story_id,
+                                "name": story.name,
+                                "due_date": story.due_date,
+                            })
+                        })
+                        .collect_vec();
+                    writeln!(out, "{}", serde_json::to_string_pretty(&json)?)?;
+                }
+                OutputFormat::Table => {
+                    for (_, story_id, story) in due {
+                        let due_date = story.due_date.map(|date| date.to_string());
+                        writeln!(
+                            out,
+                            "{} | {} | {}",
+                            due_date.unwrap_or_default(),
+                            story_id,
+                            story.name
+                        )?;
+                    }
+                }
+            }
+        }
+        Command::ExportIcs { output } => {
+            let ics = db.export_ics()?;
+            write_export(&output, verbosity, out, |writer| {
+                Ok(write!(writer, "{}", ics)?)
+            })?;
+        }
+        Command::ExportNdjson { output } => {
+            write_export(&output, verbosity, out, |writer| {
+                Ok(db.export_ndjson(writer)?)
+            })?;
+        }
+        Command::ExportCsv { output } => {
+            write_export(&output, verbosity, out, |writer| Ok(db.export_csv(writer)?))?;
+        }
+        Command::ImportCsv { path, mode } => {
+            let file =
+                File::open(&path).with_context(|| format!("Failed to open {}.", path.display()))?;
+            let summary = db.import_epics(read_csv_epics(file)?, mode)?;
+            writeln!(
+                out,
+                "Imported {}: {} created, {} updated, {} skipped.",
+                path.display(),
+                summary.created,
+                summary.updated,
+                summary.skipped
+            )?;
+        }
+        Command::ExportSplit { dir, force } => {
+            let count = db.export_split(&dir, force)?;
+            match verbosity {
+                Verbosity::Quiet => writeln!(out, "{}", count)?,
+                _ => writeln!(
+                    out,
+                    "Wrote {} epic files and {} to {}.",
+                    count,
+                    SPLIT_INDEX_FILE,
+                    dir.display()
+                )?,
+            }
+        }
+        Command::ExportOnChange { format, output } => {
+            run_export_on_change(db, format, &output)?;
+        }
+        // Needs the config sources, which only main has
+        Command::Info => return Err(anyhow!("info is answered by write_info.")),
+        Command::StoryCreate {
+            epic,
+            name,
+            description,
+        } => {
+            let epic_id = resolve_epic_id(db, &epic)?;
+            let (story_id, story) =
+                db.create_story_returning(Story::new(name, description), &epic_id)?;
+            match verbosity {
+                Verbosity::Quiet => writeln!(out, "{}", story_id)?,
+                Verbosity::Normal => writeln!(out, "Created story {}.", story_id)?,
+                Verbosity::Verbose => writeln!(
+                    out,
+                    "Created story {} in epic {}: {}.",
+                    story_id, epic_id, story.name
+                )?,
+            }
+        }
+        Command::StoryList { epic, format } => {
+            let epic_ids = match epic {
+                Some(epic) => vec![resolve_epic_id(db, &epic)?],
+                None => db.read_db()?.epics.keys().sorted().cloned().collect(),
+            };
+            let db_state = db.read_db()?;
+            let stories = epic_ids
+                .iter()
+                .flat_map(|epic_id| {
+                    db_state
+                        .stories_of(epic_id)
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(move |(story_id, story)| (epic_id, story_id, story))
+                })
+                .collect_vec();
+            match format {
+                OutputFormat::Json => {
+                    let json = stories
+                        .iter()
+                        .map(|(epic_id, story_id, story)| {
+                            serde_json::json!({
+                                "epic_id": epic_id,
+                                "story_id": story_id,
+                                "name": story.name,
+                                "status": story.status,
+                                "assignee": story.assignee,
+                            })
+                        })
+                        .collect_vec();
+                    writeln!(out, "{}", serde_json::to_string_pretty(&json)?)?;
+                }
+                OutputFormat::Table => {
+                    // Plain text, lists are read by scripts as much as by people
+                    for (epic_id, story_id, story) in stories {
+                        writeln!(out, "{} | {}", epic_id, story.summary_line(story_id, false))?;
+                    }
+                }
+            }
+        }
+        Command::Restore { at } => {
+            let restored_at = db.restore_snapshot_at(at)?;
+            if verbosity >= Verbosity::Normal {
+                writeln!(
+                    out,
+                    "Restored the snapshot from {}.",
+                    restored_at.to_rfc3339()
+                )?;
+            }
+        }
+        Command::Refinement { format } => {
+            let queue = db.refinement_queue()?;
+            match format {
+                OutputFormat::Json => {
+                    let json = queue
+                        .iter()
+                        .map(|(epic_id, story_id, story)| {
+                            serde_json::json!({
+                                "epic_id": epic_id,
+                                "story_id": story_id,
+                                "name": story.name,
+                                "status": story.status,
+                            })
+                        })
+                        .collect_vec();
+                    writeln!(out, "{}", serde_json::to_string_pretty(&json)?)?;
+                }
+                OutputFormat::Table => {
+                    for (epic_id, story_id, story) in &queue {
+                        writeln!(out, "{} | {}", epic_id, story.summary_line(story_id, false))?;
+                    }
+                }
+            }
+        }
+        Command::EpicList { owner, format } => {
+            let epics = db.filter_epics(|_, epic| {
+                owner
+                    .as_deref()
+                    .is_none_or(|owner| epic.owner.as_deref() == Some(owner))
+            })?;
+            match format {
+                OutputFormat::Json => {
+                    let json = epics
+                        .iter()
+                        .map(|(epic_id, epic)| {
+                            serde_json::json!({
+                                "epic_id": epic_id,
+                                "name": epic.name,
+                                "status": epic.status,
+                                "owner": epic.owner,
+                            })
+                        })
+                        .collect_vec();
+                    writeln!(out, "{}", serde_json::to_string_pretty(&json)?)?;
+                }
+                OutputFormat::Table => {
+                    for (epic_id, epic) in &epics {
+                        writeln!(
+                            out,
+                            "{} | {}",
+                            epic.summary_line(epic_id, false),
+                            epic.owner.as_deref().unwrap_or_default()
+                        )?;
+                    }
+                }
+            }
+        }
+        Command::EpicRecompute { epic } => {
+            let epic_id = resolve_epic_id(db, &epic)?;
+            let status = db.recompute_epic_status(&epic_id)?;
+            if verbosity >= Verbosity::Normal {
+                writeln!(out, "Epic {} is {}.", epic_id, status)?;
+            }
+        }
+        Command::StoryMove { story_id, epic } => {
+            let epic_id = resolve_epic_id(db, &epic)?;
+            db.move_story(&story_id, &epic_id)?;
+            if verbosity >= Verbosity::Normal {
+                writeln!(out, "Moved story {} to epic {}.", story_id, epic_id)?;
+            }
+        }
+        Command::EpicReorder { epic, position } => {
+            let epic_id = resolve_epic_id(db, &epic)?;
+            db.reorder_epic(&epic_id, position - 1)?;
+            if verbosity >= Verbosity::Normal {
+                writeln!(out, "Moved epic {} to position {}.", epic_id, position)?;
+            }
+        }
+        Command::StoryReorder { story_id, position } => {
+            db.reorder_story(&story_id, position - 1)?;
+            if verbosity >= Verbosity::Normal {
+                writeln!(out, "Moved story {} to position {}.", story_id, position)?;
+            }
+        }
+        Command::StoryEstimate { story_id, estimate } => {
+            db.set_story_estimate(&story_id, estimate)?;
+            if verbosity >= Verbosity::Normal {
+                match estimate {
+                    Some(points) => {
+                        writeln!(out, "Estimated story {} at {} points.", story_id, points)?
+                    }
+                    None => writeln!(out, "Cleared the estimate of story {}.", story_id)?,
+                }
+            }
+        }
+        Command::Open { id, all } => {
+            let db_state = db.read_db()?;
+            let width = if all {
+                TableWidth::Full
+            } else {
+                TableWidth::Fit(OPEN_WIDTH)
+            };
+            match db.find_item(&id)? {
+                ItemRef::Epic { epic_id } => write_epic_detail(
+                    out,
+                    &db_state,
+                    &epic_id,
+                    SortMode::Name,
+                    width,
+                    display,
+                    None,
+                )?,
+                ItemRef::Story { story_id } => {
+                    write_story_detail(out, &db_state, &story_id, width, display)?
+                }
+            }
+        }
+        Command::Query { query, format } => {
+            let stories = db.filter_stories(parse_story_query(&query)?)?;
+            match format {
+                OutputFormat::Json => {
+                    let json = stories
+                        .iter()
+                        .map(|(story_id, story)| {
+                            serde_json::json!({
+                                "story_id": story_id,
+                                "name": story.name,
+                                "status": story.status,
+                                "assignee": story.assignee,
+                            })
+                        })
+                        .collect_vec();
+                    writeln!(out, "{}", serde_json::to_string_pretty(&json)?)?;
+                }
+                OutputFormat::Table => {
+                    for (story_id, story) in stories {
+                        writeln!(
+                            out,
+                            "{} | {}",
+                            story.summary_line(&story_id, false),
+                            story.assignee.as_deref().unwrap_or(UNASSIGNED)
+                        )?;
+                    }
+                }
+            }
+        }
+        Command::Report { format } => {
+            let rows = get_report_rows(&db.read_db()?);
+            let done = rows.iter().map(|row| row.done).sum::<usize>();
+            let total = rows.iter().map(|row| row.total).sum::<usize>();
+
+            match format {
+                OutputFormat::Json => {
+                    let epics = rows
+                        .iter()
+                        .map(|row| {
+                            serde_json::json!({
+                                "id": row.epic_id,
+                                "name": row.name,
+                                "done": row.done,
+                                "total": row.total,
+                                "percent": get_completion_percent(row.done, row.total),
+                            })
+                        })
+                        .collect::<Vec<_>>();
+                    let report = serde_json::json!({
+                        "epics": epics,
+                        "total": {
+                            "done": done,
+                            "total": total,
+                            "percent": get_completion_percent(done, total),
+                        },
+                    });
+                    writeln!(out, "{}", serde_json::to_string_pretty(&report)?)?;
+                }
+                OutputFormat::Table => {
+                    writeln!(
+                        out,
+                        "{:<8} | {:<30} | {:>4} | {:>5} | {:>4}",
+                        "id", "epic", "done", "total", "%"
+                    )?;
+                    for row in &rows {
+                        writeln!(
+                            out,
+                            "{:<8} | {:<30} | {:>4} | {:>5} | {:>3}%",
+                            row.epic_id,
+                            row.name,
+                            row.done,
+                            row.total,
+                            get_completion_percent(row.done, row.total)
+                        )?;
+                    }
+                    writeln!(
+                        out,
+                        "{:<8} | {:<30} | {:>4} | {:>5} | {:>3}%",
+                        "total",
+                        "",
+                        done,
+                        total,
+                        get_completion_percent(done, total)
+                    )?;
+                }
+            }
+        }
+        Command::Stats { format } => {
+            // Sort by name, keeping the unassigned bucket last
+            let mut stats = db
+                .stats_by_assignee()?
+                .into_iter()
+                .collect::<BTreeMap<_, _>>();
+            let unassigned = stats.remove(UNASSIGNED);
+            let mut rows = stats.into_iter().collect::<Vec<_>>();
+            rows.extend(unassigned.map(|row| (UNASSIGNED.to_owned(), row)));
+
+            match format {
+                OutputFormat::Json => {
+                    let rows = rows
+                        .into_iter()
+                        .map(|(assignee, stats)| {
+                            serde_json::json!({
+                                "assignee": assignee,
+                                "open": stats.open,
+                                "in_progress": stats.in_progress,
+                                "blocked": stats.blocked,
+                                "resolved": stats.resolved,
+                                "closed": stats.closed,
+                                "total": stats.total(),
+                            })
+                        })
+                        .collect::<Vec<_>>();
+                    writeln!(out, "{}", serde_json::to_string_pretty(&rows)?)?;
+                }
+                OutputFormat::Table => {
+                    writeln!(
+                        out,
+                        "{:<20} | {:>6} | {:>11} | {:>7} | {:>8} | {:>6} | {:>5}",
+                        "assignee", "open", "in progress", "blocked", "resolved", "closed", "total"
+                    )?;
+                    for (assignee, stats) in rows {
+                        writeln!(
+                            out,
+                            "{:<20} | {:>6} | {:>11} | {:>7} | {:>8} | {:>6} | {:>5}",
+                            assignee,
+                            stats.open,
+                            stats.in_progress,
+                            stats.blocked,
+                            stats.resolved,
+                            stats.closed,
+                            stats.total()
+                        )?;
+                    }
+                }
+            }
+        }
+        Command::Metrics { format } => {
+            let metrics = db.flow_metrics()?;
+            match format {
+                OutputFormat::Json => {
+                    let json = serde_json::json!({
+                        "average_cycle_time_secs": metrics.average_cycle_time.map(|time| time.num_seconds()),
+                        "cycle_time_stories": metrics.cycle_time_count,
+                        "average_lead_time_secs": metrics.average_lead_time.map(|time| time.num_seconds()),
+                        "lead_time_stories": metrics.lead_time_count,
+                    });
+                    writeln!(out, "{}", serde_json::to_string_pretty(&json)?)?;
+                }
+                OutputFormat::Table => {
+                    let average = |time: Option<chrono::Duration>| {
+                        time.map_or("-".to_owned(), |time| format_duration(&time))
+                    };
+                    writeln!(
+                        out,
+                        "{:<10} | {:>8} | {:>7}",
+                        "metric", "average", "stories"
+                    )?;
+                    writeln!(
+                        out,
+                        "{:<10} | {:>8} | {:>7}",
+                        "cycle time",
+                        average(metrics.average_cycle_time),
+                        metrics.cycle_time_count
+                    )?;
+                    writeln!(
+                        out,
+                        "{:<10} | {:>8} | {:>7}",
+                        "lead time",
+                        average(metrics.average_lead_time),
+                        metrics.lead_time_count
+                    )?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_utils::{arrange_test, walk_epic_to, walk_story_to};
+    use crate::models::Status;
+    use chrono::TimeZone;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn arrange_orphan() -> JiraDatabase {
+        let (db, _epic_id, _story_id) = arrange_test();
+        let mut db_state = db.read_db().unwrap();
+        db_state.stories.insert(
+            "orphan".to_owned(),
+            Story::new("".to_owned(), "".to_owned()),
+        );
+        db.database.write_db(&db_state).unwrap();
+        db
+    }
+
+    fn run_check(db: &JiraDatabase, strict: bool, format: OutputFormat) -> (Result<()>, String) {
+        let mut out = Vec::new();
+        let result = run_command(
+            Command::Check { strict, format },
+            db,
+            &DisplayConfig::default(),
+            Verbosity::Normal,
+            &mut out,
+        );
+        (result, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn parse_args_should_parse_check() {
+        assert_eq!(
+            parse_args(&args(&["check", "--strict", "--format", "json"])).unwrap(),
+            Some(Command::Check {
+                strict: true,
+                format: OutputFormat::Json,
+            })
+        );
+        assert!(parse_args(&args(&["check", "--fix"])).is_err());
+    }
+
+    #[test]
+    fn check_strict_should_fail_on_problems_without_changing_the_file() {
+        let db = arrange_orphan();
+        let mut db_state = db.read_db().unwrap();
+        let epic_id = db_state.epics.keys().next().unwrap().clone();
+        db_state
+            .epics
+            .get_mut(&epic_id)
+            .unwrap()
+            .stories
+            .push("gone".to_owned());
+        db.database.write_db(&db_state).unwrap();
+        let before = db.read_db().unwrap();
+
+        let (result, out) = run_check(&db, true, OutputFormat::Json);
+        let (lenient, _) = run_check(&db, false, OutputFormat::Table);
+
+        assert!(result.is_err());
+        assert!(lenient.is_ok());
+        let problems: Vec<serde_json::Value> = serde_json::from_str(&out).unwrap();
+        assert_eq!(
+            problems
+                .iter()
+                .map(|problem| (
+                    problem["kind"].as_str().unwrap(),
+                    problem["id"].as_str().unwrap()
+                ))
+                .collect_vec(),
+            vec![
+                ("missing_story", epic_id.as_str()),
+                ("orphan_story", "orphan")
+            ]
+        );
+        assert_eq!(db.read_db().unwrap(), before);
+    }
+
+    #[test]
+    fn check_strict_should_pass_a_clean_database() {
+        let (db, _epic_id, _story_id) = arrange_test();
+
+        let (result, out) = run_check(&db, true, OutputFormat::Table);
+        let (_, json) = run_check(&db, true, OutputFormat::Json);
+
+        assert!(result.is_ok());
+        assert_eq!(out, "No problems found.\n");
+        assert_eq!(json, "[]\n");
+    }
+
+    #[test]
+    fn check_should_report_files_that_do_not_parse() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("db.json");
+        fs::write(&path, r#"{"epics": 1}"#).unwrap();
+        let db = JiraDatabase::new(path.clone());
+
+        let (result, out) = run_check(&db, true, OutputFormat::Table);
+
+        assert!(result.is_err());
+        assert!(out.starts_with("schema |  | Invalid database JSON:"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), r#"{"epics": 1}"#);
+    }
+
+    #[test]
+    fn parse_args_should_return_none_without_subcommand() {
+        assert_eq!(parse_args(&[]).unwrap(), None);
+    }
+
+    #[test]
+    fn parse_args_should_parse_gc() {
+        assert_eq!(
+            parse_args(&args(&["gc"])).unwrap(),
+            Some(Command::Gc { dry_run: false })
+        );
+        assert_eq!(
+            parse_args(&args(&["gc", "--dry-run"])).unwrap(),
+            Some(Command::Gc { dry_run: true })
+        );
+    }
+
+    #[test]
+    fn parse_args_should_parse_archive() {
+        assert_eq!(
+            parse_args(&args(&["archive", "old.json"])).unwrap(),
+            Some(Command::Archive {
+                path: PathBuf::from("old.json")
+            })
+        );
+        assert!(parse_args(&args(&["archive"])).is_err());
+    }
+
+    #[test]
+    fn archive_should_report_the_count() {
+        let (db, epic_id, _story_id) = arrange_test();
+        walk_epic_to(&db, &epic_id, Status::Closed).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let mut out = Vec::new();
+
+        run_command(
+            Command::Archive {
+                path: dir.path().join("archive.json"),
+            },
+            &db,
+            &DisplayConfig::default(),
+            Verbosity::Quiet,
+            &mut out,
+        )
+        .unwrap();
+
+        assert_eq!(String::from_utf8(out).unwrap(), "1\n");
+        assert!(db.read_db().unwrap().epics.is_empty());
+    }
+
+    #[test]
+    fn parse_args_should_parse_due() {
+        let day = |day| NaiveDate::from_ymd_opt(2022, 1, day).unwrap();
+
+        assert_eq!(
+            parse_args(&args(&[
+                "due",
+                "2022-01-10",
+                "2022-01-16",
+                "--format",
+                "json"
+            ]))
+            .unwrap(),
+            Some(Command::Due {
+                start: day(10),
+                end: day(16),
+                format: OutputFormat::Json,
+            })
+        );
+        assert!(parse_args(&args(&["due", "2022-01-10"])).is_err());
+        assert!(parse_args(&args(&["due", "next week", "2022-01-16"])).is_err());
+    }
+
+    #[test]
+    fn due_should_list_stories_in_the_range() {
+        let (db, _epic_id, story_id) = arrange_test();
+        let day = |day| NaiveDate::from_ymd_opt(2022, 1, day).unwrap();
+        db.set_story_due_date(&story_id, Some(day(12))).unwrap();
+        let mut out = Vec::new();
+
+        run_command(
+            Command::Due {
+                start: day(10),
+                end: day(16),
+                format: OutputFormat::Table,
+            },
+            &db,
+            &DisplayConfig::default(),
+            Verbosity::Normal,
+            &mut out,
+        )
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!("2022-01-12 | {} | \n", story_id)
+        );
+    }
+
+    #[test]
+    fn parse_args_should_parse_export_ics() {
+        assert_eq!(
+            parse_args(&args(&["export-ics"])).unwrap(),
+            Some(Command::ExportIcs {
+                output: ExportOutput::default()
+            })
+        );
+        assert!(parse_args(&args(&["export-ics", "extra"])).is_err());
+        assert_eq!(
+            parse_args(&args(&["export-ndjson"])).unwrap(),
+            Some(Command::ExportNdjson {
+                output: ExportOutput::default()
+            })
+        );
+    }
+
+    #[test]
+    fn parse_args_should_parse_export_output() {
+        assert_eq!(
+            parse_args(&args(&["export-ics", "--output", "out/cal.ics", "--force"])).unwrap(),
+            Some(Command::ExportIcs {
+                output: ExportOutput {
+                    path: Some(PathBuf::from("out/cal.ics")),
+                    force: true,
+                }
+            })
+        );
+        assert!(parse_args(&args(&["export-ndjson", "--output"])).is_err());
+    }
+
+    #[test]
+    fn parse_args_should_parse_import_csv() {
+        assert_eq!(
+            parse_args(&args(&["import-csv", "db.csv"])).unwrap(),
+            Some(Command::ImportCsv {
+                path: PathBuf::from("db.csv"),
+                mode: MergeMode::SkipExisting,
+            })
+        );
+        assert_eq!(
+            parse_args(&args(&["import-csv", "db.csv", "--mode", "append"])).unwrap(),
+            Some(Command::ImportCsv {
+                path: PathBuf::from("db.csv"),
+                mode: MergeMode::Append,
+            })
+        );
+        assert!(parse_args(&args(&["import-csv"])).is_err());
+        assert!(parse_args(&args(&["import-csv", "db.csv", "--mode", "merge"])).is_err());
+    }
+
+    #[test]
+    fn export_output_should_create_the_file_and_parent_dirs() {
+        let (db, epic_id, _story_id) = arrange_test();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("db.ndjson");
+        let mut out = Vec::new();
+
+        run_command(
+            Command::ExportNdjson {
+                output: ExportOutput {
+                    path: Some(path.clone()),
+                    force: false,
+                },
+            },
+            &db,
+            &DisplayConfig::default(),
+            Verbosity::Normal,
+            &mut out,
+        )
+        .unwrap();
+
+        let written = fs::read_to_string(&path).unwrap();
+        assert!(written.contains(&epic_id));
+        assert!(String::from_utf8(out).unwrap().starts_with("Wrote "));
+    }
+
+    #[test]
+    fn parse_args_should_parse_export_split() {
+        assert_eq!(
+            parse_args(&args(&["export-split", "out/epics", "--force"])).unwrap(),
+            Some(Command::ExportSplit {
+                dir: PathBuf::from("out/epics"),
+                force: true,
+            })
+        );
+        assert!(parse_args(&args(&["export-split"])).is_err());
+        assert!(parse_args(&args(&["export-split", "out", "--output"])).is_err());
+    }
+
+    #[test]
+    fn export_output_should_not_overwrite_without_force() {
+        let (db, _epic_id, _story_id) = arrange_test();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("calendar.ics");
+        fs::write(&path, "keep me").unwrap();
+        let command = |force| Command::ExportIcs {
+            output: ExportOutput {
+                path: Some(path.clone()),
+                force,
+            },
+        };
+
+        let result = run_command(
+            command(false),
+            &db,
+            &DisplayConfig::default(),
+            Verbosity::Normal,
+            &mut Vec::new(),
+        );
+
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "keep me");
+
+        run_command(
+            command(true),
+            &db,
+            &DisplayConfig::default(),
+            Verbosity::Normal,
+            &mut Vec::new(),
+        )
+        .unwrap();
+
+        assert!(fs::read_to_string(&path)
+            .unwrap()
+            .starts_with("BEGIN:VCALENDAR"));
+    }
+
+    #[test]
+    fn parse_args_should_parse_stats_format() {
+        assert_eq!(
+            parse_args(&args(&["stats"])).unwrap(),
+            Some(Command::Stats {
+                format: OutputFormat::Table
+            })
+        );
+        assert_eq!(
+            parse_args(&args(&["stats", "--format", "json"])).unwrap(),
+            Some(Command::Stats {
+                format: OutputFormat::Json
+            })
+        );
+        assert!(parse_args(&args(&["stats", "--format"])).is_err());
+        assert!(parse_args(&args(&["stats", "--format", "xml"])).is_err());
+    }
+
+    #[test]
+    fn parse_args_should_parse_export_on_change() {
+        assert_eq!(
+            parse_args(&args(&["--export-on-change", "ndjson", "out.ndjson"])).unwrap(),
+            Some(Command::ExportOnChange {
+                format: ExportFormat::Ndjson,
+                output: PathBuf::from("out.ndjson"),
+            })
+        );
+        assert!(parse_args(&args(&["--export-on-change", "ndjson"])).is_err());
+        assert!(parse_args(&args(&["--export-on-change", "pdf", "out.pdf"])).is_err());
+    }
+
+    #[test]
+    fn parse_args_should_parse_metrics_format() {
+        assert_eq!(
+            parse_args(&args(&["metrics", "--format", "json"])).unwrap(),
+            Some(Command::Metrics {
+                format: OutputFormat::Json
+            })
+        );
+        assert!(parse_args(&args(&["metrics", "--all"])).is_err());
+    }
+
+    #[test]
+    fn metrics_should_show_a_dash_without_finished_stories() {
+        let (db, _epic_id, _story_id) = arrange_test();
+        let mut out = Vec::new();
+
+        run_command(
+            Command::Metrics {
+                format: OutputFormat::Table,
+            },
+            &db,
+            &DisplayConfig::default(),
+            Verbosity::Normal,
+            &mut out,
+        )
+        .unwrap();
+
+        let output = String::from_utf8(out).unwrap();
+        assert!(output.contains("cycle time |        - |       0"));
+    }
+
+    #[test]
+    fn stats_should_list_unassigned_last() {
+        let (db, _epic_id, story_id) = arrange_test();
+        let mut db_state = db.read_db().unwrap();
+        let mut story = Story::new("".to_owned(), "".to_owned());
+        story.assignee = Some("zed".to_owned());
+        db_state.stories.insert("2".to_owned(), story);
+        db.database.write_db(&db_state).unwrap();
+        let mut out = Vec::new();
+
+        run_command(
+            Command::Stats {
+                format: OutputFormat::Table,
+            },
+            &db,
+            &DisplayConfig::default(),
+            Verbosity::Normal,
+            &mut out,
+        )
+        .unwrap();
+
+        let output = String::from_utf8(out).unwrap();
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with("zed"));
+        assert!(lines[2].starts_with(UNASSIGNED));
+        assert!(!output.contains(&story_id));
+    }
+
+    #[test]
+    fn stats_json_should_parse() {
+        let (db, _epic_id, _story_id) = arrange_test();
+        let mut out = Vec::new();
+
+        run_command(
+            Command::Stats {
+                format: OutputFormat::Json,
+            },
+            &db,
+            &DisplayConfig::default(),
+            Verbosity::Normal,
+            &mut out,
+        )
+        .unwrap();
+
+        let rows: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(rows[0]["assignee"], UNASSIGNED);
+        assert_eq!(rows[0]["open"], 1);
+    }
+
+    #[test]
+    fn parse_args_should_parse_open() {
+        assert_eq!(
+            parse_args(&args(&["open", "abc"])).unwrap(),
+            Some(Command::Open {
+                id: "abc".to_owned(),
+                all: false,
+            })
+        );
+        assert_eq!(
+            parse_args(&args(&["open", "abc", "--wide"])).unwrap(),
+            Some(Command::Open {
+                id: "abc".to_owned(),
+                all: true,
+            })
+        );
+        assert!(parse_args(&args(&["open"])).is_err());
+        assert!(parse_args(&args(&["open", "abc", "def"])).is_err());
+    }
+
+    #[test]
+    fn open_should_print_epic_detail() {
+        let (db, epic_id, story_id) = arrange_test();
+        let mut out = Vec::new();
+
+        run_command(
+            Command::Open {
+                id: epic_id,
+                all: false,
+            },
+            &db,
+            &DisplayConfig::default(),
+            Verbosity::Normal,
+            &mut out,
+        )
+        .unwrap();
+
+        let output = String::from_utf8(out).unwrap();
+        assert!(output.contains("EPIC"));
+        assert!(output.contains("STORIES"));
+        assert!(output.contains(&story_id));
+    }
+
+    #[test]
+    fn open_should_print_story_detail() {
+        let (db, _epic_id, story_id) = arrange_test();
+        let mut out = Vec::new();
+
+        run_command(
+            Command::Open {
+                id: story_id.clone(),
+                all: false,
+            },
+            &db,
+            &DisplayConfig::default(),
+            Verbosity::Normal,
+            &mut out,
+        )
+        .unwrap();
+
+        let output = String::from_utf8(out).unwrap();
+        assert!(output.contains("STORY"));
+        assert!(output.contains(&story_id));
+    }
+
+    #[test]
+    fn open_all_should_keep_long_descriptions_whole() {
+        let (db, epic_id, _story_id) = arrange_test();
+        let description = "Everything the checkout rework has to cover: ".repeat(4);
+        let mut db_state = db.read_db().unwrap();
+        db_state.epics.get_mut(&epic_id).unwrap().description = description.clone();
+        db.database.write_db(&db_state).unwrap();
+        let open = |all| {
+            let mut out = Vec::new();
+            run_command(
+                Command::Open {
+                    id: epic_id.clone(),
+                    all,
+                },
+                &db,
+                &DisplayConfig::default(),
+                Verbosity::Normal,
+                &mut out,
+            )
+            .unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        let compact = open(false);
+        let full = open(true);
+
+        assert!(!compact.contains(&description));
+        assert!(compact.contains("..."));
+        assert!(full.contains(&description));
+    }
+
+    #[test]
+    fn open_should_fail_for_unknown_id() {
+        let (db, _epic_id, _story_id) = arrange_test();
+        let mut out = Vec::new();
+
+        let result = run_command(
+            Command::Open {
+                id: "missing".to_owned(),
+                all: false,
+            },
+            &db,
+            &DisplayConfig::default(),
+            Verbosity::Normal,
+            &mut out,
+        );
+
+        assert!(result.is_err());
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn parse_args_should_reject_unknown_input() {
+        assert!(parse_args(&args(&["nope"])).is_err());
+        assert!(parse_args(&args(&["gc", "--force"])).is_err());
+    }
+
+    #[test]
+    fn gc_dry_run_should_report_without_deleting() {
+        let db = arrange_orphan();
+        let mut out = Vec::new();
+
+        run_command(
+            Command::Gc { dry_run: true },
+            &db,
+            &DisplayConfig::default(),
+            Verbosity::Normal,
+            &mut out,
+        )
+        .unwrap();
+
+        let output = String::from_utf8(out).unwrap();
+        assert!(output.contains("Would remove 1 orphan stories."));
+        assert!(db.read_db().unwrap().stories.contains_key("orphan"));
+    }
+
+    #[test]
+    fn gc_should_delete_orphans() {
+        let db = arrange_orphan();
+        let mut out = Vec::new();
+
+        run_command(
+            Command::Gc { dry_run: false },
+            &db,
+            &DisplayConfig::default(),
+            Verbosity::Normal,
+            &mut out,
+        )
+        .unwrap();
+
+        let output = String::from_utf8(out).unwrap();
+        assert!(output.contains("Removed 1 orphan stories."));
+        assert!(!db.read_db().unwrap().stories.contains_key("orphan"));
+    }
+
+    #[test]
+    fn parse_global_args_should_strip_leading_flags() {
+        let verbosity = |input: &[&str]| {
+            let (global, rest) = parse_global_args(&args(input)).unwrap();
+            (global.verbosity, rest)
+        };
+
+        assert_eq!(
+            verbosity(&["--quiet", "gc"]),
+            (Verbosity::Quiet, args(&["gc"]))
+        );
+        assert_eq!(
+            verbosity(&["-v", "stats"]),
+            (Verbosity::Verbose, args(&["stats"]))
+        );
+        assert_eq!(
+            verbosity(&["create-epic", "-q"]),
+            (Verbosity::Normal, args(&["create-epic", "-q"]))
+        );
+        assert!(parse_global_args(&args(&["-q", "-v", "gc"])).is_err());
+    }
+
+    #[test]
+    fn parse_global_args_should_read_no_notify_and_no_clear() {
+        let (global, rest) =
+            parse_global_args(&args(&["--no-notify", "-q", "--no-clear"])).unwrap();
+
+        assert!(global.no_notify);
+        assert!(global.no_clear);
+        assert!(!global.run_rule_actions);
+        assert_eq!(global.verbosity, Verbosity::Quiet);
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn parse_global_args_should_read_the_script_path() {
+        let (global, rest) = parse_global_args(&args(&["--script", "demo.txt"])).unwrap();
+
+        assert_eq!(global.script, Some(PathBuf::from("demo.txt")));
+        assert!(rest.is_empty());
+        assert!(parse_global_args(&args(&["--script"])).is_err());
+    }
+
+    #[test]
+    fn parse_global_args_should_collect_config_overrides() {
+        let (global, rest) = parse_global_args(&args(&[
+            "-c",
+            "timezone=Asia/Tokyo",
+            "--config",
+            "idle_secs=5",
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            global.config,
+            vec![
+                ("timezone".to_owned(), "Asia/Tokyo".to_owned()),
+                ("idle_secs".to_owned(), "5".to_owned()),
+            ]
+        );
+        assert!(rest.is_empty());
+        assert!(parse_global_args(&args(&["-c"])).is_err());
+        assert!(parse_global_args(&args(&["-c", "timezone"])).is_err());
+    }
+
+    // Two epics sharing the prefix "abc", each without stories
+    fn arrange_prefixed_epics() -> JiraDatabase {
+        let (db, _epic_id, _story_id) = arrange_test();
+        let mut db_state = db.read_db().unwrap();
+        for epic_id in ["abc1", "abc2"] {
+            db_state.epics.insert(
+                epic_id.to_owned(),
+                Epic::new(epic_id.to_owned(), "".to_owned()),
+            );
+        }
+        db.database.write_db(&db_state).unwrap();
+        db
+    }
+
+    #[test]
+    fn parse_args_should_parse_story_commands() {
+        assert_eq!(
+            parse_args(&args(&["story", "create", "--epic", "ab", "Login"])).unwrap(),
+            Some(Command::StoryCreate {
+                epic: "ab".to_owned(),
+                name: "Login".to_owned(),
+                description: "".to_owned(),
+            })
+        );
+        assert_eq!(
+            parse_args(&args(&["story", "list", "--format", "json"])).unwrap(),
+            Some(Command::StoryList {
+                epic: None,
+                format: OutputFormat::Json,
+            })
+        );
+        assert_eq!(
+            parse_args(&args(&["story", "move", "7", "--epic", "3"])).unwrap(),
+            Some(Command::StoryMove {
+                story_id: "7".to_owned(),
+                epic: "3".to_owned(),
+            })
+        );
+        assert!(parse_args(&args(&["story", "create", "Login"])).is_err());
+        assert!(parse_args(&args(&["story", "move", "7"])).is_err());
+        assert_eq!(
+            parse_args(&args(&["story", "reorder", "7", "2"])).unwrap(),
+            Some(Command::StoryReorder {
+                story_id: "7".to_owned(),
+                position: 2,
+            })
+        );
+        assert!(parse_args(&args(&["story", "reorder", "7"])).is_err());
+        assert!(parse_args(&args(&["story", "reorder", "7", "0"])).is_err());
+        assert_eq!(
+            parse_args(&args(&["story", "estimate", "7", "5"])).unwrap(),
+            Some(Command::StoryEstimate {
+                story_id: "7".to_owned(),
+                estimate: Some(5),
+            })
+        );
+        assert_eq!(
+            parse_args(&args(&["story", "estimate", "7", "none"])).unwrap(),
+            Some(Command::StoryEstimate {
+                story_id: "7".to_owned(),
+                estimate: None,
+            })
+        );
+        assert!(parse_args(&args(&["story", "estimate", "7", "-1"])).is_err());
+        assert!(parse_args(&args(&["story", "list", "extra"])).is_err());
+        assert!(parse_args(&args(&["story", "delete"])).is_err());
+    }
+
+    #[test]
+    fn run_command_should_resolve_an_epic_prefix() {
+        let db = arrange_prefixed_epics();
+        let mut out = Vec::new();
+
+        run_command(
+            Command::StoryCreate {
+                epic: "abc2".to_owned(),
+                name: "Login".to_owned(),
+                description: "".to_owned(),
+            },
+            &db,
+            &DisplayConfig::default(),
+            Verbosity::Quiet,
+            &mut out,
+        )
+        .unwrap();
+        let story_id = String::from_utf8(out).unwrap().trim().to_owned();
+        let mut out = Vec::new();
+        run_command(
+            Command::StoryList {
+                epic: Some("abc2".to_owned()),
+                format: OutputFormat::Table,
+            },
+            &db,
+            &DisplayConfig::default(),
+            Verbosity::Normal,
+            &mut out,
+        )
+        .unwrap();
+
+        assert_eq!(
+            db.read_db().unwrap().epics["abc2"].stories,
+            vec![story_id.clone()]
+        );
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!("abc2 | {} | OPEN | Login\n", story_id)
+        );
+        assert_eq!(resolve_epic_id(&db, "abc1").unwrap(), "abc1");
+    }
+
+    #[test]
+    fn parse_args_should_parse_restore_times() {
+        assert_eq!(
+            parse_args(&args(&["restore", "2024-05-01T11:00:00+02:00"])).unwrap(),
+            Some(Command::Restore {
+                at: Utc.with_ymd_and_hms(2024, 5, 1, 9, 0, 0).unwrap(),
+            })
+        );
+        assert!(parse_args(&args(&["restore", "2024-05-01"])).is_err());
+        assert!(parse_args(&args(&["restore"])).is_err());
+    }
+
+    #[test]
+    fn run_command_should_list_the_refinement_queue() {
+        let (db, epic_id, story_id) = arrange_test();
+        db.create_story(Story::new("Refined".to_owned(), "".to_owned()), &epic_id)
+            .unwrap();
+        db.toggle_refinement(&story_id).unwrap();
+        let mut out = Vec::new();
+
+        run_command(
+            Command::Refinement {
+                format: OutputFormat::Table,
+            },
+            &db,
+            &DisplayConfig::default(),
+            Verbosity::Normal,
+            &mut out,
+        )
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!("{} | {} | OPEN | \n", epic_id, story_id)
+        );
+        assert_eq!(
+            parse_args(&args(&["refinement", "--format", "json"])).unwrap(),
+            Some(Command::Refinement {
+                format: OutputFormat::Json,
+            })
+        );
+    }
+
+    #[test]
+    fn run_command_should_list_epics_by_owner() {
+        let db = arrange_prefixed_epics();
+        db.set_epic_owner(&"abc1".to_owned(), "ana".to_owned())
+            .unwrap();
+        db.set_epic_owner(&"abc2".to_owned(), "bo".to_owned())
+            .unwrap();
+        let mut out = Vec::new();
+
+        run_command(
+            Command::EpicList {
+                owner: Some("ana".to_owned()),
+                format: OutputFormat::Table,
+            },
+            &db,
+            &DisplayConfig::default(),
+            Verbosity::Normal,
+            &mut out,
+        )
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "abc1 | OPEN | abc1 | ana\n"
+        );
+        assert_eq!(
+            parse_args(&args(&["epic", "list", "--owner", "ana"])).unwrap(),
+            Some(Command::EpicList {
+                owner: Some("ana".to_owned()),
+                format: OutputFormat::Table,
+            })
+        );
+        assert!(parse_args(&args(&["epic", "list", "--owner"])).is_err());
+        assert!(parse_args(&args(&["epic", "delete"])).is_err());
+        assert_eq!(
+            parse_args(&args(&["epic", "recompute", "abc1"])).unwrap(),
+            Some(Command::EpicRecompute {
+                epic: "abc1".to_owned()
+            })
+        );
+        assert!(parse_args(&args(&["epic", "recompute"])).is_err());
+        assert_eq!(
+            parse_args(&args(&["epic", "reorder", "abc1", "1"])).unwrap(),
+            Some(Command::EpicReorder {
+                epic: "abc1".to_owned(),
+                position: 1,
+            })
+        );
+        assert!(parse_args(&args(&["epic", "reorder", "abc1", "first"])).is_err());
+    }
+
+    #[test]
+    fn run_command_should_reject_ambiguous_epic_prefixes() {
+        let db = arrange_prefixed_epics();
+
+        let ambiguous = run_command(
+            Command::StoryMove {
+                story_id: "1".to_owned(),
+                epic: "abc".to_owned(),
+            },
+            &db,
+            &DisplayConfig::default(),
+            Verbosity::Normal,
+            &mut Vec::new(),
+        );
+
+        assert_eq!(
+            ambiguous.unwrap_err().to_string(),
+            "Epic id 'abc' is ambiguous, it matches 2 epics."
+        );
+        assert!(resolve_epic_id(&db, "zzz").is_err());
+    }
+
+    #[test]
+    fn parse_args_should_parse_query() {
+        assert_eq!(
+            parse_args(&args(&[
+                "query",
+                "status=open",
+                "--format",
+                "json",
+                "assignee=ana"
+            ]))
+            .unwrap(),
+            Some(Command::Query {
+                query: "status=open assignee=ana".to_owned(),
+                format: OutputFormat::Json,
+            })
+        );
+        assert!(parse_args(&args(&["query", "label=backend"])).is_err());
+    }
+
+    #[test]
+    fn run_command_should_list_stories_matching_the_query() {
+        let (db, epic_id, story_id) = arrange_test();
+        let closed_id = db
+            .create_story(Story::new("Logout".to_owned(), "".to_owned()), &epic_id)
+            .unwrap();
+        walk_story_to(&db, &closed_id, Status::Closed).unwrap();
+        let mut out = Vec::new();
+
+        run_command(
+            Command::Query {
+                query: "status=closed".to_owned(),
+                format: OutputFormat::Table,
+            },
+            &db,
+            &DisplayConfig::default(),
+            Verbosity::Normal,
+            &mut out,
+        )
+        .unwrap();
+
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(
+            out,
+            format!("{} | CLOSED | Logout | {}\n", closed_id, UNASSIGNED)
+        );
+        assert!(!out.contains(&format!("{} |", story_id)));
+    }
+
+    #[test]
+    fn run_command_should_estimate_stories_for_the_burndown() {
+        let (db, epic_id, story_id) = arrange_test();
+        walk_story_to(&db, &story_id, Status::InProgress).unwrap();
+        let mut out = Vec::new();
+
+        for command in [
+            Command::StoryEstimate {
+                story_id: story_id.clone(),
+                estimate: Some(5),
+            },
+            Command::Burndown {
+                epic_id: epic_id.clone(),
+                format: OutputFormat::Table,
+            },
+        ] {
+            run_command(
+                command,
+                &db,
+                &DisplayConfig::default(),
+                Verbosity::Normal,
+                &mut out,
+            )
+            .unwrap();
+        }
+
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with(&format!("Estimated story {} at 5 points.\n", story_id)));
+        assert!(out.ends_with("|     5\n"));
+    }
+
+    #[test]
+    fn parse_args_should_parse_info() {
+        assert_eq!(parse_args(&args(&["info"])).unwrap(), Some(Command::Info));
+        assert!(parse_args(&args(&["info", "--format"])).is_err());
+    }
+
+    #[test]
+    fn info_should_report_the_db_path_given_on_the_command_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("elsewhere.json").display().to_string();
+        let (global, rest) = parse_global_args(&args(&["--db-path", &db_path, "info"])).unwrap();
+        let sources = vec![ConfigSource {
+            name: "command line".to_owned(),
+            layer: global
+                .config
+                .iter()
+                .map(|(key, value)| (key.clone(), serde_json::Value::from(value.clone())))
+                .collect(),
+        }];
+        let config = crate::config::merge_config_sources(&sources).unwrap();
+        let db = JiraDatabase::new(config.db_path.clone());
+        let mut out = Vec::new();
+
+        assert_eq!(parse_args(&rest).unwrap(), Some(Command::Info));
+        write_info(&mut out, &config, &sources, &db).unwrap();
+
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains(&format!("path: {} (not created yet)", db_path)));
+        assert!(out.contains("backend: JSON file"));
+        assert!(out.contains("command line: db_path"));
+        assert!(out.contains(&format!("db_path = {:?}", db_path)));
+        // Info must not create the file
+        assert!(!dir.path().join("elsewhere.json").exists());
+    }
+
+    #[test]
+    fn parse_args_should_parse_burndown() {
+        assert_eq!(
+            parse_args(&args(&["burndown", "abc", "--format", "json"])).unwrap(),
+            Some(Command::Burndown {
+                epic_id: "abc".to_owned(),
+                format: OutputFormat::Json,
+            })
+        );
+        assert!(parse_args(&args(&["burndown"])).is_err());
+    }
+
+    // Epics "half" (1 of 2 done), "empty" (no stories) and "done" (1 of 1 done)
+    fn arrange_report() -> JiraDatabase {
+        let (db, epic_id, _story_id) = arrange_test();
+        let mut db_state = db.read_db().unwrap();
+        let half = db_state.epics.get_mut(&epic_id).unwrap();
+        half.name = "half".to_owned();
+        half.stories.push("2".to_owned());
+        let mut done_story = Story::new("".to_owned(), "".to_owned());
+        done_story.status = Status::Closed;
+        db_state.stories.insert("2".to_owned(), done_story.clone());
+        db_state.stories.insert("3".to_owned(), done_story);
+        let mut done = Epic::new("done".to_owned(), "".to_owned());
+        done.stories.push("3".to_owned());
+        db_state.epics.insert("d".to_owned(), done);
+        db_state
+            .epics
+            .insert("e".to_owned(), Epic::new("empty".to_owned(), "".to_owned()));
+        db.database.write_db(&db_state).unwrap();
+        db
+    }
+
+    #[test]
+    fn report_should_list_least_complete_epics_first() {
+        let db = arrange_report();
+
+        let names = get_report_rows(&db.read_db().unwrap())
+            .into_iter()
+            .map(|row| row.name)
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, vec!["empty", "half", "done"]);
+    }
+
+    #[test]
+    fn report_should_end_with_totals() {
+        let db = arrange_report();
+        let mut out = Vec::new();
+
+        run_command(
+            Command::Report {
+                format: OutputFormat::Table,
+            },
+            &db,
+            &DisplayConfig::default(),
+            Verbosity::Normal,
+            &mut out,
+        )
+        .unwrap();
+
+        let output = String::from_utf8(out).unwrap();
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[1].starts_with("e "));
+        assert!(lines[1].ends_with("  0%"));
+        assert!(lines[4].starts_with("total"));
+        assert!(lines[4].ends_with("|    2 |     3 |  66%"));
+    }
+
+    #[test]
+    fn report_json_should_include_totals() {
+        let db = arrange_report();
+        let mut out = Vec::new();
+
+        run_command(
+            Command::Report {
+                format: OutputFormat::Json,
+            },
+            &db,
+            &DisplayConfig::default(),
+            Verbosity::Normal,
+            &mut out,
+        )
+        .unwrap();
+
+        let report: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(report["epics"][0]["percent"], 0);
+        assert_eq!(report["epics"][2]["percent"], 100);
+        assert_eq!(report["total"]["done"], 2);
+        assert_eq!(report["total"]["total"], 3);
+    }
+
+    #[test]
+    fn parse_args_should_parse_create_epic() {
+        assert_eq!(
+            parse_args(&args(&["create-epic", "Epic", "Details"])).unwrap(),
+            Some(Command::CreateEpic {
+                name: "Epic".to_owned(),
+                description: "Details".to_owned(),
+            })
+        );
+        assert!(parse_args(&args(&["create-epic"])).is_err());
+    }
+
+    #[test]
+    fn quiet_create_epic_should_only_print_the_id() {
+        let (db, _epic_id, _story_id) = arrange_test();
+        let mut out = Vec::new();
+
+        run_command(
+            Command::CreateEpic {
+                name: "Epic".to_owned(),
+                description: "".to_owned(),
+            },
+            &db,
+            &DisplayConfig::default(),
+            Verbosity::Quiet,
+            &mut out,
+        )
+        .unwrap();
+
+        let output = String::from_utf8(out).unwrap();
+        let epic_id = output.trim_end();
+        assert_eq!(output, format!("{}\n", epic_id));
+        assert!(db.read_db().unwrap().epics.contains_key(epic_id));
+    }
+
+    #[test]
+    fn quiet_gc_should_only_print_the_ids() {
+        let db = arrange_orphan();
+        let mut out = Vec::new();
+
+        run_command(
+            Command::Gc { dry_run: false },
+            &db,
+            &DisplayConfig::default(),
+            Verbosity::Quiet,
+            &mut out,
+        )
+        .unwrap();
+
+        assert_eq!(String::from_utf8(out).unwrap(), "orphan\n");
+    }
+}
@@ -0,0 +1,451 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::db::{DEFAULT_STATUS_HISTORY_LIMIT, DEFAULT_UNDO_LIMIT};
+use crate::models::{ClosePolicy, DonePolicy};
+use crate::rules::WatchRule;
+use crate::ui::{
+    parse_display_timezone, AgeThresholds, DisplayConfig, Truncation, UrgencyThresholds,
+};
+
+// Looked up in the working directory, next to ./data
+pub const PROJECT_CONFIG_FILE: &str = ".jira_cli.json";
+
+// Environment variables and the config keys they set
+const ENV_KEYS: [(&str, &str); 20] = [
+    ("JIRA_CLI_DB", "db_path"),
+    ("JIRA_CLI_BACKUP_DIR", "backup_dir"),
+    ("JIRA_CLI_BACKUP_KEEP", "backup_keep"),
+    ("JIRA_CLI_CLOSE_POLICY", "close_policy"),
+    ("JIRA_CLI_TZ", "timezone"),
+    ("JIRA_CLI_IDLE_SECS", "idle_secs"),
+    ("JIRA_CLI_DUE_SOON_DAYS", "due_soon_days"),
+    ("JIRA_CLI_AGING_DAYS", "aging_days"),
+    ("JIRA_CLI_STALE_DAYS", "stale_days"),
+    ("JIRA_CLI_EPIC_TEMPLATE", "epic_template"),
+    ("JIRA_CLI_STATUS_HISTORY_LIMIT", "status_history_limit"),
+    ("JIRA_CLI_STORY_ID_LENGTH", "story_id_length"),
+    ("JIRA_CLI_USER", "user"),
+    ("JIRA_CLI_REQUIRE_BLOCKED_REASON", "require_blocked_reason"),
+    ("JIRA_CLI_DONE_POLICY", "done_policy"),
+    ("JIRA_CLI_UNDO_LIMIT", "undo_limit"),
+    ("JIRA_CLI_DB_URL", "db_url"),
+    ("JIRA_CLI_DB_TOKEN", "db_token"),
+    ("JIRA_CLI_TRUNCATION", "truncation"),
+    ("JIRA_CLI_SNAPSHOT_HISTORY", "snapshot_history"),
+];
+
+// Config keys whose values aren't strings, so environment and flag values for them
+// are read as JSON
+const JSON_KEYS: [&str; 12] = [
+    "backup_keep",
+    "idle_secs",
+    "due_soon_days",
+    "aging_days",
+    "stale_days",
+    "status_history_limit",
+    "projects",
+    "story_id_length",
+    "require_blocked_reason",
+    "undo_limit",
+    "watch_rules",
+    "saved_filters",
+];
+
+// Config keys set by one source, e.g. a config file or the environment
+pub type ConfigLayer = Map<String, Value>;
+
+// A layer together with where it came from, e.g. "project file .jira_cli.json"
+#[derive(Debug, PartialEq, Clone)]
+pub struct ConfigSource {
+    pub name: String,
+    pub layer: ConfigLayer,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub db_path: PathBuf,
+    // Backups are off unless a directory is given
+    pub backup_dir: Option<PathBuf>,
+    pub backup_keep: usize,
+    pub close_policy: ClosePolicy,
+    // IANA zone name, e.g. Europe/Madrid. Unset means UTC.
+    pub timezone: Option<String>,
+    // Reload the database after this many idle seconds. Unset or 0 means never.
+    pub idle_secs: Option<u64>,
+    pub due_soon_days: u32,
+    pub aging_days: u32,
+    pub stale_days: u32,
+    // Description given to new epics created without one, e.g.
+    // "Goal: ... | Scope: ... | Out of scope: ..."
+    pub epic_template: Option<String>,
+    // Status changes kept per story, at least 1
+    pub status_history_limit: usize,
+    // Project name to database path. When set, the TUI opens a read-only
+    // overview of all of them instead of db_path.
+    pub projects: BTreeMap<String, PathBuf>,
+    // Leading characters of story ids shown in story lists. Unset or 0 shows the full id.
+    pub story_id_length: Option<usize>,
+    // Name used by "assign to me". Falls back to $USER.
+    pub user: Option<String>,
+    // Refuse to block a story without a reason instead of leaving it blank
+    pub require_blocked_reason: bool,
+    // "strict" refuses to close stories with unchecked checklist items
+    pub done_policy: DonePolicy,
+    // Changes the TUI can undo, oldest dropped first. 0 turns undo off.
+    pub undo_limit: usize,
+    // File every written state is appended to, for `restore`. Unset keeps no history.
+    pub snapshot_history: Option<String>,
+    // Base URL of a shared database server, used instead of db_path when set.
+    // Needs a build with the http feature.
+    pub db_url: Option<String>,
+    // Bearer token for db_url
+    pub db_token: Option<String>,
+    // Items to watch for a status, see WatchRule. Empty means no rules.
+    pub watch_rules: Vec<WatchRule>,
+    // Name to story query, e.g. {"my bugs": "status=open assignee=ana"}, picked from
+    // the home page
+    pub saved_filters: BTreeMap<String, String>,
+    // Marks cells cut to fit a column: "dots" for "...", "ellipsis" for "…" or "none"
+    pub truncation: Truncation,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let due_soon = UrgencyThresholds::default();
+        let age = AgeThresholds::default();
+        Self {
+            db_path: get_default_db_path(),
+            backup_dir: None,
+            backup_keep: 5,
+            close_policy: ClosePolicy::default(),
+            timezone: None,
+            idle_secs: None,
+            due_soon_days: due_soon.due_soon_days as u32,
+            aging_days: age.aging_days as u32,
+            stale_days: age.stale_days as u32,
+            epic_template: None,
+            status_history_limit: DEFAULT_STATUS_HISTORY_LIMIT,
+            projects: BTreeMap::new(),
+            story_id_length: None,
+            user: None,
+            require_blocked_reason: false,
+            done_policy: DonePolicy::default(),
+            undo_limit: DEFAULT_UNDO_LIMIT,
+            snapshot_history: None,
+            db_url: None,
+            db_token: None,
+            watch_rules: Vec::new(),
+            saved_filters: BTreeMap::new(),
+            truncation: Truncation::default(),
+        }
+    }
+}
+
+impl Config {
+    // An unknown time zone is not fatal, so it comes back as a warning next to a UTC display
+    pub fn get_display_config(&self) -> (DisplayConfig, Option<String>) {
+        let (tz, warning) = match parse_display_timezone(self.timezone.as_deref()) {
+            Ok(tz) => (tz, None),
+            Err(error) => (
+                chrono_tz::Tz::UTC,
+                Some(format!("{}, showing times in UTC.", error)),
+            ),
+        };
+        let display = DisplayConfig {
+            tz,
+            due_soon: UrgencyThresholds {
+                due_soon_days: i64::from(self.due_soon_days),
+            },
+            age: AgeThresholds {
+                aging_days: i64::from(self.aging_days),
+                stale_days: i64::from(self.stale_days),
+            },
+            story_id_length: self.story_id_length,
+            truncation: self.truncation,
+        };
+        (display, warning)
+    }
+}
+
+// Reads the user file, the project file, the environment and the -c flags,
+// in the order they override each other. Missing files give empty layers.
+pub fn load_config_sources(flags: &[(String, String)]) -> Result<Vec<ConfigSource>> {
+    let mut sources = Vec::new();
+    if let Some(path) = get_user_config_path() {
+        sources.push(ConfigSource {
+            name: format!("user file {}", path.display()),
+            layer: read_config_file(&path)?,
+        });
+    }
+    sources.push(ConfigSource {
+        name: format!("project file {}", PROJECT_CONFIG_FILE),
+        layer: read_config_file(Path::new(PROJECT_CONFIG_FILE))?,
+    });
+    sources.push(ConfigSource {
+        name: "environment".to_owned(),
+        layer: get_env_layer(),
+    });
+    sources.push(ConfigSource {
+        name: "command line".to_owned(),
+        layer: get_flag_layer(flags),
+    });
+    Ok(sources)
+}
+
+pub fn merge_config_sources(sources: &[ConfigSource]) -> Result<Config> {
+    merge_config_layers(sources.iter().map(|source| source.layer.clone()).collect())
+}
+
+// Later layers win key by key; keys no layer sets keep their defaults
+pub fn merge_config_layers(layers: Vec<ConfigLayer>) -> Result<Config> {
+    let mut merged = ConfigLayer::new();
+    for layer in layers {
+        merged.extend(layer);
+    }
+
+    let mut config: Config = serde_json::from_value(Value::Object(merged))
+        .map_err(|error| anyhow!("Invalid configuration: {}.", error))?;
+
+    // Out of range values are clamped, contradictory ones are rejected
+    config.backup_keep = config.backup_keep.max(1);
+    config.status_history_limit = config.status_history_limit.max(1);
+    config.idle_secs = config.idle_secs.filter(|secs| *secs > 0);
+    config.story_id_length = config.story_id_length.filter(|length| *length > 0);
+    if config.aging_days > config.stale_days {
+        return Err(anyhow!(
+            "Invalid configuration: aging_days ({}) is greater than stale_days ({}).",
+            config.aging_days,
+            config.stale_days
+        ));
+    }
+
+    Ok(config)
+}
+
+// $XDG_CONFIG_HOME/jira_cli/config.json, falling back to ~/.config
+fn get_user_config_path() -> Option<PathBuf> {
+    Some(get_user_config_dir()?.join("jira_cli").join("config.json"))
+}
+
+// %APPDATA%, e.g. C:\Users\ana\AppData\Roaming
+#[cfg(windows)]
+fn get_user_config_dir() -> Option<PathBuf> {
+    std::env::var_os("APPDATA").map(PathBuf::from)
+}
+
+#[cfg(not(windows))]
+fn get_user_config_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+}
+
+// $XDG_DATA_HOME/jira_cli/db.json, falling back to ~/.local/share, or ./data/db.json
+// when neither is known
+pub fn get_default_db_path() -> PathBuf {
+    get_user_data_dir()
+        .map(|dir| dir.join("jira_cli").join("db.json"))
+        .unwrap_or_else(|| Path::new(".").join("data").join("db.json"))
+}
+
+// %APPDATA% as well, next to the config
+#[cfg(windows)]
+fn get_user_data_dir() -> Option<PathBuf> {
+    std::env::var_os("APPDATA").map(PathBuf::from)
+}
+
+#[cfg(not(windows))]
+fn get_user_data_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share"))
+        })
+}
+
+fn read_config_file(path: &Path) -> Result<ConfigLayer> {
+    if !path.exists() {
+        return Ok(ConfigLayer::new());
+    }
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config file {}.", path.display()))?;
+    serde_json::from_str(&contents)
+        .with_context(|| format!("Config file {} is not a JSON object.", path.display()))
+}
+
+fn get_env_layer() -> ConfigLayer {
+    ENV_KEYS
+        .iter()
+        .filter_map(|(var, key)| {
+            let value = std::env::var(var).ok()?;
+            Some((key.to_string(), parse_raw_value(key, &value)))
+        })
+        .collect()
+}
+
+fn get_flag_layer(flags: &[(String, String)]) -> ConfigLayer {
+    flags
+        .iter()
+        .map(|(key, value)| (key.clone(), parse_raw_value(key, value)))
+        .collect()
+}
+
+// Environment and flag values are plain strings. For JSON_KEYS "5" is read as a number
+// and anything that isn't JSON stays a string. Other keys always keep the string, so
+// a user named "007" or a db_path of "null" comes through as given.
+fn parse_raw_value(key: &str, raw: &str) -> Value {
+    if !JSON_KEYS.contains(&key) {
+        return Value::String(raw.to_owned());
+    }
+    serde_json::from_str(raw.trim()).unwrap_or_else(|_| Value::String(raw.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layer(json: &str) -> ConfigLayer {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn merge_config_layers_should_default_missing_keys() {
+        assert_eq!(merge_config_layers(vec![]).unwrap(), Config::default());
+    }
+
+    #[test]
+    fn flags_should_override_files_and_environment() {
+        let user_file = layer(r#"{ "due_soon_days": 5, "backup_keep": 9 }"#);
+        let project_file = layer(r#"{ "due_soon_days": 4, "timezone": "Asia/Tokyo" }"#);
+        let env = layer(r#"{ "timezone": "Europe/Madrid" }"#);
+        let flags = get_flag_layer(&[("due_soon_days".to_owned(), "1".to_owned())]);
+
+        let config = merge_config_layers(vec![user_file, project_file, env, flags]).unwrap();
+
+        assert_eq!(config.due_soon_days, 1);
+        assert_eq!(config.timezone.as_deref(), Some("Europe/Madrid"));
+        assert_eq!(config.backup_keep, 9);
+    }
+
+    #[test]
+    fn flags_should_keep_numeric_looking_strings_as_strings() {
+        let flags = get_flag_layer(&[
+            ("user".to_owned(), "007".to_owned()),
+            ("db_path".to_owned(), "2024".to_owned()),
+            ("db_token".to_owned(), "null".to_owned()),
+            ("undo_limit".to_owned(), "3".to_owned()),
+        ]);
+
+        let config = merge_config_layers(vec![flags]).unwrap();
+
+        assert_eq!(config.user.as_deref(), Some("007"));
+        assert_eq!(config.db_path, PathBuf::from("2024"));
+        assert_eq!(config.db_token.as_deref(), Some("null"));
+        assert_eq!(config.undo_limit, 3);
+    }
+
+    #[test]
+    fn merge_config_layers_should_reject_invalid_values() {
+        assert!(merge_config_layers(vec![layer(r#"{ "close_policy": "maybe" }"#)]).is_err());
+        assert!(merge_config_layers(vec![layer(r#"{ "due_soon_days": -1 }"#)]).is_err());
+        assert!(merge_config_layers(vec![layer(r#"{ "unknown_key": 1 }"#)]).is_err());
+        assert!(
+            merge_config_layers(vec![layer(r#"{ "aging_days": 9, "stale_days": 3 }"#)]).is_err()
+        );
+    }
+
+    #[test]
+    fn merge_config_layers_should_clamp_out_of_range_values() {
+        let config =
+            merge_config_layers(vec![layer(r#"{ "backup_keep": 0, "idle_secs": 0 }"#)]).unwrap();
+
+        assert_eq!(config.backup_keep, 1);
+        assert_eq!(config.idle_secs, None);
+    }
+
+    #[test]
+    fn merge_config_layers_should_read_the_epic_template() {
+        let config = merge_config_layers(vec![layer(
+            r#"{ "epic_template": "Goal: | Scope: | Out of scope:" }"#,
+        )])
+        .unwrap();
+
+        assert_eq!(
+            config.epic_template.as_deref(),
+            Some("Goal: | Scope: | Out of scope:")
+        );
+    }
+
+    #[test]
+    fn merge_config_layers_should_read_the_truncation_indicator() {
+        let config = merge_config_layers(vec![layer(r#"{ "truncation": "ellipsis" }"#)]).unwrap();
+
+        assert_eq!(
+            config.get_display_config().0.truncation,
+            Truncation::Ellipsis
+        );
+        assert!(merge_config_layers(vec![layer(r#"{ "truncation": "stars" }"#)]).is_err());
+    }
+
+    #[test]
+    fn merge_config_layers_should_read_projects() {
+        let config = merge_config_layers(vec![layer(
+            r#"{ "projects": { "work": "./work.json", "home": "./home.json" } }"#,
+        )])
+        .unwrap();
+
+        assert_eq!(
+            config.projects.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("home".to_owned(), PathBuf::from("./home.json")),
+                ("work".to_owned(), PathBuf::from("./work.json")),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_raw_value_should_keep_non_json_as_strings() {
+        assert_eq!(parse_raw_value("due_soon_days", "5"), Value::from(5));
+        assert_eq!(
+            parse_raw_value("due_soon_days", "soon"),
+            Value::from("soon")
+        );
+        assert_eq!(parse_raw_value("close_policy", "warn"), Value::from("warn"));
+        assert_eq!(
+            parse_raw_value("timezone", "Europe/Madrid"),
+            Value::from("Europe/Madrid")
+        );
+    }
+
+    #[test]
+    fn merge_config_sources_should_let_the_command_line_win() {
+        let sources = vec![
+            ConfigSource {
+                name: "environment".to_owned(),
+                layer: layer(r#"{ "db_path": "./env.json" }"#),
+            },
+            ConfigSource {
+                name: "command line".to_owned(),
+                layer: get_flag_layer(&[("db_path".to_owned(), "./flag.json".to_owned())]),
+            },
+        ];
+
+        let config = merge_config_sources(&sources).unwrap();
+
+        assert_eq!(config.db_path, PathBuf::from("./flag.json"));
+    }
+
+    #[test]
+    fn read_config_file_should_skip_missing_files() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let layer = read_config_file(&dir.path().join("missing.json")).unwrap();
+
+        assert!(layer.is_empty());
+    }
+}
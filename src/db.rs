@@ -1,22 +1,308 @@
 use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
 
-use crate::models::{DBState, Epic, Status, Story};
+use crate::models::{
+    AssigneeStats, Attachment, BurndownPoint, ChecklistItem, ClosePolicy, DBState, DonePolicy,
+    Epic, FlowMetrics, GcReport, Health, IdMatch, ImportSummary, ImportedEpic, ImportedStory,
+    IntegrityProblem, ItemRef, MergeMode, SearchResult, SplitOriginal, Status, StatusChange,
+    StatusEvent, Story, UNASSIGNED,
+};
+use crate::ordering::{compare_keys, get_moved_keys};
 
 use nanoid::nanoid;
 
 pub trait Database {
     fn read_db(&self) -> Result<DBState, anyhow::Error>;
     fn write_db(&self, db_state: &DBState) -> Result<()>;
+
+    // What the backend can do, so callers can pick a code path without downcasting
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::default()
+    }
+
+    // Backends with a full-text index can answer searches themselves instead of
+    // JiraDatabase scanning every epic and story
+    fn supports_fts(&self) -> bool {
+        self.capabilities().searchable
+    }
+
+    // Where the data lives on disk, for backends stored in a single file
+    fn file_path(&self) -> Option<&Path> {
+        None
+    }
+
+    // Shown by `jira_cli info`
+    fn backend_name(&self) -> String {
+        "custom".to_owned()
+    }
+
+    fn search_fts(&self, _query: &str) -> Result<Vec<SearchResult>> {
+        Err(anyhow::anyhow!(
+            "Full-text search is not supported by this database."
+        ))
+    }
+}
+
+// What JiraDatabase methods fail with, so callers can tell a missing item from a
+// broken file. anyhow's blanket From impl still lets the app code use `?`.
+#[derive(Debug)]
+pub enum DbError {
+    EpicNotFound(String),
+    StoryNotFound(String),
+    Io(std::io::Error),
+    Serde(serde_json::Error),
+    // The request itself doesn't make sense, e.g. merging an epic into itself
+    Validation(String),
+    // The file was written by someone else since it was read. Reload and retry.
+    Conflict { expected: u64, found: u64 },
+    // Any other failure reported by the Database backend
+    Backend(anyhow::Error),
+}
+
+pub type DbResult<T> = std::result::Result<T, DbError>;
+
+impl std::fmt::Display for DbError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DbError::EpicNotFound(epic_id) => write!(f, "Epic with id {} does not exist.", epic_id),
+            DbError::StoryNotFound(story_id) => {
+                write!(f, "Story with id {} does not exist.", story_id)
+            }
+            DbError::Io(error) => write!(f, "I/O error: {}", error),
+            DbError::Serde(error) => write!(f, "Invalid database JSON: {}", error),
+            DbError::Validation(message) => write!(f, "{}", message),
+            DbError::Conflict { expected, found } => write!(
+                f,
+                "The database changed on disk (revision {}, expected {}). Reload and try again.",
+                found, expected
+            ),
+            DbError::Backend(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for DbError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DbError::Io(error) => Some(error),
+            DbError::Serde(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for DbError {
+    fn from(error: std::io::Error) -> Self {
+        DbError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for DbError {
+    fn from(error: serde_json::Error) -> Self {
+        DbError::Serde(error)
+    }
+}
+
+// Backends report anyhow errors. IO and JSON failures, and a DbError the backend
+// raised itself, are picked out of them so callers can still match on those.
+impl From<anyhow::Error> for DbError {
+    fn from(error: anyhow::Error) -> Self {
+        let error = match error.downcast::<DbError>() {
+            Ok(error) => return error,
+            Err(error) => error,
+        };
+        let error = match error.downcast::<std::io::Error>() {
+            Ok(error) => return DbError::Io(error),
+            Err(error) => error,
+        };
+        match error.downcast::<serde_json::Error>() {
+            Ok(error) => DbError::Serde(error),
+            Err(error) => DbError::Backend(error),
+        }
+    }
+}
+
+// Defaults to what the JSON file backend offers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    // Several writes can be committed or rolled back together
+    pub transactional: bool,
+    // Has its own full-text index, see Database::search_fts
+    pub searchable: bool,
+    pub writable: bool,
+    // Can hand out items without loading the whole state
+    pub streaming: bool,
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Self {
+            transactional: false,
+            searchable: false,
+            writable: true,
+            streaming: false,
+        }
+    }
+}
+
+// Copy the current file into `dir` before each write, keeping the newest `keep` copies
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupPolicy {
+    pub dir: PathBuf,
+    pub keep: usize,
 }
 
 struct JSONFileDatabase {
-    pub file_path: String,
+    pub file_path: PathBuf,
+    pub backup: Option<BackupPolicy>,
+    // Stamps the backup names
+    pub clock: Box<dyn Clock>,
+}
+
+impl JSONFileDatabase {
+    fn backup_current_file(&self, policy: &BackupPolicy) -> Result<()> {
+        let file_path = self.file_path.as_path();
+        // Nothing to back up before the very first write
+        if !file_path.exists() {
+            return Ok(());
+        }
+
+        std::fs::create_dir_all(&policy.dir)
+            .with_context(|| format!("Failed to create backup directory {:?}.", policy.dir))?;
+
+        // Timestamped names sort chronologically. Compressed files are copied as they are.
+        let prefix = get_backup_prefix(file_path);
+        let extension = if self.is_compressed() {
+            ".json.gz"
+        } else {
+            ".json"
+        };
+        let backup_name = format!(
+            "{}{}{}",
+            prefix,
+            self.clock.now().format("%Y%m%dT%H%M%S%.9fZ"),
+            extension
+        );
+        std::fs::copy(file_path, policy.dir.join(backup_name))
+            .with_context(|| "Failed to back up database file.")?;
+
+        // Prune the oldest backups beyond the retention limit
+        let mut backups = std::fs::read_dir(&policy.dir)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|name| name.starts_with(&prefix) && name.ends_with(extension))
+            .collect::<Vec<String>>();
+        backups.sort();
+        let excess = backups.len().saturating_sub(policy.keep);
+        for name in backups.iter().take(excess) {
+            std::fs::remove_file(policy.dir.join(name))?;
+        }
+
+        Ok(())
+    }
+}
+
+// Just the revision of a database file, without the epics and stories
+#[derive(Deserialize)]
+struct RevisionOnly {
+    #[serde(default)]
+    revision: u64,
+}
+
+impl JSONFileDatabase {
+    // A path ending in .json.gz is stored gzip-compressed
+    fn is_compressed(&self) -> bool {
+        self.file_path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().ends_with(".json.gz"))
+    }
+
+    fn read_contents(&self) -> std::io::Result<String> {
+        if !self.is_compressed() {
+            return std::fs::read_to_string(&self.file_path);
+        }
+        let mut file_contents = String::new();
+        GzDecoder::new(File::open(&self.file_path)?).read_to_string(&mut file_contents)?;
+        Ok(file_contents)
+    }
+
+    fn write_contents(&self, file_contents: &str) -> std::io::Result<()> {
+        if !self.is_compressed() {
+            return std::fs::write(&self.file_path, file_contents);
+        }
+        let mut encoder = GzEncoder::new(File::create(&self.file_path)?, Compression::default());
+        encoder.write_all(file_contents.as_bytes())?;
+        encoder.finish()?;
+        Ok(())
+    }
+
+    // The revision on disk, 0 before the first write
+    fn read_revision(&self) -> Result<u64> {
+        if !self.file_path.exists() {
+            return Ok(0);
+        }
+        let file_contents = self
+            .read_contents()
+            .with_context(|| "Failed to read from file system.")?;
+        let revision: RevisionOnly = serde_json::from_str(&file_contents)
+            .with_context(|| "Failed to write current state to memory.")?;
+        Ok(revision.revision)
+    }
+}
+
+// Writes an empty database, and the directories above it, unless the file is
+// already there. Reading a missing file is an error, so first runs start here.
+pub fn create_db_file_if_missing(file_path: &Path) -> Result<()> {
+    if file_path.exists() {
+        return Ok(());
+    }
+    if let Some(parent) = file_path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}.", parent.display()))?;
+    }
+    let database = JSONFileDatabase {
+        file_path: file_path.to_path_buf(),
+        backup: None,
+        clock: Box::new(SystemClock),
+    };
+    database.write_db(&DBState {
+        epics: HashMap::new(),
+        stories: HashMap::new(),
+        last_item_id: "0".to_owned(),
+        revision: 0,
+    })
+}
+
+fn get_backup_prefix(file_path: &Path) -> String {
+    // db.json.gz backs up as db-<timestamp>.json.gz, like db.json does
+    let file_path = match file_path.extension() {
+        Some(extension) if extension == "gz" => file_path.with_extension(""),
+        _ => file_path.to_path_buf(),
+    };
+    let stem = file_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "db".to_owned());
+    format!("{}-", stem)
 }
 
 impl Database for JSONFileDatabase {
     fn read_db(&self) -> Result<DBState, anyhow::Error> {
         // Read the file
-        let file_contents = std::fs::read_to_string(&self.file_path)
+        let file_contents = self
+            .read_contents()
             .with_context(|| format!("Failed to read from file system."))?;
         // Deserialize the file contents into a DBState
         let db_state: DBState = serde_json::from_str(&file_contents)
@@ -25,59 +311,377 @@ impl Database for JSONFileDatabase {
         Ok(db_state)
     }
 
+    fn file_path(&self) -> Option<&Path> {
+        Some(&self.file_path)
+    }
+
+    fn backend_name(&self) -> String {
+        if self.is_compressed() {
+            "gzip-compressed JSON file".to_owned()
+        } else {
+            "JSON file".to_owned()
+        }
+    }
+
     fn write_db(&self, db_state: &DBState) -> Result<(), anyhow::Error> {
-        // Serialize db_state to json and store it in self.file_path
+        // Refuse to overwrite changes made since db_state was read
+        let found = self.read_revision()?;
+        if found != db_state.revision {
+            return Err(DbError::Conflict {
+                expected: db_state.revision,
+                found,
+            }
+            .into());
+        }
+        // Keep a copy of the current file first, if backups are enabled
+        if let Some(policy) = &self.backup {
+            self.backup_current_file(policy)?;
+        }
+        // Serialize db_state to json under the next revision and store it in self.file_path
+        let db_state = DBState {
+            revision: db_state.revision + 1,
+            ..db_state.clone()
+        };
         let file_contents = serde_json::to_string_pretty(&db_state)
             .with_context(|| "Failed to write current state to memory.")?;
         // Write to file
-        std::fs::write(&self.file_path, file_contents).map_err(|e| e.into())
+        self.write_contents(&file_contents).map_err(|e| e.into())
+    }
+}
+
+// Read-only view over several project databases. Ids are namespaced as
+// `project/id` so the same id in two projects doesn't collide.
+pub struct AggregateDatabase {
+    pub sources: Vec<(String, Box<dyn Database>)>,
+}
+
+impl Database for AggregateDatabase {
+    fn read_db(&self) -> Result<DBState> {
+        let mut merged = DBState {
+            epics: HashMap::new(),
+            stories: HashMap::new(),
+            last_item_id: String::new(),
+            revision: 0,
+        };
+        for (project, source) in &self.sources {
+            let db_state = source
+                .read_db()
+                .with_context(|| format!("Failed to read project {}.", project))?;
+            let namespace = |id: &String| format!("{}/{}", project, id);
+            for (epic_id, mut epic) in db_state.epics {
+                epic.stories = epic.stories.iter().map(namespace).collect();
+                merged.epics.insert(namespace(&epic_id), epic);
+            }
+            for (story_id, mut story) in db_state.stories {
+                story.parent = story.parent.as_ref().map(namespace);
+                merged.stories.insert(namespace(&story_id), story);
+            }
+        }
+        Ok(merged)
+    }
+
+    fn write_db(&self, _db_state: &DBState) -> Result<()> {
+        Err(anyhow::anyhow!(
+            "The combined view of all projects is read-only. Open a single project's database to make changes."
+        ))
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            writable: false,
+            ..Capabilities::default()
+        }
+    }
+
+    fn backend_name(&self) -> String {
+        format!("read-only overview of {} projects", self.sources.len())
+    }
+}
+
+pub trait Clock {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
     }
 }
 
 pub struct JiraDatabase {
     pub database: Box<dyn Database>,
+    pub clock: Box<dyn Clock>,
+    pub close_policy: ClosePolicy,
+    // Used as the description of new epics created without one
+    pub epic_template: Option<String>,
+    // How many status changes each story keeps
+    pub status_history_limit: usize,
+    // Refuse to block a story without saying why
+    pub require_blocked_reason: bool,
+    // Under DonePolicy::Strict, stories only close once their checklist is complete
+    pub done_policy: DonePolicy,
+    // How many changes undo can step back through, 0 turns undo off
+    pub undo_limit: usize,
+    history: RefCell<UndoHistory>,
+    // Serve reads from memory until the next change or invalidate_cache. Only worth it
+    // in the TUI, which reads on every draw and keypress.
+    pub cache_reads: bool,
+    cache: RefCell<Option<DBState>>,
+    // Every written state is appended here with the time of the write, for
+    // restore_snapshot_at. None keeps no history.
+    pub snapshot_history: Option<PathBuf>,
+}
+
+// One line of the snapshot history
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    at: DateTime<Utc>,
+    state: DBState,
+}
+
+pub const DEFAULT_STATUS_HISTORY_LIMIT: usize = 50;
+
+pub const DEFAULT_UNDO_LIMIT: usize = 20;
+
+// Name of the epic quick_capture files stories under. It's found by name, so
+// renaming it makes the next capture start a new one.
+pub const INBOX_EPIC_NAME: &str = "Inbox";
+
+// Whole-database snapshots taken before each change, newest last
+#[derive(Default)]
+struct UndoHistory {
+    undo: VecDeque<DBState>,
+    redo: VecDeque<DBState>,
+}
+
+// Drops the oldest snapshots past `limit`
+fn push_bounded(stack: &mut VecDeque<DBState>, db_state: DBState, limit: usize) {
+    stack.push_back(db_state);
+    while stack.len() > limit {
+        stack.pop_front();
+    }
 }
 
 impl JiraDatabase {
-    pub fn new(file_path: String) -> Self {
+    pub fn new(file_path: PathBuf) -> Self {
+        Self::with_database(Box::new(JSONFileDatabase {
+            file_path,
+            backup: None,
+            clock: Box::new(SystemClock),
+        }))
+    }
+
+    pub fn with_backups(file_path: PathBuf, backup: BackupPolicy) -> Self {
+        Self::with_database(Box::new(JSONFileDatabase {
+            file_path,
+            backup: Some(backup),
+            clock: Box::new(SystemClock),
+        }))
+    }
+
+    // Read-only overview of several projects, given as (project name, file path)
+    pub fn aggregate(projects: Vec<(String, PathBuf)>) -> Self {
+        let sources = projects
+            .into_iter()
+            .map(|(project, file_path)| {
+                let source: Box<dyn Database> = Box::new(JSONFileDatabase {
+                    file_path,
+                    backup: None,
+                    clock: Box::new(SystemClock),
+                });
+                (project, source)
+            })
+            .collect();
+        Self::with_database(Box::new(AggregateDatabase { sources }))
+    }
+
+    pub fn with_database(database: Box<dyn Database>) -> Self {
         Self {
-            database: Box::new(JSONFileDatabase { file_path }),
+            database,
+            clock: Box::new(SystemClock),
+            close_policy: ClosePolicy::default(),
+            epic_template: None,
+            status_history_limit: DEFAULT_STATUS_HISTORY_LIMIT,
+            require_blocked_reason: false,
+            done_policy: DonePolicy::default(),
+            undo_limit: DEFAULT_UNDO_LIMIT,
+            history: RefCell::default(),
+            cache_reads: false,
+            cache: RefCell::default(),
+            snapshot_history: None,
+        }
+    }
+
+    pub fn read_db(&self) -> DbResult<DBState> {
+        if !self.cache_reads {
+            return Ok(self.database.read_db()?);
+        }
+        if let Some(db_state) = &*self.cache.borrow() {
+            return Ok(db_state.clone());
+        }
+        let db_state = self.database.read_db()?;
+        *self.cache.borrow_mut() = Some(db_state.clone());
+        Ok(db_state)
+    }
+
+    // Makes the next read go to the backend, e.g. to pick up changes made elsewhere
+    pub fn invalidate_cache(&self) {
+        self.cache.borrow_mut().take();
+    }
+
+    // The only place the backend is written, so no change can leave a stale cache
+    // behind. The backend bumps the revision, so the next read fetches the result
+    // rather than caching what was passed in. A failed write may still have
+    // changed the file, so it invalidates too.
+    fn write_through(&self, db_state: &DBState) -> DbResult<()> {
+        self.invalidate_cache();
+        self.database.write_db(db_state)?;
+        self.append_snapshot(db_state)
+    }
+
+    // Whole states, one JSON line each, never rotated. Much heavier than backups.
+    fn append_snapshot(&self, db_state: &DBState) -> DbResult<()> {
+        let Some(path) = &self.snapshot_history else {
+            return Ok(());
+        };
+        let line = serde_json::to_string(&Snapshot {
+            at: self.clock.now(),
+            state: db_state.clone(),
+        })?;
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        writeln!(file, "{}", line)?;
+        Ok(())
+    }
+
+    // Writes back the newest snapshot taken at or before `at`, as a change that can be
+    // undone. Returns when that snapshot was taken.
+    pub fn restore_snapshot_at(&self, at: DateTime<Utc>) -> DbResult<DateTime<Utc>> {
+        let Some(path) = &self.snapshot_history else {
+            return Err(DbError::Validation(
+                "No snapshot history is configured.".to_owned(),
+            ));
+        };
+        let contents = std::fs::read_to_string(path)?;
+        let mut found: Option<Snapshot> = None;
+        for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+            let snapshot: Snapshot = serde_json::from_str(line)?;
+            if snapshot.at <= at && found.as_ref().is_none_or(|found| found.at <= snapshot.at) {
+                found = Some(snapshot);
+            }
         }
+        let snapshot = found
+            .ok_or_else(|| DbError::Validation(format!("No snapshot at or before {}.", at)))?;
+        // Under the current revision so the write isn't taken for a conflict
+        let current = self.read_db()?;
+        self.write_db(&DBState {
+            revision: current.revision,
+            ..snapshot.state
+        })?;
+        Ok(snapshot.at)
+    }
+
+    // Every change goes through here so it can be undone. A new change also
+    // discards whatever was undone before it.
+    fn write_db(&self, db_state: &DBState) -> DbResult<()> {
+        let previous = self.read_db()?;
+        self.write_through(db_state)?;
+        let history = &mut *self.history.borrow_mut();
+        push_bounded(&mut history.undo, previous, self.undo_limit);
+        history.redo.clear();
+        Ok(())
+    }
+
+    // Puts back the state before the last change. False when there is nothing to undo.
+    pub fn undo(&self) -> DbResult<bool> {
+        let history = &mut *self.history.borrow_mut();
+        self.restore_snapshot(&mut history.undo, &mut history.redo)
+    }
+
+    // Reapplies the last undone change. False when there is nothing to redo.
+    pub fn redo(&self) -> DbResult<bool> {
+        let history = &mut *self.history.borrow_mut();
+        self.restore_snapshot(&mut history.redo, &mut history.undo)
+    }
+
+    // Writes the newest snapshot of `from`, keeping the state it replaces in `to`
+    fn restore_snapshot(
+        &self,
+        from: &mut VecDeque<DBState>,
+        to: &mut VecDeque<DBState>,
+    ) -> DbResult<bool> {
+        let Some(snapshot) = from.back() else {
+            return Ok(false);
+        };
+        let current = self.read_db()?;
+        // The snapshot carries the current revision so the write isn't taken for a conflict
+        self.write_through(&DBState {
+            revision: current.revision,
+            ..snapshot.clone()
+        })?;
+        from.pop_back();
+        push_bounded(to, current, self.undo_limit);
+        Ok(true)
     }
 
-    pub fn read_db(&self) -> Result<DBState> {
-        self.database.read_db()
+    pub fn create_epic(&self, epic: Epic) -> DbResult<String> {
+        Ok(self.create_epic_returning(epic)?.0)
     }
 
-    pub fn create_epic(&self, epic: Epic) -> Result<String> {
+    // Like create_epic, but also hands back the epic as stored, timestamps and all
+    pub fn create_epic_returning(&self, epic: Epic) -> DbResult<(String, Epic)> {
         // Grab a mutable reference to the database
         let mut db_state = self.read_db()?;
-        // Create a new epic
-        let epic = Epic::new(epic.name, epic.description);
+        // Create a new epic, starting from the template when no description was given
+        let description = match &self.epic_template {
+            Some(template) if epic.description.trim().is_empty() => template.clone(),
+            _ => epic.description,
+        };
+        let mut epic = Epic::new(epic.name, description);
+        // Stamp creation time
+        let now = self.clock.now();
+        epic.created_at = Some(now);
+        epic.updated_at = Some(now);
         // Generate a new id
         let id = nanoid!(6);
         // Add the epic to the database
-        db_state.epics.insert(id.clone(), epic);
+        db_state.epics.insert(id.clone(), epic.clone());
         // Add last_item_id to the database
         db_state.last_item_id = id.clone();
         // Write the database to disk
-        self.database.write_db(&db_state)?;
-        // Return the id of the new epic
-        Ok(id)
+        self.write_db(&db_state)?;
+        // Return the id of the new epic with the epic itself
+        Ok((id, epic))
+    }
+
+    pub fn create_story(&self, story: Story, epic_id: &String) -> DbResult<String> {
+        Ok(self.create_story_returning(story, epic_id)?.0)
     }
 
-    pub fn create_story(&self, story: Story, epic_id: &String) -> Result<String, anyhow::Error> {
+    // Like create_story, but also hands back the story as stored, timestamps and all
+    pub fn create_story_returning(
+        &self,
+        story: Story,
+        epic_id: &String,
+    ) -> DbResult<(String, Story)> {
         // Grab a mutable reference to the database
-        let mut db_state = self
-            .read_db()
-            .with_context(|| format!("Failed to read database when creating story."))?;
+        let mut db_state = self.read_db()?;
 
         // Create a new story
-        let story = Story::new(story.name, story.description);
+        let mut story = Story::new(story.name, story.description);
+
+        // Stamp creation time
+        let now = self.clock.now();
+        story.created_at = Some(now);
+        story.updated_at = Some(now);
 
         // Check if the epic exists
         if !db_state.epics.contains_key(epic_id) {
-            return Err(anyhow::anyhow!("Epic with id {} does not exist.", epic_id));
+            return Err(DbError::EpicNotFound(epic_id.clone()));
         }
 
         // Generate story id
@@ -87,7 +691,7 @@ impl JiraDatabase {
         db_state.last_item_id = id.clone();
 
         // Add the story to the database
-        db_state.stories.insert(id.clone(), story);
+        db_state.stories.insert(id.clone(), story.clone());
 
         // Add story to corresponding epic
         db_state
@@ -96,22 +700,103 @@ impl JiraDatabase {
             .unwrap()
             .stories
             .push(id.clone());
+        apply_derived_epic_status(&mut db_state, epic_id, now);
 
         // Write the database to disk
-        self.database.write_db(&db_state)?;
+        self.write_db(&db_state)?;
+
+        // Return the id of the new story with the story itself
+        Ok((id, story))
+    }
 
+    // Files a new story under the Inbox epic, creating the epic on first use, in a single write
+    pub fn quick_capture(&self, name: String) -> DbResult<String> {
+        // Grab database
+        let mut db_state = self.read_db()?;
+        let now = self.clock.now();
+        // Reuse the first epic named Inbox, or start one
+        let inbox_id = db_state
+            .epics_sorted()
+            .into_iter()
+            .find(|(_, epic)| epic.name == INBOX_EPIC_NAME)
+            .map(|(epic_id, _)| epic_id.clone());
+        let inbox_id = match inbox_id {
+            Some(inbox_id) => inbox_id,
+            None => {
+                let mut inbox = Epic::new(
+                    INBOX_EPIC_NAME.to_owned(),
+                    "Quickly captured stories, waiting to be filed.".to_owned(),
+                );
+                inbox.created_at = Some(now);
+                inbox.updated_at = Some(now);
+                let inbox_id = nanoid!(6);
+                db_state.epics.insert(inbox_id.clone(), inbox);
+                inbox_id
+            }
+        };
+        // Create the story and add it to the inbox
+        let mut story = Story::new(name, "".to_owned());
+        story.created_at = Some(now);
+        story.updated_at = Some(now);
+        let story_id = nanoid!(6);
+        db_state.stories.insert(story_id.clone(), story);
+        db_state
+            .epics
+            .get_mut(&inbox_id)
+            .unwrap()
+            .stories
+            .push(story_id.clone());
+        db_state.last_item_id = story_id.clone();
+        apply_derived_epic_status(&mut db_state, &inbox_id, now);
+        // Write the database to disk
+        self.write_db(&db_state)?;
         // Return the id of the new story
-        Ok(id)
+        Ok(story_id)
+    }
+
+    // Adds an open story per name to the epic in a single write. No names means no write.
+    pub fn create_stories(&self, names: Vec<String>, epic_id: &String) -> DbResult<Vec<String>> {
+        if names.is_empty() {
+            return Ok(Vec::new());
+        }
+        // Grab database
+        let mut db_state = self.read_db()?;
+        if !db_state.epics.contains_key(epic_id) {
+            return Err(DbError::EpicNotFound(epic_id.clone()));
+        }
+        let now = self.clock.now();
+
+        let mut new_ids = Vec::new();
+        for name in names {
+            let mut story = Story::new(name, "".to_owned());
+            story.created_at = Some(now);
+            story.updated_at = Some(now);
+            let id = nanoid!(6);
+            db_state.stories.insert(id.clone(), story);
+            db_state
+                .epics
+                .get_mut(epic_id)
+                .unwrap()
+                .stories
+                .push(id.clone());
+            db_state.last_item_id = id.clone();
+            new_ids.push(id);
+        }
+        apply_derived_epic_status(&mut db_state, epic_id, now);
+
+        // Write the database to disk
+        self.write_db(&db_state)?;
+        Ok(new_ids)
     }
 
-    pub fn delete_epic(&self, epic_id: &String) -> Result<(), anyhow::Error> {
+    pub fn delete_epic(&self, epic_id: &String) -> DbResult<()> {
         // Grab a mutable reference to the database
-        let mut db_state = self.read_db().with_context(|| "Failed to read database.")?;
+        let mut db_state = self.read_db()?;
         // Grab a mutable reference to the epic
         let epic = db_state
             .epics
             .get_mut(epic_id)
-            .with_context(|| format!("Epic with id {} does not exist.", epic_id))?;
+            .ok_or_else(|| DbError::EpicNotFound(epic_id.clone()))?;
         // Delete all stories associated with the epic
         for story_id in epic.stories.iter() {
             db_state.stories.remove(story_id);
@@ -121,362 +806,5115 @@ impl JiraDatabase {
         // Set epic ID as the last item id
         db_state.last_item_id = epic_id.to_string();
         // Write the database to disk
-        self.database.write_db(&db_state)?;
+        self.write_db(&db_state)?;
+        // Return Ok
+        Ok(())
+    }
+
+    pub fn merge_epics(&self, source_id: &String, target_id: &String) -> DbResult<()> {
+        if source_id == target_id {
+            return Err(DbError::Validation(
+                "An epic cannot be merged into itself.".to_owned(),
+            ));
+        }
+        // Grab database
+        let mut db_state = self.read_db()?;
+        // Take the source out; nothing is written unless the target exists too
+        let source = db_state
+            .epics
+            .remove(source_id)
+            .ok_or_else(|| DbError::EpicNotFound(source_id.clone()))?;
+        let target = db_state
+            .epics
+            .get_mut(target_id)
+            .ok_or_else(|| DbError::EpicNotFound(target_id.clone()))?;
+        // Keep the target's metadata, appending the source's stories and description
+        target.stories.extend(source.stories);
+        if !source.description.is_empty() {
+            target.description = if target.description.is_empty() {
+                source.description
+            } else {
+                format!("{} {}", target.description, source.description)
+            };
+        }
+        target.updated_at = Some(self.clock.now());
+        apply_derived_epic_status(&mut db_state, target_id, self.clock.now());
+        // Write the database to disk once
+        self.write_db(&db_state)?;
+        // Return Ok
+        Ok(())
+    }
+
+    // Subtask links don't cross epics, so the story leaves its parent and its subtasks
+    // stay behind without it
+    pub fn move_story(&self, story_id: &String, target_id: &String) -> DbResult<()> {
+        // Grab database
+        let mut db_state = self.read_db()?;
+        if !db_state.epics.contains_key(target_id) {
+            return Err(DbError::EpicNotFound(target_id.clone()));
+        }
+        let source_id = db_state
+            .find_epic_of_story(story_id)
+            .cloned()
+            .ok_or_else(|| DbError::StoryNotFound(story_id.clone()))?;
+        if &source_id == target_id {
+            return Err(DbError::Validation(format!(
+                "Story {} is already in epic {}.",
+                story_id, target_id
+            )));
+        }
+        relocate_story(
+            &mut db_state,
+            story_id,
+            &source_id,
+            target_id,
+            self.clock.now(),
+        );
+        drop_cross_epic_parents(&mut db_state);
+        // Write the database to disk once
+        self.write_db(&db_state)?;
         // Return Ok
         Ok(())
     }
 
-    pub fn delete_story(&self, epic_id: &String, story_id: &String) -> Result<()> {
+    // Moves every story the predicate accepts into the target epic with a single write
+    // and returns how many moved. Stories already in the target, or in no epic at all,
+    // stay where they are. Subtasks moving along with their parent keep the link.
+    pub fn move_stories_matching(
+        &self,
+        predicate: impl Fn(&str, &Story) -> bool,
+        target_id: &String,
+    ) -> DbResult<usize> {
+        // Grab database
+        let mut db_state = self.read_db()?;
+        if !db_state.epics.contains_key(target_id) {
+            return Err(DbError::EpicNotFound(target_id.clone()));
+        }
+        // Pair each matching story with the epic it leaves, in id order
+        let moves = db_state
+            .stories
+            .iter()
+            .filter(|(story_id, story)| predicate(story_id, story))
+            .filter_map(|(story_id, _)| {
+                let source_id = db_state.find_epic_of_story(story_id)?;
+                (source_id != target_id).then(|| (story_id.clone(), source_id.clone()))
+            })
+            .sorted()
+            .collect_vec();
+        // Nothing to write
+        if moves.is_empty() {
+            return Ok(0);
+        }
+        let now = self.clock.now();
+        for (story_id, source_id) in &moves {
+            relocate_story(&mut db_state, story_id, source_id, target_id, now);
+        }
+        drop_cross_epic_parents(&mut db_state);
+        // Write the database to disk once
+        self.write_db(&db_state)?;
+        Ok(moves.len())
+    }
+
+    // Moves the epic to `index` of the manual order, counting from 0. Past the end
+    // means last.
+    pub fn reorder_epic(&self, epic_id: &String, index: usize) -> DbResult<()> {
+        // Grab database
+        let mut db_state = self.read_db()?;
+        if !db_state.epics.contains_key(epic_id) {
+            return Err(DbError::EpicNotFound(epic_id.clone()));
+        }
+        let ordered = db_state
+            .epics
+            .iter()
+            .sorted_by(|a, b| {
+                compare_keys(a.1.order, b.1.order)
+                    .then_with(|| a.1.name.cmp(&b.1.name))
+                    .then_with(|| a.0.cmp(b.0))
+            })
+            .map(|(id, epic)| (id.clone(), epic.order))
+            .collect_vec();
+        for (id, key) in get_moved_keys(&ordered, epic_id, index) {
+            db_state.epics.get_mut(&id).unwrap().order = Some(key);
+        }
+        // Write the database to disk
+        self.write_db(&db_state)?;
+        Ok(())
+    }
+
+    // Moves the story to `index` of its epic's manual order, like reorder_epic
+    pub fn reorder_story(&self, story_id: &String, index: usize) -> DbResult<()> {
+        // Grab database
+        let mut db_state = self.read_db()?;
+        let epic_id = db_state
+            .find_epic_of_story(story_id)
+            .cloned()
+            .ok_or_else(|| DbError::StoryNotFound(story_id.clone()))?;
+        let ordered = db_state
+            .stories_of(&epic_id)
+            .unwrap_or_default()
+            .into_iter()
+            .sorted_by(|a, b| {
+                compare_keys(a.1.order, b.1.order)
+                    .then_with(|| a.1.name.cmp(&b.1.name))
+                    .then_with(|| a.0.cmp(b.0))
+            })
+            .map(|(id, story)| (id.clone(), story.order))
+            .collect_vec();
+        for (id, key) in get_moved_keys(&ordered, story_id, index) {
+            db_state.stories.get_mut(&id).unwrap().order = Some(key);
+        }
+        // Write the database to disk
+        self.write_db(&db_state)?;
+        Ok(())
+    }
+
+    pub fn delete_story(&self, epic_id: &String, story_id: &String) -> DbResult<()> {
         // Grab a mutable reference to the database
         let mut db_state = self.read_db()?;
 
         // Confirm that the story actually exists
         if !db_state.stories.contains_key(story_id) {
-            return Err(anyhow::anyhow!(
-                "Story with id {} does not exist.",
-                story_id
-            ));
+            return Err(DbError::StoryNotFound(story_id.clone()));
         }
 
         // Grab a mutable reference to the epic
         let epic = db_state
             .epics
             .get_mut(epic_id)
-            .with_context(|| format!("Epic with id {} does not exist.", epic_id))?;
+            .ok_or_else(|| DbError::EpicNotFound(epic_id.clone()))?;
 
         // Iterate over epic stories and remove the story
         epic.stories.retain(|id| id != story_id);
 
         // Find the corresponding story and remove it
         db_state.stories.remove(story_id);
+        detach_subtasks(&mut db_state, std::slice::from_ref(story_id));
+        apply_derived_epic_status(&mut db_state, epic_id, self.clock.now());
 
         // Set story ID as the last item id
         db_state.last_item_id = story_id.to_string();
 
         // Write the database to disk
-        self.database.write_db(&db_state)?;
+        self.write_db(&db_state)?;
 
         // Return Ok
         Ok(())
     }
 
-    pub fn update_epic_status(&self, epic_id: &String, status: Status) -> Result<()> {
+    // Returns the stories that were still open when the epic was closed under ClosePolicy::Warn
+    pub fn update_epic_status(&self, epic_id: &String, status: Status) -> DbResult<Vec<String>> {
         // Grab database
         let mut db_state = self.read_db()?;
+        if let Some(epic) = db_state.epics.get(epic_id) {
+            check_status_transition(epic_id, &epic.status, &status)?;
+        }
+        // Find stories that would be left open by resolving or closing the epic
+        let unfinished = match status {
+            Status::Resolved | Status::Closed => get_unfinished_story_ids(&db_state, epic_id),
+            Status::Open | Status::InProgress | Status::Blocked => Vec::new(),
+        };
+        let warnings = match self.close_policy {
+            ClosePolicy::Block if !unfinished.is_empty() => {
+                return Err(DbError::Validation(format!(
+                    "Epic {} still has unfinished stories: {}.",
+                    epic_id,
+                    unfinished.join(", ")
+                )));
+            }
+            ClosePolicy::Warn => unfinished,
+            _ => Vec::new(),
+        };
         // Grab a mutable reference to the epic
         let epic = db_state
             .epics
             .get_mut(epic_id)
-            .with_context(|| format!("Epic with id {} does not exist.", epic_id))?;
+            .ok_or_else(|| DbError::EpicNotFound(epic_id.clone()))?;
         // Update epic status
         epic.status = status;
+        epic.updated_at = Some(self.clock.now());
         // Write the database to disk
-        self.database.write_db(&db_state)?;
-        // Return Ok
-        Ok(())
+        self.write_db(&db_state)?;
+        // Return the stories worth warning about
+        Ok(warnings)
     }
 
-    pub fn update_story_status(&self, story_id: &String, status: Status) -> Result<()> {
+    // Blocking this way gives no reason, see block_story
+    pub fn update_story_status(&self, story_id: &String, status: Status) -> DbResult<()> {
+        if status == Status::Blocked {
+            return self.block_story(story_id, None);
+        }
         // Grab database
         let mut db_state = self.read_db()?;
+        self.check_definition_of_done(&db_state, std::slice::from_ref(story_id), &status)?;
         // Grab a mutable reference to the epic
         let story = db_state
             .stories
             .get_mut(story_id)
-            .with_context(|| format!("Story with id {} does not exist.", story_id))?;
+            .ok_or_else(|| DbError::StoryNotFound(story_id.clone()))?;
+        check_status_transition(story_id, &story.status, &status)?;
         // Update story status
-        story.status = status;
+        let now = self.clock.now();
+        set_story_status(story, status, now, self.status_history_limit);
+        apply_derived_epic_statuses(&mut db_state, std::slice::from_ref(story_id), now);
         // Write the database to disk
-        self.database.write_db(&db_state)?;
+        self.write_db(&db_state)?;
         // Return Ok
         Ok(())
     }
 
-    pub fn get_epic(&self, epic_id: &String) -> Result<Epic> {
+    // A blank reason counts as none, which require_blocked_reason rejects
+    pub fn block_story(&self, story_id: &String, reason: Option<String>) -> DbResult<()> {
+        let reason = reason
+            .map(|reason| reason.trim().to_owned())
+            .filter(|reason| !reason.is_empty());
+        self.check_blocked_reason(std::slice::from_ref(story_id), reason.as_ref())?;
         // Grab database
-        let db_state = self.read_db()?;
-        // Grab a mutable reference to the epic
-        let epic = db_state
-            .epics
-            .get(epic_id)
-            .with_context(|| format!("Epic with id {} does not exist.", epic_id))?;
+        let mut db_state = self.read_db()?;
+        // Grab a mutable reference to the story
+        let story = db_state
+            .stories
+            .get_mut(story_id)
+            .ok_or_else(|| DbError::StoryNotFound(story_id.clone()))?;
+        check_status_transition(story_id, &story.status, &Status::Blocked)?;
+        // Block the story and record why
+        set_story_status(
+            story,
+            Status::Blocked,
+            self.clock.now(),
+            self.status_history_limit,
+        );
+        story.blocked_reason = reason;
+        apply_derived_epic_statuses(
+            &mut db_state,
+            std::slice::from_ref(story_id),
+            self.clock.now(),
+        );
+        // Write the database to disk
+        self.write_db(&db_state)?;
         // Return Ok
-        Ok(epic.clone())
+        Ok(())
+    }
+
+    fn check_blocked_reason(&self, story_ids: &[String], reason: Option<&String>) -> DbResult<()> {
+        if self.require_blocked_reason && reason.is_none() {
+            return Err(DbError::Validation(format!(
+                "Blocking {} needs a reason.",
+                story_ids.join(", ")
+            )));
+        }
+        Ok(())
+    }
+
+    // Names the unchecked items of the first story that can't be closed yet
+    fn check_definition_of_done(
+        &self,
+        db_state: &DBState,
+        story_ids: &[String],
+        status: &Status,
+    ) -> DbResult<()> {
+        if *status != Status::Closed || self.done_policy == DonePolicy::Allow {
+            return Ok(());
+        }
+        for story_id in story_ids {
+            let Some(story) = db_state.stories.get(story_id) else {
+                continue;
+            };
+            let unchecked = story
+                .checklist
+                .iter()
+                .filter(|item| !item.done)
+                .map(|item| item.text.as_str())
+                .collect_vec();
+            if !unchecked.is_empty() {
+                return Err(DbError::Validation(format!(
+                    "Story {} can't be closed before its checklist is done: {}.",
+                    story_id,
+                    unchecked.join(", ")
+                )));
+            }
+        }
+        Ok(())
     }
 
-    pub fn get_epic_story(&self, epic_id: &String, story_id: &String) -> Result<Story> {
+    // Reopening is always intentional, so it skips the close policy. The epic's stories are left alone.
+    pub fn reopen_epic(&self, epic_id: &String) -> DbResult<()> {
         // Grab database
-        let db_state = self.read_db()?;
+        let mut db_state = self.read_db()?;
         // Grab a mutable reference to the epic
         let epic = db_state
             .epics
-            .get(epic_id)
-            .with_context(|| format!("Epic with id {} does not exist.", epic_id))?;
-        // Grab a mutable reference to the story
-        let story = epic
+            .get_mut(epic_id)
+            .ok_or_else(|| DbError::EpicNotFound(epic_id.clone()))?;
+        // Set the epic back to open
+        epic.status = Status::Open;
+        epic.updated_at = Some(self.clock.now());
+        // Write the database to disk
+        self.write_db(&db_state)?;
+        // Return Ok
+        Ok(())
+    }
+
+    pub fn reopen_story(&self, story_id: &String) -> DbResult<()> {
+        self.update_story_status(story_id, Status::Open)
+    }
+
+    // Story changes already do this in their own write. Only writes when the status
+    // changes, and returns the epic's status either way.
+    pub fn recompute_epic_status(&self, epic_id: &String) -> DbResult<Status> {
+        // Grab database
+        let mut db_state = self.read_db()?;
+        if !db_state.epics.contains_key(epic_id) {
+            return Err(DbError::EpicNotFound(epic_id.clone()));
+        }
+        if apply_derived_epic_status(&mut db_state, epic_id, self.clock.now()) {
+            self.write_db(&db_state)?;
+        }
+        Ok(db_state.epics[epic_id].status.clone())
+    }
+
+    pub fn update_stories_status(&self, story_ids: &[String], status: Status) -> DbResult<()> {
+        // A bulk update has no reason to give
+        if status == Status::Blocked {
+            self.check_blocked_reason(story_ids, None)?;
+        }
+        // Grab database
+        let mut db_state = self.read_db()?;
+        self.check_definition_of_done(&db_state, story_ids, &status)?;
+        let now = self.clock.now();
+        // Update every story, failing before the write if any is missing
+        for story_id in story_ids {
+            let story = db_state
+                .stories
+                .get_mut(story_id)
+                .ok_or_else(|| DbError::StoryNotFound(story_id.clone()))?;
+            check_status_transition(story_id, &story.status, &status)?;
+            set_story_status(story, status.clone(), now, self.status_history_limit);
+        }
+        apply_derived_epic_statuses(&mut db_state, story_ids, now);
+        // Write the database to disk once
+        self.write_db(&db_state)?;
+        // Return Ok
+        Ok(())
+    }
+
+    pub fn delete_stories(&self, epic_id: &String, story_ids: &[String]) -> DbResult<()> {
+        // Grab database
+        let mut db_state = self.read_db()?;
+        // Grab a mutable reference to the epic
+        let epic = db_state
+            .epics
+            .get_mut(epic_id)
+            .ok_or_else(|| DbError::EpicNotFound(epic_id.clone()))?;
+        // Only stories of this epic can be deleted through it
+        if let Some(story_id) = story_ids.iter().find(|id| !epic.stories.contains(id)) {
+            return Err(DbError::Validation(format!(
+                "Story with id {} does not exist in epic {}.",
+                story_id, epic_id
+            )));
+        }
+        // Detach the stories from the epic and remove them
+        epic.stories.retain(|id| !story_ids.contains(id));
+        for story_id in story_ids {
+            db_state.stories.remove(story_id);
+        }
+        detach_subtasks(&mut db_state, story_ids);
+        apply_derived_epic_status(&mut db_state, epic_id, self.clock.now());
+        // Write the database to disk once
+        self.write_db(&db_state)?;
+        // Return Ok
+        Ok(())
+    }
+
+    pub fn split_story(
+        &self,
+        epic_id: &String,
+        story_id: &String,
+        new_names: Vec<String>,
+        original: SplitOriginal,
+    ) -> DbResult<Vec<String>> {
+        // Validate the names before touching anything
+        if new_names.is_empty() {
+            return Err(DbError::Validation(
+                "A story must be split into at least one story.".to_owned(),
+            ));
+        }
+        if new_names.iter().any(|name| name.trim().is_empty()) {
+            return Err(DbError::Validation(
+                "Story names cannot be empty.".to_owned(),
+            ));
+        }
+        // Grab database
+        let mut db_state = self.read_db()?;
+        let epic = db_state
+            .epics
+            .get(epic_id)
+            .ok_or_else(|| DbError::EpicNotFound(epic_id.clone()))?;
+        if !epic.stories.contains(story_id) {
+            return Err(DbError::Validation(format!(
+                "Story with id {} does not exist in epic {}.",
+                story_id, epic_id
+            )));
+        }
+        let description = db_state
             .stories
-            .iter()
-            .find(|id| id == &story_id)
-            .with_context(|| format!("Story with id {} does not exist.", story_id))?;
+            .get(story_id)
+            .ok_or_else(|| DbError::StoryNotFound(story_id.clone()))?
+            .description
+            .clone();
+        let now = self.clock.now();
+
+        // Create one open story per name, copying the description
+        let mut new_ids = Vec::new();
+        for name in new_names {
+            let mut story = Story::new(name.trim().to_owned(), description.clone());
+            story.created_at = Some(now);
+            story.updated_at = Some(now);
+            let id = nanoid!(6);
+            db_state.stories.insert(id.clone(), story);
+            db_state
+                .epics
+                .get_mut(epic_id)
+                .unwrap()
+                .stories
+                .push(id.clone());
+            db_state.last_item_id = id.clone();
+            new_ids.push(id);
+        }
+
+        // Close, delete or leave the original story
+        match original {
+            SplitOriginal::Keep => {}
+            SplitOriginal::Close => {
+                let story = db_state.stories.get_mut(story_id).unwrap();
+                set_story_status(story, Status::Closed, now, self.status_history_limit);
+            }
+            SplitOriginal::Delete => {
+                db_state
+                    .epics
+                    .get_mut(epic_id)
+                    .unwrap()
+                    .stories
+                    .retain(|id| id != story_id);
+                db_state.stories.remove(story_id);
+                detach_subtasks(&mut db_state, std::slice::from_ref(story_id));
+            }
+        }
+        // New open stories, and a closed or deleted original, can change the epic
+        apply_derived_epic_status(&mut db_state, epic_id, now);
+
+        // Write the database to disk once
+        self.write_db(&db_state)?;
+        // Return the new story ids
+        Ok(new_ids)
+    }
+
+    // Turns a story that outgrew its epic into an epic of its own. There are no
+    // checklists to carry over, so `move_subtasks` moves the story's subtasks into
+    // the new epic instead; otherwise they are detached and stay where they are.
+    pub fn promote_story_to_epic(
+        &self,
+        epic_id: &String,
+        story_id: &String,
+        move_subtasks: bool,
+    ) -> DbResult<String> {
+        // Grab database
+        let mut db_state = self.read_db()?;
+        let epic = db_state
+            .epics
+            .get(epic_id)
+            .ok_or_else(|| DbError::EpicNotFound(epic_id.clone()))?;
+        if !epic.stories.contains(story_id) {
+            return Err(DbError::Validation(format!(
+                "Story with id {} does not exist in epic {}.",
+                story_id, epic_id
+            )));
+        }
+        let story = db_state
+            .stories
+            .remove(story_id)
+            .ok_or_else(|| DbError::StoryNotFound(story_id.clone()))?;
+
+        // Create the epic from the story
+        let now = self.clock.now();
+        let mut new_epic = Epic::new(story.name, story.description);
+        new_epic.created_at = Some(now);
+        new_epic.updated_at = Some(now);
+
+        // Take the subtasks along before the story's links are cleared
+        if move_subtasks {
+            let subtask_ids: Vec<String> = db_state
+                .stories
+                .iter()
+                .filter(|(_, subtask)| subtask.parent.as_ref() == Some(story_id))
+                .map(|(id, _)| id.clone())
+                .sorted()
+                .collect();
+            for epic in db_state.epics.values_mut() {
+                epic.stories.retain(|id| !subtask_ids.contains(id));
+            }
+            new_epic.stories = subtask_ids;
+        }
+        detach_subtasks(&mut db_state, std::slice::from_ref(story_id));
+
+        // Remove the story from its epic and add the new epic
+        db_state
+            .epics
+            .get_mut(epic_id)
+            .unwrap()
+            .stories
+            .retain(|id| id != story_id);
+        let id = nanoid!(6);
+        db_state.epics.insert(id.clone(), new_epic);
+        db_state.last_item_id = id.clone();
+        // Both epics lost or gained stories
+        apply_derived_epic_status(&mut db_state, epic_id, now);
+        apply_derived_epic_status(&mut db_state, &id, now);
+
+        // Write the database to disk once
+        self.write_db(&db_state)?;
+        // Return the id of the new epic
+        Ok(id)
+    }
+
+    pub fn set_story_due_date(
+        &self,
+        story_id: &String,
+        due_date: Option<NaiveDate>,
+    ) -> DbResult<()> {
+        // Grab database
+        let mut db_state = self.read_db()?;
+        // Grab a mutable reference to the story
+        let story = db_state
+            .stories
+            .get_mut(story_id)
+            .ok_or_else(|| DbError::StoryNotFound(story_id.clone()))?;
+        // Update story due date
+        story.due_date = due_date;
+        story.updated_at = Some(self.clock.now());
+        // Write the database to disk
+        self.write_db(&db_state)?;
         // Return Ok
-        Ok(db_state.stories.get(story).unwrap().clone())
+        Ok(())
     }
-}
 
-pub mod test_utils {
-    use super::*;
-    use std::{cell::RefCell, collections::HashMap};
+    // Story points for the burndown. None clears the estimate.
+    pub fn set_story_estimate(&self, story_id: &String, estimate: Option<u32>) -> DbResult<()> {
+        // Grab database
+        let mut db_state = self.read_db()?;
+        // Grab a mutable reference to the story
+        let story = db_state
+            .stories
+            .get_mut(story_id)
+            .ok_or_else(|| DbError::StoryNotFound(story_id.clone()))?;
+        // Update story estimate
+        story.estimate = estimate;
+        story.updated_at = Some(self.clock.now());
+        // Write the database to disk
+        self.write_db(&db_state)?;
+        // Return Ok
+        Ok(())
+    }
 
-    pub struct MockDB {
-        last_written_state: RefCell<DBState>,
+    // None unassigns. All stories are checked before anything is written.
+    pub fn set_stories_assignee(
+        &self,
+        story_ids: &[String],
+        assignee: Option<String>,
+    ) -> DbResult<()> {
+        // Grab database
+        let mut db_state = self.read_db()?;
+        let now = self.clock.now();
+        // Update every story, failing before the write if any is missing
+        for story_id in story_ids {
+            let story = db_state
+                .stories
+                .get_mut(story_id)
+                .ok_or_else(|| DbError::StoryNotFound(story_id.clone()))?;
+            story.assignee = assignee.clone();
+            story.updated_at = Some(now);
+        }
+        // Write the database to disk once
+        self.write_db(&db_state)?;
+        // Return Ok
+        Ok(())
     }
 
-    impl MockDB {
-        pub fn new() -> Self {
-            Self {
-                last_written_state: RefCell::new(DBState {
-                    last_item_id: "0".to_string(),
-                    epics: HashMap::new(),
-                    stories: HashMap::new(),
-                }),
+    // A blank name unassigns the story
+    pub fn assign_story(&self, story_id: &String, assignee: String) -> DbResult<()> {
+        let assignee = assignee.trim();
+        let assignee = (!assignee.is_empty()).then(|| assignee.to_owned());
+        self.set_stories_assignee(std::slice::from_ref(story_id), assignee)
+    }
+
+    pub fn set_story_parent(
+        &self,
+        epic_id: &String,
+        story_id: &String,
+        parent_id: Option<String>,
+    ) -> DbResult<()> {
+        // Grab database
+        let mut db_state = self.read_db()?;
+        let epic = db_state
+            .epics
+            .get(epic_id)
+            .ok_or_else(|| DbError::EpicNotFound(epic_id.clone()))?;
+        if !epic.stories.contains(story_id) {
+            return Err(DbError::Validation(format!(
+                "Story with id {} does not exist in epic {}.",
+                story_id, epic_id
+            )));
+        }
+        // The parent has to live in the same epic and must not lead back to the story
+        if let Some(parent_id) = &parent_id {
+            if parent_id == story_id {
+                return Err(DbError::Validation(
+                    "A story cannot be its own parent.".to_owned(),
+                ));
+            }
+            if !epic.stories.contains(parent_id) {
+                return Err(DbError::Validation(format!(
+                    "Story with id {} does not exist in epic {}.",
+                    parent_id, epic_id
+                )));
+            }
+            if get_story_ancestors(&db_state, parent_id).contains(story_id) {
+                return Err(DbError::Validation(format!(
+                    "Story {} is already a subtask of {}.",
+                    parent_id, story_id
+                )));
             }
         }
+        // Grab a mutable reference to the story
+        let story = db_state
+            .stories
+            .get_mut(story_id)
+            .ok_or_else(|| DbError::StoryNotFound(story_id.clone()))?;
+        // Update story parent
+        story.parent = parent_id;
+        story.updated_at = Some(self.clock.now());
+        // Write the database to disk
+        self.write_db(&db_state)?;
+        // Return Ok
+        Ok(())
     }
 
-    impl Database for MockDB {
-        fn read_db(&self) -> Result<DBState> {
-            let state = self.last_written_state.borrow().clone();
-            Ok(state)
+    // Items start unchecked
+    pub fn add_checklist_item(&self, story_id: &String, text: String) -> DbResult<()> {
+        if text.trim().is_empty() {
+            return Err(DbError::Validation(
+                "Checklist items need some text.".to_owned(),
+            ));
         }
+        // Grab database
+        let mut db_state = self.read_db()?;
+        // Grab a mutable reference to the story
+        let story = db_state
+            .stories
+            .get_mut(story_id)
+            .ok_or_else(|| DbError::StoryNotFound(story_id.clone()))?;
+        // Append the item
+        story.checklist.push(ChecklistItem {
+            text: text.trim().to_owned(),
+            done: false,
+        });
+        story.updated_at = Some(self.clock.now());
+        // Write the database to disk
+        self.write_db(&db_state)?;
+        // Return Ok
+        Ok(())
+    }
 
-        fn write_db(&self, db_state: &DBState) -> Result<()> {
-            let latest_state = &self.last_written_state;
-            *latest_state.borrow_mut() = db_state.clone();
-            Ok(())
+    // Checks an unchecked item and unchecks a checked one
+    pub fn toggle_checklist_item(&self, story_id: &String, index: usize) -> DbResult<()> {
+        // Grab database
+        let mut db_state = self.read_db()?;
+        // Grab a mutable reference to the story
+        let story = db_state
+            .stories
+            .get_mut(story_id)
+            .ok_or_else(|| DbError::StoryNotFound(story_id.clone()))?;
+        let item = story.checklist.get_mut(index).ok_or_else(|| {
+            DbError::Validation(format!(
+                "Story {} has no checklist item number {}.",
+                story_id,
+                index + 1
+            ))
+        })?;
+        // Flip the item
+        item.done = !item.done;
+        story.updated_at = Some(self.clock.now());
+        // Write the database to disk
+        self.write_db(&db_state)?;
+        // Return Ok
+        Ok(())
+    }
+
+    pub fn add_attachment(&self, story_id: &String, attachment: Attachment) -> DbResult<()> {
+        // Both halves of the reference are required
+        if attachment.label.trim().is_empty() {
+            return Err(DbError::Validation("Attachments need a label.".to_owned()));
         }
+        if attachment.target.trim().is_empty() {
+            return Err(DbError::Validation(
+                "Attachments need a path or URL.".to_owned(),
+            ));
+        }
+        // Grab database
+        let mut db_state = self.read_db()?;
+        // Grab a mutable reference to the story
+        let story = db_state
+            .stories
+            .get_mut(story_id)
+            .ok_or_else(|| DbError::StoryNotFound(story_id.clone()))?;
+        // Append the attachment
+        story.attachments.push(Attachment {
+            label: attachment.label.trim().to_owned(),
+            target: attachment.target.trim().to_owned(),
+        });
+        story.updated_at = Some(self.clock.now());
+        // Write the database to disk
+        self.write_db(&db_state)?;
+        // Return Ok
+        Ok(())
     }
 
-    pub fn arrange_test() -> (JiraDatabase, String, String) {
-        // Arrange db and data
-        let mock = Box::new(MockDB::new());
-        let db = JiraDatabase { database: mock };
-        let epic = Epic::new("".to_owned(), "".to_owned());
-        let story = Story::new("".to_owned(), "".to_owned());
+    pub fn remove_attachment(&self, story_id: &String, index: usize) -> DbResult<Attachment> {
+        // Grab database
+        let mut db_state = self.read_db()?;
+        // Grab a mutable reference to the story
+        let story = db_state
+            .stories
+            .get_mut(story_id)
+            .ok_or_else(|| DbError::StoryNotFound(story_id.clone()))?;
+        if index >= story.attachments.len() {
+            return Err(DbError::Validation(format!(
+                "Story {} has no attachment number {}.",
+                story_id,
+                index + 1
+            )));
+        }
+        // Remove the attachment
+        let attachment = story.attachments.remove(index);
+        story.updated_at = Some(self.clock.now());
+        // Write the database to disk
+        self.write_db(&db_state)?;
+        // Return the removed attachment
+        Ok(attachment)
+    }
 
-        // Add data to db
-        let result = db.create_epic(epic);
-        let epic_id = result.unwrap();
-        let result = db.create_story(story, &epic_id);
-        let story_id = result.unwrap();
+    pub fn get_attachment(&self, story_id: &String, index: usize) -> DbResult<Attachment> {
+        // Grab database
+        let db_state = self.read_db()?;
+        let story = db_state
+            .stories
+            .get(story_id)
+            .ok_or_else(|| DbError::StoryNotFound(story_id.clone()))?;
+        story.attachments.get(index).cloned().ok_or_else(|| {
+            DbError::Validation(format!(
+                "Story {} has no attachment number {}.",
+                story_id,
+                index + 1
+            ))
+        })
+    }
+
+    pub fn cycle_time(&self, story_id: &String) -> DbResult<Option<Duration>> {
+        let db_state = self.read_db()?;
+        let story = db_state
+            .stories
+            .get(story_id)
+            .ok_or_else(|| DbError::StoryNotFound(story_id.clone()))?;
+        Ok(story.cycle_time())
+    }
+
+    pub fn lead_time(&self, story_id: &String) -> DbResult<Option<Duration>> {
+        let db_state = self.read_db()?;
+        let story = db_state
+            .stories
+            .get(story_id)
+            .ok_or_else(|| DbError::StoryNotFound(story_id.clone()))?;
+        Ok(story.lead_time())
+    }
+
+    // Average cycle and lead time across finished stories
+    pub fn flow_metrics(&self) -> DbResult<FlowMetrics> {
+        let db_state = self.read_db()?;
+        let finished = db_state
+            .stories
+            .values()
+            .filter(|story| story.is_finished())
+            .collect_vec();
+
+        let cycle_times = finished
+            .iter()
+            .filter_map(|story| story.cycle_time())
+            .collect_vec();
+        let lead_times = finished
+            .iter()
+            .filter_map(|story| story.lead_time())
+            .collect_vec();
+
+        Ok(FlowMetrics {
+            average_cycle_time: get_average_duration(&cycle_times),
+            cycle_time_count: cycle_times.len(),
+            average_lead_time: get_average_duration(&lead_times),
+            lead_time_count: lead_times.len(),
+        })
+    }
+
+    // An empty or blank owner clears it
+    pub fn set_epic_owner(&self, epic_id: &String, owner: String) -> DbResult<()> {
+        // Grab database
+        let mut db_state = self.read_db()?;
+        // Grab a mutable reference to the epic
+        let epic = db_state
+            .epics
+            .get_mut(epic_id)
+            .ok_or_else(|| DbError::EpicNotFound(epic_id.clone()))?;
+        // Set or clear the owner
+        let owner = owner.trim();
+        epic.owner = (!owner.is_empty()).then(|| owner.to_owned());
+        epic.updated_at = Some(self.clock.now());
+        // Write the database to disk
+        self.write_db(&db_state)
+    }
+
+    // Flags the story for refinement, or clears the flag once it has been refined
+    pub fn toggle_refinement(&self, story_id: &String) -> DbResult<bool> {
+        // Grab database
+        let mut db_state = self.read_db()?;
+        // Grab a mutable reference to the story
+        let story = db_state
+            .stories
+            .get_mut(story_id)
+            .ok_or_else(|| DbError::StoryNotFound(story_id.clone()))?;
+        // Flip the flag
+        story.needs_refinement = !story.needs_refinement;
+        story.updated_at = Some(self.clock.now());
+        let needs_refinement = story.needs_refinement;
+        // Write the database to disk
+        self.write_db(&db_state)?;
+        // Return the new flag
+        Ok(needs_refinement)
+    }
+
+    pub fn toggle_pin(&self, epic_id: &String) -> DbResult<bool> {
+        // Grab database
+        let mut db_state = self.read_db()?;
+        // Grab a mutable reference to the epic
+        let epic = db_state
+            .epics
+            .get_mut(epic_id)
+            .ok_or_else(|| DbError::EpicNotFound(epic_id.clone()))?;
+        // Flip the pin
+        epic.pinned = !epic.pinned;
+        epic.updated_at = Some(self.clock.now());
+        let pinned = epic.pinned;
+        // Write the database to disk
+        self.write_db(&db_state)?;
+        // Return the new pin state
+        Ok(pinned)
+    }
+
+    pub fn get_epic(&self, epic_id: &String) -> DbResult<Epic> {
+        // Grab database
+        let db_state = self.read_db()?;
+        // Grab a mutable reference to the epic
+        let epic = db_state
+            .epics
+            .get(epic_id)
+            .ok_or_else(|| DbError::EpicNotFound(epic_id.clone()))?;
+        // Return Ok
+        Ok(epic.clone())
+    }
+
+    pub fn get_epic_story(&self, epic_id: &String, story_id: &String) -> DbResult<Story> {
+        // Grab database
+        let db_state = self.read_db()?;
+        // Grab a mutable reference to the epic
+        let epic = db_state
+            .epics
+            .get(epic_id)
+            .ok_or_else(|| DbError::EpicNotFound(epic_id.clone()))?;
+        // Grab a mutable reference to the story
+        let story = epic
+            .stories
+            .iter()
+            .find(|id| id == &story_id)
+            .ok_or_else(|| DbError::StoryNotFound(story_id.clone()))?;
+        // Return Ok
+        Ok(db_state.stories.get(story).unwrap().clone())
+    }
+
+    pub fn epic_exists(&self, epic_id: &String) -> DbResult<bool> {
+        Ok(self.read_db()?.epics.contains_key(epic_id))
+    }
+
+    pub fn story_exists(&self, story_id: &String) -> DbResult<bool> {
+        Ok(self.read_db()?.stories.contains_key(story_id))
+    }
+
+    pub fn find_item(&self, id: &String) -> DbResult<ItemRef> {
+        // Grab database
+        let db_state = self.read_db()?;
+        // Ids are unique across epics and stories, so at most one of these matches
+        if db_state.epics.contains_key(id) {
+            return Ok(ItemRef::Epic {
+                epic_id: id.clone(),
+            });
+        }
+        if db_state.stories.contains_key(id) {
+            return Ok(ItemRef::Story {
+                story_id: id.clone(),
+            });
+        }
+        Err(DbError::Validation(format!(
+            "No epic or story with id {} exists.",
+            id
+        )))
+    }
+
+    pub fn capabilities(&self) -> Capabilities {
+        self.database.capabilities()
+    }
+
+    // Resolves a typed prefix against every epic and story id
+    pub fn resolve_id_prefix(&self, prefix: &str) -> DbResult<IdMatch> {
+        let db_state = self.read_db()?;
+        Ok(IdMatch::from_ids(
+            prefix,
+            db_state.epics.keys().chain(db_state.stories.keys()),
+        ))
+    }
+
+    pub fn search(&self, query: &str) -> DbResult<Vec<SearchResult>> {
+        // Blank queries match nothing rather than everything
+        let query = query.trim();
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+        // Use the backend's index when it has one, otherwise scan
+        let mut results = if self.database.supports_fts() {
+            self.database.search_fts(query)?
+        } else {
+            self.scan_search(query)?
+        };
+
+        // Best first, then by name and id so equal scores keep a stable order
+        results.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| a.name.cmp(&b.name))
+                .then_with(|| (&a.epic_id, &a.story_id).cmp(&(&b.epic_id, &b.story_id)))
+        });
+        Ok(results)
+    }
+
+    fn scan_search(&self, query: &str) -> DbResult<Vec<SearchResult>> {
+        // Grab database
+        let db_state = self.read_db()?;
+        let matcher = SkimMatcherV2::default().ignore_case();
+
+        // Score every epic and every story reachable from one
+        let mut results = Vec::new();
+        for (epic_id, epic) in &db_state.epics {
+            if let Some(score) = get_search_score(&matcher, query, &epic.name, &epic.description) {
+                results.push(SearchResult {
+                    epic_id: epic_id.clone(),
+                    story_id: None,
+                    name: epic.name.clone(),
+                    score,
+                });
+            }
+            for story_id in &epic.stories {
+                let Some(story) = db_state.stories.get(story_id) else {
+                    continue;
+                };
+                if let Some(score) =
+                    get_search_score(&matcher, query, &story.name, &story.description)
+                {
+                    results.push(SearchResult {
+                        epic_id: epic_id.clone(),
+                        story_id: Some(story_id.clone()),
+                        name: story.name.clone(),
+                        score,
+                    });
+                }
+            }
+        }
+        Ok(results)
+    }
+
+    pub fn find_orphan_stories(&self) -> DbResult<Vec<String>> {
+        // Grab database
+        let db_state = self.read_db()?;
+        // Return every story no epic points to
+        Ok(get_orphan_story_ids(&db_state))
+    }
+
+    pub fn gc(&self) -> DbResult<GcReport> {
+        // Grab database
+        let mut db_state = self.read_db()?;
+        // Find stories no epic points to
+        let story_ids = get_orphan_story_ids(&db_state);
+        // Nothing to collect, leave the file untouched
+        if story_ids.is_empty() {
+            return Ok(GcReport::default());
+        }
+        // Remove the orphans
+        for story_id in story_ids.iter() {
+            db_state.stories.remove(story_id);
+        }
+        // Write the database to disk
+        self.write_db(&db_state)?;
+        // Return the removed ids
+        Ok(GcReport { story_ids })
+    }
+
+    // Moves every closed epic and its stories into the JSON file at `archive_path`,
+    // adding to whatever is archived there already. The archive is written before
+    // the live database, so a failure in between leaves the epics in both, not neither.
+    pub fn archive_closed_epics(&self, archive_path: &Path) -> DbResult<usize> {
+        // Checked up front, the archive would otherwise be written before the failure
+        if !self.capabilities().writable {
+            return Err(DbError::Validation(
+                "This database is read-only.".to_owned(),
+            ));
+        }
+        // Grab database
+        let mut db_state = self.read_db()?;
+        let epic_ids: Vec<String> = db_state
+            .epics
+            .iter()
+            .filter(|(_, epic)| epic.status == Status::Closed)
+            .map(|(epic_id, _)| epic_id.clone())
+            .sorted()
+            .collect();
+        // Nothing to archive, leave both files untouched
+        if epic_ids.is_empty() {
+            return Ok(0);
+        }
+
+        let archive = JSONFileDatabase {
+            file_path: archive_path.to_path_buf(),
+            backup: None,
+            clock: Box::new(SystemClock),
+        };
+        let mut archived = if archive_path.exists() {
+            archive.read_db()?
+        } else {
+            DBState {
+                epics: HashMap::new(),
+                stories: HashMap::new(),
+                last_item_id: String::new(),
+                revision: 0,
+            }
+        };
+
+        // Move the epics over together with their stories
+        for epic_id in epic_ids.iter() {
+            let epic = db_state.epics.remove(epic_id).unwrap();
+            for story_id in epic.stories.iter() {
+                if let Some(story) = db_state.stories.remove(story_id) {
+                    archived.stories.insert(story_id.clone(), story);
+                }
+            }
+            archived.epics.insert(epic_id.clone(), epic);
+            archived.last_item_id = epic_id.clone();
+        }
+
+        // Write the archive first, then the database
+        archive.write_db(&archived)?;
+        self.write_db(&db_state)?;
+        // Return how many epics moved
+        Ok(epic_ids.len())
+    }
+
+    pub fn import_epics(
+        &self,
+        epics: Vec<ImportedEpic>,
+        mode: MergeMode,
+    ) -> DbResult<ImportSummary> {
+        // Grab database
+        let mut db_state = self.read_db()?;
+        let mut summary = ImportSummary::default();
+        let now = self.clock.now();
+
+        for imported in epics {
+            // Append never looks for matches, the other modes do
+            let existing_id = match mode {
+                MergeMode::Append => None,
+                _ => find_matching_epic(&db_state, &imported),
+            };
+
+            let epic_id = match existing_id {
+                Some(epic_id) if mode == MergeMode::SkipExisting => {
+                    summary.skipped += 1;
+                    epic_id
+                }
+                Some(epic_id) => {
+                    let epic = db_state.epics.get_mut(&epic_id).unwrap();
+                    epic.name = imported.name.clone();
+                    epic.description = imported.description.clone();
+                    epic.status = imported.status.clone();
+                    epic.updated_at = Some(now);
+                    summary.updated += 1;
+                    epic_id
+                }
+                None => {
+                    let mut epic = Epic::new(imported.name.clone(), imported.description.clone());
+                    epic.status = imported.status.clone();
+                    epic.created_at = Some(now);
+                    epic.updated_at = Some(now);
+                    let id = nanoid!(6);
+                    db_state.epics.insert(id.clone(), epic);
+                    db_state.last_item_id = id.clone();
+                    summary.created += 1;
+                    id
+                }
+            };
+
+            // Stories are merged with the same mode, matched within their epic
+            for imported_story in imported.stories {
+                let existing_id = match mode {
+                    MergeMode::Append => None,
+                    _ => find_matching_story(&db_state, &epic_id, &imported_story),
+                };
+
+                match existing_id {
+                    Some(_) if mode == MergeMode::SkipExisting => summary.skipped += 1,
+                    Some(story_id) => {
+                        let story = db_state.stories.get_mut(&story_id).unwrap();
+                        story.name = imported_story.name;
+                        story.description = imported_story.description;
+                        story.status = imported_story.status;
+                        story.updated_at = Some(now);
+                        summary.updated += 1;
+                    }
+                    None => {
+                        let mut story = Story::new(imported_story.name, imported_story.description);
+                        story.status = imported_story.status;
+                        story.created_at = Some(now);
+                        story.updated_at = Some(now);
+                        let id = nanoid!(6);
+                        db_state.stories.insert(id.clone(), story);
+                        db_state
+                            .epics
+                            .get_mut(&epic_id)
+                            .unwrap()
+                            .stories
+                            .push(id.clone());
+                        db_state.last_item_id = id;
+                        summary.created += 1;
+                    }
+                }
+            }
+        }
+
+        // Write the database to disk once for the whole import
+        self.write_db(&db_state)?;
+
+        Ok(summary)
+    }
+
+    // Stories the predicate accepts, as (story_id, story) sorted by id, from a single read
+    pub fn filter_stories(
+        &self,
+        predicate: impl Fn(&str, &Story) -> bool,
+    ) -> DbResult<Vec<(String, Story)>> {
+        // Grab database
+        let db_state = self.read_db()?;
+        // Keep the matching stories
+        Ok(db_state
+            .stories
+            .into_iter()
+            .filter(|(story_id, story)| predicate(story_id, story))
+            .sorted_by(|(a, _), (b, _)| a.cmp(b))
+            .collect())
+    }
+
+    // Epics the predicate accepts, as (epic_id, epic) sorted by id, from a single read
+    pub fn filter_epics(
+        &self,
+        predicate: impl Fn(&str, &Epic) -> bool,
+    ) -> DbResult<Vec<(String, Epic)>> {
+        // Grab database
+        let db_state = self.read_db()?;
+        // Keep the matching epics
+        Ok(db_state
+            .epics
+            .into_iter()
+            .filter(|(epic_id, epic)| predicate(epic_id, epic))
+            .sorted_by(|(a, _), (b, _)| a.cmp(b))
+            .collect())
+    }
+
+    pub fn stories_with_status(&self, status: &Status) -> DbResult<Vec<(String, Story)>> {
+        self.filter_stories(|_, story| story.status == *status)
+    }
+
+    // (epic_id, story_id, story) for the assignee's stories in progress, by epic id and
+    // then oldest first. Stories created at the same time are ordered by id.
+    pub fn in_progress_stories_for(
+        &self,
+        assignee: &str,
+    ) -> DbResult<Vec<(String, String, Story)>> {
+        // Grab database
+        let db_state = self.read_db()?;
+        let mut in_progress = Vec::new();
+        for (epic_id, _) in db_state.epics_sorted() {
+            let stories = db_state
+                .stories_of(epic_id)
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|(_, story)| {
+                    story.status == Status::InProgress
+                        && story.assignee.as_deref() == Some(assignee)
+                })
+                .sorted_by(|(a_id, a), (b_id, b)| {
+                    a.created_at.cmp(&b.created_at).then_with(|| a_id.cmp(b_id))
+                });
+            for (story_id, story) in stories {
+                in_progress.push((epic_id.clone(), story_id.clone(), story.clone()));
+            }
+        }
+        Ok(in_progress)
+    }
+
+    // None gives the unassigned stories
+    pub fn stories_assigned_to(&self, assignee: Option<&str>) -> DbResult<Vec<(String, Story)>> {
+        self.filter_stories(|_, story| story.assignee.as_deref() == assignee)
+    }
+
+    pub fn stats_by_assignee(&self) -> DbResult<HashMap<String, AssigneeStats>> {
+        // Grab database
+        let db_state = self.read_db()?;
+        let mut stats: HashMap<String, AssigneeStats> = HashMap::new();
+        // Bucket every story by assignee, unassigned ones under their own key
+        for story in db_state.stories.values() {
+            let assignee = story
+                .assignee
+                .clone()
+                .unwrap_or_else(|| UNASSIGNED.to_owned());
+            stats.entry(assignee).or_default().add(&story.status);
+        }
+        // Return the stats
+        Ok(stats)
+    }
+
+    // (epic_id, story_id, story) for stories flagged for refinement, grouped by epic in
+    // id order and in epic order within each
+    pub fn refinement_queue(&self) -> DbResult<Vec<(String, String, Story)>> {
+        // Grab database
+        let db_state = self.read_db()?;
+        let mut queue = Vec::new();
+        for (epic_id, _) in db_state.epics_sorted() {
+            for (story_id, story) in db_state.stories_of(epic_id).unwrap_or_default() {
+                if story.needs_refinement {
+                    queue.push((epic_id.clone(), story_id.clone(), story.clone()));
+                }
+            }
+        }
+        Ok(queue)
+    }
+
+    // (epic_id, story_id, story) for stories due within start..=end, soonest first
+    pub fn stories_due_between(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> DbResult<Vec<(String, String, Story)>> {
+        // Grab database
+        let db_state = self.read_db()?;
+        let mut due = Vec::new();
+        for epic_id in db_state.epics.keys() {
+            for (story_id, story) in db_state.stories_of(epic_id).unwrap_or_default() {
+                if story
+                    .due_date
+                    .is_some_and(|due_date| start <= due_date && due_date <= end)
+                {
+                    due.push((epic_id.clone(), story_id.clone(), story.clone()));
+                }
+            }
+        }
+        // Ties are broken by id so the order is stable
+        due.sort_by(|(_, a_id, a), (_, b_id, b)| {
+            a.due_date.cmp(&b.due_date).then_with(|| a_id.cmp(b_id))
+        });
+        Ok(due)
+    }
+
+    // Everything wrong with the stored state, without changing it. A file that doesn't
+    // parse is reported as a single schema problem rather than an error.
+    pub fn check_integrity(&self) -> DbResult<Vec<IntegrityProblem>> {
+        match self.read_db() {
+            Ok(db_state) => Ok(get_integrity_problems(&db_state)),
+            Err(DbError::Serde(error)) => Ok(vec![IntegrityProblem {
+                kind: "schema".to_owned(),
+                id: "".to_owned(),
+                message: format!("Invalid database JSON: {}", error),
+            }]),
+            Err(error) => Err(error),
+        }
+    }
+
+    pub fn health(&self) -> DbResult<Health> {
+        // Grab database
+        let db_state = self.read_db()?;
+        let today = self.clock.now().date_naive();
+        let mut health = Health::default();
+        for story in db_state.stories.values() {
+            if story.status == Status::InProgress {
+                health.wip += 1;
+            }
+            if story.is_finished() {
+                continue;
+            }
+            if story.due_date.is_some_and(|due_date| due_date < today) {
+                health.overdue += 1;
+            }
+            if story.assignee.is_none() {
+                health.unassigned += 1;
+            }
+        }
+        // Return the counts
+        Ok(health)
+    }
+
+    pub fn export_ics(&self) -> DbResult<String> {
+        // Grab database
+        let db_state = self.read_db()?;
+        let stamp = self.clock.now().format("%Y%m%dT%H%M%SZ").to_string();
+
+        // Collect stories with a due date along with their epic name
+        let mut dated = db_state
+            .epics
+            .values()
+            .flat_map(|epic| {
+                epic.stories
+                    .iter()
+                    .map(move |story_id| (story_id, &epic.name))
+            })
+            .filter_map(|(story_id, epic_name)| {
+                let story = db_state.stories.get(story_id)?;
+                let due_date = story.due_date?;
+                Some((due_date, story_id, story, epic_name))
+            })
+            .collect::<Vec<_>>();
+        // Soonest first, ids break ties so the output is stable
+        dated.sort_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)));
+
+        let mut lines = vec![
+            "BEGIN:VCALENDAR".to_owned(),
+            "VERSION:2.0".to_owned(),
+            "PRODID:-//jira_cli//EN".to_owned(),
+        ];
+        for (due_date, story_id, story, epic_name) in dated {
+            lines.push("BEGIN:VTODO".to_owned());
+            lines.push(format!("UID:{}@jira_cli", story_id));
+            lines.push(format!("DTSTAMP:{}", stamp));
+            lines.push(format!("DUE;VALUE=DATE:{}", due_date.format("%Y%m%d")));
+            lines.push(format!("SUMMARY:{}", escape_ics_text(&story.name)));
+            lines.push(format!(
+                "DESCRIPTION:{}",
+                escape_ics_text(&format!("Epic: {}", epic_name))
+            ));
+            lines.push(format!("STATUS:{}", get_ics_status(&story.status)));
+            lines.push("END:VTODO".to_owned());
+        }
+        lines.push("END:VCALENDAR".to_owned());
+
+        // RFC 5545 lines end in CRLF
+        Ok(lines.join("\r\n") + "\r\n")
+    }
+
+    // Replays the epic's status changes to get the estimate points left at the end of each day
+    pub fn burndown(&self, epic_id: &String) -> DbResult<Vec<BurndownPoint>> {
+        // Grab database
+        let db_state = self.read_db()?;
+        let epic = db_state
+            .epics
+            .get(epic_id)
+            .ok_or_else(|| DbError::EpicNotFound(epic_id.clone()))?;
+
+        let estimates = epic
+            .stories
+            .iter()
+            .filter_map(|story_id| {
+                let story = db_state.stories.get(story_id)?;
+                Some((story_id.clone(), story.estimate.unwrap_or(0)))
+            })
+            .collect::<HashMap<_, _>>();
+        let events = get_status_events(&db_state, epic_id);
+
+        Ok(get_burndown_points(&estimates, &events))
+    }
+
+    pub fn export_ndjson(&self, mut writer: impl Write) -> DbResult<()> {
+        // Grab database
+        let db_state = self.read_db()?;
+
+        // Epics first, then stories, each sorted by id so the output is stable
+        for (epic_id, epic) in db_state.epics_sorted() {
+            write_ndjson_line(&mut writer, "epic", epic_id, epic)?;
+        }
+        let stories = db_state.stories.iter().sorted_by(|a, b| a.0.cmp(b.0));
+        for (story_id, story) in stories {
+            write_ndjson_line(&mut writer, "story", story_id, story)?;
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+
+    // Epics and stories as one CSV, see CsvRow. read_csv_epics reads it back.
+    pub fn export_csv(&self, writer: impl Write) -> DbResult<()> {
+        // Grab database
+        let db_state = self.read_db()?;
+
+        write_csv_rows(writer, &db_state)?;
+        Ok(())
+    }
+
+    // One JSON file per epic holding the epic and its stories, plus index.json listing
+    // them, for a per-epic file layout. A directory that already has files in it is
+    // only written into with `force`. Returns the number of epic files.
+    pub fn export_split(&self, dir: &Path, force: bool) -> DbResult<usize> {
+        let is_empty = match std::fs::read_dir(dir) {
+            Ok(mut entries) => entries.next().is_none(),
+            Err(_) => true,
+        };
+        if !is_empty && !force {
+            return Err(DbError::Validation(format!(
+                "{} already exists. Use --force to overwrite it.",
+                dir.display()
+            )));
+        }
+        // Grab database
+        let db_state = self.read_db()?;
+
+        std::fs::create_dir_all(dir)?;
+        let mut entries = Vec::new();
+        for (epic_id, epic) in db_state.epics_sorted() {
+            let file = get_split_file_name(epic_id, &epic.name);
+            let stories = db_state
+                .stories_of(epic_id)
+                .unwrap_or_default()
+                .into_iter()
+                .collect::<BTreeMap<_, _>>();
+            let contents = serde_json::to_string_pretty(&SplitEpicFile {
+                epic_id,
+                epic,
+                stories,
+            })?;
+            std::fs::write(dir.join(&file), contents)?;
+            entries.push(SplitIndexEntry {
+                epic_id,
+                name: &epic.name,
+                file,
+            });
+        }
+        let index = serde_json::to_string_pretty(&SplitIndex {
+            last_item_id: &db_state.last_item_id,
+            revision: db_state.revision,
+            epics: &entries,
+        })?;
+        std::fs::write(dir.join(SPLIT_INDEX_FILE), index)?;
+        Ok(entries.len())
+    }
+}
+
+pub const SPLIT_INDEX_FILE: &str = "index.json";
+
+// One epic's file in export_split. Files of epics deleted since an earlier export
+// are left in place, the index is what lists the current ones.
+#[derive(Serialize)]
+struct SplitEpicFile<'a> {
+    epic_id: &'a String,
+    epic: &'a Epic,
+    stories: BTreeMap<&'a String, &'a Story>,
+}
+
+#[derive(Serialize)]
+struct SplitIndex<'a> {
+    last_item_id: &'a String,
+    revision: u64,
+    epics: &'a [SplitIndexEntry<'a>],
+}
+
+#[derive(Serialize)]
+struct SplitIndexEntry<'a> {
+    epic_id: &'a String,
+    name: &'a String,
+    file: String,
+}
+
+// "<id>-<slug>.json", e.g. "3-launch-v2.json". The id keeps names unique, the slug
+// keeps only ASCII letters and digits so a name can't reach outside the directory.
+fn get_split_file_name(epic_id: &str, name: &str) -> String {
+    let slug = name
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .join("-");
+    let slug = slug.chars().take(40).collect::<String>();
+    let slug = slug.trim_end_matches('-');
+    let epic_id = epic_id
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-')
+        .collect::<String>();
+    if slug.is_empty() {
+        format!("{}.json", epic_id)
+    } else {
+        format!("{}-{}.json", epic_id, slug)
+    }
+}
+
+// One row of the combined CSV. Epics leave epic_id blank.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct CsvRow {
+    #[serde(rename = "type")]
+    kind: String,
+    id: String,
+    epic_id: String,
+    name: String,
+    description: String,
+    status: Status,
+}
+
+// Each epic is followed by its own stories in epic order, epics sorted by id, so the
+// output is stable and an importer meets every epic before its stories
+fn write_csv_rows(writer: impl Write, db_state: &DBState) -> Result<()> {
+    let mut csv_writer = csv::Writer::from_writer(writer);
+    for (epic_id, epic) in db_state.epics_sorted() {
+        csv_writer.serialize(CsvRow {
+            kind: "epic".to_owned(),
+            id: epic_id.clone(),
+            epic_id: "".to_owned(),
+            name: epic.name.clone(),
+            description: epic.description.clone(),
+            status: epic.status.clone(),
+        })?;
+        for (story_id, story) in db_state.stories_of(epic_id).unwrap_or_default() {
+            csv_writer.serialize(CsvRow {
+                kind: "story".to_owned(),
+                id: story_id.clone(),
+                epic_id: epic_id.clone(),
+                name: story.name.clone(),
+                description: story.description.clone(),
+                status: story.status.clone(),
+            })?;
+        }
+    }
+    csv_writer.flush()?;
+    Ok(())
+}
+
+// Reads the combined CSV written by export_csv, for import_epics. The exported ids
+// become external ids, so merging into the same database matches items by id.
+pub fn read_csv_epics(reader: impl Read) -> Result<Vec<ImportedEpic>> {
+    let mut epics: Vec<ImportedEpic> = Vec::new();
+    let mut csv_reader = csv::Reader::from_reader(reader);
+    for (index, row) in csv_reader.deserialize::<CsvRow>().enumerate() {
+        // Line 1 holds the headers
+        let line = index + 2;
+        let row = row.with_context(|| format!("Line {} of the CSV could not be read.", line))?;
+        match row.kind.as_str() {
+            "epic" => epics.push(ImportedEpic {
+                external_id: Some(row.id),
+                name: row.name,
+                description: row.description,
+                status: row.status,
+                stories: Vec::new(),
+            }),
+            "story" => {
+                let epic = epics
+                    .iter_mut()
+                    .find(|epic| epic.external_id.as_ref() == Some(&row.epic_id))
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "Line {}: story {} belongs to epic '{}', which no earlier line defines.",
+                            line,
+                            row.id,
+                            row.epic_id
+                        )
+                    })?;
+                epic.stories.push(ImportedStory {
+                    external_id: Some(row.id),
+                    name: row.name,
+                    description: row.description,
+                    status: row.status,
+                });
+            }
+            other => {
+                return Err(anyhow::anyhow!(
+                    "Line {}: unknown type '{}'. Expected epic or story.",
+                    line,
+                    other
+                ))
+            }
+        }
+    }
+    Ok(epics)
+}
+
+fn write_ndjson_line(
+    writer: &mut impl Write,
+    kind: &str,
+    id: &str,
+    item: &impl Serialize,
+) -> Result<()> {
+    let mut value = serde_json::to_value(item)?;
+    if let serde_json::Value::Object(fields) = &mut value {
+        fields.insert("type".to_owned(), kind.into());
+        fields.insert("id".to_owned(), id.into());
+    }
+    writeln!(writer, "{}", serde_json::to_string(&value)?)?;
+    Ok(())
+}
+
+// Every recorded status change of the epic's stories. Histories are capped at
+// status_history_limit, so changes older than that are no longer replayed.
+fn get_status_events(db_state: &DBState, epic_id: &String) -> Vec<StatusEvent> {
+    db_state
+        .stories_of(epic_id)
+        .unwrap_or_default()
+        .into_iter()
+        .flat_map(|(story_id, story)| {
+            story.status_history.iter().map(|change| StatusEvent {
+                story_id: story_id.clone(),
+                status: change.status.clone(),
+                at: change.at,
+            })
+        })
+        .collect()
+}
+
+// Events for stories outside `estimates` are ignored. A resolved or closed story burns
+// its estimate down, and reopening it adds the estimate back.
+fn get_burndown_points(
+    estimates: &HashMap<String, u32>,
+    events: &[StatusEvent],
+) -> Vec<BurndownPoint> {
+    let mut remaining: u32 = estimates.values().sum();
+    let mut done: HashSet<&String> = HashSet::new();
+    let mut points: Vec<BurndownPoint> = Vec::new();
+
+    for event in events.iter().sorted_by_key(|event| event.at) {
+        let Some(estimate) = estimates.get(&event.story_id) else {
+            continue;
+        };
+        let is_done = matches!(event.status, Status::Resolved | Status::Closed);
+        if is_done && done.insert(&event.story_id) {
+            remaining -= estimate;
+        } else if !is_done && done.remove(&event.story_id) {
+            remaining += estimate;
+        }
+
+        // Keep one point per day, holding the remaining points at the end of it
+        let date = event.at.date_naive();
+        match points.last_mut() {
+            Some(point) if point.date == date => point.remaining = remaining,
+            _ => points.push(BurndownPoint { date, remaining }),
+        }
+    }
+
+    points
+}
+
+// Name matches count for more than description matches
+const SEARCH_NAME_WEIGHT: i64 = 2;
+// Added when the name starts with the query, so prefixes beat mid-word hits
+const SEARCH_PREFIX_BONUS: i64 = 1000;
+
+fn get_search_score(
+    matcher: &SkimMatcherV2,
+    query: &str,
+    name: &str,
+    description: &str,
+) -> Option<i64> {
+    let name_score = matcher.fuzzy_match(name, query).map(|score| {
+        let is_prefix = name.to_lowercase().starts_with(&query.to_lowercase());
+        score * SEARCH_NAME_WEIGHT + if is_prefix { SEARCH_PREFIX_BONUS } else { 0 }
+    });
+    let description_score = matcher.fuzzy_match(description, query);
+    name_score.max(description_score)
+}
+
+fn escape_ics_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn get_ics_status(status: &Status) -> &'static str {
+    match status {
+        Status::Open | Status::Blocked => "NEEDS-ACTION",
+        Status::InProgress => "IN-PROCESS",
+        Status::Resolved | Status::Closed => "COMPLETED",
+    }
+}
+
+fn get_average_duration(durations: &[Duration]) -> Option<Duration> {
+    if durations.is_empty() {
+        return None;
+    }
+    let total = durations.iter().fold(Duration::zero(), |sum, d| sum + *d);
+    Some(total / durations.len() as i32)
+}
+
+// See Status::can_transition_to. Reopening goes through here too, Open is always allowed.
+fn check_status_transition(id: &String, current: &Status, next: &Status) -> DbResult<()> {
+    if !current.can_transition_to(next) {
+        return Err(DbError::Validation(format!(
+            "{} can't move from {} to {}.",
+            id, current, next
+        )));
+    }
+    Ok(())
+}
+
+// Sets the status and records the change, dropping the oldest entries past `limit`.
+// The blocked reason goes away with the Blocked status.
+fn set_story_status(story: &mut Story, status: Status, now: DateTime<Utc>, limit: usize) {
+    if status != Status::Blocked {
+        story.blocked_reason = None;
+    }
+    story.status = status.clone();
+    story.updated_at = Some(now);
+    story.status_history.push(StatusChange { status, at: now });
+    let excess = story.status_history.len().saturating_sub(limit);
+    story.status_history.drain(..excess);
+}
+
+// The status an epic's stories call for, or None to leave the epic as it is. All
+// stories closed closes it, any story in progress starts it, and a closed epic that
+// gets unclosed work again is reopened. Epics without stories are Open.
+fn get_derived_epic_status(db_state: &DBState, epic_id: &String) -> Option<Status> {
+    let epic = db_state.epics.get(epic_id)?;
+    let stories = db_state.stories_of(epic_id)?;
+    let derived = if stories.is_empty() {
+        Status::Open
+    } else if stories
+        .iter()
+        .all(|(_, story)| story.status == Status::Closed)
+    {
+        Status::Closed
+    } else if stories
+        .iter()
+        .any(|(_, story)| story.status == Status::InProgress)
+    {
+        Status::InProgress
+    } else if epic.status == Status::Closed {
+        Status::Open
+    } else {
+        return None;
+    };
+    (derived != epic.status).then_some(derived)
+}
+
+// Derived statuses skip check_status_transition, the stories already moved
+fn apply_derived_epic_status(db_state: &mut DBState, epic_id: &String, now: DateTime<Utc>) -> bool {
+    let Some(status) = get_derived_epic_status(db_state, epic_id) else {
+        return false;
+    };
+    let epic = db_state.epics.get_mut(epic_id).unwrap();
+    epic.status = status;
+    epic.updated_at = Some(now);
+    true
+}
+
+// For the epics listing any of the given stories
+fn apply_derived_epic_statuses(db_state: &mut DBState, story_ids: &[String], now: DateTime<Utc>) {
+    let epic_ids = story_ids
+        .iter()
+        .filter_map(|story_id| db_state.find_epic_of_story(story_id).cloned())
+        .unique()
+        .collect_vec();
+    for epic_id in epic_ids {
+        apply_derived_epic_status(db_state, &epic_id, now);
+    }
+}
+
+// Stories of the epic that are neither resolved nor closed, sorted by id
+fn get_unfinished_story_ids(db_state: &DBState, epic_id: &String) -> Vec<String> {
+    db_state
+        .stories_of(epic_id)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|(_, story)| !story.is_finished())
+        .map(|(story_id, _)| story_id.clone())
+        .sorted()
+        .collect()
+}
+
+// Walks up the parent chain, stopping if it ever loops back on itself
+fn get_story_ancestors(db_state: &DBState, story_id: &String) -> Vec<String> {
+    let mut ancestors: Vec<String> = Vec::new();
+    let mut current = db_state
+        .stories
+        .get(story_id)
+        .and_then(|story| story.parent.clone());
+    while let Some(parent_id) = current {
+        if ancestors.contains(&parent_id) {
+            break;
+        }
+        current = db_state
+            .stories
+            .get(&parent_id)
+            .and_then(|story| story.parent.clone());
+        ancestors.push(parent_id);
+    }
+    ancestors
+}
+
+// Takes the story off the source epic's list and appends it to the target's
+fn relocate_story(
+    db_state: &mut DBState,
+    story_id: &String,
+    source_id: &String,
+    target_id: &String,
+    now: DateTime<Utc>,
+) {
+    if let Some(source) = db_state.epics.get_mut(source_id) {
+        source.stories.retain(|id| id != story_id);
+    }
+    if let Some(target) = db_state.epics.get_mut(target_id) {
+        target.stories.push(story_id.clone());
+    }
+    // Its key only meant something among the stories it left
+    if let Some(story) = db_state.stories.get_mut(story_id) {
+        story.updated_at = Some(now);
+        story.order = None;
+    }
+    apply_derived_epic_status(db_state, source_id, now);
+    apply_derived_epic_status(db_state, target_id, now);
+}
+
+// Subtask links don't cross epics, so after a move a story whose parent lives in
+// another epic loses the link
+fn drop_cross_epic_parents(db_state: &mut DBState) {
+    let epic_of: HashMap<&String, &String> = db_state
+        .epics
+        .iter()
+        .flat_map(|(epic_id, epic)| epic.stories.iter().map(move |story_id| (story_id, epic_id)))
+        .collect();
+    let crossing = db_state
+        .stories
+        .iter()
+        .filter(|(story_id, story)| {
+            story
+                .parent
+                .as_ref()
+                .is_some_and(|parent_id| epic_of.get(parent_id) != epic_of.get(story_id))
+        })
+        .map(|(story_id, _)| story_id.clone())
+        .collect_vec();
+    for story_id in crossing {
+        if let Some(story) = db_state.stories.get_mut(&story_id) {
+            story.parent = None;
+        }
+    }
+}
+
+// Subtasks of deleted stories become top-level stories again
+fn detach_subtasks(db_state: &mut DBState, deleted_ids: &[String]) {
+    for story in db_state.stories.values_mut() {
+        if story
+            .parent
+            .as_ref()
+            .is_some_and(|parent_id| deleted_ids.contains(parent_id))
+        {
+            story.parent = None;
+        }
+    }
+}
+
+// Epics are checked in id order, then stories, so the report is stable
+fn get_integrity_problems(db_state: &DBState) -> Vec<IntegrityProblem> {
+    let problem = |kind: &str, id: &String, message: String| IntegrityProblem {
+        kind: kind.to_owned(),
+        id: id.clone(),
+        message,
+    };
+    let mut problems = Vec::new();
+
+    // Which epics list each story, to find stories listed more than once
+    let mut owners: HashMap<&String, Vec<&String>> = HashMap::new();
+    for (epic_id, epic) in db_state.epics_sorted() {
+        for story_id in &epic.stories {
+            if !db_state.stories.contains_key(story_id) {
+                problems.push(problem(
+                    "missing_story",
+                    epic_id,
+                    format!(
+                        "Epic {} lists story {}, which does not exist.",
+                        epic_id, story_id
+                    ),
+                ));
+            }
+            owners.entry(story_id).or_default().push(epic_id);
+        }
+    }
+    for (story_id, epic_ids) in owners.iter().sorted_by(|a, b| a.0.cmp(b.0)) {
+        if epic_ids.len() > 1 {
+            problems.push(problem(
+                "shared_story",
+                story_id,
+                format!(
+                    "Story {} is listed {} times, by epics {}.",
+                    story_id,
+                    epic_ids.len(),
+                    epic_ids.iter().join(", ")
+                ),
+            ));
+        }
+    }
+
+    for story_id in get_orphan_story_ids(db_state) {
+        let message = format!("Story {} belongs to no epic.", story_id);
+        problems.push(problem("orphan_story", &story_id, message));
+    }
+
+    for (story_id, story) in db_state.stories.iter().sorted_by(|a, b| a.0.cmp(b.0)) {
+        if let Some(parent_id) = &story.parent {
+            let epic_of = |id| owners.get(id).and_then(|epic_ids| epic_ids.first());
+            if !db_state.stories.contains_key(parent_id) {
+                problems.push(problem(
+                    "missing_parent",
+                    story_id,
+                    format!(
+                        "Story {} has parent {}, which does not exist.",
+                        story_id, parent_id
+                    ),
+                ));
+            } else if epic_of(story_id) != epic_of(parent_id) {
+                problems.push(problem(
+                    "foreign_parent",
+                    story_id,
+                    format!(
+                        "Story {} has parent {} from another epic.",
+                        story_id, parent_id
+                    ),
+                ));
+            }
+        }
+        if story.blocked_reason.is_some() && story.status != Status::Blocked {
+            problems.push(problem(
+                "stale_blocked_reason",
+                story_id,
+                format!(
+                    "Story {} has a blocked reason but is {}.",
+                    story_id, story.status
+                ),
+            ));
+        }
+    }
+
+    problems
+}
+
+fn get_orphan_story_ids(db_state: &DBState) -> Vec<String> {
+    let referenced: HashSet<&String> = db_state
+        .epics
+        .values()
+        .flat_map(|epic| epic.stories.iter())
+        .collect();
+
+    let mut orphans: Vec<String> = db_state
+        .stories
+        .keys()
+        .filter(|id| !referenced.contains(id))
+        .cloned()
+        .collect();
+    orphans.sort();
+
+    orphans
+}
+
+fn find_matching_epic(db_state: &DBState, imported: &ImportedEpic) -> Option<String> {
+    // Prefer the external id, then fall back to the epic name
+    if let Some(external_id) = &imported.external_id {
+        if db_state.epics.contains_key(external_id) {
+            return Some(external_id.clone());
+        }
+    }
+
+    db_state
+        .epics
+        .iter()
+        .find(|(_, epic)| epic.name == imported.name)
+        .map(|(id, _)| id.clone())
+}
+
+fn find_matching_story(
+    db_state: &DBState,
+    epic_id: &String,
+    imported: &ImportedStory,
+) -> Option<String> {
+    let epic = db_state.epics.get(epic_id)?;
+
+    // Only stories that already belong to this epic are considered
+    if let Some(external_id) = &imported.external_id {
+        if epic.stories.contains(external_id) {
+            return Some(external_id.clone());
+        }
+    }
+
+    epic.stories
+        .iter()
+        .find(|id| {
+            db_state
+                .stories
+                .get(*id)
+                .is_some_and(|story| story.name == imported.name)
+        })
+        .cloned()
+}
+
+pub mod test_utils {
+    use super::*;
+    use chrono::TimeZone;
+    use std::{cell::RefCell, collections::HashMap};
+
+    pub struct MockDB {
+        last_written_state: RefCell<DBState>,
+    }
+
+    impl MockDB {
+        pub fn new() -> Self {
+            Self {
+                last_written_state: RefCell::new(DBState {
+                    last_item_id: "0".to_string(),
+                    epics: HashMap::new(),
+                    stories: HashMap::new(),
+                    revision: 0,
+                }),
+            }
+        }
+    }
+
+    impl Database for MockDB {
+        fn read_db(&self) -> Result<DBState> {
+            let state = self.last_written_state.borrow().clone();
+            Ok(state)
+        }
+
+        fn write_db(&self, db_state: &DBState) -> Result<()> {
+            let latest_state = &self.last_written_state;
+            *latest_state.borrow_mut() = db_state.clone();
+            Ok(())
+        }
+    }
+
+    pub struct FixedClock(pub DateTime<Utc>);
+
+    pub fn fixed_time() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2022, 1, 1, 12, 0, 0).unwrap()
+    }
+
+    impl Clock for FixedClock {
+        fn now(&self) -> DateTime<Utc> {
+            self.0
+        }
+    }
+
+    pub fn arrange_test() -> (JiraDatabase, String, String) {
+        // Arrange db and data
+        let mock = Box::new(MockDB::new());
+        let mut db = JiraDatabase::with_database(mock);
+        db.clock = Box::new(FixedClock(fixed_time()));
+        let epic = Epic::new("".to_owned(), "".to_owned());
+        let story = Story::new("".to_owned(), "".to_owned());
+
+        // Add data to db
+        let result = db.create_epic(epic);
+        let epic_id = result.unwrap();
+        let result = db.create_story(story, &epic_id);
+        let story_id = result.unwrap();
+
+        (db, epic_id, story_id)
+    }
+
+    // The statuses an open item passes through to reach status, see Status::can_transition_to
+    fn get_workflow_steps(status: Status) -> Vec<Status> {
+        match status {
+            Status::Resolved => vec![Status::InProgress, Status::Resolved],
+            Status::Closed => vec![Status::InProgress, Status::Resolved, Status::Closed],
+            other => vec![other],
+        }
+    }
+
+    // Walks an open story to status one workflow step at a time
+    pub fn walk_story_to(db: &JiraDatabase, story_id: &String, status: Status) -> DbResult<()> {
+        for step in get_workflow_steps(status) {
+            db.update_story_status(story_id, step)?;
+        }
+        Ok(())
+    }
+
+    // Walks an open epic to status one workflow step at a time
+    pub fn walk_epic_to(
+        db: &JiraDatabase,
+        epic_id: &String,
+        status: Status,
+    ) -> DbResult<Vec<String>> {
+        let mut warnings = Vec::new();
+        for step in get_workflow_steps(status) {
+            warnings = db.update_epic_status(epic_id, step)?;
+        }
+        Ok(warnings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::test_utils::arrange_test;
+    use crate::models::{ChecklistItem, DBState, Epic, Story};
+    use nanoid::nanoid;
+
+    use super::test_utils::{fixed_time, walk_epic_to, walk_story_to, FixedClock, MockDB};
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    #[test]
+    fn create_epic_should_work() {
+        // Arrange
+        let mock = Box::new(MockDB::new());
+        let mut db = JiraDatabase::with_database(mock);
+        db.clock = Box::new(FixedClock(fixed_time()));
+        let mut epic = Epic::new("An Epic".to_owned(), "Description".to_owned());
+
+        // Act
+        let result = db.create_epic(epic.clone());
+
+        // Assert part 1
+        assert_eq!(result.is_ok(), true);
+
+        // Arrange part 2
+        let epic_id = result.unwrap();
+        let db_state = db.read_db().unwrap();
+        epic.created_at = Some(fixed_time());
+        epic.updated_at = Some(fixed_time());
+
+        // Assert
+        assert_eq!(db_state.epics.get(&epic_id), Some(&epic));
+        assert_eq!(&db_state.last_item_id, &epic_id);
+    }
+
+    #[test]
+    fn create_epic_should_use_template_for_blank_description() {
+        // Arrange
+        let mut db = JiraDatabase::with_database(Box::new(MockDB::new()));
+        db.epic_template = Some("Goal: | Scope: | Out of scope:".to_owned());
+
+        // Act
+        let blank_id = db
+            .create_epic(Epic::new("Blank".to_owned(), "  ".to_owned()))
+            .unwrap();
+        let given_id = db
+            .create_epic(Epic::new("Given".to_owned(), "Own words".to_owned()))
+            .unwrap();
+
+        // Assert
+        let db_state = db.read_db().unwrap();
+        assert_eq!(
+            db_state.epics[&blank_id].description,
+            "Goal: | Scope: | Out of scope:"
+        );
+        assert_eq!(db_state.epics[&given_id].description, "Own words");
+    }
+
+    #[test]
+    fn create_epic_should_keep_blank_description_without_template() {
+        // Arrange
+        let db = JiraDatabase::with_database(Box::new(MockDB::new()));
+
+        // Act
+        let epic_id = db
+            .create_epic(Epic::new("Blank".to_owned(), "".to_owned()))
+            .unwrap();
+
+        // Assert
+        assert_eq!(db.read_db().unwrap().epics[&epic_id].description, "");
+    }
+
+    #[test]
+    fn create_story_should_error_if_invalid_epic_id() {
+        // Arrange
+        let mock = Box::new(MockDB::new());
+        let db = JiraDatabase::with_database(mock);
+        let story = Story::new("".to_owned(), "".to_owned());
+        let non_existent_epic_id = nanoid!(6);
+
+        // Act
+        let result = db.create_story(story, &non_existent_epic_id);
+
+        // Assert
+        assert_eq!(result.is_err(), true);
+    }
+
+    #[test]
+    fn create_story_should_work() {
+        // Arrange test
+        let (db, epic_id, story_id) = arrange_test();
+        let mut story = Story::new("".to_owned(), "".to_owned());
+        story.created_at = Some(fixed_time());
+        story.updated_at = Some(fixed_time());
+
+        // Arrange for reading the DB assertion
+        let db_state = db.read_db().unwrap();
+        let contains_story = db_state
+            .epics
+            .get(&epic_id)
+            .unwrap()
+            .stories
+            .contains(&story_id);
+
+        // Assert
+        assert_eq!(db_state.last_item_id, story_id);
+        assert_eq!(contains_story, true);
+        assert_eq!(db_state.stories.get(&story_id), Some(&story));
+    }
+
+    #[test]
+    fn a_new_change_after_undo_should_discard_the_redo() {
+        // Arrange
+        let db = JiraDatabase::with_database(Box::new(MockDB::new()));
+        let first_id = db
+            .create_epic(Epic::new("first".to_owned(), "".to_owned()))
+            .unwrap();
+
+        // Act
+        let undone = db.undo().unwrap();
+        let after_undo = db.read_db().unwrap();
+        let redone = db.redo().unwrap();
+        let after_redo = db.read_db().unwrap();
+        db.undo().unwrap();
+        db.create_epic(Epic::new("second".to_owned(), "".to_owned()))
+            .unwrap();
+        let redone_after_create = db.redo().unwrap();
+
+        // Assert
+        assert!(undone);
+        assert!(after_undo.epics.is_empty());
+        assert!(redone);
+        assert_eq!(after_redo.epics[&first_id].name, "first");
+        assert!(!redone_after_create);
+        let db_state = db.read_db().unwrap();
+        assert_eq!(db_state.epics.len(), 1);
+        assert!(!db_state.epics.contains_key(&first_id));
+    }
+
+    #[test]
+    fn restore_snapshot_at_should_bring_back_an_earlier_state() {
+        // Arrange
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = JiraDatabase::with_database(Box::new(MockDB::new()));
+        db.snapshot_history = Some(dir.path().join("history.ndjson"));
+        db.clock = Box::new(FixedClock(fixed_time()));
+        let first_id = db
+            .create_epic(Epic::new("first".to_owned(), "".to_owned()))
+            .unwrap();
+        let first_state = db.read_db().unwrap();
+        db.clock = Box::new(FixedClock(fixed_time() + Duration::hours(2)));
+        let second_id = db
+            .create_epic(Epic::new("second".to_owned(), "".to_owned()))
+            .unwrap();
+
+        // Act
+        let restored_at = db
+            .restore_snapshot_at(fixed_time() + Duration::hours(1))
+            .unwrap();
+        let db_state = db.read_db().unwrap();
+
+        // Assert
+        assert_eq!(restored_at, fixed_time());
+        assert_eq!(db_state.epics, first_state.epics);
+        assert!(db_state.epics.contains_key(&first_id));
+        assert!(!db_state.epics.contains_key(&second_id));
+        // Nothing was written that early
+        assert!(matches!(
+            db.restore_snapshot_at(fixed_time() - Duration::hours(1)),
+            Err(DbError::Validation(_))
+        ));
+        // The restore is a change like any other
+        assert!(db.undo().unwrap());
+        assert!(db.read_db().unwrap().epics.contains_key(&second_id));
+    }
+
+    #[test]
+    fn restore_snapshot_at_should_need_a_history_file() {
+        // Arrange
+        let (db, _epic_id, _story_id) = arrange_test();
+
+        // Act
+        let result = db.restore_snapshot_at(fixed_time());
+
+        // Assert
+        assert!(matches!(result, Err(DbError::Validation(_))));
+    }
+
+    #[test]
+    fn undo_should_keep_at_most_undo_limit_changes() {
+        // Arrange
+        let mut db = JiraDatabase::with_database(Box::new(MockDB::new()));
+        db.undo_limit = 2;
+        for name in ["a", "b", "c"] {
+            db.create_epic(Epic::new(name.to_owned(), "".to_owned()))
+                .unwrap();
+        }
+
+        // Act
+        let undone = [db.undo().unwrap(), db.undo().unwrap(), db.undo().unwrap()];
+
+        // Assert
+        assert_eq!(undone, [true, true, false]);
+        let names = db
+            .read_db()
+            .unwrap()
+            .epics
+            .into_values()
+            .map(|epic| epic.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["a".to_owned()]);
+    }
+
+    // Counts reads that reach the wrapped backend
+    struct CountingDB {
+        inner: MockDB,
+        reads: Rc<Cell<usize>>,
+    }
+
+    impl Database for CountingDB {
+        fn read_db(&self) -> Result<DBState> {
+            self.reads.set(self.reads.get() + 1);
+            self.inner.read_db()
+        }
+
+        fn write_db(&self, db_state: &DBState) -> Result<()> {
+            self.inner.write_db(db_state)
+        }
+    }
+
+    fn arrange_counting_db(cache_reads: bool) -> (JiraDatabase, Rc<Cell<usize>>) {
+        let reads = Rc::new(Cell::new(0));
+        let mut db = JiraDatabase::with_database(Box::new(CountingDB {
+            inner: MockDB::new(),
+            reads: Rc::clone(&reads),
+        }));
+        db.cache_reads = cache_reads;
+        (db, reads)
+    }
+
+    #[test]
+    fn cached_reads_should_hit_the_backend_once_until_a_change() {
+        // Arrange
+        let (uncached, uncached_reads) = arrange_counting_db(false);
+        let (cached, cached_reads) = arrange_counting_db(true);
+
+        // Act, roughly what drawing a page and handling a key press read
+        for db in [&uncached, &cached] {
+            for _ in 0..4 {
+                db.read_db().unwrap();
+            }
+        }
+
+        // Assert
+        assert_eq!(uncached_reads.get(), 4);
+        assert_eq!(cached_reads.get(), 1);
+        cached.invalidate_cache();
+        cached.read_db().unwrap();
+        assert_eq!(cached_reads.get(), 2);
+    }
+
+    #[test]
+    fn cached_reads_should_see_every_change() {
+        // Arrange
+        let (db, _reads) = arrange_counting_db(true);
+        db.read_db().unwrap();
+
+        // Act
+        let epic_id = db
+            .create_epic(Epic::new("".to_owned(), "".to_owned()))
+            .unwrap();
+        let created = db.read_db().unwrap();
+        db.delete_epic(&epic_id).unwrap();
+        let deleted = db.read_db().unwrap();
+        db.undo().unwrap();
+        let undone = db.read_db().unwrap();
+
+        // Assert
+        assert!(created.epics.contains_key(&epic_id));
+        assert!(!deleted.epics.contains_key(&epic_id));
+        assert!(undone.epics.contains_key(&epic_id));
+    }
+
+    #[test]
+    fn repeated_quick_captures_should_reuse_the_inbox_epic() {
+        // Arrange test
+        let (db, epic_id, _story_id) = arrange_test();
+
+        // Act
+        let first_id = db.quick_capture("call the bank".to_owned()).unwrap();
+        let second_id = db.quick_capture("renew passport".to_owned()).unwrap();
+
+        // Assert
+        let db_state = db.read_db().unwrap();
+        let inboxes = db_state
+            .epics
+            .iter()
+            .filter(|(_, epic)| epic.name == INBOX_EPIC_NAME)
+            .collect::<Vec<_>>();
+        assert_eq!(inboxes.len(), 1);
+        let (inbox_id, inbox) = inboxes[0];
+        assert_ne!(*inbox_id, epic_id);
+        assert_eq!(inbox.stories, vec![first_id.clone(), second_id]);
+        assert_eq!(db_state.stories[&first_id].name, "call the bank");
+        assert_eq!(db_state.stories[&first_id].created_at, Some(fixed_time()));
+    }
+
+    #[test]
+    fn create_epic_returning_should_match_what_is_stored() {
+        // Arrange
+        let (db, _epic_id, _story_id) = arrange_test();
+
+        // Act
+        let (epic_id, epic) = db
+            .create_epic_returning(Epic::new("An Epic".to_owned(), "".to_owned()))
+            .unwrap();
+
+        // Assert
+        assert_eq!(epic.created_at, Some(fixed_time()));
+        assert_eq!(db.read_db().unwrap().epics.get(&epic_id), Some(&epic));
+    }
+
+    #[test]
+    fn create_story_returning_should_match_what_is_stored() {
+        // Arrange
+        let (db, epic_id, _story_id) = arrange_test();
+
+        // Act
+        let (story_id, story) = db
+            .create_story_returning(Story::new("A Story".to_owned(), "".to_owned()), &epic_id)
+            .unwrap();
+
+        // Assert
+        assert_eq!(story.created_at, Some(fixed_time()));
+        assert_eq!(db.read_db().unwrap().stories.get(&story_id), Some(&story));
+    }
+
+    #[test]
+    fn delete_epic_should_error_if_invalid_epic_id() {
+        // Arrange
+        let mock = Box::new(MockDB::new());
+        let db = JiraDatabase::with_database(mock);
+        let non_existent_epic_id = nanoid!(6);
+
+        // Act
+        let result = db.delete_epic(&non_existent_epic_id);
+
+        // Assert
+        assert_eq!(result.is_err(), true);
+    }
+
+    #[test]
+    fn delete_epic_should_report_a_missing_epic_as_not_found() {
+        // Arrange
+        let db = JiraDatabase::with_database(Box::new(MockDB::new()));
+
+        // Act
+        let result = db.delete_epic(&"missing".to_owned());
+
+        // Assert
+        assert!(matches!(
+            result,
+            Err(DbError::EpicNotFound(epic_id)) if epic_id == "missing"
+        ));
+    }
+
+    #[test]
+    fn backend_errors_should_keep_io_failures_distinguishable() {
+        // Arrange
+        let db = JiraDatabase::new(PathBuf::from("./data/missing/db.json"));
+
+        // Act
+        let result = db.read_db();
+
+        // Assert
+        assert!(matches!(result, Err(DbError::Io(_))));
+    }
+
+    #[test]
+    fn exists_predicates_should_check_ids() {
+        // Arrange
+        let (db, epic_id, story_id) = arrange_test();
+
+        // Act
+        let epic_exists = db.epic_exists(&epic_id).unwrap();
+        let story_as_epic = db.epic_exists(&story_id).unwrap();
+        let story_exists = db.story_exists(&story_id).unwrap();
+        let missing_story = db.story_exists(&"missing".to_owned()).unwrap();
+
+        // Assert
+        assert!(epic_exists);
+        assert!(!story_as_epic);
+        assert!(story_exists);
+        assert!(!missing_story);
+    }
+
+    #[test]
+    fn exists_predicates_should_propagate_read_errors() {
+        // Arrange
+        let db = JiraDatabase::new(PathBuf::from("./data/missing/db.json"));
+
+        // Act
+        let epic = db.epic_exists(&"1".to_owned());
+        let story = db.story_exists(&"1".to_owned());
+
+        // Assert
+        assert!(matches!(epic, Err(DbError::Io(_))));
+        assert!(matches!(story, Err(DbError::Io(_))));
+    }
+
+    #[test]
+    fn delete_epic_should_work() {
+        // Arrange test
+        let (db, epic_id, story_id) = arrange_test();
+
+        // Act
+        let result = db.delete_epic(&epic_id);
+        let db_state = db.read_db().unwrap();
+
+        // Assert
+        assert_eq!(result.is_ok(), true);
+        assert_eq!(db_state.last_item_id, epic_id);
+        assert_eq!(db_state.epics.get(&epic_id), None);
+        assert_eq!(db_state.stories.get(&story_id), None);
+    }
+
+    #[test]
+    fn delete_story_should_error_if_invalid_epic_id() {
+        // Arrange test
+        let (db, _epic_id, story_id) = arrange_test();
+
+        // Generate a non-existent epic id
+        let non_existent_epic_id = nanoid!(6);
+
+        // Act
+        let result = db.delete_story(&non_existent_epic_id, &story_id);
+
+        // Assert
+        assert_eq!(result.is_err(), true);
+    }
+
+    #[test]
+    fn delete_story_should_error_if_story_not_found_in_epic() {
+        // Arrange test
+        let (db, epic_id, _story_id) = arrange_test();
+
+        // Generate a non-existent story id
+        let non_existent_story_id = nanoid!(6);
+
+        // Act
+        let result = db.delete_story(&epic_id, &non_existent_story_id);
+
+        // Assert
+        assert_eq!(result.is_err(), true);
+    }
+
+    #[test]
+    fn delete_story_should_work() {
+        // Arrange test
+        let (db, epic_id, story_id) = arrange_test();
+
+        // Act
+        let result = db.delete_story(&epic_id, &story_id);
+        let db_state = db.read_db().unwrap();
+        let contains_stories = db_state
+            .epics
+            .get(&epic_id)
+            .unwrap()
+            .stories
+            .contains(&story_id);
+
+        // Assert
+        assert_eq!(result.is_ok(), true);
+        assert_eq!(db_state.last_item_id, story_id);
+        assert_eq!(contains_stories, false);
+        assert_eq!(db_state.stories.get(&story_id), None);
+    }
+
+    #[test]
+    fn update_epic_status_should_error_if_invalid_epic_id() {
+        // Arrange
+        let mock = Box::new(MockDB::new());
+        let db = JiraDatabase::with_database(mock);
+        let non_existent_epic_id = nanoid!(6);
+
+        // Act
+        let result = db.update_epic_status(&non_existent_epic_id, Status::Closed);
+
+        // Assert
+        assert_eq!(result.is_err(), true);
+    }
+
+    #[test]
+    fn update_epic_status_should_work() {
+        // Arrange test
+        let (db, epic_id, _story_id) = arrange_test();
+
+        // Act
+        let result = walk_epic_to(&db, &epic_id, Status::Closed);
+        let db_state = db.read_db().unwrap();
+
+        // Assert
+        assert_eq!(result.is_ok(), true);
+        assert_eq!(db_state.epics.get(&epic_id).unwrap().status, Status::Closed);
+    }
+
+    #[test]
+    fn update_epic_status_should_block_closing_with_open_stories() {
+        // Arrange
+        let (mut db, epic_id, story_id) = arrange_test();
+        walk_epic_to(&db, &epic_id, Status::Resolved).unwrap();
+        db.close_policy = ClosePolicy::Block;
+
+        // Act
+        let result = db.update_epic_status(&epic_id, Status::Closed);
+
+        // Assert
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains(&story_id));
+        let db_state = db.read_db().unwrap();
+        assert_eq!(
+            db_state.epics.get(&epic_id).unwrap().status,
+            Status::Resolved
+        );
+    }
+
+    #[test]
+    fn update_epic_status_should_allow_closing_when_stories_are_done() {
+        // Arrange
+        let (mut db, epic_id, story_id) = arrange_test();
+        db.close_policy = ClosePolicy::Block;
+        walk_story_to(&db, &story_id, Status::Resolved).unwrap();
+
+        // Act
+        let result = walk_epic_to(&db, &epic_id, Status::Closed);
+
+        // Assert
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn update_epic_status_should_allow_closing_with_open_stories_by_default() {
+        // Arrange
+        let (db, epic_id, _story_id) = arrange_test();
+
+        // Act
+        let result = walk_epic_to(&db, &epic_id, Status::Resolved);
+
+        // Assert
+        assert_eq!(result.unwrap(), Vec::<String>::new());
+        let db_state = db.read_db().unwrap();
+        assert_eq!(
+            db_state.epics.get(&epic_id).unwrap().status,
+            Status::Resolved
+        );
+    }
+
+    #[test]
+    fn update_epic_status_should_report_open_stories_when_warning() {
+        // Arrange
+        let (mut db, epic_id, story_id) = arrange_test();
+        db.close_policy = ClosePolicy::Warn;
+
+        // Act
+        let result = walk_epic_to(&db, &epic_id, Status::Closed);
+
+        // Assert
+        assert_eq!(result.unwrap(), vec![story_id]);
+        let db_state = db.read_db().unwrap();
+        assert_eq!(db_state.epics.get(&epic_id).unwrap().status, Status::Closed);
+    }
+
+    #[test]
+    fn leaving_blocked_should_clear_the_reason() {
+        // Arrange test
+        let (db, _epic_id, story_id) = arrange_test();
+        db.block_story(&story_id, Some(" waiting on design ".to_owned()))
+            .unwrap();
+        let blocked = db.read_db().unwrap().stories[&story_id].clone();
+
+        // Act
+        db.update_story_status(&story_id, Status::InProgress)
+            .unwrap();
+
+        // Assert
+        assert_eq!(blocked.status, Status::Blocked);
+        assert_eq!(blocked.blocked_reason.as_deref(), Some("waiting on design"));
+        let story = &db.read_db().unwrap().stories[&story_id];
+        assert_eq!(story.status, Status::InProgress);
+        assert_eq!(story.blocked_reason, None);
+    }
+
+    #[test]
+    fn blocking_without_a_reason_should_follow_require_blocked_reason() {
+        // Arrange test
+        let (mut db, epic_id, story_id) = arrange_test();
+        let other_id = db
+            .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+            .unwrap();
+
+        // Act
+        db.update_story_status(&story_id, Status::Blocked).unwrap();
+        db.require_blocked_reason = true;
+        let blank = db.block_story(&other_id, Some("  ".to_owned()));
+        let bulk = db.update_stories_status(std::slice::from_ref(&other_id), Status::Blocked);
+
+        // Assert
+        let db_state = db.read_db().unwrap();
+        assert_eq!(db_state.stories[&story_id].status, Status::Blocked);
+        assert_eq!(db_state.stories[&story_id].blocked_reason, None);
+        assert!(matches!(blank, Err(DbError::Validation(_))));
+        assert!(matches!(bulk, Err(DbError::Validation(_))));
+        assert_eq!(db_state.stories[&other_id].status, Status::Open);
+    }
+
+    // A story with one checked and two unchecked checklist items
+    fn arrange_checklist_test() -> (JiraDatabase, String) {
+        let (db, _epic_id, story_id) = arrange_test();
+        let mut db_state = db.read_db().unwrap();
+        db_state.stories.get_mut(&story_id).unwrap().checklist = [
+            ("tests pass", true),
+            ("docs updated", false),
+            ("reviewed", false),
+        ]
+        .into_iter()
+        .map(|(text, done)| ChecklistItem {
+            text: text.to_owned(),
+            done,
+        })
+        .collect();
+        db.database.write_db(&db_state).unwrap();
+        (db, story_id)
+    }
+
+    #[test]
+    fn closing_should_need_a_complete_checklist_under_the_strict_done_policy() {
+        // Arrange test
+        let (mut db, story_id) = arrange_checklist_test();
+        db.done_policy = DonePolicy::Strict;
+        db.update_story_status(&story_id, Status::InProgress)
+            .unwrap();
+
+        // Act
+        let single = db.update_story_status(&story_id, Status::Closed);
+        let bulk = db.update_stories_status(std::slice::from_ref(&story_id), Status::Closed);
+        let resolved = db.update_story_status(&story_id, Status::Resolved);
+
+        // Assert
+        assert_eq!(
+            single.unwrap_err().to_string(),
+            format!(
+                "Story {} can't be closed before its checklist is done: docs updated, reviewed.",
+                story_id
+            )
+        );
+        assert!(matches!(bulk, Err(DbError::Validation(_))));
+        assert!(resolved.is_ok());
+        assert_eq!(
+            db.read_db().unwrap().stories[&story_id].status,
+            Status::Resolved
+        );
+    }
+
+    #[test]
+    fn closing_should_ignore_the_checklist_under_the_allow_done_policy() {
+        // Arrange test
+        let (db, story_id) = arrange_checklist_test();
+
+        // Act
+        let result = walk_story_to(&db, &story_id, Status::Closed);
+
+        // Assert
+        assert!(result.is_ok());
+        assert_eq!(
+            db.read_db().unwrap().stories[&story_id].status,
+            Status::Closed
+        );
+    }
+
+    #[test]
+    fn add_checklist_item_should_append_an_unchecked_item() {
+        // Arrange
+        let (db, story_id) = arrange_checklist_test();
+
+        // Act
+        db.add_checklist_item(&story_id, " changelog ".to_owned())
+            .unwrap();
+        let blank = db.add_checklist_item(&story_id, "  ".to_owned());
+
+        // Assert
+        assert!(matches!(blank, Err(DbError::Validation(_))));
+        let checklist = &db.read_db().unwrap().stories[&story_id].checklist;
+        assert_eq!(checklist.len(), 4);
+        assert_eq!(
+            checklist[3],
+            ChecklistItem {
+                text: "changelog".to_owned(),
+                done: false,
+            }
+        );
+    }
+
+    #[test]
+    fn toggle_checklist_item_should_let_a_strict_story_close() {
+        // Arrange
+        let (mut db, story_id) = arrange_checklist_test();
+        db.done_policy = DonePolicy::Strict;
+
+        // Act
+        db.toggle_checklist_item(&story_id, 0).unwrap();
+        db.toggle_checklist_item(&story_id, 0).unwrap();
+        db.toggle_checklist_item(&story_id, 1).unwrap();
+        db.toggle_checklist_item(&story_id, 2).unwrap();
+        let out_of_range = db.toggle_checklist_item(&story_id, 3);
+
+        // Assert
+        assert!(matches!(out_of_range, Err(DbError::Validation(_))));
+        assert!(walk_story_to(&db, &story_id, Status::Closed).is_ok());
+    }
+
+    #[test]
+    fn update_story_status_should_error_if_invalid_story_id() {
+        // Arrange
+        let mock = Box::new(MockDB::new());
+        let db = JiraDatabase::with_database(mock);
+        let non_existent_story_id = nanoid!(6);
+
+        // Act
+        let result = db.update_story_status(&non_existent_story_id, Status::Closed);
+
+        // Assert
+        assert_eq!(result.is_err(), true);
+    }
+
+    #[test]
+    fn update_story_status_should_work() {
+        // Arrange test
+        let (db, _epic_id, story_id) = arrange_test();
+
+        // Act
+        let result = walk_story_to(&db, &story_id, Status::Closed);
+        let db_state = db.read_db().unwrap();
+        let new_status = &db_state.stories.get(&story_id).unwrap().status;
+
+        // Assert
+        assert_eq!(result.is_ok(), true);
+        assert_eq!(*new_status, Status::Closed);
+    }
+
+    #[test]
+    fn update_status_should_reject_invalid_transitions() {
+        // Arrange
+        let (db, epic_id, story_id) = arrange_test();
+        walk_story_to(&db, &story_id, Status::Closed).unwrap();
+        db.update_epic_status(&epic_id, Status::Closed).unwrap();
+
+        // Act
+        let story = db.update_story_status(&story_id, Status::InProgress);
+        let blocked = db.block_story(&story_id, Some("waiting".to_owned()));
+        let bulk = db.update_stories_status(std::slice::from_ref(&story_id), Status::Resolved);
+        let epic = db.update_epic_status(&epic_id, Status::InProgress);
+        let unchanged = db.read_db().unwrap();
+        let reopened = db.update_story_status(&story_id, Status::Open);
+
+        // Assert
+        assert!(matches!(story, Err(DbError::Validation(_))));
+        assert!(matches!(blocked, Err(DbError::Validation(_))));
+        assert!(matches!(bulk, Err(DbError::Validation(_))));
+        assert!(matches!(epic, Err(DbError::Validation(_))));
+        assert_eq!(
+            story.unwrap_err().to_string(),
+            format!("{} can't move from CLOSED to IN PROGRESS.", story_id)
+        );
+        assert_eq!(unchanged.epics[&epic_id].status, Status::Closed);
+        assert!(reopened.is_ok());
+        assert_eq!(
+            db.read_db().unwrap().stories[&story_id].status,
+            Status::Open
+        );
+    }
+
+    #[test]
+    fn recompute_epic_status_should_derive_the_status_from_mixed_stories() {
+        use Status::*;
+        // The epic starts Open. An empty list means it has no stories.
+        let cases = [
+            (vec![Open, Resolved], Open),
+            (vec![Open, InProgress, Closed], InProgress),
+            (vec![Blocked, InProgress], InProgress),
+            (vec![Closed, Resolved], Open),
+            (vec![Resolved, Resolved], Open),
+            (vec![Closed, Closed], Closed),
+            (vec![], Open),
+        ];
+
+        for (statuses, expected) in cases {
+            // Arrange
+            let db = JiraDatabase::with_database(Box::new(MockDB::new()));
+            let epic_id = db
+                .create_epic(Epic::new("".to_owned(), "".to_owned()))
+                .unwrap();
+            let mut db_state = db.read_db().unwrap();
+            for (index, status) in statuses.iter().enumerate() {
+                let story_id = format!("s{}", index);
+                let story = Story {
+                    status: status.clone(),
+                    ..Story::new("".to_owned(), "".to_owned())
+                };
+                db_state.stories.insert(story_id.clone(), story);
+                db_state
+                    .epics
+                    .get_mut(&epic_id)
+                    .unwrap()
+                    .stories
+                    .push(story_id);
+            }
+            db.database.write_db(&db_state).unwrap();
+
+            // Act
+            let result = db.recompute_epic_status(&epic_id);
+
+            // Assert
+            assert_eq!(result.unwrap(), expected, "{:?}", statuses);
+            assert_eq!(db.read_db().unwrap().epics[&epic_id].status, expected);
+        }
+    }
+
+    #[test]
+    fn deleting_the_last_story_should_reopen_the_epic() {
+        // Arrange
+        let (db, epic_id, story_id) = arrange_test();
+        walk_story_to(&db, &story_id, Status::Closed).unwrap();
+        let closed = db.read_db().unwrap().epics[&epic_id].status.clone();
+
+        // Act
+        db.delete_story(&epic_id, &story_id).unwrap();
+
+        // Assert
+        assert_eq!(closed, Status::Closed);
+        assert_eq!(db.read_db().unwrap().epics[&epic_id].status, Status::Open);
+    }
+
+    #[test]
+    fn delete_stories_should_update_the_epic_status() {
+        // Arrange
+        let (db, epic_id, started_id) = arrange_test();
+        let closed_id = db
+            .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+            .unwrap();
+        walk_story_to(&db, &closed_id, Status::Closed).unwrap();
+        db.update_story_status(&started_id, Status::InProgress)
+            .unwrap();
+        let started = db.read_db().unwrap().epics[&epic_id].status.clone();
+
+        // Act
+        db.delete_stories(&epic_id, std::slice::from_ref(&started_id))
+            .unwrap();
+
+        // Assert
+        assert_eq!(started, Status::InProgress);
+        assert_eq!(db.read_db().unwrap().epics[&epic_id].status, Status::Closed);
+    }
+
+    #[test]
+    fn moving_an_open_story_should_reopen_a_closed_target_epic() {
+        // Arrange
+        let (db, target_id, closed_id) = arrange_test();
+        walk_story_to(&db, &closed_id, Status::Closed).unwrap();
+        let source_id = db
+            .create_epic(Epic::new("".to_owned(), "".to_owned()))
+            .unwrap();
+        let open_id = db
+            .create_story(Story::new("".to_owned(), "".to_owned()), &source_id)
+            .unwrap();
+        let closed = db.read_db().unwrap().epics[&target_id].status.clone();
+
+        // Act
+        db.move_story(&open_id, &target_id).unwrap();
+
+        // Assert
+        assert_eq!(closed, Status::Closed);
+        assert_eq!(db.read_db().unwrap().epics[&target_id].status, Status::Open);
+    }
+
+    #[test]
+    fn story_changes_should_update_the_epic_status() {
+        // Arrange
+        let (db, epic_id, story_id) = arrange_test();
+        let epic_status = || db.read_db().unwrap().epics[&epic_id].status.clone();
+
+        // Act
+        db.update_story_status(&story_id, Status::InProgress)
+            .unwrap();
+        let started = epic_status();
+        walk_story_to(&db, &story_id, Status::Closed).unwrap();
+        let closed = epic_status();
+        let other_id = db
+            .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+            .unwrap();
+        let reopened = epic_status();
+        db.delete_story(&epic_id, &other_id).unwrap();
+        let closed_again = epic_status();
+
+        // Assert
+        assert_eq!(started, Status::InProgress);
+        assert_eq!(closed, Status::Closed);
+        assert_eq!(reopened, Status::Open);
+        assert_eq!(closed_again, Status::Closed);
+        assert!(matches!(
+            db.recompute_epic_status(&nanoid!(6)),
+            Err(DbError::EpicNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn update_story_status_should_append_one_history_entry() {
+        // Arrange
+        let (mut db, _epic_id, story_id) = arrange_test();
+        db.clock = Box::new(FixedClock(fixed_time()));
+
+        // Act
+        db.update_story_status(&story_id, Status::InProgress)
+            .unwrap();
+
+        // Assert
+        let history = db.read_db().unwrap().stories[&story_id]
+            .status_history
+            .clone();
+        assert_eq!(
+            history,
+            vec![StatusChange {
+                status: Status::InProgress,
+                at: fixed_time(),
+            }]
+        );
+    }
+
+    #[test]
+    fn reopen_story_should_set_open_and_record_history() {
+        // Arrange
+        let (mut db, _epic_id, story_id) = arrange_test();
+        db.clock = Box::new(FixedClock(fixed_time()));
+        walk_story_to(&db, &story_id, Status::Closed).unwrap();
+
+        // Act
+        db.reopen_story(&story_id).unwrap();
+
+        // Assert
+        let story = db.read_db().unwrap().stories[&story_id].clone();
+        assert_eq!(story.status, Status::Open);
+        assert_eq!(
+            story.status_history.last(),
+            Some(&StatusChange {
+                status: Status::Open,
+                at: fixed_time(),
+            })
+        );
+    }
+
+    #[test]
+    fn reopen_epic_should_skip_the_close_policy_and_leave_stories_alone() {
+        // Arrange
+        let (mut db, epic_id, story_id) = arrange_test();
+        walk_story_to(&db, &story_id, Status::Closed).unwrap();
+        db.update_epic_status(&epic_id, Status::Closed).unwrap();
+        db.close_policy = ClosePolicy::Block;
+
+        // Act
+        let result = db.reopen_epic(&epic_id);
+
+        // Assert
+        let db_state = db.read_db().unwrap();
+        assert!(result.is_ok());
+        assert_eq!(db_state.epics[&epic_id].status, Status::Open);
+        assert_eq!(db_state.stories[&story_id].status, Status::Closed);
+    }
+
+    #[test]
+    fn update_story_status_should_cap_history() {
+        // Arrange
+        let (mut db, _epic_id, story_id) = arrange_test();
+        db.status_history_limit = 2;
+
+        // Act
+        for status in [Status::InProgress, Status::Resolved, Status::Closed] {
+            db.update_story_status(&story_id, status).unwrap();
+        }
+
+        // Assert
+        let statuses = db.read_db().unwrap().stories[&story_id]
+            .status_history
+            .iter()
+            .map(|change| change.status.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(statuses, vec![Status::Resolved, Status::Closed]);
+    }
+
+    // Created at noon, started two hours later, resolved a day after that, then closed
+    fn arrange_flow_history(db: &JiraDatabase, story_id: &String) {
+        let start = fixed_time();
+        let mut db_state = db.read_db().unwrap();
+        let story = db_state.stories.get_mut(story_id).unwrap();
+        story.created_at = Some(start);
+        story.status = Status::Closed;
+        story.status_history = vec![
+            StatusChange {
+                status: Status::InProgress,
+                at: start + Duration::hours(2),
+            },
+            StatusChange {
+                status: Status::Resolved,
+                at: start + Duration::hours(26),
+            },
+            StatusChange {
+                status: Status::Closed,
+                at: start + Duration::hours(30),
+            },
+        ];
+        db.database.write_db(&db_state).unwrap();
+    }
+
+    #[test]
+    fn cycle_and_lead_time_should_use_the_first_finishing_status() {
+        // Arrange
+        let (db, _epic_id, story_id) = arrange_test();
+        arrange_flow_history(&db, &story_id);
+
+        // Act
+        let cycle_time = db.cycle_time(&story_id).unwrap();
+        let lead_time = db.lead_time(&story_id).unwrap();
+
+        // Assert
+        assert_eq!(cycle_time, Some(Duration::hours(24)));
+        assert_eq!(lead_time, Some(Duration::hours(26)));
+    }
+
+    #[test]
+    fn cycle_time_should_be_none_if_never_started() {
+        // Arrange
+        let (db, _epic_id, story_id) = arrange_test();
+        let mut db_state = db.read_db().unwrap();
+        db_state.stories.get_mut(&story_id).unwrap().status = Status::Closed;
+        db.database.write_db(&db_state).unwrap();
+
+        // Act
+        let cycle_time = db.cycle_time(&story_id).unwrap();
+        let missing = db.cycle_time(&"missing".to_owned());
+
+        // Assert
+        assert_eq!(cycle_time, None);
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn flow_metrics_should_average_finished_stories() {
+        // Arrange
+        let (db, epic_id, story_id) = arrange_test();
+        arrange_flow_history(&db, &story_id);
+        let unfinished = db
+            .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+            .unwrap();
+        db.update_story_status(&unfinished, Status::InProgress)
+            .unwrap();
+
+        // Act
+        let metrics = db.flow_metrics().unwrap();
+
+        // Assert
+        assert_eq!(
+            metrics,
+            FlowMetrics {
+                average_cycle_time: Some(Duration::hours(24)),
+                cycle_time_count: 1,
+                average_lead_time: Some(Duration::hours(26)),
+                lead_time_count: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn set_epic_owner_should_set_and_clear_the_owner() {
+        // Arrange test
+        let (db, epic_id, story_id) = arrange_test();
+        db.set_stories_assignee(std::slice::from_ref(&story_id), Some("ana".to_owned()))
+            .unwrap();
+
+        // Act
+        db.set_epic_owner(&epic_id, " bo ".to_owned()).unwrap();
+        let owned = db.read_db().unwrap();
+        db.set_epic_owner(&epic_id, "".to_owned()).unwrap();
+        let cleared = db.read_db().unwrap();
+
+        // Assert
+        assert_eq!(owned.epics[&epic_id].owner.as_deref(), Some("bo"));
+        // The story keeps its own assignee
+        assert_eq!(owned.stories[&story_id].assignee.as_deref(), Some("ana"));
+        assert_eq!(cleared.epics[&epic_id].owner, None);
+        assert!(db.set_epic_owner(&nanoid!(6), "bo".to_owned()).is_err());
+    }
+
+    #[test]
+    fn toggle_pin_should_error_if_invalid_epic_id() {
+        // Arrange
+        let mock = Box::new(MockDB::new());
+        let db = JiraDatabase::with_database(mock);
+        let non_existent_epic_id = nanoid!(6);
+
+        // Act
+        let result = db.toggle_pin(&non_existent_epic_id);
+
+        // Assert
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn in_progress_stories_for_should_order_by_epic_then_creation() {
+        // Arrange
+        let mut db = JiraDatabase::with_database(Box::new(MockDB::new()));
+        let mut db_state = db.read_db().unwrap();
+        db_state
+            .epics
+            .insert("b".to_owned(), Epic::new("".to_owned(), "".to_owned()));
+        db_state
+            .epics
+            .insert("a".to_owned(), Epic::new("".to_owned(), "".to_owned()));
+        db.database.write_db(&db_state).unwrap();
+        let mut expected = Vec::new();
+        // Created newest first within each epic, so epic order alone would be wrong
+        for (hours, epic_id, assignee, status) in [
+            (4, "b", "ana", Status::InProgress),
+            (3, "a", "ana", Status::InProgress),
+            (2, "a", "ana", Status::InProgress),
+            (1, "a", "bo", Status::InProgress),
+            (0, "a", "ana", Status::Open),
+        ] {
+            db.clock = Box::new(FixedClock(fixed_time() + Duration::hours(hours)));
+            let story_id = db
+                .create_story(
+                    Story::new("".to_owned(), "".to_owned()),
+                    &epic_id.to_owned(),
+                )
+                .unwrap();
+            db.set_stories_assignee(std::slice::from_ref(&story_id), Some(assignee.to_owned()))
+                .unwrap();
+            db.update_story_status(&story_id, status.clone()).unwrap();
+            if assignee == "ana" && status == Status::InProgress {
+                expected.push((hours, epic_id.to_owned(), story_id));
+            }
+        }
+
+        // Act
+        let stories = db
+            .in_progress_stories_for("ana")
+            .unwrap()
+            .into_iter()
+            .map(|(epic_id, story_id, _)| (epic_id, story_id))
+            .collect::<Vec<_>>();
+
+        // Assert
+        expected.sort_by(|a, b| a.1.cmp(&b.1).then(a.0.cmp(&b.0)));
+        let expected = expected
+            .into_iter()
+            .map(|(_, epic_id, story_id)| (epic_id, story_id))
+            .collect::<Vec<_>>();
+        assert_eq!(stories, expected);
+    }
+
+    #[test]
+    fn toggle_refinement_should_flip_the_flag() {
+        // Arrange test
+        let (db, _epic_id, story_id) = arrange_test();
+
+        // Act
+        let flagged = db.toggle_refinement(&story_id).unwrap();
+        let db_state = db.read_db().unwrap();
+
+        // Assert
+        assert!(flagged);
+        assert!(db_state.stories[&story_id].needs_refinement);
+        // Toggling again marks it refined
+        assert!(!db.toggle_refinement(&story_id).unwrap());
+        assert!(db.toggle_refinement(&nanoid!(6)).is_err());
+    }
+
+    #[test]
+    fn refinement_queue_should_list_only_flagged_stories_by_epic() {
+        // Arrange test
+        let (db, epic_id, story_id) = arrange_test();
+        let other_epic_id = db
+            .create_epic(Epic::new("".to_owned(), "".to_owned()))
+            .unwrap();
+        let mut flagged = vec![(epic_id.clone(), story_id.clone())];
+        for epic_id in [&epic_id, &other_epic_id] {
+            let refined_id = db
+                .create_story(Story::new("".to_owned(), "".to_owned()), epic_id)
+                .unwrap();
+            let flagged_id = db
+                .create_story(Story::new("".to_owned(), "".to_owned()), epic_id)
+                .unwrap();
+            db.toggle_refinement(&flagged_id).unwrap();
+            // Flagged and refined again
+            db.toggle_refinement(&refined_id).unwrap();
+            db.toggle_refinement(&refined_id).unwrap();
+            flagged.push((epic_id.clone(), flagged_id));
+        }
+        db.toggle_refinement(&story_id).unwrap();
+
+        // Act
+        let queue = db
+            .refinement_queue()
+            .unwrap()
+            .into_iter()
+            .map(|(epic_id, story_id, _)| (epic_id, story_id))
+            .collect::<Vec<_>>();
+
+        // Assert
+        // Grouped by epic id, in epic order within each epic
+        flagged.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(queue, flagged);
+    }
+
+    #[test]
+    fn toggle_pin_should_work() {
+        // Arrange test
+        let (db, epic_id, _story_id) = arrange_test();
+
+        // Act
+        let pinned = db.toggle_pin(&epic_id).unwrap();
+        let db_state = db.read_db().unwrap();
+
+        // Assert
+        assert!(pinned);
+        assert!(db_state.epics.get(&epic_id).unwrap().pinned);
+
+        // Toggling again unpins
+        assert!(!db.toggle_pin(&epic_id).unwrap());
+    }
+
+    #[test]
+    fn fixed_clock_should_produce_identical_created_at() {
+        // Arrange
+        let mock = Box::new(MockDB::new());
+        let mut db = JiraDatabase::with_database(mock);
+        db.clock = Box::new(FixedClock(fixed_time()));
+
+        // Act
+        let first_id = db
+            .create_epic(Epic::new("".to_owned(), "".to_owned()))
+            .unwrap();
+        let second_id = db
+            .create_epic(Epic::new("".to_owned(), "".to_owned()))
+            .unwrap();
+        let db_state = db.read_db().unwrap();
+
+        // Assert
+        let first = db_state.epics.get(&first_id).unwrap();
+        let second = db_state.epics.get(&second_id).unwrap();
+        assert_eq!(first.created_at, Some(fixed_time()));
+        assert_eq!(first.created_at, second.created_at);
+    }
+
+    #[test]
+    fn update_story_status_should_touch_updated_at() {
+        // Arrange test
+        let (mut db, _epic_id, story_id) = arrange_test();
+        let later = fixed_time() + chrono::Duration::days(1);
+        db.clock = Box::new(FixedClock(later));
+
+        // Act
+        db.update_story_status(&story_id, Status::InProgress)
+            .unwrap();
+        let db_state = db.read_db().unwrap();
+        let story = db_state.stories.get(&story_id).unwrap();
+
+        // Assert
+        assert_eq!(story.created_at, Some(fixed_time()));
+        assert_eq!(story.updated_at, Some(later));
+    }
+
+    #[test]
+    fn gc_should_remove_orphans_and_keep_referenced_stories() {
+        // Arrange test
+        let (db, epic_id, story_id) = arrange_test();
+        let mut db_state = db.read_db().unwrap();
+        db_state.stories.insert(
+            "orphan".to_owned(),
+            Story::new("".to_owned(), "".to_owned()),
+        );
+        db.database.write_db(&db_state).unwrap();
+
+        // Act
+        let orphans = db.find_orphan_stories().unwrap();
+        let report = db.gc().unwrap();
+        let db_state = db.read_db().unwrap();
+
+        // Assert
+        assert_eq!(orphans, vec!["orphan".to_owned()]);
+        assert_eq!(report.story_ids, vec!["orphan".to_owned()]);
+        assert!(db_state.stories.contains_key(&story_id));
+        assert!(!db_state.stories.contains_key("orphan"));
+        assert!(db_state
+            .epics
+            .get(&epic_id)
+            .unwrap()
+            .stories
+            .contains(&story_id));
+    }
+
+    #[test]
+    fn check_integrity_should_report_shared_stories_and_bad_parents() {
+        // Arrange test
+        let (db, epic_id, story_id) = arrange_test();
+        let other_id = db
+            .create_epic(Epic::new("".to_owned(), "".to_owned()))
+            .unwrap();
+        let other_story_id = db
+            .create_story(Story::new("".to_owned(), "".to_owned()), &other_id)
+            .unwrap();
+        let mut db_state = db.read_db().unwrap();
+        db_state
+            .epics
+            .get_mut(&other_id)
+            .unwrap()
+            .stories
+            .push(story_id.clone());
+        let story = db_state.stories.get_mut(&story_id).unwrap();
+        story.blocked_reason = Some("waiting".to_owned());
+        let other_story = db_state.stories.get_mut(&other_story_id).unwrap();
+        other_story.parent = Some("gone".to_owned());
+        db.database.write_db(&db_state).unwrap();
+
+        // Act
+        let problems = db.check_integrity().unwrap();
+
+        // Assert
+        let kinds = problems
+            .iter()
+            .map(|problem| (problem.kind.as_str(), problem.id.clone()))
+            .sorted()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                ("missing_parent", other_story_id),
+                ("shared_story", story_id.clone()),
+                ("stale_blocked_reason", story_id),
+            ]
+        );
+        assert!(problems
+            .iter()
+            .any(|problem| problem.message.contains(&epic_id)));
+    }
+
+    #[test]
+    fn find_orphan_stories_should_not_delete() {
+        // Arrange test
+        let (db, _epic_id, _story_id) = arrange_test();
+        let mut db_state = db.read_db().unwrap();
+        db_state.stories.insert(
+            "orphan".to_owned(),
+            Story::new("".to_owned(), "".to_owned()),
+        );
+        db.database.write_db(&db_state).unwrap();
+
+        // Act
+        let orphans = db.find_orphan_stories().unwrap();
+
+        // Assert
+        assert_eq!(orphans.len(), 1);
+        assert!(db.read_db().unwrap().stories.contains_key("orphan"));
+    }
+
+    #[test]
+    fn update_stories_status_should_only_touch_given_stories() {
+        // Arrange test
+        let (db, epic_id, story_id) = arrange_test();
+        let other_id = db
+            .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+            .unwrap();
+
+        // Act
+        let result = db.update_stories_status(std::slice::from_ref(&story_id), Status::InProgress);
+        let db_state = db.read_db().unwrap();
+
+        // Assert
+        assert!(result.is_ok());
+        assert_eq!(
+            db_state.stories.get(&story_id).unwrap().status,
+            Status::InProgress
+        );
+        assert_eq!(
+            db_state.stories.get(&other_id).unwrap().status,
+            Status::Open
+        );
+    }
+
+    #[test]
+    fn update_stories_status_should_error_without_writing_if_any_id_is_invalid() {
+        // Arrange test
+        let (db, _epic_id, story_id) = arrange_test();
+
+        // Act
+        let result = db.update_stories_status(&[story_id.clone(), nanoid!(6)], Status::InProgress);
+        let db_state = db.read_db().unwrap();
+
+        // Assert
+        assert!(result.is_err());
+        assert_eq!(
+            db_state.stories.get(&story_id).unwrap().status,
+            Status::Open
+        );
+    }
+
+    #[test]
+    fn delete_stories_should_work() {
+        // Arrange test
+        let (db, epic_id, story_id) = arrange_test();
+        let other_id = db
+            .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+            .unwrap();
+
+        // Act
+        let result = db.delete_stories(&epic_id, std::slice::from_ref(&story_id));
+        let db_state = db.read_db().unwrap();
+
+        // Assert
+        assert!(result.is_ok());
+        assert_eq!(
+            db_state.epics.get(&epic_id).unwrap().stories,
+            vec![other_id]
+        );
+        assert_eq!(db_state.stories.get(&story_id), None);
+    }
+
+    #[test]
+    fn delete_stories_should_error_if_story_not_in_epic() {
+        // Arrange test
+        let (db, epic_id, story_id) = arrange_test();
+
+        // Act
+        let result = db.delete_stories(&epic_id, &[story_id.clone(), nanoid!(6)]);
+
+        // Assert
+        assert!(result.is_err());
+        assert!(db.read_db().unwrap().stories.contains_key(&story_id));
+    }
+
+    #[test]
+    fn set_story_due_date_should_work() {
+        // Arrange test
+        let (db, _epic_id, story_id) = arrange_test();
+        let due_date = NaiveDate::from_ymd_opt(2022, 1, 15);
+
+        // Act
+        let result = db.set_story_due_date(&story_id, due_date);
+        let db_state = db.read_db().unwrap();
+
+        // Assert
+        assert!(result.is_ok());
+        assert_eq!(db_state.stories.get(&story_id).unwrap().due_date, due_date);
+    }
+
+    #[test]
+    fn set_story_due_date_should_error_if_invalid_story_id() {
+        // Arrange test
+        let (db, _epic_id, _story_id) = arrange_test();
+
+        // Act
+        let result = db.set_story_due_date(&nanoid!(6), None);
+
+        // Assert
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn export_ics_should_emit_a_vtodo_per_dated_story() {
+        // Arrange test
+        let (db, epic_id, story_id) = arrange_test();
+        let mut db_state = db.read_db().unwrap();
+        db_state.epics.get_mut(&epic_id).unwrap().name = "Launch".to_owned();
+        let story = db_state.stories.get_mut(&story_id).unwrap();
+        story.name = "Write docs, then ship".to_owned();
+        story.due_date = NaiveDate::from_ymd_opt(2022, 1, 15);
+        db.database.write_db(&db_state).unwrap();
+        let undated_id = db
+            .create_story(Story::new("Undated".to_owned(), "".to_owned()), &epic_id)
+            .unwrap();
+
+        // Act
+        let ics = db.export_ics().unwrap();
+
+        // Assert
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(ics.matches("BEGIN:VTODO").count(), 1);
+        assert!(ics.contains(&format!("UID:{}@jira_cli\r\n", story_id)));
+        assert!(ics.contains("DTSTAMP:20220101T120000Z\r\n"));
+        assert!(ics.contains("DUE;VALUE=DATE:20220115\r\n"));
+        assert!(ics.contains("SUMMARY:Write docs\\, then ship\r\n"));
+        assert!(ics.contains("DESCRIPTION:Epic: Launch\r\n"));
+        assert!(ics.contains("STATUS:NEEDS-ACTION\r\n"));
+        assert!(!ics.contains(&undated_id));
+    }
+
+    #[test]
+    fn stories_due_between_should_include_both_ends_sorted_by_due_date() {
+        // Arrange
+        let (db, epic_id, story_id) = arrange_test();
+        let day = |day| NaiveDate::from_ymd_opt(2022, 1, day).unwrap();
+        let mut ids = vec![story_id];
+        for _ in 0..4 {
+            ids.push(
+                db.create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+                    .unwrap(),
+            );
+        }
+        for (story_id, due_date) in ids.iter().zip([
+            Some(day(16)),
+            Some(day(10)),
+            Some(day(9)),
+            Some(day(17)),
+            None,
+        ]) {
+            db.set_story_due_date(story_id, due_date).unwrap();
+        }
+
+        // Act
+        let due = db.stories_due_between(day(10), day(16)).unwrap();
+
+        // Assert
+        let due_ids: Vec<&String> = due.iter().map(|(_, story_id, _)| story_id).collect();
+        assert_eq!(due_ids, vec![&ids[1], &ids[0]]);
+        assert!(due
+            .iter()
+            .all(|(due_epic_id, _, _)| *due_epic_id == epic_id));
+    }
+
+    #[test]
+    fn health_should_count_wip_overdue_and_unassigned_stories() {
+        // Arrange
+        let (db, epic_id, _story_id) = arrange_test();
+        let today = fixed_time().date_naive();
+        for (status, due_date, assignee) in [
+            (
+                Status::InProgress,
+                Some(today - Duration::days(1)),
+                Some("ada"),
+            ),
+            (Status::InProgress, Some(today), None),
+            (Status::Closed, Some(today - Duration::days(1)), None),
+            (Status::Open, Some(today - Duration::days(3)), None),
+        ] {
+            let story_id = db
+                .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+                .unwrap();
+            let mut db_state = db.read_db().unwrap();
+            let story = db_state.stories.get_mut(&story_id).unwrap();
+            story.status = status;
+            story.due_date = due_date;
+            story.assignee = assignee.map(|name| name.to_owned());
+            db.database.write_db(&db_state).unwrap();
+        }
+
+        // Act
+        let health = db.health().unwrap();
+
+        // Assert
+        assert_eq!(
+            health,
+            Health {
+                wip: 2,
+                overdue: 2,
+                unassigned: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn archive_closed_epics_should_move_them_to_the_archive_file() {
+        // Arrange
+        let (db, epic_id, story_id) = arrange_test();
+        let open_epic_id = db
+            .create_epic(Epic::new("".to_owned(), "".to_owned()))
+            .unwrap();
+        walk_epic_to(&db, &epic_id, Status::Closed).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let archive_path = dir.path().join("archive.json");
+        let mut earlier = db.read_db().unwrap();
+        earlier.epics.clear();
+        earlier.stories.clear();
+        earlier
+            .epics
+            .insert("old".to_owned(), Epic::new("".to_owned(), "".to_owned()));
+        std::fs::write(&archive_path, serde_json::to_string(&earlier).unwrap()).unwrap();
+
+        // Act
+        let count = db.archive_closed_epics(&archive_path).unwrap();
+
+        // Assert
+        assert_eq!(count, 1);
+        let db_state = db.read_db().unwrap();
+        assert!(!db_state.epics.contains_key(&epic_id));
+        assert!(!db_state.stories.contains_key(&story_id));
+        assert!(db_state.epics.contains_key(&open_epic_id));
+        let archived: DBState =
+            serde_json::from_str(&std::fs::read_to_string(&archive_path).unwrap()).unwrap();
+        assert!(archived.epics.contains_key("old"));
+        assert!(archived.epics.contains_key(&epic_id));
+        assert!(archived.stories.contains_key(&story_id));
+    }
+
+    #[test]
+    fn archive_closed_epics_should_not_create_a_file_when_nothing_is_closed() {
+        // Arrange
+        let (db, _epic_id, _story_id) = arrange_test();
+        let dir = tempfile::tempdir().unwrap();
+        let archive_path = dir.path().join("archive.json");
+
+        // Act
+        let count = db.archive_closed_epics(&archive_path).unwrap();
+
+        // Assert
+        assert_eq!(count, 0);
+        assert!(!archive_path.exists());
+    }
+
+    #[test]
+    fn filter_stories_should_return_matches_sorted_by_id() {
+        // Arrange test
+        let (db, epic_id, first_id) = arrange_test();
+        let second_id = db
+            .create_story(Story::new("b".to_owned(), "".to_owned()), &epic_id)
+            .unwrap();
+        db.set_stories_assignee(std::slice::from_ref(&second_id), Some("ana".to_owned()))
+            .unwrap();
+        walk_story_to(&db, &first_id, Status::Closed).unwrap();
+
+        // Act
+        let all = db.filter_stories(|_, _| true).unwrap();
+        let by_id = db
+            .filter_stories(|story_id, _| story_id == second_id)
+            .unwrap();
+        let closed = db.stories_with_status(&Status::Closed).unwrap();
+        let ana = db.stories_assigned_to(Some("ana")).unwrap();
+        let unassigned = db.stories_assigned_to(None).unwrap();
+
+        // Assert
+        let ids = |stories: Vec<(String, Story)>| {
+            stories.into_iter().map(|(id, _)| id).collect::<Vec<_>>()
+        };
+        let mut sorted = vec![first_id.clone(), second_id.clone()];
+        sorted.sort();
+        assert_eq!(ids(all), sorted);
+        assert_eq!(ids(by_id), vec![second_id.clone()]);
+        assert_eq!(ids(closed), vec![first_id.clone()]);
+        assert_eq!(ids(ana), vec![second_id]);
+        assert_eq!(ids(unassigned), vec![first_id]);
+    }
+
+    #[test]
+    fn filter_epics_should_return_matches() {
+        // Arrange test
+        let (db, epic_id, _story_id) = arrange_test();
+        let pinned_id = db
+            .create_epic(Epic::new("pinned".to_owned(), "".to_owned()))
+            .unwrap();
+        db.toggle_pin(&pinned_id).unwrap();
+
+        // Act
+        let pinned = db.filter_epics(|_, epic| epic.pinned).unwrap();
+        let all = db.filter_epics(|_, _| true).unwrap();
+
+        // Assert
+        assert_eq!(pinned.len(), 1);
+        assert_eq!(pinned[0].0, pinned_id);
+        assert_eq!(pinned[0].1.name, "pinned");
+        let mut sorted = vec![epic_id, pinned_id];
+        sorted.sort();
+        assert_eq!(
+            all.into_iter().map(|(id, _)| id).collect::<Vec<_>>(),
+            sorted
+        );
+    }
+
+    #[test]
+    fn stats_by_assignee_should_bucket_assigned_and_unassigned_stories() {
+        // Arrange test
+        let (db, epic_id, _story_id) = arrange_test();
+        let mut db_state = db.read_db().unwrap();
+        for (assignee, status) in [
+            (Some("ana"), Status::Open),
+            (Some("ana"), Status::Closed),
+            (Some("bo"), Status::InProgress),
+            (None, Status::Resolved),
+        ] {
+            let mut story = Story::new("".to_owned(), "".to_owned());
+            story.assignee = assignee.map(|name| name.to_owned());
+            story.status = status;
+            let story_id = nanoid!(6);
+            db_state.stories.insert(story_id.clone(), story);
+            db_state
+                .epics
+                .get_mut(&epic_id)
+                .unwrap()
+                .stories
+                .push(story_id);
+        }
+        db.database.write_db(&db_state).unwrap();
+
+        // Act
+        let stats = db.stats_by_assignee().unwrap();
+
+        // Assert
+        assert_eq!(stats.len(), 3);
+        let ana = stats.get("ana").unwrap();
+        assert_eq!((ana.open, ana.closed, ana.total()), (1, 1, 2));
+        assert_eq!(stats.get("bo").unwrap().in_progress, 1);
+        // The story from arrange_test plus the explicitly unassigned one
+        let unassigned = stats.get(UNASSIGNED).unwrap();
+        assert_eq!((unassigned.open, unassigned.resolved), (1, 1));
+    }
+
+    #[test]
+    fn set_story_parent_should_link_and_clear() {
+        // Arrange
+        let (db, epic_id, story_id) = arrange_test();
+        let child_id = db
+            .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+            .unwrap();
+
+        // Act
+        let linked = db.set_story_parent(&epic_id, &child_id, Some(story_id.clone()));
+
+        // Assert
+        assert!(linked.is_ok());
+        let db_state = db.read_db().unwrap();
+        assert_eq!(db_state.stories[&child_id].parent, Some(story_id.clone()));
+
+        // Act
+        let cleared = db.set_story_parent(&epic_id, &child_id, None);
+
+        // Assert
+        assert!(cleared.is_ok());
+        let db_state = db.read_db().unwrap();
+        assert_eq!(db_state.stories[&child_id].parent, None);
+    }
+
+    #[test]
+    fn set_story_parent_should_reject_itself() {
+        // Arrange
+        let (db, epic_id, story_id) = arrange_test();
+
+        // Act
+        let result = db.set_story_parent(&epic_id, &story_id, Some(story_id.clone()));
+
+        // Assert
+        assert!(result.is_err());
+        assert_eq!(db.read_db().unwrap().stories[&story_id].parent, None);
+    }
+
+    #[test]
+    fn set_story_parent_should_reject_cycles() {
+        // Arrange
+        let (db, epic_id, first_id) = arrange_test();
+        let second_id = db
+            .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+            .unwrap();
+        let third_id = db
+            .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+            .unwrap();
+        db.set_story_parent(&epic_id, &second_id, Some(first_id.clone()))
+            .unwrap();
+        db.set_story_parent(&epic_id, &third_id, Some(second_id.clone()))
+            .unwrap();
+
+        // Act
+        let result = db.set_story_parent(&epic_id, &first_id, Some(third_id.clone()));
+
+        // Assert
+        assert!(result.is_err());
+        assert_eq!(db.read_db().unwrap().stories[&first_id].parent, None);
+    }
+
+    #[test]
+    fn set_story_parent_should_reject_stories_from_other_epics() {
+        // Arrange
+        let (db, epic_id, story_id) = arrange_test();
+        let other_epic_id = db
+            .create_epic(Epic::new("".to_owned(), "".to_owned()))
+            .unwrap();
+        let other_story_id = db
+            .create_story(Story::new("".to_owned(), "".to_owned()), &other_epic_id)
+            .unwrap();
+
+        // Act
+        let result = db.set_story_parent(&epic_id, &story_id, Some(other_story_id));
+
+        // Assert
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn delete_story_should_detach_its_subtasks() {
+        // Arrange
+        let (db, epic_id, story_id) = arrange_test();
+        let child_id = db
+            .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+            .unwrap();
+        db.set_story_parent(&epic_id, &child_id, Some(story_id.clone()))
+            .unwrap();
+
+        // Act
+        db.delete_story(&epic_id, &story_id).unwrap();
+
+        // Assert
+        assert_eq!(db.read_db().unwrap().stories[&child_id].parent, None);
+    }
+
+    #[test]
+    fn promote_story_to_epic_should_replace_the_story_with_an_epic() {
+        // Arrange
+        let (db, epic_id, story_id) = arrange_test();
+        let child_id = db
+            .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+            .unwrap();
+        db.set_story_parent(&epic_id, &child_id, Some(story_id.clone()))
+            .unwrap();
+
+        // Act
+        let new_epic_id = db.promote_story_to_epic(&epic_id, &story_id, true).unwrap();
+
+        // Assert
+        let db_state = db.read_db().unwrap();
+        assert!(!db_state.stories.contains_key(&story_id));
+        assert!(!db_state.epics[&epic_id].stories.contains(&story_id));
+        assert!(!db_state.epics[&epic_id].stories.contains(&child_id));
+        let new_epic = &db_state.epics[&new_epic_id];
+        assert_eq!(new_epic.stories, vec![child_id.clone()]);
+        assert_eq!(new_epic.created_at, Some(fixed_time()));
+        assert_eq!(db_state.stories[&child_id].parent, None);
+    }
+
+    #[test]
+    fn promote_story_to_epic_should_leave_subtasks_unless_asked() {
+        // Arrange
+        let (db, epic_id, story_id) = arrange_test();
+        let child_id = db
+            .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+            .unwrap();
+        db.set_story_parent(&epic_id, &child_id, Some(story_id.clone()))
+            .unwrap();
+
+        // Act
+        let new_epic_id = db
+            .promote_story_to_epic(&epic_id, &story_id, false)
+            .unwrap();
+
+        // Assert
+        let db_state = db.read_db().unwrap();
+        assert!(db_state.epics[&new_epic_id].stories.is_empty());
+        assert!(db_state.epics[&epic_id].stories.contains(&child_id));
+        assert_eq!(db_state.stories[&child_id].parent, None);
+    }
+
+    #[test]
+    fn promote_story_to_epic_should_error_if_story_not_in_epic() {
+        // Arrange
+        let (db, _epic_id, story_id) = arrange_test();
+        let other_epic_id = db
+            .create_epic(Epic::new("".to_owned(), "".to_owned()))
+            .unwrap();
+
+        // Act
+        let result = db.promote_story_to_epic(&other_epic_id, &story_id, false);
+
+        // Assert
+        assert!(result.is_err());
+        assert!(db.read_db().unwrap().stories.contains_key(&story_id));
+    }
+
+    #[test]
+    fn split_story_should_create_stories_and_close_original() {
+        // Arrange
+        let (db, epic_id, story_id) = arrange_test();
+        let names = vec!["first".to_owned(), "second".to_owned()];
+
+        // Act
+        let new_ids = db
+            .split_story(&epic_id, &story_id, names, SplitOriginal::Close)
+            .unwrap();
+
+        // Assert
+        assert_eq!(new_ids.len(), 2);
+        let db_state = db.read_db().unwrap();
+        let epic = db_state.epics.get(&epic_id).unwrap();
+        assert_eq!(epic.stories.len(), 3);
+        assert_eq!(db_state.stories[&story_id].status, Status::Closed);
+        for (new_id, name) in new_ids.iter().zip(["first", "second"]) {
+            assert!(epic.stories.contains(new_id));
+            let story = &db_state.stories[new_id];
+            assert_eq!(story.name, name);
+            assert_eq!(story.status, Status::Open);
+            assert_eq!(story.description, db_state.stories[&story_id].description);
+        }
+    }
+
+    #[test]
+    fn split_story_should_delete_original_when_asked() {
+        // Arrange
+        let (db, epic_id, story_id) = arrange_test();
+
+        // Act
+        let new_ids = db
+            .split_story(
+                &epic_id,
+                &story_id,
+                vec!["only".to_owned()],
+                SplitOriginal::Delete,
+            )
+            .unwrap();
+
+        // Assert
+        let db_state = db.read_db().unwrap();
+        assert_eq!(db_state.epics[&epic_id].stories, new_ids);
+        assert!(!db_state.stories.contains_key(&story_id));
+    }
+
+    #[test]
+    fn split_story_should_keep_original_when_asked() {
+        // Arrange
+        let (db, epic_id, story_id) = arrange_test();
+
+        // Act
+        db.split_story(
+            &epic_id,
+            &story_id,
+            vec!["only".to_owned()],
+            SplitOriginal::Keep,
+        )
+        .unwrap();
+
+        // Assert
+        let db_state = db.read_db().unwrap();
+        assert_eq!(db_state.epics[&epic_id].stories.len(), 2);
+        assert_eq!(db_state.stories[&story_id].status, Status::Open);
+    }
+
+    #[test]
+    fn split_story_should_reject_empty_names() {
+        // Arrange
+        let (db, epic_id, story_id) = arrange_test();
+
+        // Act
+        let blank = db.split_story(
+            &epic_id,
+            &story_id,
+            vec!["ok".to_owned(), "  ".to_owned()],
+            SplitOriginal::Close,
+        );
+        let none = db.split_story(&epic_id, &story_id, vec![], SplitOriginal::Close);
+
+        // Assert
+        assert!(blank.is_err());
+        assert!(none.is_err());
+        let db_state = db.read_db().unwrap();
+        assert_eq!(db_state.stories.len(), 1);
+        assert_eq!(db_state.stories[&story_id].status, Status::Open);
+    }
+
+    #[test]
+    fn search_should_rank_prefix_matches_first() {
+        // Arrange
+        let (db, epic_id, _story_id) = arrange_test();
+        db.create_story(
+            Story::new("Catalog cleanup".to_owned(), "".to_owned()),
+            &epic_id,
+        )
+        .unwrap();
+        let prefix_id = db
+            .create_story(Story::new("Login page".to_owned(), "".to_owned()), &epic_id)
+            .unwrap();
+
+        // Act
+        let results = db.search("log").unwrap();
+
+        // Assert
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].story_id, Some(prefix_id));
+        assert_eq!(results[0].epic_id, epic_id);
+        assert!(results[0].score > results[1].score);
+    }
+
+    #[test]
+    fn search_should_rank_name_matches_above_description_matches() {
+        // Arrange
+        let (db, _epic_id, _story_id) = arrange_test();
+        db.create_epic(Epic::new(
+            "Billing".to_owned(),
+            "Move the invoice export".to_owned(),
+        ))
+        .unwrap();
+        let named_id = db
+            .create_epic(Epic::new("Invoice export".to_owned(), "".to_owned()))
+            .unwrap();
+
+        // Act
+        let results = db.search("invoice").unwrap();
+
+        // Assert
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].epic_id, named_id);
+        assert_eq!(results[0].story_id, None);
+    }
+
+    #[test]
+    fn search_should_ignore_case_in_names_and_descriptions() {
+        // Arrange
+        let (db, epic_id, _story_id) = arrange_test();
+        let story_id = db
+            .create_story(
+                Story::new("Checkout".to_owned(), "Fix the PAYMENT form".to_owned()),
+                &epic_id,
+            )
+            .unwrap();
+        let named_id = db
+            .create_epic(Epic::new("Payments".to_owned(), "".to_owned()))
+            .unwrap();
+
+        // Act
+        let results = db.search("payment").unwrap();
+
+        // Assert
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].epic_id, named_id);
+        assert_eq!(results[0].story_id, None);
+        assert_eq!(results[1].epic_id, epic_id);
+        assert_eq!(results[1].story_id, Some(story_id));
+    }
+
+    #[test]
+    fn search_should_ignore_blank_queries() {
+        // Arrange
+        let (db, _epic_id, _story_id) = arrange_test();
+
+        // Act
+        let empty = db.search("").unwrap();
+        let blank = db.search("   ").unwrap();
+
+        // Assert
+        assert!(empty.is_empty());
+        assert!(blank.is_empty());
+    }
+
+    // Stands in for an indexed backend: plain substring matching over the same data
+    struct IndexedDB(MockDB);
+
+    impl Database for IndexedDB {
+        fn read_db(&self) -> Result<DBState> {
+            self.0.read_db()
+        }
+
+        fn write_db(&self, db_state: &DBState) -> Result<()> {
+            self.0.write_db(db_state)
+        }
+
+        fn capabilities(&self) -> Capabilities {
+            Capabilities {
+                searchable: true,
+                ..Capabilities::default()
+            }
+        }
+
+        fn search_fts(&self, query: &str) -> Result<Vec<SearchResult>> {
+            let db_state = self.0.read_db()?;
+            let query = query.to_lowercase();
+            let matches = |name: &str, description: &str| {
+                format!("{} {}", name, description)
+                    .to_lowercase()
+                    .contains(&query)
+            };
+            let mut results = Vec::new();
+            for (epic_id, epic) in &db_state.epics {
+                if matches(&epic.name, &epic.description) {
+                    results.push(SearchResult {
+                        epic_id: epic_id.clone(),
+                        story_id: None,
+                        name: epic.name.clone(),
+                        score: 0,
+                    });
+                }
+                for story_id in &epic.stories {
+                    let story = &db_state.stories[story_id];
+                    if matches(&story.name, &story.description) {
+                        results.push(SearchResult {
+                            epic_id: epic_id.clone(),
+                            story_id: Some(story_id.clone()),
+                            name: story.name.clone(),
+                            score: 0,
+                        });
+                    }
+                }
+            }
+            Ok(results)
+        }
+    }
+
+    #[test]
+    fn search_should_use_the_index_when_supported() {
+        // Arrange
+        let (scan_db, epic_id, _story_id) = arrange_test();
+        scan_db
+            .create_story(
+                Story::new("Invoice export".to_owned(), "".to_owned()),
+                &epic_id,
+            )
+            .unwrap();
+        scan_db
+            .create_epic(Epic::new("Billing".to_owned(), "invoice run".to_owned()))
+            .unwrap();
+        let indexed = IndexedDB(MockDB::new());
+        indexed.write_db(&scan_db.read_db().unwrap()).unwrap();
+        let indexed_db = JiraDatabase::with_database(Box::new(indexed));
+
+        // Act
+        let ids = |db: &JiraDatabase| {
+            db.search("invoice")
+                .unwrap()
+                .into_iter()
+                .map(|result| (result.epic_id, result.story_id))
+                .sorted()
+                .collect_vec()
+        };
+
+        // Assert
+        assert!(!scan_db.database.supports_fts());
+        assert_eq!(ids(&indexed_db).len(), 2);
+        assert_eq!(ids(&indexed_db), ids(&scan_db));
+    }
+
+    #[test]
+    fn resolve_id_prefix_should_match_like_short_hashes() {
+        // Arrange
+        let db = JiraDatabase::with_database(Box::new(MockDB::new()));
+        let mut db_state = db.read_db().unwrap();
+        for id in ["abc123", "abd456"] {
+            db_state
+                .epics
+                .insert(id.to_owned(), Epic::new("".to_owned(), "".to_owned()));
+        }
+        db_state.stories.insert(
+            "xyz789".to_owned(),
+            Story::new("".to_owned(), "".to_owned()),
+        );
+        db.database.write_db(&db_state).unwrap();
+
+        // Act
+        let unique = db.resolve_id_prefix("abc").unwrap();
+        let story = db.resolve_id_prefix("x").unwrap();
+        let ambiguous = db.resolve_id_prefix("ab").unwrap();
+        let none = db.resolve_id_prefix("q").unwrap();
+        let blank = db.resolve_id_prefix("").unwrap();
+
+        // Assert
+        assert_eq!(unique, IdMatch::Unique("abc123".to_owned()));
+        assert_eq!(story, IdMatch::Unique("xyz789".to_owned()));
+        assert_eq!(ambiguous, IdMatch::Ambiguous(2));
+        assert_eq!(none, IdMatch::None);
+        assert_eq!(blank, IdMatch::None);
+    }
+
+    #[test]
+    fn aggregate_database_should_namespace_ids_by_project() {
+        // Arrange
+        let sources = ["work", "home"].map(|project| {
+            let db = JiraDatabase::with_database(Box::new(MockDB::new()));
+            let mut db_state = db.read_db().unwrap();
+            let mut epic = Epic::new(project.to_owned(), "".to_owned());
+            epic.stories = vec!["s1".to_owned()];
+            db_state.epics.insert("e1".to_owned(), epic);
+            let mut story = Story::new("".to_owned(), "".to_owned());
+            story.parent = Some("s0".to_owned());
+            db_state.stories.insert("s1".to_owned(), story);
+            db.database.write_db(&db_state).unwrap();
+            (project.to_owned(), db.database)
+        });
+        let db = JiraDatabase::with_database(Box::new(AggregateDatabase {
+            sources: sources.into_iter().collect(),
+        }));
+
+        // Act
+        let db_state = db.read_db().unwrap();
+        let write = db.create_epic(Epic::new("".to_owned(), "".to_owned()));
+
+        // Assert
+        assert_eq!(
+            db_state.epics.keys().sorted().collect_vec(),
+            vec!["home/e1", "work/e1"]
+        );
+        assert_eq!(db_state.epics["work/e1"].name, "work");
+        assert_eq!(db_state.epics["home/e1"].stories, vec!["home/s1"]);
+        assert_eq!(
+            db_state.stories["work/s1"].parent.as_deref(),
+            Some("work/s0")
+        );
+        assert!(write.unwrap_err().to_string().contains("read-only"));
+        assert!(!db.capabilities().writable);
+    }
+
+    #[test]
+    fn backends_should_report_the_json_capabilities_by_default() {
+        // Arrange
+        let dir = tempfile::tempdir().unwrap();
+        let json = JSONFileDatabase {
+            file_path: dir.path().join("db.json"),
+            backup: None,
+            clock: Box::new(SystemClock),
+        };
+        let mock = MockDB::new();
+
+        // Act
+        let capabilities = [json.capabilities(), mock.capabilities()];
+
+        // Assert
+        for capabilities in capabilities {
+            assert!(capabilities.writable);
+            assert!(!capabilities.searchable);
+            assert!(!capabilities.transactional);
+            assert!(!capabilities.streaming);
+        }
+        assert!(!json.supports_fts());
+    }
+
+    fn get_attachment(label: &str) -> Attachment {
+        Attachment {
+            label: label.to_owned(),
+            target: "https://example.com/design".to_owned(),
+        }
+    }
+
+    #[test]
+    fn add_attachment_should_append_to_story() {
+        // Arrange
+        let (db, _epic_id, story_id) = arrange_test();
+
+        // Act
+        db.add_attachment(&story_id, get_attachment("design"))
+            .unwrap();
+        db.add_attachment(&story_id, get_attachment("logs"))
+            .unwrap();
+
+        // Assert
+        let db_state = db.read_db().unwrap();
+        let attachments = &db_state.stories[&story_id].attachments;
+        assert_eq!(attachments.len(), 2);
+        assert_eq!(attachments[0], get_attachment("design"));
+        assert_eq!(attachments[1].label, "logs");
+    }
+
+    #[test]
+    fn add_attachment_should_require_a_label() {
+        // Arrange
+        let (db, _epic_id, story_id) = arrange_test();
+
+        // Act
+        let result = db.add_attachment(&story_id, get_attachment("  "));
+
+        // Assert
+        assert!(result.is_err());
+        assert!(db.read_db().unwrap().stories[&story_id]
+            .attachments
+            .is_empty());
+    }
+
+    #[test]
+    fn remove_attachment_should_remove_by_index() {
+        // Arrange
+        let (db, _epic_id, story_id) = arrange_test();
+        db.add_attachment(&story_id, get_attachment("design"))
+            .unwrap();
+        db.add_attachment(&story_id, get_attachment("logs"))
+            .unwrap();
+
+        // Act
+        let removed = db.remove_attachment(&story_id, 0).unwrap();
+
+        // Assert
+        assert_eq!(removed.label, "design");
+        let db_state = db.read_db().unwrap();
+        assert_eq!(
+            db_state.stories[&story_id].attachments,
+            vec![get_attachment("logs")]
+        );
+    }
+
+    #[test]
+    fn attachment_index_out_of_range_should_error() {
+        // Arrange
+        let (db, _epic_id, story_id) = arrange_test();
+        db.add_attachment(&story_id, get_attachment("design"))
+            .unwrap();
+
+        // Act
+        let get_result = db.get_attachment(&story_id, 1);
+        let remove_result = db.remove_attachment(&story_id, 1);
+
+        // Assert
+        assert!(get_result.is_err());
+        assert!(remove_result.is_err());
+        assert_eq!(
+            db.read_db().unwrap().stories[&story_id].attachments.len(),
+            1
+        );
+    }
+
+    #[test]
+    fn merge_epics_should_move_stories_and_delete_source() {
+        // Arrange
+        let (db, target_id, target_story_id) = arrange_test();
+        let source_id = db
+            .create_epic(Epic::new("".to_owned(), "source notes".to_owned()))
+            .unwrap();
+        let mut source_story_ids = vec![];
+        for _ in 0..2 {
+            source_story_ids.push(
+                db.create_story(Story::new("".to_owned(), "".to_owned()), &source_id)
+                    .unwrap(),
+            );
+        }
+
+        // Act
+        let result = db.merge_epics(&source_id, &target_id);
+
+        // Assert
+        assert!(result.is_ok());
+        let db_state = db.read_db().unwrap();
+        assert!(!db_state.epics.contains_key(&source_id));
+        let target = db_state.epics.get(&target_id).unwrap();
+        let mut expected = vec![target_story_id];
+        expected.extend(source_story_ids);
+        assert_eq!(target.stories, expected);
+        assert_eq!(target.description, "source notes");
+        assert_eq!(db_state.stories.len(), 3);
+    }
+
+    #[test]
+    fn reorder_story_should_only_rekey_the_moved_story_once_placed() {
+        // Arrange test
+        let (db, epic_id, first_id) = arrange_test();
+        let second_id = db
+            .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+            .unwrap();
+        let third_id = db
+            .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+            .unwrap();
+        let get_manual_order = || {
+            let db_state = db.read_db().unwrap();
+            db_state.epics[&epic_id]
+                .stories
+                .iter()
+                .sorted_by(|a, b| {
+                    compare_keys(db_state.stories[*a].order, db_state.stories[*b].order)
+                })
+                .cloned()
+                .collect_vec()
+        };
+
+        // Act
+        db.reorder_story(&third_id, 0).unwrap();
+        let placed = db.read_db().unwrap();
+        db.reorder_story(&first_id, 9).unwrap();
+        let moved = db.read_db().unwrap();
+
+        // Assert
+        assert_eq!(get_manual_order()[0], third_id);
+        assert_eq!(get_manual_order()[2], first_id);
+        assert_eq!(
+            moved.stories[&second_id].order,
+            placed.stories[&second_id].order
+        );
+        assert!(matches!(
+            db.reorder_story(&nanoid!(6), 0),
+            Err(DbError::StoryNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn reorder_epic_should_move_the_epic_in_the_manual_order() {
+        // Arrange test
+        let (db, epic_id, _story_id) = arrange_test();
+        let other_id = db
+            .create_epic(Epic::new("".to_owned(), "".to_owned()))
+            .unwrap();
+
+        // Act
+        db.reorder_epic(&other_id, 0).unwrap();
+
+        // Assert
+        let db_state = db.read_db().unwrap();
+        assert!(db_state.epics[&other_id].order < db_state.epics[&epic_id].order);
+        assert!(db_state.epics[&epic_id].order.is_some());
+        assert!(matches!(
+            db.reorder_epic(&nanoid!(6), 0),
+            Err(DbError::EpicNotFound(_))
+        ));
+    }
 
-        (db, epic_id, story_id)
+    #[test]
+    fn move_story_should_move_the_story_and_drop_subtask_links() {
+        // Arrange
+        let (db, source_id, story_id) = arrange_test();
+        let subtask_id = db
+            .create_story(Story::new("".to_owned(), "".to_owned()), &source_id)
+            .unwrap();
+        db.set_story_parent(&source_id, &subtask_id, Some(story_id.clone()))
+            .unwrap();
+        let target_id = db
+            .create_epic(Epic::new("".to_owned(), "".to_owned()))
+            .unwrap();
+
+        // Act
+        let result = db.move_story(&story_id, &target_id);
+
+        // Assert
+        assert!(result.is_ok());
+        let db_state = db.read_db().unwrap();
+        assert_eq!(db_state.epics[&source_id].stories, vec![subtask_id.clone()]);
+        assert_eq!(db_state.epics[&target_id].stories, vec![story_id.clone()]);
+        assert_eq!(db_state.stories[&subtask_id].parent, None);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::db::test_utils::arrange_test;
-    use crate::models::{DBState, Epic, Story};
-    use nanoid::nanoid;
+    #[test]
+    fn move_stories_matching_should_move_only_matching_stories() {
+        // Arrange
+        let (db, first_id, untouched_id) = arrange_test();
+        let second_id = db
+            .create_epic(Epic::new("".to_owned(), "".to_owned()))
+            .unwrap();
+        let target_id = db
+            .create_epic(Epic::new("".to_owned(), "".to_owned()))
+            .unwrap();
+        let mut matching_ids = vec![];
+        for epic_id in [&first_id, &second_id, &target_id] {
+            matching_ids.push(
+                db.create_story(Story::new("frontend".to_owned(), "".to_owned()), epic_id)
+                    .unwrap(),
+            );
+        }
+        let subtask_id = db
+            .create_story(Story::new("frontend".to_owned(), "".to_owned()), &first_id)
+            .unwrap();
+        db.set_story_parent(&first_id, &subtask_id, Some(matching_ids[0].clone()))
+            .unwrap();
 
-    use super::test_utils::MockDB;
-    use super::*;
+        // Act
+        let moved = db
+            .move_stories_matching(|_, story| story.name == "frontend", &target_id)
+            .unwrap();
+
+        // Assert
+        assert_eq!(moved, 3);
+        let db_state = db.read_db().unwrap();
+        assert_eq!(db_state.epics[&first_id].stories, vec![untouched_id]);
+        assert!(db_state.epics[&second_id].stories.is_empty());
+        let mut target_story_ids = db_state.epics[&target_id].stories.clone();
+        target_story_ids.sort();
+        let mut expected = matching_ids.clone();
+        expected.push(subtask_id.clone());
+        expected.sort();
+        assert_eq!(target_story_ids, expected);
+        assert_eq!(db_state.epics[&target_id].stories[0], matching_ids[2]);
+        assert_eq!(
+            db_state.stories[&subtask_id].parent,
+            Some(matching_ids[0].clone())
+        );
+    }
 
     #[test]
-    fn create_epic_should_work() {
+    fn move_stories_matching_should_need_an_existing_target() {
         // Arrange
-        let mock = Box::new(MockDB::new());
-        let db = JiraDatabase { database: mock };
-        let epic = Epic::new("An Epic".to_owned(), "Description".to_owned());
+        let (db, _epic_id, _story_id) = arrange_test();
+        let revision = db.read_db().unwrap().revision;
 
         // Act
-        let result = db.create_epic(epic.clone());
+        let missing = db.move_stories_matching(|_, _| true, &"missing".to_owned());
 
-        // Assert part 1
-        assert_eq!(result.is_ok(), true);
+        // Assert
+        assert!(matches!(missing, Err(DbError::EpicNotFound(_))));
+        assert_eq!(db.read_db().unwrap().revision, revision);
+    }
 
-        // Arrange part 2
-        let epic_id = result.unwrap();
-        let db_state = db.read_db().unwrap();
+    #[test]
+    fn move_story_should_reject_missing_ids_and_the_same_epic() {
+        // Arrange
+        let (db, epic_id, story_id) = arrange_test();
+        let missing_id = "missing".to_owned();
+
+        // Act
+        let same = db.move_story(&story_id, &epic_id);
+        let missing_story = db.move_story(&missing_id, &epic_id);
+        let missing_epic = db.move_story(&story_id, &missing_id);
 
         // Assert
-        assert_eq!(db_state.epics.get(&epic_id), Some(&epic));
-        assert_eq!(&db_state.last_item_id, &epic_id);
+        assert!(same.is_err());
+        assert!(missing_story.is_err());
+        assert!(missing_epic.is_err());
+        assert_eq!(
+            db.read_db().unwrap().epics[&epic_id].stories,
+            vec![story_id]
+        );
     }
 
     #[test]
-    fn create_story_should_error_if_invalid_epic_id() {
+    fn merge_epics_should_reject_missing_or_identical_ids() {
         // Arrange
-        let mock = Box::new(MockDB::new());
-        let db = JiraDatabase { database: mock };
-        let story = Story::new("".to_owned(), "".to_owned());
-        let non_existent_epic_id = nanoid!(6);
+        let (db, epic_id, _story_id) = arrange_test();
+        let missing_id = "missing".to_owned();
 
         // Act
-        let result = db.create_story(story, &non_existent_epic_id);
+        let same = db.merge_epics(&epic_id, &epic_id);
+        let missing_source = db.merge_epics(&missing_id, &epic_id);
+        let missing_target = db.merge_epics(&epic_id, &missing_id);
 
         // Assert
-        assert_eq!(result.is_err(), true);
+        assert!(same.is_err());
+        assert!(missing_source.is_err());
+        assert!(missing_target.is_err());
+        assert!(db.read_db().unwrap().epics.contains_key(&epic_id));
     }
 
     #[test]
-    fn create_story_should_work() {
-        // Arrange test
+    fn find_item_should_resolve_epics_and_stories() {
+        // Arrange
         let (db, epic_id, story_id) = arrange_test();
-        let story = Story::new("".to_owned(), "".to_owned());
 
-        // Arrange for reading the DB assertion
-        let db_state = db.read_db().unwrap();
-        let contains_story = db_state
-            .epics
-            .get(&epic_id)
-            .unwrap()
-            .stories
-            .contains(&story_id);
+        // Act
+        let epic = db.find_item(&epic_id).unwrap();
+        let story = db.find_item(&story_id).unwrap();
 
         // Assert
-        assert_eq!(db_state.last_item_id, story_id);
-        assert_eq!(contains_story, true);
-        assert_eq!(db_state.stories.get(&story_id), Some(&story));
+        assert_eq!(epic, ItemRef::Epic { epic_id });
+        assert_eq!(story, ItemRef::Story { story_id });
     }
 
     #[test]
-    fn delete_epic_should_error_if_invalid_epic_id() {
+    fn find_item_should_error_for_unknown_id() {
         // Arrange
-        let mock = Box::new(MockDB::new());
-        let db = JiraDatabase { database: mock };
-        let non_existent_epic_id = nanoid!(6);
+        let (db, _epic_id, _story_id) = arrange_test();
 
         // Act
-        let result = db.delete_epic(&non_existent_epic_id);
+        let result = db.find_item(&"missing".to_owned());
 
         // Assert
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
     }
 
     #[test]
-    fn delete_epic_should_work() {
-        // Arrange test
+    fn export_ndjson_should_write_one_tagged_object_per_line() {
+        // Arrange
         let (db, epic_id, story_id) = arrange_test();
+        let mut out = Vec::new();
 
         // Act
-        let result = db.delete_epic(&epic_id);
-        let db_state = db.read_db().unwrap();
+        db.export_ndjson(&mut out).unwrap();
 
         // Assert
-        assert_eq!(result.is_ok(), true);
-        assert_eq!(db_state.last_item_id, epic_id);
-        assert_eq!(db_state.epics.get(&epic_id), None);
-        assert_eq!(db_state.stories.get(&story_id), None);
+        let output = String::from_utf8(out).unwrap();
+        let lines = output
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["type"], "epic");
+        assert_eq!(lines[0]["id"], epic_id.as_str());
+        assert_eq!(lines[0]["stories"][0], story_id.as_str());
+        assert_eq!(lines[1]["type"], "story");
+        assert_eq!(lines[1]["id"], story_id.as_str());
     }
 
     #[test]
-    fn delete_story_should_error_if_invalid_epic_id() {
-        // Arrange test
-        let (db, _epic_id, story_id) = arrange_test();
-
-        // Generate a non-existent epic id
-        let non_existent_epic_id = nanoid!(6);
+    fn export_ndjson_should_order_by_id() {
+        // Arrange
+        let (db, _epic_id, _story_id) = arrange_test();
+        db.create_epic(Epic::new("".to_owned(), "".to_owned()))
+            .unwrap();
+        let mut out = Vec::new();
 
         // Act
-        let result = db.delete_story(&non_existent_epic_id, &story_id);
+        db.export_ndjson(&mut out).unwrap();
 
         // Assert
-        assert_eq!(result.is_err(), true);
+        let output = String::from_utf8(out).unwrap();
+        let epic_ids = output
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .filter(|value| value["type"] == "epic")
+            .map(|value| value["id"].as_str().unwrap().to_owned())
+            .collect::<Vec<_>>();
+        let mut sorted = epic_ids.clone();
+        sorted.sort();
+        assert_eq!(epic_ids.len(), 2);
+        assert_eq!(epic_ids, sorted);
     }
 
-    #[test]
-    fn delete_story_should_error_if_story_not_found_in_epic() {
-        // Arrange test
-        let (db, epic_id, _story_id) = arrange_test();
+    // Everything but the ids, which an import into another database regenerates
+    // as one line per item, each epic followed by its stories
+    fn get_contents(db_state: &DBState) -> Vec<Vec<String>> {
+        let describe = |name: &String, description: &String, status: &Status| {
+            format!("{} / {} / {}", name, description, status)
+        };
+        db_state
+            .epics
+            .iter()
+            .map(|(epic_id, epic)| {
+                let stories = db_state.stories_of(epic_id).unwrap().into_iter();
+                std::iter::once(describe(&epic.name, &epic.description, &epic.status))
+                    .chain(
+                        stories.map(|(_, story)| {
+                            describe(&story.name, &story.description, &story.status)
+                        }),
+                    )
+                    .collect()
+            })
+            .sorted()
+            .collect()
+    }
 
-        // Generate a non-existent story id
-        let non_existent_story_id = nanoid!(6);
+    #[test]
+    fn export_csv_should_round_trip_through_import() {
+        // Arrange
+        let (db, epic_id, story_id) = arrange_test();
+        db.update_story_status(&story_id, Status::Blocked).unwrap();
+        db.create_story(
+            Story::new("Quotes \"and\", commas".to_owned(), "two\nlines".to_owned()),
+            &epic_id,
+        )
+        .unwrap();
+        db.create_epic(Epic::new("Empty, for now".to_owned(), "".to_owned()))
+            .unwrap();
+        let copy = JiraDatabase::with_database(Box::new(MockDB::new()));
+        let mut out = Vec::new();
 
         // Act
-        let result = db.delete_story(&epic_id, &non_existent_story_id);
+        db.export_csv(&mut out).unwrap();
+        let imported = read_csv_epics(out.as_slice()).unwrap();
+        copy.import_epics(imported, MergeMode::Append).unwrap();
 
         // Assert
-        assert_eq!(result.is_err(), true);
+        let output = String::from_utf8(out).unwrap();
+        assert!(output.starts_with("type,id,epic_id,name,description,status\n"));
+        assert_eq!(
+            get_contents(&copy.read_db().unwrap()),
+            get_contents(&db.read_db().unwrap())
+        );
     }
 
     #[test]
-    fn delete_story_should_work() {
-        // Arrange test
+    fn export_csv_should_list_each_epic_before_its_stories() {
+        // Arrange
         let (db, epic_id, story_id) = arrange_test();
+        let mut out = Vec::new();
 
         // Act
-        let result = db.delete_story(&epic_id, &story_id);
-        let db_state = db.read_db().unwrap();
-        let contains_stories = db_state
-            .epics
-            .get(&epic_id)
-            .unwrap()
-            .stories
-            .contains(&story_id);
+        db.export_csv(&mut out).unwrap();
 
         // Assert
-        assert_eq!(result.is_ok(), true);
-        assert_eq!(db_state.last_item_id, story_id);
-        assert_eq!(contains_stories, false);
-        assert_eq!(db_state.stories.get(&story_id), None);
+        let output = String::from_utf8(out).unwrap();
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with(&format!("epic,{},,", epic_id)));
+        assert!(lines[2].starts_with(&format!("story,{},{},", story_id, epic_id)));
     }
 
     #[test]
-    fn update_epic_status_should_error_if_invalid_epic_id() {
+    fn read_csv_epics_should_reject_stories_without_an_earlier_epic() {
+        let orphan = "type,id,epic_id,name,description,status\nstory,2,1,Login,,Open\n";
+        let unknown = "type,id,epic_id,name,description,status\ntask,2,,Login,,Open\n";
+        let bad_status = "type,id,epic_id,name,description,status\nepic,1,,Launch,,Done\n";
+
+        let error = read_csv_epics(orphan.as_bytes()).unwrap_err();
+
+        assert!(error
+            .to_string()
+            .starts_with("Line 2: story 2 belongs to epic '1'"));
+        assert!(read_csv_epics(unknown.as_bytes()).is_err());
+        assert!(read_csv_epics(bad_status.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn export_split_should_write_a_file_per_epic_and_an_index() {
         // Arrange
-        let mock = Box::new(MockDB::new());
-        let db = JiraDatabase { database: mock };
-        let non_existent_epic_id = nanoid!(6);
+        let (db, epic_id, story_id) = arrange_test();
+        let second_id = db
+            .create_epic(Epic::new("../Launch / v2".to_owned(), "".to_owned()))
+            .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let split_dir = dir.path().join("split");
 
         // Act
-        let result = db.update_epic_status(&non_existent_epic_id, Status::Closed);
+        let count = db.export_split(&split_dir, false).unwrap();
 
         // Assert
-        assert_eq!(result.is_err(), true);
+        assert_eq!(count, 2);
+        let index: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(split_dir.join("index.json")).unwrap())
+                .unwrap();
+        let files = index["epics"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| entry["file"].as_str().unwrap().to_owned())
+            .collect::<Vec<_>>();
+        let mut expected = vec![
+            format!("{}.json", epic_id),
+            format!("{}-launch-v2.json", second_id),
+        ];
+        expected.sort();
+        assert_eq!(files, expected);
+        let first: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string(split_dir.join(format!("{}.json", epic_id))).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(first["epic_id"], epic_id.as_str());
+        assert!(first["stories"].get(&story_id).is_some());
+        // Every listed file exists and nothing else was written
+        assert_eq!(std::fs::read_dir(&split_dir).unwrap().count(), 3);
     }
 
     #[test]
-    fn update_epic_status_should_work() {
-        // Arrange test
-        let (db, epic_id, _story_id) = arrange_test();
+    fn export_split_should_only_overwrite_with_force() {
+        // Arrange
+        let (db, _epic_id, _story_id) = arrange_test();
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "").unwrap();
 
         // Act
-        let result = db.update_epic_status(&epic_id, Status::Closed);
-        let db_state = db.read_db().unwrap();
+        let refused = db.export_split(dir.path(), false);
+        let forced = db.export_split(dir.path(), true);
 
         // Assert
-        assert_eq!(result.is_ok(), true);
-        assert_eq!(db_state.epics.get(&epic_id).unwrap().status, Status::Closed);
+        assert!(matches!(refused, Err(DbError::Validation(_))));
+        assert_eq!(forced.unwrap(), 1);
     }
 
     #[test]
-    fn update_story_status_should_error_if_invalid_story_id() {
+    fn get_burndown_points_should_replay_status_changes_per_day() {
+        use chrono::TimeZone;
+
         // Arrange
-        let mock = Box::new(MockDB::new());
-        let db = JiraDatabase { database: mock };
-        let non_existent_story_id = nanoid!(6);
+        let estimates = HashMap::from([
+            ("a".to_owned(), 3),
+            ("b".to_owned(), 5),
+            ("c".to_owned(), 2),
+        ]);
+        let event = |story_id: &str, status, day, hour| StatusEvent {
+            story_id: story_id.to_owned(),
+            status,
+            at: Utc.with_ymd_and_hms(2022, 1, day, hour, 0, 0).unwrap(),
+        };
+        let events = vec![
+            event("b", Status::Closed, 3, 9),
+            event("a", Status::InProgress, 1, 9),
+            event("a", Status::Resolved, 1, 17),
+            event("b", Status::Open, 4, 9),
+            event("c", Status::Closed, 4, 10),
+            event("a", Status::Closed, 4, 11),
+            event("unknown", Status::Closed, 5, 9),
+        ];
 
         // Act
-        let result = db.update_story_status(&non_existent_story_id, Status::Closed);
+        let points = get_burndown_points(&estimates, &events);
 
         // Assert
-        assert_eq!(result.is_err(), true);
+        let date = |day| NaiveDate::from_ymd_opt(2022, 1, day).unwrap();
+        assert_eq!(
+            points,
+            vec![
+                BurndownPoint {
+                    date: date(1),
+                    remaining: 7,
+                },
+                BurndownPoint {
+                    date: date(3),
+                    remaining: 2,
+                },
+                BurndownPoint {
+                    date: date(4),
+                    remaining: 5,
+                },
+            ]
+        );
     }
 
     #[test]
-    fn update_story_status_should_work() {
-        // Arrange test
-        let (db, _epic_id, story_id) = arrange_test();
+    fn burndown_should_replay_the_story_status_histories() {
+        // Arrange
+        let (db, epic_id, closed_id) = arrange_test();
+        let open_id = db
+            .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+            .unwrap();
+        db.set_story_estimate(&closed_id, Some(3)).unwrap();
+        db.set_story_estimate(&open_id, Some(2)).unwrap();
+        walk_story_to(&db, &closed_id, Status::Closed).unwrap();
 
         // Act
-        let result = db.update_story_status(&story_id, Status::Closed);
-        let db_state = db.read_db().unwrap();
-        let new_status = &db_state.stories.get(&story_id).unwrap().status;
+        let points = db.burndown(&epic_id).unwrap();
 
         // Assert
-        assert_eq!(result.is_ok(), true);
-        assert_eq!(*new_status, Status::Closed);
+        assert_eq!(
+            points,
+            vec![BurndownPoint {
+                date: fixed_time().date_naive(),
+                remaining: 2,
+            }]
+        );
+        assert!(matches!(
+            db.set_story_estimate(&nanoid!(6), None),
+            Err(DbError::StoryNotFound(_))
+        ));
+    }
+
+    mod import {
+        use super::*;
+        use crate::models::{ImportSummary, ImportedEpic, MergeMode};
+
+        fn arrange_import() -> (JiraDatabase, String, Vec<ImportedEpic>) {
+            let mock = Box::new(MockDB::new());
+            let db = JiraDatabase::with_database(mock);
+            let epic_id = db
+                .create_epic(Epic::new("Existing".to_owned(), "old".to_owned()))
+                .unwrap();
+
+            let imported = vec![ImportedEpic {
+                external_id: None,
+                name: "Existing".to_owned(),
+                description: "new".to_owned(),
+                status: Status::InProgress,
+                stories: vec![],
+            }];
+
+            (db, epic_id, imported)
+        }
+
+        #[test]
+        fn append_should_always_create() {
+            // Arrange
+            let (db, _epic_id, imported) = arrange_import();
+
+            // Act
+            let summary = db.import_epics(imported, MergeMode::Append).unwrap();
+            let db_state = db.read_db().unwrap();
+
+            // Assert
+            assert_eq!(
+                summary,
+                ImportSummary {
+                    created: 1,
+                    updated: 0,
+                    skipped: 0
+                }
+            );
+            assert_eq!(db_state.epics.len(), 2);
+        }
+
+        #[test]
+        fn skip_existing_should_ignore_matches() {
+            // Arrange
+            let (db, epic_id, imported) = arrange_import();
+
+            // Act
+            let summary = db.import_epics(imported, MergeMode::SkipExisting).unwrap();
+            let db_state = db.read_db().unwrap();
+
+            // Assert
+            assert_eq!(summary.skipped, 1);
+            assert_eq!(summary.created, 0);
+            assert_eq!(db_state.epics.len(), 1);
+            assert_eq!(db_state.epics.get(&epic_id).unwrap().description, "old");
+        }
+
+        #[test]
+        fn overwrite_should_update_matches() {
+            // Arrange
+            let (db, epic_id, imported) = arrange_import();
+
+            // Act
+            let summary = db.import_epics(imported, MergeMode::Overwrite).unwrap();
+            let db_state = db.read_db().unwrap();
+            let epic = db_state.epics.get(&epic_id).unwrap();
+
+            // Assert
+            assert_eq!(summary.updated, 1);
+            assert_eq!(db_state.epics.len(), 1);
+            assert_eq!(epic.description, "new");
+            assert_eq!(epic.status, Status::InProgress);
+        }
+
+        #[test]
+        fn overwrite_should_match_by_external_id_before_name() {
+            // Arrange
+            let (db, epic_id, mut imported) = arrange_import();
+            imported[0].external_id = Some(epic_id.clone());
+            imported[0].name = "Renamed".to_owned();
+
+            // Act
+            let summary = db.import_epics(imported, MergeMode::Overwrite).unwrap();
+            let db_state = db.read_db().unwrap();
+
+            // Assert
+            assert_eq!(summary.updated, 1);
+            assert_eq!(db_state.epics.get(&epic_id).unwrap().name, "Renamed");
+        }
+
+        #[test]
+        fn skip_existing_should_still_add_new_stories_to_matched_epic() {
+            // Arrange
+            let (db, epic_id, mut imported) = arrange_import();
+            imported[0].stories.push(ImportedStory {
+                external_id: None,
+                name: "New story".to_owned(),
+                description: "".to_owned(),
+                status: Status::Open,
+            });
+
+            // Act
+            let summary = db.import_epics(imported, MergeMode::SkipExisting).unwrap();
+            let db_state = db.read_db().unwrap();
+
+            // Assert
+            assert_eq!(summary.skipped, 1);
+            assert_eq!(summary.created, 1);
+            assert_eq!(db_state.epics.get(&epic_id).unwrap().stories.len(), 1);
+        }
+
+        #[test]
+        fn merge_mode_should_parse_from_flag_value() {
+            assert_eq!("append".parse::<MergeMode>().unwrap(), MergeMode::Append);
+            assert_eq!(
+                "skip-existing".parse::<MergeMode>().unwrap(),
+                MergeMode::SkipExisting
+            );
+            assert_eq!(
+                "overwrite".parse::<MergeMode>().unwrap(),
+                MergeMode::Overwrite
+            );
+            assert!("merge".parse::<MergeMode>().is_err());
+        }
     }
 
     mod database {
@@ -489,7 +5927,9 @@ mod tests {
         #[test]
         fn read_db_should_fail_with_invalid_path() {
             let db = JSONFileDatabase {
-                file_path: "INVALID_PATH".to_owned(),
+                file_path: PathBuf::from("INVALID_PATH"),
+                backup: None,
+                clock: Box::new(SystemClock),
             };
             assert_eq!(db.read_db().is_err(), true);
         }
@@ -507,7 +5947,9 @@ mod tests {
             path.persist(&file_path).unwrap();
 
             let db = JSONFileDatabase {
-                file_path: file_path.clone(),
+                file_path: PathBuf::from(&file_path),
+                backup: None,
+                clock: Box::new(SystemClock),
             };
 
             let result = db.read_db();
@@ -530,7 +5972,9 @@ mod tests {
             path.persist(&file_path).unwrap();
 
             let db = JSONFileDatabase {
-                file_path: file_path.clone(),
+                file_path: PathBuf::from(&file_path),
+                backup: None,
+                clock: Box::new(SystemClock),
             };
 
             let result = db.read_db();
@@ -553,19 +5997,38 @@ mod tests {
             path.persist(&file_path).unwrap();
 
             let db = JSONFileDatabase {
-                file_path: file_path.clone(),
+                file_path: PathBuf::from(&file_path),
+                backup: None,
+                clock: Box::new(SystemClock),
             };
 
             let story = Story {
                 name: "epic 1".to_owned(),
                 description: "epic 1".to_owned(),
                 status: Status::Open,
+                created_at: None,
+                updated_at: None,
+                due_date: None,
+                assignee: None,
+                parent: None,
+                attachments: Vec::new(),
+                estimate: None,
+                status_history: Vec::new(),
+                blocked_reason: None,
+                checklist: Vec::new(),
+                needs_refinement: false,
+                order: None,
             };
             let epic = Epic {
                 name: "epic 1".to_owned(),
                 description: "epic 1".to_owned(),
                 status: Status::Open,
                 stories: vec!["2".to_owned()],
+                pinned: false,
+                created_at: None,
+                updated_at: None,
+                owner: None,
+                order: None,
             };
 
             let mut stories = HashMap::new();
@@ -578,6 +6041,7 @@ mod tests {
                 last_item_id: "1".to_owned(),
                 epics,
                 stories,
+                revision: 0,
             };
 
             let write_result = db.write_db(&state);
@@ -586,7 +6050,200 @@ mod tests {
             remove_file(file_path).unwrap();
 
             assert_eq!(write_result.is_ok(), true);
-            assert_eq!(read_result, state);
+            assert_eq!(
+                read_result,
+                DBState {
+                    revision: 1,
+                    ..state
+                }
+            );
+        }
+
+        #[test]
+        fn write_db_should_retain_only_the_newest_backups() {
+            let dir = tempfile::tempdir().unwrap();
+            let file_path = dir.path().join("db.json");
+            let backup_dir = dir.path().join("backups");
+            let keep = 3;
+
+            let db = JSONFileDatabase {
+                file_path: file_path.clone(),
+                backup: Some(BackupPolicy {
+                    dir: backup_dir.clone(),
+                    keep,
+                }),
+                clock: Box::new(SystemClock),
+            };
+
+            let mut state = DBState {
+                last_item_id: "0".to_owned(),
+                epics: HashMap::new(),
+                stories: HashMap::new(),
+                revision: 0,
+            };
+
+            // The first write has no file to back up yet, so write one more time
+            for index in 0..keep + 3 {
+                state.last_item_id = index.to_string();
+                db.write_db(&state).unwrap();
+                state.revision += 1;
+            }
+
+            let mut backups = std::fs::read_dir(&backup_dir)
+                .unwrap()
+                .map(|entry| entry.unwrap().path())
+                .collect::<Vec<_>>();
+            backups.sort();
+
+            assert_eq!(backups.len(), keep);
+
+            // The newest backup holds the state before the last write
+            let newest: DBState =
+                serde_json::from_str(&std::fs::read_to_string(backups.last().unwrap()).unwrap())
+                    .unwrap();
+            assert_eq!(newest.last_item_id, (keep + 1).to_string());
+        }
+
+        #[test]
+        fn write_db_should_stamp_backups_with_the_clock() {
+            let dir = tempfile::tempdir().unwrap();
+            let backup_dir = dir.path().join("backups");
+            let db = JSONFileDatabase {
+                file_path: dir.path().join("db.json"),
+                backup: Some(BackupPolicy {
+                    dir: backup_dir.clone(),
+                    keep: 1,
+                }),
+                clock: Box::new(FixedClock(fixed_time())),
+            };
+            let state = DBState {
+                last_item_id: "0".to_owned(),
+                epics: HashMap::new(),
+                stories: HashMap::new(),
+                revision: 0,
+            };
+
+            db.write_db(&state).unwrap();
+            db.write_db(&db.read_db().unwrap()).unwrap();
+
+            let backups = std::fs::read_dir(&backup_dir)
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name())
+                .collect::<Vec<_>>();
+            assert_eq!(backups, vec!["db-20220101T120000.000000000Z.json"]);
+        }
+
+        #[test]
+        fn write_db_should_not_back_up_when_disabled() {
+            let dir = tempfile::tempdir().unwrap();
+            let file_path = dir.path().join("db.json");
+
+            let db = JSONFileDatabase {
+                file_path: file_path.clone(),
+                backup: None,
+                clock: Box::new(SystemClock),
+            };
+
+            let state = DBState {
+                last_item_id: "0".to_owned(),
+                epics: HashMap::new(),
+                stories: HashMap::new(),
+                revision: 0,
+            };
+            db.write_db(&state).unwrap();
+            db.write_db(&db.read_db().unwrap()).unwrap();
+
+            assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+        }
+
+        #[test]
+        fn create_db_file_if_missing_should_only_create_a_missing_file() {
+            let dir = tempfile::tempdir().unwrap();
+            let file_path = dir.path().join("jira_cli").join("db.json");
+
+            create_db_file_if_missing(&file_path).unwrap();
+            let db = JiraDatabase::new(file_path.clone());
+            db.create_epic(Epic::new("kept".to_owned(), "".to_owned()))
+                .unwrap();
+            create_db_file_if_missing(&file_path).unwrap();
+
+            assert_eq!(db.read_db().unwrap().epics.len(), 1);
+        }
+
+        #[test]
+        fn write_db_should_round_trip_through_a_compressed_file() {
+            let dir = tempfile::tempdir().unwrap();
+            let file_path = dir.path().join("db.json.gz");
+            let db = JSONFileDatabase {
+                file_path: file_path.clone(),
+                backup: None,
+                clock: Box::new(SystemClock),
+            };
+            let mut epics = HashMap::new();
+            epics.insert(
+                "1".to_owned(),
+                Epic::new("epic 1".to_owned(), "compressed".to_owned()),
+            );
+            let state = DBState {
+                last_item_id: "1".to_owned(),
+                epics,
+                stories: HashMap::new(),
+                revision: 0,
+            };
+
+            db.write_db(&state).unwrap();
+            let read_result = db.read_db().unwrap();
+
+            // Stored as gzip, which starts with the magic bytes 1f 8b
+            let bytes = std::fs::read(&file_path).unwrap();
+            assert_eq!(&bytes[..2], &[0x1f, 0x8b]);
+            assert_eq!(
+                read_result,
+                DBState {
+                    revision: 1,
+                    ..state
+                }
+            );
+        }
+
+        #[test]
+        fn backup_prefix_should_ignore_the_compression_extension() {
+            assert_eq!(get_backup_prefix(Path::new("data/db.json")), "db-");
+            assert_eq!(get_backup_prefix(Path::new("data/db.json.gz")), "db-");
+        }
+
+        #[test]
+        fn write_db_should_conflict_when_the_file_changed_since_the_read() {
+            let dir = tempfile::tempdir().unwrap();
+            let file_path = dir.path().join("db.json");
+            std::fs::write(
+                &file_path,
+                r#"{ "last_item_id": "0", "epics": {}, "stories": {} }"#,
+            )
+            .unwrap();
+            let db = JSONFileDatabase {
+                file_path: file_path.clone(),
+                backup: None,
+                clock: Box::new(SystemClock),
+            };
+
+            let mut first = db.read_db().unwrap();
+            let mut second = db.read_db().unwrap();
+            first.last_item_id = "first".to_owned();
+            second.last_item_id = "second".to_owned();
+            db.write_db(&first).unwrap();
+            let error = DbError::from(db.write_db(&second).unwrap_err());
+
+            assert!(matches!(
+                error,
+                DbError::Conflict {
+                    expected: 0,
+                    found: 1
+                }
+            ));
+            let on_disk = db.read_db().unwrap();
+            assert_eq!(on_disk.last_item_id, "first");
+            assert_eq!(on_disk.revision, 1);
         }
     }
 }
@@ -0,0 +1,231 @@
+use anyhow::{anyhow, Result};
+use reqwest::blocking::{Client, RequestBuilder};
+use reqwest::StatusCode;
+
+use crate::db::{Database, DbError};
+use crate::models::DBState;
+
+// Keeps the whole state on a server: GET <base_url>/db reads it and PUT <base_url>/db
+// replaces it. The PUT body carries the next revision, and a server that has moved
+// past the revision the client read answers 409 Conflict.
+pub struct HttpDatabase {
+    pub base_url: String,
+    // Sent as a bearer token when set
+    pub token: Option<String>,
+    client: Client,
+}
+
+impl HttpDatabase {
+    pub fn new(base_url: String, token: Option<String>) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            token,
+            client: Client::new(),
+        }
+    }
+
+    fn get_db_url(&self) -> String {
+        format!("{}/db", self.base_url)
+    }
+
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+}
+
+// Connection failures surface as DbError::Io, like a file that can't be read
+fn get_network_error(error: reqwest::Error) -> anyhow::Error {
+    std::io::Error::other(error).into()
+}
+
+impl Database for HttpDatabase {
+    fn read_db(&self) -> Result<DBState> {
+        let response = self
+            .authorize(self.client.get(self.get_db_url()))
+            .send()
+            .map_err(get_network_error)?;
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "Reading {} failed with {}.",
+                self.get_db_url(),
+                response.status()
+            ));
+        }
+        let body = response.text().map_err(get_network_error)?;
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    fn write_db(&self, db_state: &DBState) -> Result<()> {
+        let next = DBState {
+            revision: db_state.revision + 1,
+            ..db_state.clone()
+        };
+        let response = self
+            .authorize(self.client.put(self.get_db_url()))
+            .json(&next)
+            .send()
+            .map_err(get_network_error)?;
+        match response.status() {
+            status if status.is_success() => Ok(()),
+            StatusCode::CONFLICT => {
+                // Best effort, the conflict is reported either way
+                let found = self.read_db().map_or(0, |found| found.revision);
+                Err(DbError::Conflict {
+                    expected: db_state.revision,
+                    found,
+                }
+                .into())
+            }
+            status => Err(anyhow!(
+                "Writing {} failed with {}.",
+                self.get_db_url(),
+                status
+            )),
+        }
+    }
+
+    fn backend_name(&self) -> String {
+        format!("HTTP server at {}", self.base_url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::JiraDatabase;
+    use crate::models::Epic;
+    use std::collections::HashMap;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread::JoinHandle;
+
+    // Answers each request in turn with the given status and body, handing back
+    // the request lines and bodies it received
+    fn serve(responses: Vec<(u16, String)>) -> (String, JoinHandle<Vec<(String, String)>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            for (status, body) in responses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut content_length = 0;
+                let mut authorization = String::new();
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    if header.trim().is_empty() {
+                        break;
+                    }
+                    let (name, value) = header.split_once(':').unwrap();
+                    match name.to_lowercase().as_str() {
+                        "content-length" => content_length = value.trim().parse().unwrap(),
+                        "authorization" => authorization = value.trim().to_owned(),
+                        _ => {}
+                    }
+                }
+                let mut request_body = vec![0; content_length];
+                reader.read_exact(&mut request_body).unwrap();
+                requests.push((
+                    format!("{} {}", request_line.trim(), authorization),
+                    String::from_utf8(request_body).unwrap(),
+                ));
+                let response = format!(
+                    "HTTP/1.1 {} X\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                reader.get_mut().write_all(response.as_bytes()).unwrap();
+            }
+            requests
+        });
+        (base_url, server)
+    }
+
+    fn known_state() -> DBState {
+        DBState {
+            last_item_id: "1".to_owned(),
+            epics: HashMap::from([(
+                "1".to_owned(),
+                Epic::new("Shared".to_owned(), "".to_owned()),
+            )]),
+            stories: HashMap::new(),
+            revision: 4,
+        }
+    }
+
+    #[test]
+    fn read_db_should_return_the_served_state() {
+        let body = serde_json::to_string(&known_state()).unwrap();
+        let (base_url, server) = serve(vec![(200, body)]);
+        let database = HttpDatabase::new(format!("{}/", base_url), Some("secret".to_owned()));
+
+        let db_state = database.read_db().unwrap();
+
+        assert_eq!(db_state, known_state());
+        let requests = server.join().unwrap();
+        assert_eq!(requests[0].0, "GET /db HTTP/1.1 Bearer secret");
+    }
+
+    #[test]
+    fn write_db_should_put_the_next_revision() {
+        let body = serde_json::to_string(&known_state()).unwrap();
+        // Read for the change, read again for the undo history, then write
+        let (base_url, server) =
+            serve(vec![(200, body.clone()), (200, body), (204, "".to_owned())]);
+        let db = JiraDatabase::with_database(Box::new(HttpDatabase::new(base_url, None)));
+
+        db.toggle_pin(&"1".to_owned()).unwrap();
+
+        let requests = server.join().unwrap();
+        assert!(requests[2].0.starts_with("PUT /db HTTP/1.1"));
+        let written: DBState = serde_json::from_str(&requests[2].1).unwrap();
+        assert_eq!(written.revision, 5);
+        assert!(written.epics["1"].pinned);
+    }
+
+    #[test]
+    fn write_db_should_report_a_conflict() {
+        let newer = DBState {
+            revision: 6,
+            ..known_state()
+        };
+        let (base_url, _server) = serve(vec![
+            (409, "".to_owned()),
+            (200, serde_json::to_string(&newer).unwrap()),
+        ]);
+        let database = HttpDatabase::new(base_url, None);
+
+        let error = DbError::from(database.write_db(&known_state()).unwrap_err());
+
+        assert!(matches!(
+            error,
+            DbError::Conflict {
+                expected: 4,
+                found: 6
+            }
+        ));
+    }
+
+    #[test]
+    fn network_errors_should_be_io_errors() {
+        // Nothing listens on a port that was just released
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let db = JiraDatabase::with_database(Box::new(HttpDatabase::new(
+            format!("http://127.0.0.1:{}", port),
+            None,
+        )));
+
+        assert!(matches!(db.read_db(), Err(DbError::Io(_))));
+    }
+}
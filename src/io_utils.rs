@@ -1,6 +1,100 @@
-use std::io;
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::io::{self, IsTerminal, Write};
+use std::panic::PanicHookInfo;
+use std::process::Command;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute, queue,
+    terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType},
+};
+
+type PanicHook = Box<dyn Fn(&PanicHookInfo<'_>) + Send + Sync + 'static>;
+
+thread_local! {
+    // Lines loaded with --script, handed out as if typed before stdin is used
+    static SCRIPT: RefCell<VecDeque<String>> = const { RefCell::new(VecDeque::new()) };
+    // Everything typed at free-text prompts this run, recalled with up and down
+    static HISTORY: RefCell<InputHistory> = RefCell::new(InputHistory::default());
+}
+
+// Shell-like recall of earlier entries. `position` points into `entries`, or one
+// past the end while editing a fresh line, which `draft` keeps while browsing.
+#[derive(Debug, Default)]
+pub struct InputHistory {
+    entries: Vec<String>,
+    position: usize,
+    draft: String,
+}
+
+impl InputHistory {
+    // Blank lines and repeats of the last entry aren't worth recalling
+    pub fn push(&mut self, entry: &str) {
+        if !entry.trim().is_empty() && self.entries.last().map(String::as_str) != Some(entry) {
+            self.entries.push(entry.to_owned());
+        }
+        self.reset();
+    }
+
+    // Back to a fresh line, ready for the next prompt
+    pub fn reset(&mut self) {
+        self.position = self.entries.len();
+        self.draft.clear();
+    }
+
+    // The entry before the current one, None at the oldest
+    pub fn previous(&mut self, current: &str) -> Option<&str> {
+        if self.position == 0 {
+            return None;
+        }
+        if self.position == self.entries.len() {
+            self.draft = current.to_owned();
+        }
+        self.position -= 1;
+        Some(&self.entries[self.position])
+    }
+
+    // The entry after the current one, or the draft again past the newest
+    pub fn next(&mut self) -> Option<&str> {
+        if self.position >= self.entries.len() {
+            return None;
+        }
+        self.position += 1;
+        match self.entries.get(self.position) {
+            Some(entry) => Some(entry),
+            None => Some(&self.draft),
+        }
+    }
+}
+
+// Queues one line of input per script line, e.g. `c`, an epic name, `q`
+pub fn load_script(contents: &str) {
+    SCRIPT.with(|script| {
+        script
+            .borrow_mut()
+            .extend(contents.lines().map(str::to_owned))
+    });
+}
+
+fn next_script_line() -> Option<String> {
+    SCRIPT.with(|script| script.borrow_mut().pop_front())
+}
+
+fn is_script_playing() -> bool {
+    SCRIPT.with(|script| !script.borrow().is_empty())
+}
 
 pub fn get_user_input() -> String {
+    // Echo scripted lines so a replay reads like a typed session
+    if let Some(line) = next_script_line() {
+        println!("{}", line);
+        return line;
+    }
+
     let mut user_input = String::new();
 
     io::stdin().read_line(&mut user_input).unwrap();
@@ -8,6 +102,497 @@ pub fn get_user_input() -> String {
     user_input
 }
 
+// Like get_user_input, but on a terminal up and down recall earlier entries.
+// Meant for names and descriptions, not for single command keys.
+pub fn get_text_input() -> String {
+    if is_script_playing() || !io::stdin().is_terminal() {
+        return get_user_input();
+    }
+
+    let line = HISTORY.with(|history| {
+        let mut history = history.borrow_mut();
+        history.reset();
+        enable_raw_mode()?;
+        let line = read_history_line(&mut history);
+        disable_raw_mode()?;
+        println!();
+        let line = line?;
+        history.push(&line);
+        Ok::<_, anyhow::Error>(line)
+    });
+
+    line.unwrap()
+}
+
+// Raw mode delivers Ctrl-C as a key instead of a signal, so prompts read that way
+// check for it themselves. It and Esc drop the typed text.
+fn is_cancel_key(key: &KeyEvent) -> bool {
+    key.code == KeyCode::Esc
+        || (key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c'))
+}
+
+// A cancelled prompt answers with a blank line, which every prompt treats as no answer
+fn read_history_line(history: &mut InputHistory) -> Result<String> {
+    let mut line = String::new();
+    let mut stdout = io::stdout();
+    loop {
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        if is_cancel_key(&key) {
+            return Ok(String::new());
+        }
+        let recalled = match key.code {
+            KeyCode::Enter => return Ok(line),
+            KeyCode::Up => history.previous(&line).map(str::to_owned),
+            KeyCode::Down => history.next().map(str::to_owned),
+            KeyCode::Backspace if line.pop().is_some() => {
+                write!(stdout, "\x08 \x08")?;
+                None
+            }
+            // Other control chords aren't text either
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                line.push(c);
+                write!(stdout, "{}", c)?;
+                None
+            }
+            _ => None,
+        };
+        // Redraw the whole line with the recalled entry
+        if let Some(recalled) = recalled {
+            line = recalled;
+            queue!(
+                stdout,
+                Clear(ClearType::CurrentLine),
+                cursor::MoveToColumn(0)
+            )?;
+            write!(stdout, "{}", line)?;
+        }
+        stdout.flush()?;
+    }
+}
+
 pub fn wait_for_key_press() {
+    // A replay shouldn't stall on error messages
+    if is_script_playing() {
+        return;
+    }
     io::stdin().read_line(&mut String::new()).unwrap();
 }
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Input {
+    Key(String),
+    Timeout,
+}
+
+// Where the main loop reads a line of input from
+pub trait KeyReader {
+    // Whether input arrives before the timeout runs out
+    fn poll(&mut self, timeout: Duration) -> Result<bool>;
+    fn read_line(&mut self) -> Result<String>;
+}
+
+// Plain line-buffered stdin, used when no idle timeout is configured
+pub struct StdinReader;
+
+impl KeyReader for StdinReader {
+    fn poll(&mut self, _timeout: Duration) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn read_line(&mut self) -> Result<String> {
+        Ok(get_user_input())
+    }
+}
+
+// Reads keys through crossterm so waiting for the first one can time out.
+// Once typing starts it behaves like a line prompt until Enter.
+pub struct TerminalReader;
+
+impl KeyReader for TerminalReader {
+    fn poll(&mut self, timeout: Duration) -> Result<bool> {
+        Ok(event::poll(timeout)?)
+    }
+
+    fn read_line(&mut self) -> Result<String> {
+        enable_raw_mode()?;
+        let line = read_raw_line();
+        disable_raw_mode()?;
+        println!();
+        line
+    }
+}
+
+// A cancelled line comes back blank, like pressing Enter on nothing
+fn read_raw_line() -> Result<String> {
+    let mut line = String::new();
+    let mut stdout = io::stdout();
+    loop {
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        if is_cancel_key(&key) {
+            return Ok(String::new());
+        }
+        match key.code {
+            KeyCode::Enter => return Ok(line),
+            KeyCode::Backspace if line.pop().is_some() => {
+                write!(stdout, "\x08 \x08")?;
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                line.push(c);
+                write!(stdout, "{}", c)?;
+            }
+            _ => {}
+        }
+        stdout.flush()?;
+    }
+}
+
+// Leaves raw mode and shows the cursor again. Errors are ignored, there is
+// nothing better to do with them while crashing.
+pub fn restore_terminal() {
+    let _ = disable_raw_mode();
+    let _ = execute!(io::stdout(), cursor::Show);
+}
+
+// Restores the terminal before reporting the panic, so a crash inside a raw-mode
+// prompt leaves a usable shell and a readable message
+fn get_panic_hook(restore: impl Fn() + Send + Sync + 'static) -> PanicHook {
+    Box::new(move |info| {
+        restore();
+        eprintln!("\njira_cli crashed: {}", info);
+    })
+}
+
+// The panic still unwinds out of main afterwards, which exits with a non-zero code
+pub fn install_panic_hook() {
+    std::panic::set_hook(get_panic_hook(restore_terminal));
+}
+
+// Waits for a line of input, giving up after `timeout` if nothing was typed.
+// Without a timeout this blocks like get_user_input.
+pub fn read_input(reader: &mut dyn KeyReader, timeout: Option<Duration>) -> Result<Input> {
+    if let Some(line) = next_script_line() {
+        return Ok(Input::Key(line));
+    }
+    if let Some(timeout) = timeout {
+        if !reader.poll(timeout)? {
+            return Ok(Input::Timeout);
+        }
+    }
+    Ok(Input::Key(reader.read_line()?))
+}
+
+// Blank lines that push the previous page out of view when the screen can't be cleared
+const FALLBACK_CLEAR_LINES: usize = 50;
+
+// Clears the screen between pages. Terminals that can't be cleared, such as dumb
+// terminals and CI logs, get blank lines instead and a single warning.
+pub struct ScreenClearer {
+    clear: Box<dyn Fn() -> Result<()>>,
+    // Off with --no-clear, in which case pages are simply printed one after another
+    enabled: bool,
+    warned: Cell<bool>,
+}
+
+impl ScreenClearer {
+    pub fn new(clear: Box<dyn Fn() -> Result<()>>, enabled: bool) -> Self {
+        Self {
+            clear,
+            enabled,
+            warned: Cell::new(false),
+        }
+    }
+
+    pub fn clear(&self, out: &mut dyn Write) {
+        if !self.enabled {
+            return;
+        }
+        if let Err(error) = (self.clear)() {
+            // Nothing more can be done if writing fails as well
+            let _ = write!(out, "{}", "\n".repeat(FALLBACK_CLEAR_LINES));
+            if !self.warned.replace(true) {
+                let _ = writeln!(
+                    out,
+                    "Warning: could not clear the screen ({}). Use --no-clear to skip clearing.",
+                    error
+                );
+            }
+        }
+    }
+}
+
+// Rings the terminal bell and, when built with desktop-notify, raises a desktop notification
+pub fn notify_user(message: &str) {
+    print!("\x07");
+    let _ = io::stdout().flush();
+    notify_desktop(message);
+}
+
+// Best effort, the caller shows the message in the terminal either way
+#[cfg(feature = "desktop-notify")]
+fn notify_desktop(message: &str) {
+    let _ = notify_rust::Notification::new()
+        .summary("jira_cli")
+        .body(message)
+        .show();
+}
+
+#[cfg(not(feature = "desktop-notify"))]
+fn notify_desktop(_message: &str) {}
+
+#[cfg(feature = "clipboard")]
+pub fn copy_to_clipboard(text: &str) -> Result<()> {
+    arboard::Clipboard::new()?.set_text(text)?;
+    Ok(())
+}
+
+// Callers fall back to printing the text so it can be selected by hand
+#[cfg(not(feature = "clipboard"))]
+pub fn copy_to_clipboard(_text: &str) -> Result<()> {
+    Err(anyhow!("this build has no clipboard support"))
+}
+
+#[cfg(windows)]
+const DEFAULT_EDITOR: &str = "notepad";
+#[cfg(not(windows))]
+const DEFAULT_EDITOR: &str = "vi";
+
+// $VISUAL, then $EDITOR, then the platform's default
+pub fn get_editor() -> String {
+    select_editor(std::env::var("VISUAL").ok(), std::env::var("EDITOR").ok())
+}
+
+fn select_editor(visual: Option<String>, editor: Option<String>) -> String {
+    [visual, editor]
+        .into_iter()
+        .flatten()
+        .find(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_EDITOR.to_owned())
+}
+
+// Runs a command line through the platform's shell, cmd on Windows and sh elsewhere
+pub fn shell_command(command_line: &str) -> Command {
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.arg("/C");
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.arg("-c");
+        command
+    };
+    command.arg(command_line);
+    command
+}
+
+// Hands a path or URL to the platform's default opener without waiting for it
+pub fn open_with_system(target: &str) -> Result<()> {
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else {
+        Command::new("xdg-open")
+    };
+
+    command
+        .arg(target)
+        .spawn()
+        .map_err(|error| anyhow!("Could not open {}: {}", target, error))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn select_editor_should_prefer_visual_then_editor() {
+        let set = |editor: &str| Some(editor.to_owned());
+
+        assert_eq!(select_editor(set("code -w"), set("nano")), "code -w");
+        assert_eq!(select_editor(set(" "), set("nano")), "nano");
+        assert_eq!(select_editor(None, None), DEFAULT_EDITOR);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn select_editor_should_default_to_notepad_on_windows() {
+        assert_eq!(select_editor(None, Some("".to_owned())), "notepad");
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn select_editor_should_default_to_vi_elsewhere() {
+        assert_eq!(select_editor(None, Some("".to_owned())), "vi");
+    }
+
+    #[test]
+    fn is_cancel_key_should_accept_ctrl_c_and_esc_only() {
+        let ctrl = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+        let plain = |code| KeyEvent::new(code, KeyModifiers::NONE);
+
+        assert!(is_cancel_key(&ctrl('c')));
+        assert!(is_cancel_key(&plain(KeyCode::Esc)));
+        assert!(!is_cancel_key(&plain(KeyCode::Char('c'))));
+        assert!(!is_cancel_key(&ctrl('d')));
+    }
+
+    #[test]
+    fn shell_command_should_run_through_the_platform_shell() {
+        let status = shell_command("exit 3").status().unwrap();
+
+        assert_eq!(status.code(), Some(3));
+    }
+
+    #[test]
+    fn screen_clearer_should_fall_back_to_blank_lines_and_warn_once() {
+        let clearer = ScreenClearer::new(Box::new(|| Err(anyhow!("terminal not supported"))), true);
+        let mut first = Vec::new();
+        let mut second = Vec::new();
+
+        clearer.clear(&mut first);
+        clearer.clear(&mut second);
+
+        let first = String::from_utf8(first).unwrap();
+        let second = String::from_utf8(second).unwrap();
+        assert!(first.starts_with(&"\n".repeat(FALLBACK_CLEAR_LINES)));
+        assert!(first.contains("could not clear the screen (terminal not supported)"));
+        assert_eq!(second, "\n".repeat(FALLBACK_CLEAR_LINES));
+    }
+
+    #[test]
+    fn screen_clearer_should_stay_quiet_when_clearing_works_or_is_off() {
+        let calls = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&calls);
+        let working = ScreenClearer::new(
+            Box::new(move || {
+                counter.set(counter.get() + 1);
+                Ok(())
+            }),
+            true,
+        );
+        let disabled = ScreenClearer::new(Box::new(|| Err(anyhow!("unused"))), false);
+        let mut out = Vec::new();
+
+        working.clear(&mut out);
+        disabled.clear(&mut out);
+
+        assert_eq!(calls.get(), 1);
+        assert!(out.is_empty());
+    }
+
+    struct StubReader {
+        ready: bool,
+        polled: Vec<Duration>,
+    }
+
+    impl KeyReader for StubReader {
+        fn poll(&mut self, timeout: Duration) -> Result<bool> {
+            self.polled.push(timeout);
+            Ok(self.ready)
+        }
+
+        fn read_line(&mut self) -> Result<String> {
+            Ok("q".to_owned())
+        }
+    }
+
+    #[test]
+    fn read_input_should_time_out_when_nothing_is_typed() {
+        let mut reader = StubReader {
+            ready: false,
+            polled: vec![],
+        };
+
+        let input = read_input(&mut reader, Some(Duration::from_secs(30))).unwrap();
+
+        assert_eq!(input, Input::Timeout);
+        assert_eq!(reader.polled, vec![Duration::from_secs(30)]);
+    }
+
+    #[test]
+    fn read_input_should_return_the_line_when_ready() {
+        let mut reader = StubReader {
+            ready: true,
+            polled: vec![],
+        };
+
+        let input = read_input(&mut reader, Some(Duration::from_secs(30))).unwrap();
+
+        assert_eq!(input, Input::Key("q".to_owned()));
+    }
+
+    #[test]
+    fn read_input_should_not_poll_without_timeout() {
+        let mut reader = StubReader {
+            ready: false,
+            polled: vec![],
+        };
+
+        let input = read_input(&mut reader, None).unwrap();
+
+        assert_eq!(input, Input::Key("q".to_owned()));
+        assert!(reader.polled.is_empty());
+    }
+
+    #[test]
+    fn history_should_walk_back_and_forward_through_entries() {
+        let mut history = InputHistory::default();
+        for entry in ["Login page", "Signup page", "Signup page", "", "Logout"] {
+            history.push(entry);
+        }
+
+        assert_eq!(history.previous("draft"), Some("Logout"));
+        assert_eq!(history.previous("ignored"), Some("Signup page"));
+        assert_eq!(history.previous("ignored"), Some("Login page"));
+        assert_eq!(history.previous("ignored"), None);
+        assert_eq!(history.next(), Some("Signup page"));
+        assert_eq!(history.next(), Some("Logout"));
+        assert_eq!(history.next(), Some("draft"));
+        assert_eq!(history.next(), None);
+    }
+
+    #[test]
+    fn history_should_start_each_prompt_at_a_fresh_line() {
+        let mut history = InputHistory::default();
+        history.push("first");
+        history.push("second");
+        history.previous("");
+        history.previous("");
+
+        history.reset();
+
+        assert_eq!(history.next(), None);
+        assert_eq!(history.previous(""), Some("second"));
+    }
+
+    #[test]
+    fn panic_hook_should_restore_the_terminal() {
+        let restored = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&restored);
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(get_panic_hook(move || flag.store(true, Ordering::SeqCst)));
+
+        let result = std::panic::catch_unwind(|| panic!("simulated crash"));
+
+        std::panic::set_hook(previous);
+        assert!(result.is_err());
+        assert!(restored.load(Ordering::SeqCst));
+    }
+}
@@ -1,7 +1,14 @@
+use std::path::PathBuf;
 use std::rc::Rc;
 
 mod models;
 
+mod cli;
+use cli::*;
+
+mod config;
+use config::*;
+
 mod db;
 use anyhow::Context;
 use db::*;
@@ -11,78 +18,197 @@ mod ui;
 mod io_utils;
 use io_utils::*;
 
+mod watch;
+
+mod ordering;
+
+mod query;
+use query::load_saved_filters;
+
+mod rules;
+
+#[cfg(feature = "http")]
+mod http_db;
+
 mod navigator;
 use navigator::*;
 
 fn main() {
-    // Get database
-    let db = Rc::new(JiraDatabase::new("./data/db.json".to_owned()));
+    // A crash inside a raw-mode prompt must not leave the terminal unusable
+    install_panic_hook();
 
-    // Instanciate navigator and get current page
-    let mut navigator = Navigator::new(Rc::clone(&db));
+    // Global flags come before the subcommand, if any
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (global, args) = match parse_global_args(&args) {
+        Ok(parsed) => parsed,
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            std::process::exit(1);
+        }
+    };
 
-    loop {
-        // Clear the screen on start
-        clearscreen::clear().unwrap();
-
-        // Current page
-        if let Some(page) = navigator.get_current_page() {
-            if let Err(error) = page.draw_page() {
-                println!(
-                    "Error rendering page: {}\n
-                    Press any key to continue...",
-                    error
-                );
-                wait_for_key_press();
+    // Load configuration from the config files, the environment and -c flags
+    let sources = match load_config_sources(&global.config) {
+        Ok(sources) => sources,
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            std::process::exit(1);
+        }
+    };
+    let config = match merge_config_sources(&sources) {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            std::process::exit(1);
+        }
+    };
+
+    // Get database, backing up before each write when a backup directory is configured.
+    // Configured projects are opened together as a read-only overview, and a
+    // configured server replaces the local file. Neither of those is backed up.
+    if config.backup_dir.is_some() && (config.db_url.is_some() || !config.projects.is_empty()) {
+        eprintln!("Warning: backup_dir is ignored, it only backs up db_path.");
+    }
+    // The default file lives in the user's data directory and is created on first run.
+    // A db_path that was set explicitly and is missing stays an error.
+    let uses_default_file = config.db_url.is_none()
+        && config.projects.is_empty()
+        && config.db_path == get_default_db_path();
+    if uses_default_file {
+        if let Err(error) = create_db_file_if_missing(&config.db_path) {
+            eprintln!("Error: {:#}", error);
+            std::process::exit(1);
+        }
+    }
+    let mut db = if let Some(url) = &config.db_url {
+        match open_remote(url.clone(), config.db_token.clone()) {
+            Ok(db) => db,
+            Err(error) => {
+                eprintln!("Error: {}", error);
+                std::process::exit(1);
             }
+        }
+    } else if !config.projects.is_empty() {
+        JiraDatabase::aggregate(
+            config
+                .projects
+                .iter()
+                .map(|(project, path)| (project.clone(), path.clone()))
+                .collect(),
+        )
+    } else if let Some(dir) = &config.backup_dir {
+        JiraDatabase::with_backups(
+            config.db_path.clone(),
+            BackupPolicy {
+                dir: dir.clone(),
+                keep: config.backup_keep,
+            },
+        )
+    } else {
+        JiraDatabase::new(config.db_path.clone())
+    };
+    db.close_policy = config.close_policy;
+    db.epic_template = config.epic_template.clone();
+    db.status_history_limit = config.status_history_limit;
+    db.require_blocked_reason = config.require_blocked_reason;
+    db.done_policy = config.done_policy;
+    db.undo_limit = config.undo_limit;
+    db.snapshot_history = config.snapshot_history.as_ref().map(PathBuf::from);
+    // Only the TUI reads the same state over and over, subcommands read once or
+    // watch the file for outside changes
+    db.cache_reads = args.is_empty();
+    let db = Rc::new(db);
 
-            // Get user input
-            let user_input = get_user_input();
-
-            // Handle user input
-            match page.handle_input(user_input.trim()) {
-                Err(error) => {
-                    println!(
-                        "Error getting user input: {}\n
-                        Press any key to continue...",
-                        error
-                    );
-                    wait_for_key_press();
-                }
-                Ok(action) => {
-                    if let Some(action) = action {
-                        if let Err(error) = navigator.handle_action(action) {
-                            println!(
-                                "Error handling processing user input: {}\n
-                                Press any key to continue...",
-                                error
-                            );
-                            wait_for_key_press();
-                        }
-                    }
-                }
+    // An unknown display time zone is not fatal, timestamps are shown in UTC instead
+    let (display, warning) = config.get_display_config();
+    if let Some(warning) = warning {
+        eprintln!("Warning: {}", warning);
+    }
+
+    // Run a one-off subcommand instead of the TUI when one is given
+    match parse_args(&args) {
+        Ok(Some(Command::Info)) => {
+            if let Err(error) = write_info(&mut std::io::stdout(), &config, &sources, &db) {
+                eprintln!("Error: {}", error);
+                std::process::exit(1);
+            }
+            return;
+        }
+        Ok(Some(command)) => {
+            let result = run_command(
+                command,
+                &db,
+                &display,
+                global.verbosity,
+                &mut std::io::stdout(),
+            );
+            if let Err(error) = result {
+                eprintln!("Error: {}", error);
+                std::process::exit(1);
             }
+            return;
         }
+        Ok(None) => {}
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            std::process::exit(1);
+        }
+    }
 
-        // // Get navigator current page
-        // let page = navigator.get_current_page();
-
-        // // Check if page Some variant came back from operation, and if not, print error and exit
-        // if let Some(page) = page {
-        //     if let Err(error) = page.draw_page() {
-        //         println!("Error: {}", error);
-        //         wait_for_key_press();
-        //     }
-        //     // Handle user input
-        //     let input = get_user_input();
-        //     // Pass input to page
-        //     let action = page.handle_input(&input);
-        //     // Handle input
-        //     if let Ok(Some(action)) = action {
-        //         navigator
-        //             .handle_action(action)
-        //             .expect("Action failed to perform.");
-        //     }
-        // }
+    // Instanciate navigator and get current page
+    let mut navigator = Navigator::new(Rc::clone(&db));
+    navigator.set_display_config(display);
+    // Quiet mode skips the overdue nudge along with other chatter
+    navigator.set_notify_overdue(!global.no_notify && global.verbosity != Verbosity::Quiet);
+    navigator.set_watch_rules(config.watch_rules.clone(), global.run_rule_actions);
+    // Like the time zone, a filter that no longer parses is skipped rather than fatal
+    let (saved_filters, warnings) = load_saved_filters(&config.saved_filters);
+    for warning in warnings {
+        eprintln!("Warning: {}", warning);
     }
+    navigator.set_saved_filters(saved_filters);
+    navigator.set_user(
+        config
+            .user
+            .clone()
+            .or_else(|| std::env::var("USER").ok())
+            .filter(|user| !user.trim().is_empty()),
+    );
+
+    // Off by default. When set, the database is reloaded after this many idle seconds.
+    let idle_timeout = config.idle_secs.map(std::time::Duration::from_secs);
+    let mut reader: Box<dyn KeyReader> = match idle_timeout {
+        Some(_) => Box::new(TerminalReader),
+        None => Box::new(StdinReader),
+    };
+
+    // Replay a script first, when one was given, then carry on interactively
+    if let Some(path) = &global.script {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => load_script(&contents),
+            Err(error) => {
+                eprintln!("Error: Could not read script {}: {}", path.display(), error);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let clearer = ScreenClearer::new(Box::new(|| Ok(clearscreen::clear()?)), !global.no_clear);
+    navigator.run(reader.as_mut(), idle_timeout, &|| {
+        clearer.clear(&mut std::io::stdout())
+    });
+}
+
+#[cfg(feature = "http")]
+fn open_remote(url: String, token: Option<String>) -> anyhow::Result<JiraDatabase> {
+    Ok(JiraDatabase::with_database(Box::new(
+        http_db::HttpDatabase::new(url, token),
+    )))
+}
+
+#[cfg(not(feature = "http"))]
+fn open_remote(_url: String, _token: Option<String>) -> anyhow::Result<JiraDatabase> {
+    Err(anyhow::anyhow!(
+        "db_url is set, but this build has no HTTP support. Rebuild with --features http."
+    ))
 }
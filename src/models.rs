@@ -1,24 +1,101 @@
 #![allow(dead_code)]
 
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use crossterm::style::{Color, Stylize};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 use std::fmt::Display;
+use std::str::FromStr;
 
-#[derive(Debug, PartialEq, Eq)]
+// Serialized in serde's default externally tagged form, e.g.
+// {"DeleteStory":{"epic_id":"1","story_id":"2"}}, so recorded actions stay readable
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub enum Action {
     NavigateToEpicDetail { epic_id: String },
     NavigateToStoryDetail { epic_id: String, story_id: String },
     NavigateToPreviousPage,
+    NavigateBack { count: usize },
+    NavigateToRawJson { epic_id: Option<String> },
+    Search,
+    // Pick a saved filter or a recent search and open its results
+    PickFilter,
+    NavigateToDueThisWeek,
+    NavigateToRefinementQueue,
+    FocusAssignee,
+    ClearAssigneeFocus,
+    // The epic or story id shown on a detail page
+    CopyId { id: String },
+    Refresh,
+    Undo,
+    Redo,
     CreateEpic,
+    QuickCapture,
     UpdateEpicStatus { epic_id: String },
+    UpdateEpicOwner { epic_id: String },
     DeleteEpic { epic_id: String },
+    TogglePin { epic_id: String },
     CreateStory { epic_id: String },
+    QuickAddStories { epic_id: String },
+    SelectStories { epic_id: String },
     UpdateStoryStatus { story_id: String },
+    // A status picked straight from StoryDetail, without the prompt
+    SetStoryStatus { story_id: String, status: Status },
+    AddAttachment { story_id: String },
+    OpenAttachment { story_id: String, index: usize },
+    RemoveAttachment { story_id: String, index: usize },
+    AddChecklistItem { story_id: String },
+    ToggleChecklistItem { story_id: String, index: usize },
+    SetStoryParent { epic_id: String, story_id: String },
     DeleteStory { epic_id: String, story_id: String },
+    PromoteStory { epic_id: String, story_id: String },
+    ReopenEpic { epic_id: String },
+    ReopenStory { story_id: String },
+    AssignToSelf { story_id: String },
+    // Prompts for the name, an empty one unassigns
+    AssignStory { story_id: String },
+    Unassign { story_id: String },
+    // Opens the next (or previous) of my in-progress stories in place of this one
+    CycleInProgress { story_id: String, forward: bool },
+    ToggleRefinement { story_id: String },
     Exit,
 }
 
+impl Action {
+    // Actions that change the database, as opposed to navigating or reading
+    pub fn is_write(&self) -> bool {
+        matches!(
+            self,
+            Action::Undo
+                | Action::Redo
+                | Action::CreateEpic
+                | Action::QuickCapture
+                | Action::UpdateEpicStatus { .. }
+                | Action::UpdateEpicOwner { .. }
+                | Action::DeleteEpic { .. }
+                | Action::TogglePin { .. }
+                | Action::CreateStory { .. }
+                | Action::QuickAddStories { .. }
+                | Action::SelectStories { .. }
+                | Action::UpdateStoryStatus { .. }
+                | Action::SetStoryStatus { .. }
+                | Action::AddAttachment { .. }
+                | Action::RemoveAttachment { .. }
+                | Action::AddChecklistItem { .. }
+                | Action::ToggleChecklistItem { .. }
+                | Action::SetStoryParent { .. }
+                | Action::DeleteStory { .. }
+                | Action::PromoteStory { .. }
+                | Action::ReopenEpic { .. }
+                | Action::ReopenStory { .. }
+                | Action::AssignToSelf { .. }
+                | Action::AssignStory { .. }
+                | Action::Unassign { .. }
+                | Action::ToggleRefinement { .. }
+        )
+    }
+}
+
 impl Display for Status {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -26,16 +103,81 @@ impl Display for Status {
             Status::InProgress => write!(f, "IN PROGRESS"),
             Status::Resolved => write!(f, "RESOLVED"),
             Status::Closed => write!(f, "CLOSED"),
+            Status::Blocked => write!(f, "BLOCKED"),
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub enum Status {
     InProgress,
     Closed,
     Open,
     Resolved,
+    // Waiting on something outside the story, see Story::blocked_reason
+    Blocked,
+}
+
+impl FromStr for Status {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "open" => Ok(Status::Open),
+            "in-progress" | "in_progress" => Ok(Status::InProgress),
+            "blocked" => Ok(Status::Blocked),
+            "resolved" => Ok(Status::Resolved),
+            "closed" => Ok(Status::Closed),
+            other => Err(anyhow::anyhow!(
+                "Unknown status '{}'. Expected open, in-progress, blocked, resolved or closed.",
+                other
+            )),
+        }
+    }
+}
+
+impl Status {
+    // Resolved or closed
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Status::Resolved | Status::Closed)
+    }
+
+    // The workflow is Open -> InProgress -> Resolved -> Closed, one step at a time.
+    // Unfinished work may go in and out of Blocked, resolved work can go back to
+    // InProgress for rework, and anything can be reopened.
+    pub fn can_transition_to(&self, next: &Status) -> bool {
+        match (self, next) {
+            (current, next) if current == next => true,
+            (_, Status::Open) => true,
+            (Status::Open, Status::InProgress) => true,
+            (Status::InProgress, Status::Resolved) => true,
+            (Status::Resolved, Status::Closed) => true,
+            (Status::Open | Status::InProgress, Status::Blocked) => true,
+            (Status::Blocked, Status::InProgress) => true,
+            (Status::Resolved, Status::InProgress) => true,
+            _ => false,
+        }
+    }
+
+    // Open work stays uncolored
+    pub fn get_color(&self) -> Option<Color> {
+        match self {
+            Status::Open => None,
+            Status::InProgress => Some(Color::Yellow),
+            Status::Resolved | Status::Closed => Some(Color::Green),
+            Status::Blocked => Some(Color::Red),
+        }
+    }
+}
+
+// "id | status | name", the one-line form every list of mixed items shares. Callers
+// pass whether colors are on, e.g. from colors_enabled(), which honors NO_COLOR.
+fn get_summary_line(id: &str, status: &Status, name: &str, colored: bool) -> String {
+    let status = match status.get_color() {
+        Some(color) if colored => status.to_string().with(color).to_string(),
+        _ => status.to_string(),
+    };
+    format!("{} | {} | {}", id, status, name)
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
@@ -44,6 +186,18 @@ pub struct Epic {
     pub description: String,
     pub status: Status,
     pub stories: Vec<String>,
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
+    // Who the epic belongs to, separate from the assignees of its stories
+    #[serde(default)]
+    pub owner: Option<String>,
+    // Key for the manual order, see ordering. Unset until the epic is reordered.
+    #[serde(default)]
+    pub order: Option<i64>,
 }
 
 impl Epic {
@@ -53,8 +207,21 @@ impl Epic {
             description,
             status: Status::Open,
             stories: Vec::new(),
+            pinned: false,
+            created_at: None,
+            updated_at: None,
+            owner: None,
+            order: None,
         };
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.stories.is_empty()
+    }
+
+    pub fn summary_line(&self, id: &str, colored: bool) -> String {
+        get_summary_line(id, &self.status, &self.name, colored)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
@@ -62,6 +229,37 @@ pub struct Story {
     pub name: String,
     pub description: String,
     pub status: Status,
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub due_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub assignee: Option<String>,
+    // Id of another story in the same epic this one is a subtask of
+    #[serde(default)]
+    pub parent: Option<String>,
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+    // Story points, used by the burndown
+    #[serde(default)]
+    pub estimate: Option<u32>,
+    // Oldest first, capped so it doesn't grow forever
+    #[serde(default)]
+    pub status_history: Vec<StatusChange>,
+    // Why the story is blocked. Only set while the status is Blocked.
+    #[serde(default)]
+    pub blocked_reason: Option<String>,
+    // Acceptance criteria, see DonePolicy
+    #[serde(default)]
+    pub checklist: Vec<ChecklistItem>,
+    // Flagged for the next refinement session, cleared once it has enough detail
+    #[serde(default)]
+    pub needs_refinement: bool,
+    // Key for the manual order among the epic's stories, see ordering
+    #[serde(default)]
+    pub order: Option<i64>,
 }
 
 impl Story {
@@ -70,8 +268,288 @@ impl Story {
             name,
             description,
             status: Status::Open,
+            created_at: None,
+            updated_at: None,
+            due_date: None,
+            assignee: None,
+            parent: None,
+            attachments: Vec::new(),
+            estimate: None,
+            status_history: Vec::new(),
+            blocked_reason: None,
+            checklist: Vec::new(),
+            needs_refinement: false,
+            order: None,
         };
     }
+
+    pub fn is_finished(&self) -> bool {
+        self.status.is_finished()
+    }
+
+    pub fn summary_line(&self, id: &str, colored: bool) -> String {
+        get_summary_line(id, &self.status, &self.name, colored)
+    }
+
+    // From the first move to in progress until the story was first finished after it
+    pub fn cycle_time(&self) -> Option<Duration> {
+        let started = self
+            .status_history
+            .iter()
+            .find(|change| change.status == Status::InProgress)?
+            .at;
+        let finished = self
+            .status_history
+            .iter()
+            .filter(|change| change.at >= started)
+            .find(|change| change.status.is_finished())?
+            .at;
+        Some(finished - started)
+    }
+
+    // From creation until the story was first finished
+    pub fn lead_time(&self) -> Option<Duration> {
+        let created = self.created_at?;
+        let finished = self
+            .status_history
+            .iter()
+            .find(|change| change.status.is_finished())?
+            .at;
+        Some(finished - created)
+    }
+}
+
+// When a story entered a status
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct StatusChange {
+    pub status: Status,
+    pub at: DateTime<Utc>,
+}
+
+// A labelled reference to a local file or URL. Only the reference is stored.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Attachment {
+    pub label: String,
+    pub target: String,
+}
+
+// A story moving to a new status, replayed from its status_history
+#[derive(Debug, PartialEq, Clone)]
+pub struct StatusEvent {
+    pub story_id: String,
+    pub status: Status,
+    pub at: DateTime<Utc>,
+}
+
+// Estimate points still open at the end of a day
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+pub struct BurndownPoint {
+    pub date: NaiveDate,
+    pub remaining: u32,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MergeMode {
+    Append,
+    SkipExisting,
+    Overwrite,
+}
+
+impl FromStr for MergeMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "append" => Ok(MergeMode::Append),
+            "skip-existing" => Ok(MergeMode::SkipExisting),
+            "overwrite" => Ok(MergeMode::Overwrite),
+            other => Err(anyhow::anyhow!(
+                "Unknown merge mode '{}'. Expected append, skip-existing or overwrite.",
+                other
+            )),
+        }
+    }
+}
+
+// What to do when an epic is resolved or closed while some of its stories are still open
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ClosePolicy {
+    #[default]
+    Allow,
+    Warn,
+    Block,
+}
+
+impl FromStr for ClosePolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "allow" => Ok(ClosePolicy::Allow),
+            "warn" => Ok(ClosePolicy::Warn),
+            "block" => Ok(ClosePolicy::Block),
+            other => Err(anyhow::anyhow!(
+                "Unknown close policy '{}'. Expected allow, warn or block.",
+                other
+            )),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct ChecklistItem {
+    pub text: String,
+    #[serde(default)]
+    pub done: bool,
+}
+
+// Whether a story can be closed while some of its checklist is unchecked
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DonePolicy {
+    #[default]
+    Allow,
+    Strict,
+}
+
+// What happens to a story once it has been split into new ones
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SplitOriginal {
+    Keep,
+    Close,
+    Delete,
+}
+
+// An epic as read by an importer, with its stories inlined.
+// `external_id` is matched against existing epic/story ids before falling back to the name.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ImportedEpic {
+    pub external_id: Option<String>,
+    pub name: String,
+    pub description: String,
+    pub status: Status,
+    pub stories: Vec<ImportedStory>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ImportedStory {
+    pub external_id: Option<String>,
+    pub name: String,
+    pub description: String,
+    pub status: Status,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct ImportSummary {
+    pub created: usize,
+    pub updated: usize,
+    pub skipped: usize,
+}
+
+// A search hit. Story hits carry their epic so they can be opened directly.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SearchResult {
+    pub epic_id: String,
+    pub story_id: Option<String>,
+    pub name: String,
+    pub score: i64,
+}
+
+// Stories without an assignee are counted under this key
+pub const UNASSIGNED: &str = "(unassigned)";
+
+#[derive(Serialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct AssigneeStats {
+    pub open: usize,
+    pub in_progress: usize,
+    pub blocked: usize,
+    pub resolved: usize,
+    pub closed: usize,
+}
+
+impl AssigneeStats {
+    pub fn add(&mut self, status: &Status) {
+        match status {
+            Status::Open => self.open += 1,
+            Status::InProgress => self.in_progress += 1,
+            Status::Blocked => self.blocked += 1,
+            Status::Resolved => self.resolved += 1,
+            Status::Closed => self.closed += 1,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.open + self.in_progress + self.blocked + self.resolved + self.closed
+    }
+}
+
+// Something `check` found wrong with the stored state. kind is a stable snake_case
+// name scripts can match on, id the epic or story it is about.
+#[derive(Serialize, Debug, PartialEq, Eq, Clone)]
+pub struct IntegrityProblem {
+    pub kind: String,
+    pub id: String,
+    pub message: String,
+}
+
+// At-a-glance counts for the home page banner. Overdue and unassigned only
+// count unfinished stories, finished ones need no attention.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Health {
+    pub wip: usize,
+    pub overdue: usize,
+    pub unassigned: usize,
+}
+
+// Averages over finished stories; None when no story has the needed history
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct FlowMetrics {
+    pub average_cycle_time: Option<Duration>,
+    pub cycle_time_count: usize,
+    pub average_lead_time: Option<Duration>,
+    pub lead_time_count: usize,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct GcReport {
+    pub story_ids: Vec<String>,
+}
+
+// What a bare id refers to in the database
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ItemRef {
+    Epic { epic_id: String },
+    Story { story_id: String },
+}
+
+// How a typed id or id prefix resolves, like git's short hashes
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum IdMatch {
+    Unique(String),
+    Ambiguous(usize),
+    None,
+}
+
+impl IdMatch {
+    // An exact id always wins, even if it is also a prefix of longer ids
+    pub fn from_ids<'a>(prefix: &str, ids: impl IntoIterator<Item = &'a String>) -> Self {
+        if prefix.is_empty() {
+            return IdMatch::None;
+        }
+        let matches = ids
+            .into_iter()
+            .filter(|id| id.starts_with(prefix))
+            .collect::<Vec<_>>();
+        if let Some(exact) = matches.iter().find(|id| id.as_str() == prefix) {
+            return IdMatch::Unique(exact.to_string());
+        }
+        match matches.as_slice() {
+            [] => IdMatch::None,
+            [id] => IdMatch::Unique(id.to_string()),
+            _ => IdMatch::Ambiguous(matches.len()),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
@@ -79,4 +557,296 @@ pub struct DBState {
     pub epics: HashMap<String, Epic>,
     pub stories: HashMap<String, Story>,
     pub last_item_id: String,
+    // Bumped on every write to the file, so a write based on an older read is caught
+    #[serde(default)]
+    pub revision: u64,
+}
+
+// Read helpers for the joins between epics and stories. The fields stay public,
+// but going through these keeps dangling ids handled the same way everywhere.
+impl DBState {
+    // Every epic, ordered by id
+    pub fn epics_sorted(&self) -> Vec<(&String, &Epic)> {
+        let mut epics = self.epics.iter().collect::<Vec<_>>();
+        epics.sort_by(|a, b| a.0.cmp(b.0));
+        epics
+    }
+
+    // The epic's stories in epic order, skipping ids with no story. None when the epic doesn't exist.
+    pub fn stories_of(&self, epic_id: &String) -> Option<Vec<(&String, &Story)>> {
+        let epic = self.epics.get(epic_id)?;
+        let stories = epic
+            .stories
+            .iter()
+            .filter_map(|story_id| self.stories.get(story_id).map(|story| (story_id, story)))
+            .collect();
+        Some(stories)
+    }
+
+    // The id of the epic listing the story, if any does
+    pub fn find_epic_of_story(&self, story_id: &String) -> Option<&String> {
+        self.epics
+            .iter()
+            .find(|(_, epic)| epic.stories.contains(story_id))
+            .map(|(epic_id, _)| epic_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary_line_should_read_id_status_name() {
+        let epic = Epic::new("Launch".to_owned(), "".to_owned());
+        let story = Story {
+            status: Status::Blocked,
+            ..Story::new("Login form".to_owned(), "".to_owned())
+        };
+
+        assert_eq!(epic.summary_line("1", true), "1 | OPEN | Launch");
+        assert_eq!(story.summary_line("a2", false), "a2 | BLOCKED | Login form");
+        assert_eq!(
+            story.summary_line("a2", true),
+            format!("a2 | {} | Login form", "BLOCKED".red())
+        );
+    }
+
+    #[test]
+    fn can_transition_to_should_follow_the_workflow() {
+        use Status::*;
+        // Every pair, with whether the move is allowed
+        let pairs = [
+            (Open, Open, true),
+            (Open, InProgress, true),
+            (Open, Blocked, true),
+            (Open, Resolved, false),
+            (Open, Closed, false),
+            (InProgress, Open, true),
+            (InProgress, InProgress, true),
+            (InProgress, Blocked, true),
+            (InProgress, Resolved, true),
+            (InProgress, Closed, false),
+            (Blocked, Open, true),
+            (Blocked, InProgress, true),
+            (Blocked, Blocked, true),
+            (Blocked, Resolved, false),
+            (Blocked, Closed, false),
+            (Resolved, Open, true),
+            (Resolved, InProgress, true),
+            (Resolved, Blocked, false),
+            (Resolved, Resolved, true),
+            (Resolved, Closed, true),
+            (Closed, Open, true),
+            (Closed, InProgress, false),
+            (Closed, Blocked, false),
+            (Closed, Resolved, false),
+            (Closed, Closed, true),
+        ];
+
+        for (current, next, allowed) in pairs {
+            assert_eq!(
+                current.can_transition_to(&next),
+                allowed,
+                "{} -> {}",
+                current,
+                next
+            );
+        }
+    }
+
+    #[test]
+    fn every_action_should_round_trip_through_json() {
+        let epic_id = "1".to_owned();
+        let story_id = "2".to_owned();
+        let actions = vec![
+            Action::NavigateToEpicDetail {
+                epic_id: epic_id.clone(),
+            },
+            Action::NavigateToStoryDetail {
+                epic_id: epic_id.clone(),
+                story_id: story_id.clone(),
+            },
+            Action::NavigateToPreviousPage,
+            Action::NavigateBack { count: 3 },
+            Action::NavigateToRawJson { epic_id: None },
+            Action::NavigateToRawJson {
+                epic_id: Some(epic_id.clone()),
+            },
+            Action::Search,
+            Action::PickFilter,
+            Action::NavigateToDueThisWeek,
+            Action::NavigateToRefinementQueue,
+            Action::FocusAssignee,
+            Action::ClearAssigneeFocus,
+            Action::CopyId {
+                id: story_id.clone(),
+            },
+            Action::Refresh,
+            Action::Undo,
+            Action::Redo,
+            Action::CreateEpic,
+            Action::QuickCapture,
+            Action::UpdateEpicStatus {
+                epic_id: epic_id.clone(),
+            },
+            Action::UpdateEpicOwner {
+                epic_id: epic_id.clone(),
+            },
+            Action::DeleteEpic {
+                epic_id: epic_id.clone(),
+            },
+            Action::TogglePin {
+                epic_id: epic_id.clone(),
+            },
+            Action::CreateStory {
+                epic_id: epic_id.clone(),
+            },
+            Action::QuickAddStories {
+                epic_id: epic_id.clone(),
+            },
+            Action::SelectStories {
+                epic_id: epic_id.clone(),
+            },
+            Action::UpdateStoryStatus {
+                story_id: story_id.clone(),
+            },
+            Action::SetStoryStatus {
+                story_id: story_id.clone(),
+                status: Status::Blocked,
+            },
+            Action::AddAttachment {
+                story_id: story_id.clone(),
+            },
+            Action::OpenAttachment {
+                story_id: story_id.clone(),
+                index: 0,
+            },
+            Action::RemoveAttachment {
+                story_id: story_id.clone(),
+                index: 1,
+            },
+            Action::AddChecklistItem {
+                story_id: story_id.clone(),
+            },
+            Action::ToggleChecklistItem {
+                story_id: story_id.clone(),
+                index: 0,
+            },
+            Action::SetStoryParent {
+                epic_id: epic_id.clone(),
+                story_id: story_id.clone(),
+            },
+            Action::DeleteStory {
+                epic_id: epic_id.clone(),
+                story_id: story_id.clone(),
+            },
+            Action::PromoteStory {
+                epic_id: epic_id.clone(),
+                story_id: story_id.clone(),
+            },
+            Action::ReopenEpic {
+                epic_id: epic_id.clone(),
+            },
+            Action::ReopenStory {
+                story_id: story_id.clone(),
+            },
+            Action::AssignToSelf {
+                story_id: story_id.clone(),
+            },
+            Action::AssignStory {
+                story_id: story_id.clone(),
+            },
+            Action::Unassign {
+                story_id: story_id.clone(),
+            },
+            Action::CycleInProgress {
+                story_id: story_id.clone(),
+                forward: true,
+            },
+            Action::ToggleRefinement { story_id },
+            Action::Exit,
+        ];
+
+        for action in actions {
+            let json = serde_json::to_string(&action).unwrap();
+            let parsed: Action = serde_json::from_str(&json).unwrap();
+            assert_eq!(parsed, action, "{}", json);
+        }
+    }
+
+    fn arrange_state() -> DBState {
+        let mut state = DBState {
+            epics: HashMap::new(),
+            stories: HashMap::new(),
+            last_item_id: "0".to_owned(),
+            revision: 0,
+        };
+        for (epic_id, story_ids) in [("b", vec!["2", "1", "gone"]), ("a", vec![])] {
+            let mut epic = Epic::new(epic_id.to_owned(), "".to_owned());
+            epic.stories = story_ids.iter().map(|id| id.to_string()).collect();
+            state.epics.insert(epic_id.to_owned(), epic);
+        }
+        for story_id in ["1", "2", "orphan"] {
+            state.stories.insert(
+                story_id.to_owned(),
+                Story::new(story_id.to_owned(), "".to_owned()),
+            );
+        }
+        state
+    }
+
+    #[test]
+    fn epics_sorted_should_order_by_id() {
+        let state = arrange_state();
+
+        let ids = state
+            .epics_sorted()
+            .into_iter()
+            .map(|(epic_id, _)| epic_id.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(ids, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn stories_of_should_keep_epic_order_and_skip_dangling_ids() {
+        let state = arrange_state();
+
+        let stories = state.stories_of(&"b".to_owned()).unwrap();
+        let ids = stories
+            .into_iter()
+            .map(|(story_id, _)| story_id.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(ids, vec!["2", "1"]);
+        assert!(state.stories_of(&"a".to_owned()).unwrap().is_empty());
+        assert!(state.stories_of(&"missing".to_owned()).is_none());
+    }
+
+    #[test]
+    fn find_epic_of_story_should_find_the_listing_epic() {
+        let state = arrange_state();
+
+        assert_eq!(
+            state.find_epic_of_story(&"1".to_owned()),
+            Some(&"b".to_owned())
+        );
+        assert_eq!(state.find_epic_of_story(&"orphan".to_owned()), None);
+        assert_eq!(state.find_epic_of_story(&"missing".to_owned()), None);
+    }
+
+    #[test]
+    fn action_json_should_keep_its_shape() {
+        let action = Action::DeleteStory {
+            epic_id: "1".to_owned(),
+            story_id: "2".to_owned(),
+        };
+
+        assert_eq!(
+            serde_json::to_string(&action).unwrap(),
+            r#"{"DeleteStory":{"epic_id":"1","story_id":"2"}}"#
+        );
+        assert_eq!(serde_json::to_string(&Action::Exit).unwrap(), r#""Exit""#);
+    }
 }
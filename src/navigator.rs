@@ -1,25 +1,159 @@
 use anyhow::{anyhow, Context, Ok, Result};
+use chrono::Local;
+use crossterm::style::Color;
 use std::rc::Rc;
+use std::time::Duration;
 
 use crate::{
     db::JiraDatabase,
-    models::Action,
-    ui::{EpicDetail, HomePage, Page, Prompts, StoryDetail},
+    io_utils::{notify_user, read_input, wait_for_key_press, Input, KeyReader},
+    models::{Action, DBState, Status},
+    query::SavedFilter,
+    rules::{get_rule_banner, run_rule_action, RuleEvent, RuleWatcher, WatchRule},
+    ui::{
+        get_week_bounds, paint, AssigneeFocus, BulkAction, DisplayConfig, DueStoriesPage,
+        EpicDetail, HomePage, Page, Prompts, QueryResultsPage, RawJsonPage, RefinementQueuePage,
+        SearchResultsPage, StoryDetail,
+    },
 };
 
+// Searches remembered for the filter picker
+const RECENT_SEARCHES_LIMIT: usize = 5;
+
 pub struct Navigator {
     pages: Vec<Box<dyn Page>>,
     prompts: Prompts,
     db: Rc<JiraDatabase>,
+    display: Rc<DisplayConfig>,
+    // Who "assign to me" assigns to
+    user: Option<String>,
+    focus: AssigneeFocus,
+    // Warn about overdue stories at startup and on refresh
+    notify_overdue: bool,
+    // Shown above every page until the next refresh
+    overdue_notice: Option<String>,
+    // Whether the next draw should ring the bell for the notice
+    ring_bell: bool,
+    // Checked after every change and refresh
+    watch_rules: RuleWatcher,
+    // Whether fired rules also run their shell command
+    run_rule_actions: bool,
+    // Shown above the next page only, e.g. fired rules
+    notices: Vec<String>,
+    // From the saved_filters config, already checked to parse
+    saved_filters: Vec<SavedFilter>,
+    // Newest first, for this session only
+    recent_searches: Vec<String>,
 }
 
 impl Navigator {
     pub fn new(db: Rc<JiraDatabase>) -> Self {
+        let focus = AssigneeFocus::default();
         Self {
-            pages: vec![Box::new(HomePage { db: Rc::clone(&db) })],
+            pages: vec![Box::new(HomePage {
+                focus: Rc::clone(&focus),
+                ..HomePage::new(Rc::clone(&db))
+            })],
             prompts: Prompts::new(),
             db,
+            display: Rc::new(DisplayConfig::default()),
+            user: None,
+            focus,
+            notify_overdue: false,
+            overdue_notice: None,
+            ring_bell: false,
+            watch_rules: RuleWatcher::new(Vec::new()),
+            run_rule_actions: false,
+            notices: Vec::new(),
+            saved_filters: Vec::new(),
+            recent_searches: Vec::new(),
+        }
+    }
+
+    pub fn set_saved_filters(&mut self, filters: Vec<SavedFilter>) {
+        self.saved_filters = filters;
+    }
+
+    fn remember_search(&mut self, query: &str) {
+        if query.is_empty() {
+            return;
+        }
+        self.recent_searches.retain(|recent| recent != query);
+        self.recent_searches.insert(0, query.to_owned());
+        self.recent_searches.truncate(RECENT_SEARCHES_LIMIT);
+    }
+
+    // Saved filters first, then recent searches
+    fn get_filter_labels(&self) -> Vec<String> {
+        self.saved_filters
+            .iter()
+            .map(|filter| format!("{}: {}", filter.name, filter.query))
+            .chain(
+                self.recent_searches
+                    .iter()
+                    .map(|query| format!("recent: {}", query)),
+            )
+            .collect()
+    }
+
+    pub fn set_watch_rules(&mut self, rules: Vec<WatchRule>, run_actions: bool) {
+        self.watch_rules = RuleWatcher::new(rules);
+        self.run_rule_actions = run_actions;
+    }
+
+    fn check_watch_rules(&mut self) {
+        if self.watch_rules.is_empty() {
+            return;
+        }
+        // A broken file is reported by the page itself
+        let Result::Ok(db_state) = self.db.read_db() else {
+            return;
+        };
+        for event in self.watch_rules.check(&db_state) {
+            self.notices.push(get_rule_banner(&event));
+            if let RuleEvent::Fired { rule, .. } = &event {
+                self.ring_bell = true;
+                if self.run_rule_actions {
+                    if let Err(error) = run_rule_action(rule) {
+                        self.notices.push(format!("Warning: {}", error));
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn set_notify_overdue(&mut self, notify: bool) {
+        self.notify_overdue = notify;
+    }
+
+    fn update_overdue_notice(&mut self) {
+        if !self.notify_overdue {
+            return;
         }
+        // A broken file is reported by the page itself
+        let overdue = self.db.health().map_or(0, |health| health.overdue);
+        self.overdue_notice = get_overdue_notice(overdue);
+        self.ring_bell = self.overdue_notice.is_some();
+    }
+
+    pub fn set_user(&mut self, user: Option<String>) {
+        self.user = user;
+    }
+
+    fn get_user(&self) -> Result<String> {
+        self.user
+            .clone()
+            .ok_or_else(|| anyhow!("No user configured. Pass --user or set $USER."))
+    }
+
+    // Called before any page is opened, the home page is rebuilt to pick the settings up
+    pub fn set_display_config(&mut self, display: DisplayConfig) {
+        self.display = Rc::new(display);
+        self.pages[0] = Box::new(HomePage {
+            focus: Rc::clone(&self.focus),
+            display: Rc::clone(&self.display),
+            ..HomePage::new(Rc::clone(&self.db))
+        });
     }
 
     pub fn get_current_page(&self) -> Option<&Box<dyn Page>> {
@@ -27,18 +161,51 @@ impl Navigator {
     }
 
     pub fn handle_action(&mut self, action: Action) -> Result<()> {
+        // Read-only backends can still be browsed
+        if action.is_write() && !self.db.capabilities().writable {
+            return Err(anyhow!("This database is read-only."));
+        }
+        let check_rules = action.is_write() || matches!(action, Action::Refresh);
+
         match action {
             Action::NavigateToEpicDetail { epic_id } => {
                 self.pages.push(Box::new(EpicDetail {
+                    focus: Rc::clone(&self.focus),
+                    ..EpicDetail::new(epic_id, Rc::clone(&self.db), Rc::clone(&self.display))
+                }));
+            }
+            Action::NavigateToStoryDetail { epic_id, story_id } => {
+                self.pages.push(Box::new(StoryDetail {
                     epic_id,
+                    story_id,
                     db: Rc::clone(&self.db),
+                    display: Rc::clone(&self.display),
                 }));
             }
-            Action::NavigateToStoryDetail { epic_id, story_id } => {
+            Action::CycleInProgress { story_id, forward } => {
+                let user = self.get_user()?;
+                let stories = self.db.in_progress_stories_for(&user)?;
+                let story_ids = stories
+                    .iter()
+                    .map(|(_, story_id, _)| story_id)
+                    .collect::<Vec<_>>();
+                let Some(index) = get_cycled_index(&story_ids, &story_id, forward) else {
+                    return Ok(());
+                };
+                let (epic_id, story_id, _) = stories[index].clone();
+                // In place of the current story, so cycling doesn't pile up pages
+                self.pages.pop();
                 self.pages.push(Box::new(StoryDetail {
                     epic_id,
                     story_id,
                     db: Rc::clone(&self.db),
+                    display: Rc::clone(&self.display),
+                }));
+            }
+            Action::NavigateToRawJson { epic_id } => {
+                self.pages.push(Box::new(RawJsonPage {
+                    epic_id,
+                    db: Rc::clone(&self.db),
                 }));
             }
             Action::NavigateToPreviousPage => {
@@ -46,6 +213,88 @@ impl Navigator {
                     self.pages.pop();
                 }
             }
+            Action::NavigateBack { count } => {
+                // Never pop the HomePage itself
+                let count = count.min(self.pages.len().saturating_sub(1));
+                self.pages.truncate(self.pages.len() - count);
+            }
+            Action::Search => {
+                let query = (self.prompts.search)();
+                self.remember_search(&query);
+                self.pages.push(Box::new(SearchResultsPage {
+                    query,
+                    db: Rc::clone(&self.db),
+                    display: Rc::clone(&self.display),
+                }));
+            }
+            Action::PickFilter => {
+                let labels = self.get_filter_labels();
+                if labels.is_empty() {
+                    return Err(anyhow!("No saved filters or recent searches yet."));
+                }
+                let Some(index) = (self.prompts.pick_filter)(&labels) else {
+                    return Ok(());
+                };
+                match self.saved_filters.get(index) {
+                    Some(filter) => self.pages.push(Box::new(QueryResultsPage {
+                        name: filter.name.clone(),
+                        query: filter.query.clone(),
+                        db: Rc::clone(&self.db),
+                        display: Rc::clone(&self.display),
+                    })),
+                    None => {
+                        let recent = index - self.saved_filters.len();
+                        let Some(query) = self.recent_searches.get(recent) else {
+                            return Ok(());
+                        };
+                        self.pages.push(Box::new(SearchResultsPage {
+                            query: query.clone(),
+                            db: Rc::clone(&self.db),
+                            display: Rc::clone(&self.display),
+                        }));
+                    }
+                }
+            }
+            Action::NavigateToDueThisWeek => {
+                let (start, end) = get_week_bounds(Local::now().date_naive());
+                self.pages.push(Box::new(DueStoriesPage {
+                    start,
+                    end,
+                    db: Rc::clone(&self.db),
+                    display: Rc::clone(&self.display),
+                }));
+            }
+            Action::NavigateToRefinementQueue => {
+                self.pages.push(Box::new(RefinementQueuePage {
+                    db: Rc::clone(&self.db),
+                    display: Rc::clone(&self.display),
+                }));
+            }
+            Action::FocusAssignee => {
+                let assignee = match (self.prompts.focus_assignee)() {
+                    Some(assignee) => assignee,
+                    None => self.get_user()?,
+                };
+                *self.focus.borrow_mut() = Some(assignee);
+            }
+            Action::ClearAssigneeFocus => {
+                *self.focus.borrow_mut() = None;
+            }
+            Action::Undo => {
+                self.db.undo().with_context(|| anyhow!("failed to undo!"))?;
+            }
+            Action::Redo => {
+                self.db.redo().with_context(|| anyhow!("failed to redo!"))?;
+            }
+            Action::Refresh => {
+                // Drop the cached state and re-read, to surface outside edits (or a
+                // now-broken file) right away
+                self.db.invalidate_cache();
+                self.db
+                    .read_db()
+                    .with_context(|| anyhow!("failed to reload database!"))?;
+                self.update_overdue_notice();
+            }
             Action::CreateEpic => {
                 let epic = (self.prompts.create_epic)();
                 self.db
@@ -56,13 +305,37 @@ impl Navigator {
                 let status = (self.prompts.update_status)();
 
                 if let Some(status) = status {
-                    self.db
+                    let unfinished = self
+                        .db
                         .update_epic_status(&epic_id, status)
                         .with_context(|| anyhow!("Failed to update epic!"))?;
+
+                    if !unfinished.is_empty() {
+                        (self.prompts.close_warning)(&unfinished);
+                    }
                 }
             }
+            Action::CopyId { id } => match (self.prompts.copy_to_clipboard)(&id) {
+                Result::Ok(()) => self
+                    .notices
+                    .push(format!("Copied {} to the clipboard.", id)),
+                // Printed on a line of its own so it is easy to select
+                Err(error) => {
+                    self.notices
+                        .push(format!("Could not copy to the clipboard: {}.", error));
+                    self.notices.push(format!("Id: {}", id));
+                }
+            },
+            Action::UpdateEpicOwner { epic_id } => {
+                let owner = (self.prompts.epic_owner)();
+                self.db
+                    .set_epic_owner(&epic_id, owner)
+                    .with_context(|| anyhow!("Failed to update epic owner!"))?;
+            }
             Action::DeleteEpic { epic_id } => {
-                if (self.prompts.delete_epic)() {
+                let db_state = self.db.read_db()?;
+                let preview = get_delete_epic_preview(&db_state, &epic_id);
+                if (self.prompts.delete_epic)(&preview) {
                     self.db
                         .delete_epic(&epic_id)
                         .with_context(|| anyhow!("failed to delete epic!"))?;
@@ -72,21 +345,142 @@ impl Navigator {
                     }
                 }
             }
+            Action::TogglePin { epic_id } => {
+                self.db
+                    .toggle_pin(&epic_id)
+                    .with_context(|| anyhow!("failed to pin epic!"))?;
+            }
             Action::CreateStory { epic_id } => {
                 let story = (self.prompts.create_story)();
                 self.db
                     .create_story(story, &epic_id)
                     .with_context(|| anyhow!("failed to create story!"))?;
             }
-            Action::UpdateStoryStatus { story_id } => {
-                let status = (self.prompts.update_status)();
-
-                if let Some(status) = status {
+            Action::QuickCapture => {
+                if let Some(name) = (self.prompts.quick_capture)() {
                     self.db
-                        .update_story_status(&story_id, status)
-                        .with_context(|| anyhow!("failed to update story!"))?;
+                        .quick_capture(name)
+                        .with_context(|| anyhow!("failed to capture story!"))?;
+                }
+            }
+            Action::QuickAddStories { epic_id } => {
+                let names = (self.prompts.quick_add_stories)();
+                self.db
+                    .create_stories(names, &epic_id)
+                    .with_context(|| anyhow!("failed to create stories!"))?;
+            }
+            Action::SelectStories { epic_id } => {
+                // Offer the epic's stories in their listed order
+                let db_state = self.db.read_db()?;
+                let epic = self.db.get_epic(&epic_id)?;
+                let rows = epic
+                    .stories
+                    .iter()
+                    .filter_map(|id| {
+                        db_state
+                            .stories
+                            .get(id)
+                            .map(|story| (id.clone(), story.name.clone()))
+                    })
+                    .collect::<Vec<(String, String)>>();
+
+                match (self.prompts.select_stories)(&rows) {
+                    Some((BulkAction::UpdateStatus, story_ids)) => {
+                        if let Some(status) = (self.prompts.update_status)() {
+                            self.db
+                                .update_stories_status(&story_ids, status)
+                                .with_context(|| anyhow!("failed to update stories!"))?;
+                        }
+                    }
+                    Some((BulkAction::AssignToSelf, story_ids)) => {
+                        self.db
+                            .set_stories_assignee(&story_ids, Some(self.get_user()?))
+                            .with_context(|| anyhow!("failed to assign stories!"))?;
+                    }
+                    Some((BulkAction::Delete, story_ids)) if (self.prompts.delete_story)() => {
+                        self.db
+                            .delete_stories(&epic_id, &story_ids)
+                            .with_context(|| anyhow!("failed to delete stories!"))?;
+                    }
+                    _ => {}
                 }
             }
+            Action::UpdateStoryStatus { story_id } => {
+                if let Some(status) = (self.prompts.update_status)() {
+                    self.set_story_status(&story_id, status)?;
+                }
+            }
+            Action::SetStoryStatus { story_id, status } => {
+                // A key press that changes nothing is reported instead of ignored
+                let story = self.db.read_db()?.stories.remove(&story_id);
+                if let Some(story) = story.filter(|story| story.status == status) {
+                    return Err(anyhow!("Story is already {}.", story.status));
+                }
+                self.set_story_status(&story_id, status)?;
+            }
+            Action::AssignToSelf { story_id } => {
+                self.db
+                    .set_stories_assignee(&[story_id], Some(self.get_user()?))
+                    .with_context(|| anyhow!("failed to assign story!"))?;
+            }
+            Action::ToggleRefinement { story_id } => {
+                self.db
+                    .toggle_refinement(&story_id)
+                    .with_context(|| anyhow!("failed to update story!"))?;
+            }
+            Action::AssignStory { story_id } => {
+                let assignee = (self.prompts.assignee)();
+                self.db
+                    .assign_story(&story_id, assignee)
+                    .with_context(|| anyhow!("failed to assign story!"))?;
+            }
+            Action::Unassign { story_id } => {
+                self.db
+                    .set_stories_assignee(&[story_id], None)
+                    .with_context(|| anyhow!("failed to unassign story!"))?;
+            }
+            Action::ReopenEpic { epic_id } => {
+                self.db
+                    .reopen_epic(&epic_id)
+                    .with_context(|| anyhow!("Failed to reopen epic!"))?;
+            }
+            Action::ReopenStory { story_id } => {
+                self.db
+                    .reopen_story(&story_id)
+                    .with_context(|| anyhow!("failed to reopen story!"))?;
+            }
+            Action::AddAttachment { story_id } => {
+                let attachment = (self.prompts.create_attachment)();
+                self.db
+                    .add_attachment(&story_id, attachment)
+                    .with_context(|| anyhow!("failed to add attachment!"))?;
+            }
+            Action::OpenAttachment { story_id, index } => {
+                let attachment = self.db.get_attachment(&story_id, index)?;
+                (self.prompts.open_attachment)(&attachment.target)?;
+            }
+            Action::RemoveAttachment { story_id, index } => {
+                self.db
+                    .remove_attachment(&story_id, index)
+                    .with_context(|| anyhow!("failed to remove attachment!"))?;
+            }
+            Action::AddChecklistItem { story_id } => {
+                let text = (self.prompts.checklist_item)();
+                self.db
+                    .add_checklist_item(&story_id, text)
+                    .with_context(|| anyhow!("failed to add checklist item!"))?;
+            }
+            Action::ToggleChecklistItem { story_id, index } => {
+                self.db
+                    .toggle_checklist_item(&story_id, index)
+                    .with_context(|| anyhow!("failed to tick checklist item!"))?;
+            }
+            Action::SetStoryParent { epic_id, story_id } => {
+                let parent_id = (self.prompts.set_parent)();
+                self.db
+                    .set_story_parent(&epic_id, &story_id, parent_id)
+                    .with_context(|| anyhow!("failed to set story parent!"))?;
+            }
             Action::DeleteStory { epic_id, story_id } => {
                 if (self.prompts.delete_story)() {
                     self.db
@@ -98,12 +492,142 @@ impl Navigator {
                     }
                 }
             }
+            Action::PromoteStory { epic_id, story_id } => {
+                if let Some(move_subtasks) = (self.prompts.promote_story)() {
+                    let new_epic_id = self
+                        .db
+                        .promote_story_to_epic(&epic_id, &story_id, move_subtasks)
+                        .with_context(|| anyhow!("failed to promote story!"))?;
+
+                    // The story page is gone, show the new epic in its place
+                    self.pages.pop();
+                    self.handle_action(Action::NavigateToEpicDetail {
+                        epic_id: new_epic_id,
+                    })?;
+                }
+            }
             Action::Exit => {
                 // Remove all elements from pages vector
                 self.pages.clear();
             }
         }
 
+        if check_rules {
+            self.check_watch_rules();
+        }
+        Ok(())
+    }
+
+    // The main input loop. Returns once the last page is closed.
+    pub fn run(
+        &mut self,
+        reader: &mut dyn KeyReader,
+        idle_timeout: Option<Duration>,
+        clear: &dyn Fn(),
+    ) {
+        self.update_overdue_notice();
+        self.check_watch_rules();
+
+        loop {
+            // Clear the screen on start
+            clear();
+
+            let notices = std::mem::take(&mut self.notices);
+            if std::mem::take(&mut self.ring_bell) {
+                let notices: Vec<&str> = self
+                    .overdue_notice
+                    .iter()
+                    .chain(&notices)
+                    .map(String::as_str)
+                    .collect();
+                notify_user(&notices.join(" "));
+            }
+            if let Some(notice) = &self.overdue_notice {
+                println!("{}", paint(notice, Some(Color::Red)));
+            }
+            for notice in &notices {
+                println!("{}", paint(notice, Some(Color::Yellow)));
+            }
+
+            // Current page
+            let Some(page) = self.get_current_page() else {
+                break;
+            };
+            if let Err(error) = page.draw_page() {
+                println!(
+                    "Error rendering page: {}\n
+                    Press any key to continue...",
+                    error
+                );
+                wait_for_key_press();
+            }
+
+            // Get user input, refreshing from disk if the user stays idle
+            let user_input = match read_input(reader, idle_timeout) {
+                Result::Ok(Input::Key(user_input)) => user_input,
+                Result::Ok(Input::Timeout) => {
+                    if let Err(error) = self.handle_action(Action::Refresh) {
+                        println!(
+                            "Error refreshing database: {}\n
+                            Press any key to continue...",
+                            error
+                        );
+                        wait_for_key_press();
+                    }
+                    continue;
+                }
+                Err(error) => {
+                    println!(
+                        "Error reading user input: {}\n
+                        Press any key to continue...",
+                        error
+                    );
+                    wait_for_key_press();
+                    continue;
+                }
+            };
+
+            // Handle user input
+            match page.handle_input(user_input.trim()) {
+                Err(error) => {
+                    println!(
+                        "Error getting user input: {}\n
+                        Press any key to continue...",
+                        error
+                    );
+                    wait_for_key_press();
+                }
+                Result::Ok(action) => {
+                    if let Some(action) = action {
+                        if let Err(error) = self.handle_action(action) {
+                            println!(
+                                "Error handling processing user input: {}\n
+                                Press any key to continue...",
+                                error
+                            );
+                            wait_for_key_press();
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Blocking also asks why
+    fn set_story_status(&self, story_id: &String, status: Status) -> Result<()> {
+        match status {
+            Status::Blocked => {
+                let reason = (self.prompts.blocked_reason)();
+                self.db
+                    .block_story(story_id, reason)
+                    .with_context(|| anyhow!("failed to block story!"))?;
+            }
+            status => {
+                self.db
+                    .update_story_status(story_id, status)
+                    .with_context(|| anyhow!("failed to update story!"))?;
+            }
+        }
         Ok(())
     }
 
@@ -117,19 +641,68 @@ impl Navigator {
     }
 }
 
+// Stories listed by name before the rest are only counted
+const DELETE_PREVIEW_STORIES: usize = 5;
+
+// Where [ or ] goes from `current`, wrapping around at either end. A story that
+// isn't in the list starts from the first or the last one. None when there is
+// nowhere else to go.
+fn get_cycled_index(story_ids: &[&String], current: &String, forward: bool) -> Option<usize> {
+    let count = story_ids.len();
+    match story_ids.iter().position(|story_id| *story_id == current) {
+        None if count == 0 => None,
+        None if forward => Some(0),
+        None => Some(count - 1),
+        Some(_) if count == 1 => None,
+        Some(index) if forward => Some((index + 1) % count),
+        Some(index) => Some((index + count - 1) % count),
+    }
+}
+
+// Spells out what deleting an epic takes with it, so a big epic isn't deleted by accident
+fn get_delete_epic_preview(db_state: &DBState, epic_id: &String) -> String {
+    let stories = db_state.stories_of(epic_id).unwrap_or_default();
+    let mut lines = vec![match stories.len() {
+        0 => return "This epic has no stories.".to_owned(),
+        1 => "This also deletes its 1 story:".to_owned(),
+        count => format!("This also deletes its {} stories:", count),
+    }];
+    for (_, story) in stories.iter().take(DELETE_PREVIEW_STORIES) {
+        lines.push(format!("  - {}", story.name));
+    }
+    if stories.len() > DELETE_PREVIEW_STORIES {
+        lines.push(format!(
+            "  ... and {} more",
+            stories.len() - DELETE_PREVIEW_STORIES
+        ));
+    }
+    lines.join("\n")
+}
+
+// None when nothing is overdue
+fn get_overdue_notice(overdue: usize) -> Option<String> {
+    match overdue {
+        0 => None,
+        1 => Some("Warning: 1 story is overdue.".to_owned()),
+        count => Some(format!("Warning: {} stories are overdue.", count)),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
-        db::test_utils::MockDB,
-        models::{Epic, Status, Story},
+        db::{
+            test_utils::{walk_epic_to, walk_story_to, MockDB},
+            Capabilities, Database,
+        },
+        models::{Attachment, ClosePolicy, DBState, Epic, Status, Story},
     };
+    use std::cell::{Cell, RefCell};
 
     #[test]
     fn should_start_on_home_page() {
-        let db = Rc::new(JiraDatabase {
-            database: Box::new(MockDB::new()),
-        });
+        let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
         let nav = Navigator::new(db);
 
         assert_eq!(nav.get_page_count(), 1);
@@ -142,9 +715,7 @@ mod tests {
 
     #[test]
     fn handle_action_should_navigate_pages() {
-        let db = Rc::new(JiraDatabase {
-            database: Box::new(MockDB::new()),
-        });
+        let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
 
         let mut nav = Navigator::new(db);
 
@@ -191,10 +762,23 @@ mod tests {
     }
 
     #[test]
-    fn handle_action_should_clear_pages_on_exit() {
-        let db = Rc::new(JiraDatabase {
-            database: Box::new(MockDB::new()),
-        });
+    fn handle_action_should_navigate_to_raw_json_page() {
+        let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+
+        let mut nav = Navigator::new(db);
+
+        nav.handle_action(Action::NavigateToRawJson { epic_id: None })
+            .unwrap();
+        assert_eq!(nav.get_page_count(), 2);
+
+        let current_page = nav.get_current_page().unwrap();
+        let raw_json_page = current_page.as_any().downcast_ref::<RawJsonPage>();
+        assert!(raw_json_page.is_some());
+    }
+
+    #[test]
+    fn handle_action_should_navigate_back_multiple_pages() {
+        let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
 
         let mut nav = Navigator::new(db);
 
@@ -207,115 +791,634 @@ mod tests {
             story_id: "2".to_string(),
         })
         .unwrap();
-        nav.handle_action(Action::Exit).unwrap();
+        nav.handle_action(Action::NavigateToRawJson { epic_id: None })
+            .unwrap();
+        assert_eq!(nav.get_page_count(), 4);
 
-        assert_eq!(nav.get_page_count(), 0);
+        nav.handle_action(Action::NavigateBack { count: 2 })
+            .unwrap();
+        assert_eq!(nav.get_page_count(), 2);
+
+        let current_page = nav.get_current_page().unwrap();
+        let epic_detail_page = current_page.as_any().downcast_ref::<EpicDetail>();
+        assert!(epic_detail_page.is_some());
     }
 
     #[test]
-    fn handle_action_should_handle_create_epic() {
-        let db = Rc::new(JiraDatabase {
-            database: Box::new(MockDB::new()),
-        });
+    fn handle_action_should_clamp_navigate_back_at_home_page() {
+        let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
 
-        let mut nav = Navigator::new(Rc::clone(&db));
+        let mut nav = Navigator::new(db);
 
-        let mut prompts = Prompts::new();
-        prompts.create_epic = Box::new(|| Epic::new("name".to_owned(), "description".to_owned()));
+        nav.handle_action(Action::NavigateToEpicDetail {
+            epic_id: "1".to_string(),
+        })
+        .unwrap();
 
-        nav.set_prompts(prompts);
+        nav.handle_action(Action::NavigateBack { count: 10 })
+            .unwrap();
+        assert_eq!(nav.get_page_count(), 1);
 
-        nav.handle_action(Action::CreateEpic).unwrap();
+        let current_page = nav.get_current_page().unwrap();
+        let home_page = current_page.as_any().downcast_ref::<HomePage>();
+        assert!(home_page.is_some());
+    }
 
-        let db_state = db.read_db().unwrap();
-        assert_eq!(db_state.epics.len(), 1);
+    // Counts reads and writes so tests can tell when the navigator went back to the database
+    struct CountingDB {
+        reads: Rc<Cell<usize>>,
+        writes: Rc<Cell<usize>>,
+        inner: MockDB,
+    }
 
-        let epic = db_state.epics.into_iter().next().unwrap().1;
-        assert_eq!(epic.name, "name".to_owned());
-        assert_eq!(epic.description, "description".to_owned());
+    impl Database for CountingDB {
+        fn read_db(&self) -> Result<DBState> {
+            self.reads.set(self.reads.get() + 1);
+            self.inner.read_db()
+        }
+
+        fn write_db(&self, db_state: &DBState) -> Result<()> {
+            self.writes.set(self.writes.get() + 1);
+            self.inner.write_db(db_state)
+        }
+    }
+
+    struct ReadOnlyDB(MockDB);
+
+    impl Database for ReadOnlyDB {
+        fn read_db(&self) -> Result<DBState> {
+            self.0.read_db()
+        }
+
+        fn write_db(&self, _db_state: &DBState) -> Result<()> {
+            panic!("read-only databases should never be written");
+        }
+
+        fn capabilities(&self) -> Capabilities {
+            Capabilities {
+                writable: false,
+                ..Capabilities::default()
+            }
+        }
     }
 
     #[test]
-    fn handle_action_should_handle_update_epic() {
-        let db = Rc::new(JiraDatabase {
-            database: Box::new(MockDB::new()),
-        });
+    fn run_should_replay_a_script_until_exit() {
+        let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+        let mut nav = Navigator::new(Rc::clone(&db));
+        // Create an epic named Demo with a blank description, then quit
+        crate::io_utils::load_script("c\nDemo\n\nq\n");
+
+        nav.run(&mut crate::io_utils::StdinReader, None, &|| {});
+
+        let epics = db.read_db().unwrap().epics;
+        assert_eq!(epics.len(), 1);
+        assert_eq!(epics.values().next().unwrap().name, "Demo");
+        assert_eq!(nav.get_page_count(), 0);
+    }
+
+    #[test]
+    fn assign_to_self_should_use_the_configured_user() {
+        let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
         let epic_id = db
             .create_epic(Epic::new("".to_owned(), "".to_owned()))
             .unwrap();
-
+        let story_id = db
+            .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+            .unwrap();
         let mut nav = Navigator::new(Rc::clone(&db));
+        nav.set_user(Some("ana".to_owned()));
 
-        let mut prompts = Prompts::new();
-        prompts.update_status = Box::new(|| Some(Status::InProgress));
-
-        nav.set_prompts(prompts);
-
-        nav.handle_action(Action::UpdateEpicStatus {
-            epic_id: epic_id.clone(),
+        nav.handle_action(Action::AssignToSelf {
+            story_id: story_id.clone(),
         })
         .unwrap();
+        let assigned = db.read_db().unwrap().stories[&story_id].assignee.clone();
+        nav.handle_action(Action::Unassign {
+            story_id: story_id.clone(),
+        })
+        .unwrap();
+        let unassigned = db.read_db().unwrap().stories[&story_id].assignee.clone();
 
-        let db_state = db.read_db().unwrap();
-        assert_eq!(
-            db_state.epics.get(&epic_id).unwrap().status,
-            Status::InProgress
-        );
+        assert_eq!(assigned.as_deref(), Some("ana"));
+        assert_eq!(unassigned, None);
     }
 
     #[test]
-    fn handle_action_should_handle_delete_epic() {
-        let db = Rc::new(JiraDatabase {
-            database: Box::new(MockDB::new()),
-        });
+    fn assign_story_should_prompt_for_the_assignee() {
+        let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
         let epic_id = db
             .create_epic(Epic::new("".to_owned(), "".to_owned()))
             .unwrap();
-
+        let story_id = db
+            .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+            .unwrap();
         let mut nav = Navigator::new(Rc::clone(&db));
+        let assign = |nav: &mut Navigator, answer: &'static str| {
+            let mut prompts = Prompts::new();
+            prompts.assignee = Box::new(move || answer.to_owned());
+            nav.set_prompts(prompts);
+            nav.handle_action(Action::AssignStory {
+                story_id: story_id.clone(),
+            })
+            .unwrap();
+            db.read_db().unwrap().stories[&story_id].assignee.clone()
+        };
 
-        let mut prompts = Prompts::new();
-        prompts.delete_epic = Box::new(|| true);
-
-        nav.set_prompts(prompts);
-
-        nav.handle_action(Action::DeleteEpic { epic_id }).unwrap();
+        let assigned = assign(&mut nav, " bo ");
+        let unassigned = assign(&mut nav, "");
 
-        let db_state = db.read_db().unwrap();
-        assert_eq!(db_state.epics.len(), 0);
+        assert_eq!(assigned.as_deref(), Some("bo"));
+        assert_eq!(unassigned, None);
     }
 
     #[test]
-    fn handle_action_should_handle_create_story() {
-        let db = Rc::new(JiraDatabase {
-            database: Box::new(MockDB::new()),
-        });
+    fn assign_to_self_should_error_without_a_user() {
+        let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
         let epic_id = db
             .create_epic(Epic::new("".to_owned(), "".to_owned()))
             .unwrap();
-
+        let story_id = db
+            .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+            .unwrap();
         let mut nav = Navigator::new(Rc::clone(&db));
 
-        let mut prompts = Prompts::new();
-        prompts.create_story = Box::new(|| Story::new("name".to_owned(), "description".to_owned()));
+        let result = nav.handle_action(Action::AssignToSelf {
+            story_id: story_id.clone(),
+        });
+
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("No user configured"));
+        assert_eq!(db.read_db().unwrap().stories[&story_id].assignee, None);
+    }
+
+    #[test]
+    fn handle_action_should_refuse_writes_on_read_only_databases() {
+        let db = Rc::new(JiraDatabase::with_database(Box::new(ReadOnlyDB(
+            MockDB::new(),
+        ))));
+        let mut nav = Navigator::new(db);
+
+        let result = nav.handle_action(Action::CreateEpic);
+
+        assert!(result.unwrap_err().to_string().contains("read-only"));
+        nav.handle_action(Action::Refresh).unwrap();
+        nav.handle_action(Action::NavigateToRawJson { epic_id: None })
+            .unwrap();
+        assert_eq!(nav.get_page_count(), 2);
+    }
+
+    #[test]
+    fn handle_action_should_reread_database_on_refresh() {
+        let reads = Rc::new(Cell::new(0));
+        let db = Rc::new(JiraDatabase::with_database(Box::new(CountingDB {
+            reads: Rc::clone(&reads),
+            writes: Rc::new(Cell::new(0)),
+            inner: MockDB::new(),
+        })));
+
+        let mut nav = Navigator::new(db);
+        nav.handle_action(Action::Refresh).unwrap();
+
+        assert_eq!(reads.get(), 1);
+        assert_eq!(nav.get_page_count(), 1);
+    }
+
+    #[test]
+    fn handle_action_should_open_search_results() {
+        let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+
+        let mut nav = Navigator::new(db);
+
+        let mut prompts = Prompts::new();
+        prompts.search = Box::new(|| "login".to_owned());
+
+        nav.set_prompts(prompts);
+
+        nav.handle_action(Action::Search).unwrap();
+        assert_eq!(nav.get_page_count(), 2);
+
+        let current_page = nav.get_current_page().unwrap();
+        let search_page = current_page
+            .as_any()
+            .downcast_ref::<SearchResultsPage>()
+            .unwrap();
+        assert_eq!(search_page.query, "login");
+    }
+
+    #[test]
+    fn handle_action_should_open_saved_filters_and_recent_searches() {
+        let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+
+        let mut nav = Navigator::new(db);
+        nav.set_saved_filters(vec![SavedFilter {
+            name: "mine".to_owned(),
+            query: "assignee=ana".to_owned(),
+        }]);
+
+        let mut prompts = Prompts::new();
+        prompts.search = Box::new(|| "login".to_owned());
+        let picked = Rc::new(Cell::new(0));
+        let labels = Rc::new(RefCell::new(Vec::new()));
+        let (picked_clone, labels_clone) = (Rc::clone(&picked), Rc::clone(&labels));
+        prompts.pick_filter = Box::new(move |shown| {
+            *labels_clone.borrow_mut() = shown.to_vec();
+            Some(picked_clone.get())
+        });
+        nav.set_prompts(prompts);
+
+        nav.handle_action(Action::Search).unwrap();
+        nav.handle_action(Action::PickFilter).unwrap();
+        let filter_page = nav
+            .get_current_page()
+            .unwrap()
+            .as_any()
+            .downcast_ref::<QueryResultsPage>()
+            .unwrap();
+        assert_eq!(filter_page.query, "assignee=ana");
+
+        picked.set(1);
+        nav.handle_action(Action::PickFilter).unwrap();
+        let search_page = nav
+            .get_current_page()
+            .unwrap()
+            .as_any()
+            .downcast_ref::<SearchResultsPage>()
+            .unwrap();
+        assert_eq!(search_page.query, "login");
+        assert_eq!(
+            *labels.borrow(),
+            vec!["mine: assignee=ana".to_owned(), "recent: login".to_owned()]
+        );
+        assert_eq!(nav.get_page_count(), 4);
+    }
+
+    #[test]
+    fn handle_action_should_error_without_filters_to_pick() {
+        let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+
+        let mut nav = Navigator::new(db);
+
+        assert!(nav.handle_action(Action::PickFilter).is_err());
+        assert_eq!(nav.get_page_count(), 1);
+    }
+
+    #[test]
+    fn handle_action_should_clear_pages_on_exit() {
+        let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+
+        let mut nav = Navigator::new(db);
+
+        nav.handle_action(Action::NavigateToEpicDetail {
+            epic_id: "1".to_string(),
+        })
+        .unwrap();
+        nav.handle_action(Action::NavigateToStoryDetail {
+            epic_id: "1".to_string(),
+            story_id: "2".to_string(),
+        })
+        .unwrap();
+        nav.handle_action(Action::Exit).unwrap();
+
+        assert_eq!(nav.get_page_count(), 0);
+    }
+
+    #[test]
+    fn handle_action_should_handle_create_epic() {
+        let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+
+        let mut nav = Navigator::new(Rc::clone(&db));
+
+        let mut prompts = Prompts::new();
+        prompts.create_epic = Box::new(|| Epic::new("name".to_owned(), "description".to_owned()));
+
+        nav.set_prompts(prompts);
+
+        nav.handle_action(Action::CreateEpic).unwrap();
+
+        let db_state = db.read_db().unwrap();
+        assert_eq!(db_state.epics.len(), 1);
+
+        let epic = db_state.epics.into_iter().next().unwrap().1;
+        assert_eq!(epic.name, "name".to_owned());
+        assert_eq!(epic.description, "description".to_owned());
+    }
+
+    #[test]
+    fn handle_action_should_handle_update_epic() {
+        let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+        let epic_id = db
+            .create_epic(Epic::new("".to_owned(), "".to_owned()))
+            .unwrap();
+
+        let mut nav = Navigator::new(Rc::clone(&db));
+
+        let mut prompts = Prompts::new();
+        prompts.update_status = Box::new(|| Some(Status::InProgress));
+
+        nav.set_prompts(prompts);
+
+        nav.handle_action(Action::UpdateEpicStatus {
+            epic_id: epic_id.clone(),
+        })
+        .unwrap();
+
+        let db_state = db.read_db().unwrap();
+        assert_eq!(
+            db_state.epics.get(&epic_id).unwrap().status,
+            Status::InProgress
+        );
+    }
+
+    #[test]
+    fn handle_action_should_handle_update_epic_owner() {
+        let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+        let epic_id = db
+            .create_epic(Epic::new("".to_owned(), "".to_owned()))
+            .unwrap();
+
+        let mut nav = Navigator::new(Rc::clone(&db));
+
+        let mut prompts = Prompts::new();
+        prompts.epic_owner = Box::new(|| "ana".to_owned());
+
+        nav.set_prompts(prompts);
+
+        nav.handle_action(Action::UpdateEpicOwner {
+            epic_id: epic_id.clone(),
+        })
+        .unwrap();
+
+        let db_state = db.read_db().unwrap();
+        assert_eq!(db_state.epics[&epic_id].owner.as_deref(), Some("ana"));
+    }
+
+    #[test]
+    fn handle_action_should_warn_when_closing_epic_with_open_stories() {
+        let mut db = JiraDatabase::with_database(Box::new(MockDB::new()));
+        db.close_policy = ClosePolicy::Warn;
+        let db = Rc::new(db);
+        let epic_id = db
+            .create_epic(Epic::new("".to_owned(), "".to_owned()))
+            .unwrap();
+        let story_id = db
+            .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+            .unwrap();
+        walk_epic_to(&db, &epic_id, Status::Resolved).unwrap();
+
+        let mut nav = Navigator::new(Rc::clone(&db));
+
+        let warned = Rc::new(RefCell::new(Vec::new()));
+        let mut prompts = Prompts::new();
+        prompts.update_status = Box::new(|| Some(Status::Closed));
+        let sink = Rc::clone(&warned);
+        prompts.close_warning = Box::new(move |ids| sink.borrow_mut().extend_from_slice(ids));
+
+        nav.set_prompts(prompts);
+
+        nav.handle_action(Action::UpdateEpicStatus { epic_id })
+            .unwrap();
+
+        assert_eq!(*warned.borrow(), vec![story_id]);
+    }
+
+    #[test]
+    fn handle_action_should_handle_delete_epic() {
+        let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+        let epic_id = db
+            .create_epic(Epic::new("".to_owned(), "".to_owned()))
+            .unwrap();
+
+        let mut nav = Navigator::new(Rc::clone(&db));
+
+        let mut prompts = Prompts::new();
+        let previews = Rc::new(RefCell::new(Vec::new()));
+        let previews_clone = Rc::clone(&previews);
+        prompts.delete_epic = Box::new(move |preview| {
+            previews_clone.borrow_mut().push(preview.to_owned());
+            true
+        });
+
+        nav.set_prompts(prompts);
+
+        nav.handle_action(Action::DeleteEpic { epic_id }).unwrap();
+
+        let db_state = db.read_db().unwrap();
+        assert_eq!(db_state.epics.len(), 0);
+        assert_eq!(*previews.borrow(), vec!["This epic has no stories."]);
+    }
+
+    #[test]
+    fn delete_epic_preview_should_list_a_few_stories_by_name() {
+        let db = JiraDatabase::with_database(Box::new(MockDB::new()));
+        let epic_id = db
+            .create_epic(Epic::new("".to_owned(), "".to_owned()))
+            .unwrap();
+        let add_stories = |names: &[&str]| {
+            for name in names {
+                db.create_story(Story::new(name.to_string(), "".to_owned()), &epic_id)
+                    .unwrap();
+            }
+            get_delete_epic_preview(&db.read_db().unwrap(), &epic_id)
+        };
+
+        let single = add_stories(&["a"]);
+        let small = add_stories(&["b", "c"]);
+        let large = add_stories(&["d", "e", "f", "g"]);
+
+        assert_eq!(single, "This also deletes its 1 story:\n  - a");
+        assert_eq!(
+            small,
+            "This also deletes its 3 stories:\n  - a\n  - b\n  - c"
+        );
+        assert_eq!(
+            large,
+            "This also deletes its 7 stories:\n  - a\n  - b\n  - c\n  - d\n  - e\n  ... and 2 more"
+        );
+    }
+
+    #[test]
+    fn handle_action_should_handle_toggle_pin() {
+        let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+        let epic_id = db
+            .create_epic(Epic::new("".to_owned(), "".to_owned()))
+            .unwrap();
+
+        let mut nav = Navigator::new(Rc::clone(&db));
+
+        nav.handle_action(Action::TogglePin {
+            epic_id: epic_id.clone(),
+        })
+        .unwrap();
+
+        let db_state = db.read_db().unwrap();
+        assert!(db_state.epics.get(&epic_id).unwrap().pinned);
+    }
+
+    #[test]
+    fn handle_action_should_handle_create_story() {
+        let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+        let epic_id = db
+            .create_epic(Epic::new("".to_owned(), "".to_owned()))
+            .unwrap();
+
+        let mut nav = Navigator::new(Rc::clone(&db));
+
+        let mut prompts = Prompts::new();
+        prompts.create_story = Box::new(|| Story::new("name".to_owned(), "description".to_owned()));
+
+        nav.set_prompts(prompts);
+
+        nav.handle_action(Action::CreateStory { epic_id }).unwrap();
+
+        let db_state = db.read_db().unwrap();
+        assert_eq!(db_state.stories.len(), 1);
+
+        let story = db_state.stories.into_iter().next().unwrap().1;
+        assert_eq!(story.name, "name".to_owned());
+        assert_eq!(story.description, "description".to_owned());
+    }
+
+    #[test]
+    fn handle_action_should_quick_add_stories_in_one_write() {
+        let writes = Rc::new(Cell::new(0));
+        let db = Rc::new(JiraDatabase::with_database(Box::new(CountingDB {
+            reads: Rc::new(Cell::new(0)),
+            writes: Rc::clone(&writes),
+            inner: MockDB::new(),
+        })));
+        let epic_id = db
+            .create_epic(Epic::new("".to_owned(), "".to_owned()))
+            .unwrap();
+        writes.set(0);
+
+        let mut nav = Navigator::new(Rc::clone(&db));
+
+        let mut prompts = Prompts::new();
+        prompts.quick_add_stories =
+            Box::new(|| vec!["one".to_owned(), "two".to_owned(), "three".to_owned()]);
+
+        nav.set_prompts(prompts);
+
+        nav.handle_action(Action::QuickAddStories {
+            epic_id: epic_id.clone(),
+        })
+        .unwrap();
+
+        let db_state = db.read_db().unwrap();
+        let names = db_state.epics[&epic_id]
+            .stories
+            .iter()
+            .map(|id| db_state.stories[id].name.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["one", "two", "three"]);
+        assert_eq!(writes.get(), 1);
+    }
+
+    #[test]
+    fn handle_action_should_not_write_when_quick_add_is_empty() {
+        let writes = Rc::new(Cell::new(0));
+        let db = Rc::new(JiraDatabase::with_database(Box::new(CountingDB {
+            reads: Rc::new(Cell::new(0)),
+            writes: Rc::clone(&writes),
+            inner: MockDB::new(),
+        })));
+        let epic_id = db
+            .create_epic(Epic::new("".to_owned(), "".to_owned()))
+            .unwrap();
+        writes.set(0);
+
+        let mut nav = Navigator::new(Rc::clone(&db));
+
+        let mut prompts = Prompts::new();
+        prompts.quick_add_stories = Box::new(Vec::new);
+
+        nav.set_prompts(prompts);
+
+        nav.handle_action(Action::QuickAddStories { epic_id })
+            .unwrap();
+
+        assert_eq!(writes.get(), 0);
+        assert!(db.read_db().unwrap().stories.is_empty());
+    }
+
+    #[test]
+    fn handle_action_should_apply_status_to_selected_stories() {
+        let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+        let epic_id = db
+            .create_epic(Epic::new("".to_owned(), "".to_owned()))
+            .unwrap();
+        let mut story_ids = vec![];
+        for _ in 0..3 {
+            story_ids.push(
+                db.create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+                    .unwrap(),
+            );
+        }
+
+        let mut nav = Navigator::new(Rc::clone(&db));
+
+        let selected = vec![story_ids[0].clone(), story_ids[2].clone()];
+        let mut prompts = Prompts::new();
+        prompts.select_stories = Box::new(move |rows| {
+            assert_eq!(rows.len(), 3);
+            Some((BulkAction::UpdateStatus, selected.clone()))
+        });
+        prompts.update_status = Box::new(|| Some(Status::InProgress));
+
+        nav.set_prompts(prompts);
+
+        nav.handle_action(Action::SelectStories { epic_id })
+            .unwrap();
+
+        let db_state = db.read_db().unwrap();
+        let statuses = story_ids
+            .iter()
+            .map(|id| db_state.stories.get(id).unwrap().status.clone())
+            .collect::<Vec<Status>>();
+        assert_eq!(
+            statuses,
+            vec![Status::InProgress, Status::Open, Status::InProgress]
+        );
+    }
+
+    #[test]
+    fn handle_action_should_delete_selected_stories() {
+        let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+        let epic_id = db
+            .create_epic(Epic::new("".to_owned(), "".to_owned()))
+            .unwrap();
+        let story_id = db
+            .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+            .unwrap();
+        let kept_id = db
+            .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+            .unwrap();
+
+        let mut nav = Navigator::new(Rc::clone(&db));
+
+        let selected = vec![story_id.clone()];
+        let mut prompts = Prompts::new();
+        prompts.select_stories = Box::new(move |_| Some((BulkAction::Delete, selected.clone())));
+        prompts.delete_story = Box::new(|| true);
 
         nav.set_prompts(prompts);
 
-        nav.handle_action(Action::CreateStory { epic_id }).unwrap();
+        nav.handle_action(Action::SelectStories {
+            epic_id: epic_id.clone(),
+        })
+        .unwrap();
 
         let db_state = db.read_db().unwrap();
-        assert_eq!(db_state.stories.len(), 1);
-
-        let story = db_state.stories.into_iter().next().unwrap().1;
-        assert_eq!(story.name, "name".to_owned());
-        assert_eq!(story.description, "description".to_owned());
+        assert_eq!(db_state.epics.get(&epic_id).unwrap().stories, vec![kept_id]);
+        assert_eq!(db_state.stories.get(&story_id), None);
     }
 
     #[test]
     fn handle_action_should_handle_update_story() {
-        let db = Rc::new(JiraDatabase {
-            database: Box::new(MockDB::new()),
-        });
+        let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
         let epic_id = db
             .create_epic(Epic::new("".to_owned(), "".to_owned()))
             .unwrap();
@@ -343,10 +1446,368 @@ mod tests {
     }
 
     #[test]
-    fn handle_action_should_handle_delete_story() {
-        let db = Rc::new(JiraDatabase {
-            database: Box::new(MockDB::new()),
+    fn refresh_should_count_overdue_stories_when_notifying() {
+        let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+        let epic_id = db
+            .create_epic(Epic::new("".to_owned(), "".to_owned()))
+            .unwrap();
+        let long_ago = chrono::NaiveDate::from_ymd_opt(2000, 1, 1);
+        for (due_date, status) in [
+            (long_ago, Status::Open),
+            (long_ago, Status::InProgress),
+            (long_ago, Status::Closed),
+            (None, Status::Open),
+        ] {
+            let story_id = db
+                .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+                .unwrap();
+            db.set_story_due_date(&story_id, due_date).unwrap();
+            walk_story_to(&db, &story_id, status).unwrap();
+        }
+
+        let mut quiet = Navigator::new(Rc::clone(&db));
+        quiet.handle_action(Action::Refresh).unwrap();
+        let mut nav = Navigator::new(Rc::clone(&db));
+        nav.set_notify_overdue(true);
+        nav.handle_action(Action::Refresh).unwrap();
+
+        assert_eq!(quiet.overdue_notice, None);
+        assert_eq!(
+            nav.overdue_notice.as_deref(),
+            Some("Warning: 2 stories are overdue.")
+        );
+        assert!(nav.ring_bell);
+    }
+
+    #[test]
+    fn watch_rules_should_fire_once_after_a_matching_change() {
+        let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+        let epic_id = db
+            .create_epic(Epic::new("".to_owned(), "".to_owned()))
+            .unwrap();
+        let story_id = db
+            .create_story(Story::new("Login".to_owned(), "".to_owned()), &epic_id)
+            .unwrap();
+        let mut nav = Navigator::new(Rc::clone(&db));
+        nav.set_watch_rules(
+            vec![WatchRule {
+                id: story_id.clone(),
+                status: Status::Resolved,
+                action: None,
+            }],
+            false,
+        );
+        nav.handle_action(Action::Refresh).unwrap();
+        assert!(nav.notices.is_empty());
+
+        for status in [Status::InProgress, Status::Resolved, Status::InProgress] {
+            nav.handle_action(Action::SetStoryStatus {
+                story_id: story_id.clone(),
+                status,
+            })
+            .unwrap();
+        }
+        nav.handle_action(Action::Refresh).unwrap();
+
+        assert_eq!(
+            nav.notices,
+            vec![format!("Rule: Login ({}) is now RESOLVED.", story_id)]
+        );
+        assert!(nav.ring_bell);
+    }
+
+    // Without the clipboard feature the id is printed for selecting by hand
+    #[cfg(not(feature = "clipboard"))]
+    #[test]
+    fn copy_id_should_print_the_id_without_clipboard_support() {
+        let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+        let mut nav = Navigator::new(db);
+
+        nav.handle_action(Action::CopyId {
+            id: "a1b2".to_owned(),
+        })
+        .unwrap();
+
+        assert_eq!(
+            nav.notices,
+            vec![
+                "Could not copy to the clipboard: this build has no clipboard support.".to_owned(),
+                "Id: a1b2".to_owned(),
+            ]
+        );
+    }
+
+    #[test]
+    fn copy_id_should_confirm_a_copied_id() {
+        let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+        let mut nav = Navigator::new(db);
+        let copied = Rc::new(RefCell::new(String::new()));
+        let mut prompts = Prompts::new();
+        let sink = Rc::clone(&copied);
+        prompts.copy_to_clipboard = Box::new(move |text| {
+            sink.replace(text.to_owned());
+            Result::Ok(())
+        });
+        nav.set_prompts(prompts);
+
+        nav.handle_action(Action::CopyId {
+            id: "a1b2".to_owned(),
+        })
+        .unwrap();
+
+        assert_eq!(*copied.borrow(), "a1b2");
+        assert_eq!(
+            nav.notices,
+            vec!["Copied a1b2 to the clipboard.".to_owned()]
+        );
+    }
+
+    #[test]
+    fn get_cycled_index_should_wrap_around() {
+        let ids = ["a", "b", "c"].map(str::to_owned);
+        let ids = ids.iter().collect::<Vec<_>>();
+
+        assert_eq!(get_cycled_index(&ids, &"a".to_owned(), true), Some(1));
+        assert_eq!(get_cycled_index(&ids, &"c".to_owned(), true), Some(0));
+        assert_eq!(get_cycled_index(&ids, &"a".to_owned(), false), Some(2));
+        assert_eq!(get_cycled_index(&ids, &"b".to_owned(), false), Some(0));
+        // Starting from a story that isn't in progress
+        assert_eq!(get_cycled_index(&ids, &"x".to_owned(), true), Some(0));
+        assert_eq!(get_cycled_index(&ids, &"x".to_owned(), false), Some(2));
+        // Nothing else to go to
+        assert_eq!(get_cycled_index(&ids[..1], &"a".to_owned(), true), None);
+        assert_eq!(get_cycled_index(&[], &"a".to_owned(), false), None);
+    }
+
+    #[test]
+    fn cycle_in_progress_should_replace_the_story_page() {
+        let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+        let epic_id = db
+            .create_epic(Epic::new("".to_owned(), "".to_owned()))
+            .unwrap();
+        for _ in 0..2 {
+            let story_id = db
+                .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+                .unwrap();
+            db.set_stories_assignee(std::slice::from_ref(&story_id), Some("ana".to_owned()))
+                .unwrap();
+            db.update_story_status(&story_id, Status::InProgress)
+                .unwrap();
+        }
+        let order = db
+            .in_progress_stories_for("ana")
+            .unwrap()
+            .into_iter()
+            .map(|(_, story_id, _)| story_id)
+            .collect::<Vec<_>>();
+        let mut nav = Navigator::new(Rc::clone(&db));
+        nav.set_user(Some("ana".to_owned()));
+        nav.handle_action(Action::NavigateToStoryDetail {
+            epic_id: epic_id.clone(),
+            story_id: order[0].clone(),
+        })
+        .unwrap();
+
+        nav.handle_action(Action::CycleInProgress {
+            story_id: order[0].clone(),
+            forward: true,
+        })
+        .unwrap();
+
+        assert_eq!(nav.get_page_count(), 2);
+        let page = nav.get_current_page().unwrap();
+        let page = page.as_any().downcast_ref::<StoryDetail>().unwrap();
+        assert_eq!(page.story_id, order[1]);
+    }
+
+    #[test]
+    fn get_overdue_notice_should_skip_zero_and_count_stories() {
+        assert_eq!(get_overdue_notice(0), None);
+        assert_eq!(
+            get_overdue_notice(1).as_deref(),
+            Some("Warning: 1 story is overdue.")
+        );
+        assert_eq!(
+            get_overdue_notice(3).as_deref(),
+            Some("Warning: 3 stories are overdue.")
+        );
+    }
+
+    #[test]
+    fn set_story_status_should_apply_the_status_and_report_no_ops() {
+        let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+        let epic_id = db
+            .create_epic(Epic::new("".to_owned(), "".to_owned()))
+            .unwrap();
+        let story_id = db
+            .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+            .unwrap();
+        let mut nav = Navigator::new(Rc::clone(&db));
+        let mut prompts = Prompts::new();
+        prompts.blocked_reason = Box::new(|| Some("waiting on QA".to_owned()));
+        nav.set_prompts(prompts);
+
+        // A valid walk through the workflow, see Status::can_transition_to
+        for status in [
+            Status::InProgress,
+            Status::Blocked,
+            Status::InProgress,
+            Status::Resolved,
+            Status::Closed,
+            Status::Open,
+        ] {
+            nav.handle_action(Action::SetStoryStatus {
+                story_id: story_id.clone(),
+                status: status.clone(),
+            })
+            .unwrap();
+            assert_eq!(db.read_db().unwrap().stories[&story_id].status, status);
+        }
+        let again = nav.handle_action(Action::SetStoryStatus {
+            story_id: story_id.clone(),
+            status: Status::Open,
+        });
+
+        assert_eq!(again.unwrap_err().to_string(), "Story is already OPEN.");
+    }
+
+    #[test]
+    fn handle_action_should_ask_why_a_story_is_blocked() {
+        let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+        let epic_id = db
+            .create_epic(Epic::new("".to_owned(), "".to_owned()))
+            .unwrap();
+        let story_id = db
+            .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+            .unwrap();
+
+        let mut nav = Navigator::new(Rc::clone(&db));
+
+        let mut prompts = Prompts::new();
+        prompts.update_status = Box::new(|| Some(Status::Blocked));
+        prompts.blocked_reason = Box::new(|| Some("waiting on the API".to_owned()));
+
+        nav.set_prompts(prompts);
+
+        nav.handle_action(Action::UpdateStoryStatus {
+            story_id: story_id.clone(),
+        })
+        .unwrap();
+
+        let story = &db.read_db().unwrap().stories[&story_id];
+        assert_eq!(story.status, Status::Blocked);
+        assert_eq!(story.blocked_reason.as_deref(), Some("waiting on the API"));
+    }
+
+    #[test]
+    fn handle_action_should_handle_set_story_parent() {
+        let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+        let epic_id = db
+            .create_epic(Epic::new("".to_owned(), "".to_owned()))
+            .unwrap();
+        let parent_id = db
+            .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+            .unwrap();
+        let story_id = db
+            .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+            .unwrap();
+
+        let mut nav = Navigator::new(Rc::clone(&db));
+
+        let mut prompts = Prompts::new();
+        let parent = parent_id.clone();
+        prompts.set_parent = Box::new(move || Some(parent.clone()));
+
+        nav.set_prompts(prompts);
+
+        nav.handle_action(Action::SetStoryParent {
+            epic_id,
+            story_id: story_id.clone(),
+        })
+        .unwrap();
+
+        let db_state = db.read_db().unwrap();
+        assert_eq!(
+            db_state.stories.get(&story_id).unwrap().parent,
+            Some(parent_id)
+        );
+    }
+
+    #[test]
+    fn handle_action_should_open_attachment_by_index() {
+        let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+        let epic_id = db
+            .create_epic(Epic::new("".to_owned(), "".to_owned()))
+            .unwrap();
+        let story_id = db
+            .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+            .unwrap();
+
+        let mut nav = Navigator::new(Rc::clone(&db));
+
+        let opened = Rc::new(RefCell::new(Vec::new()));
+        let mut prompts = Prompts::new();
+        prompts.create_attachment = Box::new(|| Attachment {
+            label: "design".to_owned(),
+            target: "docs/design.md".to_owned(),
+        });
+        let sink = Rc::clone(&opened);
+        prompts.open_attachment = Box::new(move |target| {
+            sink.borrow_mut().push(target.to_owned());
+            Ok(())
         });
+
+        nav.set_prompts(prompts);
+
+        nav.handle_action(Action::AddAttachment {
+            story_id: story_id.clone(),
+        })
+        .unwrap();
+        nav.handle_action(Action::OpenAttachment {
+            story_id: story_id.clone(),
+            index: 0,
+        })
+        .unwrap();
+        let out_of_range = nav.handle_action(Action::OpenAttachment { story_id, index: 1 });
+
+        assert!(out_of_range.is_err());
+        assert_eq!(*opened.borrow(), vec!["docs/design.md".to_owned()]);
+    }
+
+    #[test]
+    fn handle_action_should_add_and_tick_checklist_items() {
+        let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+        let epic_id = db
+            .create_epic(Epic::new("".to_owned(), "".to_owned()))
+            .unwrap();
+        let story_id = db
+            .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+            .unwrap();
+
+        let mut nav = Navigator::new(Rc::clone(&db));
+        let mut prompts = Prompts::new();
+        prompts.checklist_item = Box::new(|| "docs updated".to_owned());
+        nav.set_prompts(prompts);
+
+        nav.handle_action(Action::AddChecklistItem {
+            story_id: story_id.clone(),
+        })
+        .unwrap();
+        nav.handle_action(Action::ToggleChecklistItem {
+            story_id: story_id.clone(),
+            index: 0,
+        })
+        .unwrap();
+
+        let db_state = db.read_db().unwrap();
+        let checklist = &db_state.stories[&story_id].checklist;
+        assert_eq!(checklist.len(), 1);
+        assert!(checklist[0].done);
+    }
+
+    #[test]
+    fn handle_action_should_handle_delete_story() {
+        let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
         let epic_id = db
             .create_epic(Epic::new("".to_owned(), "".to_owned()))
             .unwrap();
@@ -367,4 +1828,64 @@ mod tests {
         let db_state = db.read_db().unwrap();
         assert_eq!(db_state.stories.len(), 0);
     }
+
+    #[test]
+    fn focus_should_be_shared_by_the_home_and_epic_pages() {
+        let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+        let mut nav = Navigator::new(Rc::clone(&db));
+        nav.set_user(Some("ada".to_owned()));
+        let mut prompts = Prompts::new();
+        prompts.focus_assignee = Box::new(|| None);
+        nav.set_prompts(prompts);
+
+        nav.handle_action(Action::FocusAssignee).unwrap();
+        nav.handle_action(Action::NavigateToEpicDetail {
+            epic_id: "1".to_owned(),
+        })
+        .unwrap();
+
+        let epic_page = nav.get_current_page().unwrap().as_any();
+        let epic_page = epic_page.downcast_ref::<EpicDetail>().unwrap();
+        assert_eq!(*epic_page.focus.borrow(), Some("ada".to_owned()));
+
+        nav.handle_action(Action::ClearAssigneeFocus).unwrap();
+        nav.handle_action(Action::NavigateToPreviousPage).unwrap();
+
+        let home_page = nav.get_current_page().unwrap().as_any();
+        let home_page = home_page.downcast_ref::<HomePage>().unwrap();
+        assert_eq!(*home_page.focus.borrow(), None);
+    }
+
+    #[test]
+    fn handle_action_should_replace_the_story_page_on_promote() {
+        let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+        let epic_id = db
+            .create_epic(Epic::new("".to_owned(), "".to_owned()))
+            .unwrap();
+        let story_id = db
+            .create_story(Story::new("Login".to_owned(), "".to_owned()), &epic_id)
+            .unwrap();
+
+        let mut nav = Navigator::new(Rc::clone(&db));
+
+        let mut prompts = Prompts::new();
+        prompts.promote_story = Box::new(|| Some(false));
+
+        nav.set_prompts(prompts);
+
+        nav.handle_action(Action::NavigateToStoryDetail {
+            epic_id: epic_id.clone(),
+            story_id: story_id.clone(),
+        })
+        .unwrap();
+        nav.handle_action(Action::PromoteStory { epic_id, story_id })
+            .unwrap();
+
+        assert_eq!(nav.get_page_count(), 2);
+        let current_page = nav.get_current_page().unwrap();
+        assert!(current_page.as_any().is::<EpicDetail>());
+        let db_state = db.read_db().unwrap();
+        assert_eq!(db_state.stories.len(), 0);
+        assert!(db_state.epics.values().any(|epic| epic.name == "Login"));
+    }
 }
@@ -0,0 +1,169 @@
+use std::cmp::Ordering;
+
+use itertools::Itertools;
+
+// Sparse ordering keys for manually ordered epics and stories. Keys are spaced
+// ORDER_GAP apart so moving an item only needs a key between its new neighbours;
+// the whole list is only renumbered once two neighbours have no room left.
+
+pub const ORDER_GAP: i64 = 1024;
+
+// A key strictly between two neighbours, None when they are adjacent.
+// A missing neighbour means the start or end of the list.
+pub fn key_between(before: Option<i64>, after: Option<i64>) -> Option<i64> {
+    match (before, after) {
+        (None, None) => Some(ORDER_GAP),
+        (Some(before), None) => before.checked_add(ORDER_GAP),
+        (None, Some(after)) => after.checked_sub(ORDER_GAP),
+        (Some(before), Some(after)) if after - before > 1 => Some(before + (after - before) / 2),
+        _ => None,
+    }
+}
+
+// Evenly spaced keys for `count` items, keeping their order
+pub fn get_rebalanced_keys(count: usize) -> Vec<i64> {
+    (1..=count as i64)
+        .map(|position| position * ORDER_GAP)
+        .collect()
+}
+
+// Inserts a key for a new item at `index` of the ordered `keys`. When there is no
+// gap left the existing keys are renumbered first, which the return value reports
+// so the caller knows to write them all back.
+pub fn insert_key(keys: &mut Vec<i64>, index: usize) -> bool {
+    let index = index.min(keys.len());
+    let neighbours = |keys: &[i64]| {
+        let before = index.checked_sub(1).map(|before| keys[before]);
+        (before, keys.get(index).copied())
+    };
+
+    let (before, after) = neighbours(keys);
+    let (key, rebalanced) = match key_between(before, after) {
+        Some(key) => (key, false),
+        None => {
+            *keys = get_rebalanced_keys(keys.len());
+            let (before, after) = neighbours(keys);
+            // Rebalanced keys are ORDER_GAP apart, so there is always room
+            (key_between(before, after).unwrap_or_default(), true)
+        }
+    };
+    keys.insert(index, key);
+    rebalanced
+}
+
+// Manual order of two items. Items that were never reordered have no key and
+// come after the ones that were.
+pub fn compare_keys(a: Option<i64>, b: Option<i64>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+// The keys to write so `id` ends up at `index` of `ordered`, a list in manual order
+// with the current keys. Only the moved item gets a new key unless the list had to be
+// renumbered, which also happens once for lists with items that have no key yet.
+pub fn get_moved_keys(
+    ordered: &[(String, Option<i64>)],
+    id: &String,
+    index: usize,
+) -> Vec<(String, i64)> {
+    let others = ordered
+        .iter()
+        .filter(|(other, _)| other != id)
+        .collect_vec();
+    let (mut keys, complete) = match others.iter().map(|(_, key)| *key).collect() {
+        Some(keys) => (keys, true),
+        None => (get_rebalanced_keys(others.len()), false),
+    };
+    let index = index.min(keys.len());
+    let rebalanced = insert_key(&mut keys, index) || !complete;
+
+    let mut ids = others
+        .into_iter()
+        .map(|(other, _)| other.clone())
+        .collect_vec();
+    ids.insert(index, id.clone());
+    ids.into_iter()
+        .zip(keys)
+        .enumerate()
+        .filter(|(position, _)| rebalanced || *position == index)
+        .map(|(_, pair)| pair)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_strictly_increasing(keys: &[i64]) -> bool {
+        keys.windows(2).all(|pair| pair[0] < pair[1])
+    }
+
+    #[test]
+    fn key_between_should_split_the_gap() {
+        assert_eq!(key_between(None, None), Some(ORDER_GAP));
+        assert_eq!(key_between(Some(1024), Some(2048)), Some(1536));
+        assert_eq!(key_between(Some(1024), None), Some(2048));
+        assert_eq!(key_between(None, Some(1024)), Some(0));
+        assert_eq!(key_between(Some(5), Some(6)), None);
+    }
+
+    #[test]
+    fn repeated_inserts_between_two_items_should_stay_ordered_and_rebalance() {
+        // Two items, A and B, then keep inserting right after A
+        let mut keys = get_rebalanced_keys(2);
+        let mut items = vec!["a".to_owned(), "b".to_owned()];
+        let mut rebalances = 0;
+
+        for number in 0..20 {
+            if insert_key(&mut keys, 1) {
+                rebalances += 1;
+            }
+            items.insert(1, format!("new{}", number));
+            assert!(is_strictly_increasing(&keys));
+        }
+
+        // The gap of 1024 runs out after 10 halvings
+        assert!(rebalances > 0);
+        assert_eq!(items.first().map(String::as_str), Some("a"));
+        assert_eq!(items.last().map(String::as_str), Some("b"));
+        assert_eq!(items[1], "new19");
+        assert_eq!(keys.len(), items.len());
+    }
+
+    #[test]
+    fn get_moved_keys_should_only_rekey_the_moved_item() {
+        let ordered = [("a", Some(1024)), ("b", Some(2048)), ("c", Some(3072))]
+            .map(|(id, key)| (id.to_owned(), key));
+
+        let moved = get_moved_keys(&ordered, &"c".to_owned(), 0);
+        let unkeyed = get_moved_keys(&[("a".to_owned(), None)], &"b".to_owned(), 0);
+
+        assert_eq!(moved, vec![("c".to_owned(), 0)]);
+        assert_eq!(
+            unkeyed,
+            vec![("b".to_owned(), 0), ("a".to_owned(), ORDER_GAP)]
+        );
+    }
+
+    #[test]
+    fn compare_keys_should_put_unkeyed_items_last() {
+        assert_eq!(compare_keys(Some(5), Some(7)), Ordering::Less);
+        assert_eq!(compare_keys(None, Some(7)), Ordering::Greater);
+        assert_eq!(compare_keys(None, None), Ordering::Equal);
+    }
+
+    #[test]
+    fn insert_key_should_append_and_prepend() {
+        let mut keys = vec![];
+
+        insert_key(&mut keys, 0);
+        insert_key(&mut keys, 1);
+        insert_key(&mut keys, 0);
+
+        assert_eq!(keys, vec![0, ORDER_GAP, 2 * ORDER_GAP]);
+    }
+}
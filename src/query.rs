@@ -0,0 +1,140 @@
+use anyhow::{anyhow, Result};
+use std::collections::BTreeMap;
+
+use crate::models::{Status, Story};
+
+// What JiraDatabase::filter_stories takes, so a parsed query can be passed straight in
+pub type StoryPredicate = Box<dyn Fn(&str, &Story) -> bool>;
+
+// Compiles space separated key=value terms, e.g. "status=open assignee=ana", into a
+// predicate every term must match. Keys are status, assignee (or "none") and name,
+// which matches case-insensitively anywhere in the story name. An empty query matches
+// every story.
+pub fn parse_story_query(query: &str) -> Result<StoryPredicate> {
+    let mut terms: Vec<StoryPredicate> = Vec::new();
+    for term in query.split_whitespace() {
+        let (key, value) = term
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Expected key=value in the query, got '{}'.", term))?;
+        if value.is_empty() {
+            return Err(anyhow!("'{}' in the query needs a value.", key));
+        }
+        let predicate: StoryPredicate = match key {
+            "status" => {
+                let status = value.parse::<Status>()?;
+                Box::new(move |_, story| story.status == status)
+            }
+            "assignee" if value == "none" => Box::new(|_, story| story.assignee.is_none()),
+            "assignee" => {
+                let assignee = value.to_owned();
+                Box::new(move |_, story| story.assignee.as_deref() == Some(assignee.as_str()))
+            }
+            "name" => {
+                let name = value.to_lowercase();
+                Box::new(move |_, story| story.name.to_lowercase().contains(&name))
+            }
+            other => {
+                return Err(anyhow!(
+                    "Unknown query key '{}'. Expected status, assignee or name.",
+                    other
+                ))
+            }
+        };
+        terms.push(predicate);
+    }
+
+    Ok(Box::new(move |story_id, story| {
+        terms.iter().all(|term| term(story_id, story))
+    }))
+}
+
+// A query kept under a name in the saved_filters config
+#[derive(Debug, PartialEq, Clone)]
+pub struct SavedFilter {
+    pub name: String,
+    pub query: String,
+}
+
+// Filters sorted by name, leaving out (with a warning each) those whose query no
+// longer parses, e.g. after a query key was renamed
+pub fn load_saved_filters(filters: &BTreeMap<String, String>) -> (Vec<SavedFilter>, Vec<String>) {
+    let mut saved = Vec::new();
+    let mut warnings = Vec::new();
+    for (name, query) in filters {
+        match parse_story_query(query) {
+            Ok(_) => saved.push(SavedFilter {
+                name: name.clone(),
+                query: query.clone(),
+            }),
+            Err(error) => warnings.push(format!("Saved filter '{}' skipped: {}", name, error)),
+        }
+    }
+    (saved, warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{merge_config_layers, ConfigLayer};
+
+    fn story(status: Status, assignee: Option<&str>) -> Story {
+        Story {
+            status,
+            assignee: assignee.map(|assignee| assignee.to_owned()),
+            ..Story::new("Login form".to_owned(), "".to_owned())
+        }
+    }
+
+    #[test]
+    fn parse_story_query_should_match_every_term() {
+        let query = parse_story_query("status=open assignee=ana").unwrap();
+
+        assert!(query("1", &story(Status::Open, Some("ana"))));
+        assert!(!query("1", &story(Status::Open, Some("bo"))));
+        assert!(!query("1", &story(Status::Closed, Some("ana"))));
+    }
+
+    #[test]
+    fn parse_story_query_should_read_assignee_none_and_name() {
+        let unassigned = parse_story_query("assignee=none").unwrap();
+        let name = parse_story_query("name=LOGIN").unwrap();
+        let everything = parse_story_query("  ").unwrap();
+
+        assert!(unassigned("1", &story(Status::Open, None)));
+        assert!(!unassigned("1", &story(Status::Open, Some("ana"))));
+        assert!(name("1", &story(Status::Open, None)));
+        assert!(everything("1", &story(Status::Resolved, None)));
+    }
+
+    #[test]
+    fn parse_story_query_should_reject_bad_terms() {
+        assert!(parse_story_query("status").is_err());
+        assert!(parse_story_query("status=").is_err());
+        assert!(parse_story_query("status=done").is_err());
+        assert!(parse_story_query("label=backend").is_err());
+    }
+
+    #[test]
+    fn saved_filters_should_round_trip_through_the_config() {
+        let layer: ConfigLayer = serde_json::from_str(
+            r#"{"saved_filters": {"my open": "status=open assignee=ana", "old": "label=x"}}"#,
+        )
+        .unwrap();
+        let config = merge_config_layers(vec![layer]).unwrap();
+
+        let (saved, warnings) = load_saved_filters(&config.saved_filters);
+        let query = parse_story_query(&saved[0].query).unwrap();
+
+        assert_eq!(
+            saved,
+            vec![SavedFilter {
+                name: "my open".to_owned(),
+                query: "status=open assignee=ana".to_owned(),
+            }]
+        );
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("Saved filter 'old' skipped:"));
+        assert!(query("1", &story(Status::Open, Some("ana"))));
+        assert!(!query("1", &story(Status::Closed, Some("ana"))));
+    }
+}
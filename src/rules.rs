@@ -0,0 +1,217 @@
+use crate::io_utils::shell_command;
+use crate::models::{DBState, Status};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+// Configured as watch_rules, e.g. [{"id": "3", "status": "Resolved"}]. The id may be an
+// epic or a story.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct WatchRule {
+    pub id: String,
+    pub status: Status,
+    // Shell command run when the rule fires, only with --run-rule-actions. The id and
+    // status are passed as JIRA_CLI_RULE_ID and JIRA_CLI_RULE_STATUS, so a webhook is
+    // e.g. curl -d "$JIRA_CLI_RULE_ID" https://example.com/hook
+    #[serde(default)]
+    pub action: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum RuleState {
+    // Not checked yet
+    Unknown,
+    Unmatched,
+    Matched,
+    Missing,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RuleEvent {
+    // The item changed into the watched status. name is the epic or story name.
+    Fired { rule: WatchRule, name: String },
+    // The id no longer exists, the rule is skipped until it does
+    Missing { rule: WatchRule },
+}
+
+// Remembers what each rule saw last, so a rule fires once when its item changes into
+// the status rather than on every check while it stays there
+pub struct RuleWatcher {
+    rules: Vec<(WatchRule, RuleState)>,
+}
+
+impl RuleWatcher {
+    pub fn new(rules: Vec<WatchRule>) -> Self {
+        Self {
+            rules: rules
+                .into_iter()
+                .map(|rule| (rule, RuleState::Unknown))
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    // The first check only records where things stand, items already in the watched
+    // status don't fire
+    pub fn check(&mut self, db_state: &DBState) -> Vec<RuleEvent> {
+        let mut events = Vec::new();
+        for (rule, state) in self.rules.iter_mut() {
+            let item = match db_state.epics.get(&rule.id) {
+                Some(epic) => Some((&epic.name, &epic.status)),
+                None => db_state
+                    .stories
+                    .get(&rule.id)
+                    .map(|story| (&story.name, &story.status)),
+            };
+            let next = match item {
+                None => RuleState::Missing,
+                Some((_, status)) if *status == rule.status => RuleState::Matched,
+                Some(_) => RuleState::Unmatched,
+            };
+            match (*state, next) {
+                (RuleState::Unmatched, RuleState::Matched) => events.push(RuleEvent::Fired {
+                    rule: rule.clone(),
+                    name: item.map(|(name, _)| name.clone()).unwrap_or_default(),
+                }),
+                (previous, RuleState::Missing) if previous != RuleState::Missing => {
+                    events.push(RuleEvent::Missing { rule: rule.clone() })
+                }
+                _ => {}
+            }
+            *state = next;
+        }
+        events
+    }
+}
+
+pub fn get_rule_banner(event: &RuleEvent) -> String {
+    match event {
+        RuleEvent::Fired { rule, name } => {
+            format!("Rule: {} ({}) is now {}.", name, rule.id, rule.status)
+        }
+        RuleEvent::Missing { rule } => {
+            format!("Rule skipped: {} no longer exists.", rule.id)
+        }
+    }
+}
+
+pub fn run_rule_action(rule: &WatchRule) -> Result<()> {
+    let Some(action) = &rule.action else {
+        return Ok(());
+    };
+    // Captured so the command can't scribble over the page
+    let output = shell_command(action)
+        .env("JIRA_CLI_RULE_ID", &rule.id)
+        .env("JIRA_CLI_RULE_STATUS", rule.status.to_string())
+        .output()?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Rule action for {} failed with {}.",
+            rule.id,
+            output.status
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Epic, Story};
+    use std::collections::HashMap;
+
+    fn arrange_state() -> DBState {
+        DBState {
+            last_item_id: "2".to_owned(),
+            epics: HashMap::from([(
+                "1".to_owned(),
+                Epic::new("Launch".to_owned(), "".to_owned()),
+            )]),
+            stories: HashMap::from([(
+                "2".to_owned(),
+                Story::new("Login".to_owned(), "".to_owned()),
+            )]),
+            revision: 0,
+        }
+    }
+
+    fn rule(id: &str, status: Status) -> WatchRule {
+        WatchRule {
+            id: id.to_owned(),
+            status,
+            action: None,
+        }
+    }
+
+    #[test]
+    fn check_should_fire_once_when_the_status_changes() {
+        let mut db_state = arrange_state();
+        let mut watcher = RuleWatcher::new(vec![rule("1", Status::Resolved)]);
+        assert!(watcher.check(&db_state).is_empty());
+
+        db_state.epics.get_mut("1").unwrap().status = Status::Resolved;
+        let fired = watcher.check(&db_state);
+        let again = watcher.check(&db_state);
+
+        assert_eq!(
+            fired,
+            vec![RuleEvent::Fired {
+                rule: rule("1", Status::Resolved),
+                name: "Launch".to_owned(),
+            }]
+        );
+        assert!(again.is_empty());
+    }
+
+    #[test]
+    fn check_should_not_fire_for_items_already_in_the_status() {
+        let mut db_state = arrange_state();
+        db_state.stories.get_mut("2").unwrap().status = Status::Closed;
+        let mut watcher = RuleWatcher::new(vec![rule("2", Status::Closed)]);
+
+        assert!(watcher.check(&db_state).is_empty());
+        assert!(watcher.check(&db_state).is_empty());
+    }
+
+    #[test]
+    fn check_should_report_deleted_ids_once() {
+        let mut db_state = arrange_state();
+        let mut watcher = RuleWatcher::new(vec![rule("2", Status::Closed)]);
+        watcher.check(&db_state);
+
+        db_state.stories.remove("2");
+        let missing = watcher.check(&db_state);
+        let again = watcher.check(&db_state);
+
+        assert_eq!(
+            missing,
+            vec![RuleEvent::Missing {
+                rule: rule("2", Status::Closed)
+            }]
+        );
+        assert!(again.is_empty());
+        assert_eq!(
+            get_rule_banner(&missing[0]),
+            "Rule skipped: 2 no longer exists."
+        );
+    }
+
+    #[test]
+    fn run_rule_action_should_report_failing_commands() {
+        let failing = WatchRule {
+            action: Some("exit 3".to_owned()),
+            ..rule("1", Status::Resolved)
+        };
+        let passing = WatchRule {
+            action: Some("test \"$JIRA_CLI_RULE_ID\" = 1".to_owned()),
+            ..rule("1", Status::Resolved)
+        };
+
+        assert!(run_rule_action(&failing).is_err());
+        assert!(run_rule_action(&passing).is_ok());
+        assert!(run_rule_action(&rule("1", Status::Resolved)).is_ok());
+    }
+}
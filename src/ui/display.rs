@@ -0,0 +1,27 @@
+use chrono_tz::Tz;
+
+use crate::ui::{AgeThresholds, Truncation, UrgencyThresholds};
+
+// How timestamps and urgency colors are shown, resolved once from the configuration
+#[derive(Debug, PartialEq, Clone)]
+pub struct DisplayConfig {
+    pub tz: Tz,
+    pub due_soon: UrgencyThresholds,
+    pub age: AgeThresholds,
+    // Leading id characters shown in story lists. None shows the full id.
+    pub story_id_length: Option<usize>,
+    // How cells cut to fit a column are marked
+    pub truncation: Truncation,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self {
+            tz: Tz::UTC,
+            due_soon: UrgencyThresholds::default(),
+            age: AgeThresholds::default(),
+            story_id_length: None,
+            truncation: Truncation::default(),
+        }
+    }
+}
@@ -1,5 +1,15 @@
+mod display;
 mod pages;
 mod prompts;
+mod selection;
+mod sort;
+mod timezone;
+mod urgency;
 
+pub use display::*;
 pub use pages::*;
 pub use prompts::*;
+pub use selection::*;
+pub use sort::*;
+pub use timezone::*;
+pub use urgency::*;
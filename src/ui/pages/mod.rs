@@ -1,4 +1,7 @@
 use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::io::{stdout, Write};
 use std::rc::Rc;
 
 use anyhow::anyhow;
@@ -6,10 +9,168 @@ use anyhow::Result;
 use itertools::Itertools;
 
 use crate::db::JiraDatabase;
-use crate::models::Action;
+use crate::models::{Action, DBState, Epic, Health, IdMatch, Status, Story};
+use crate::query::parse_story_query;
+use crate::ui::{
+    colors_enabled, format_duration, format_time_ago, format_timestamp, paint, sort_epics,
+    sort_stories, DisplayConfig, DueUrgency, HealthThresholds, SortMode, StoryAge,
+};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use chrono_tz::Tz;
+use crossterm::style::Color;
 
 mod page_helpers;
-use page_helpers::get_column_string;
+use page_helpers::*;
+pub use page_helpers::{TableWidth, Truncation};
+
+mod page_keys;
+use page_keys::*;
+
+const LIST_COLUMNS: [ColumnSpec; 3] = [
+    ColumnSpec {
+        header: "id",
+        percent: 15,
+        flex: false,
+    },
+    ColumnSpec {
+        header: "name",
+        percent: 55,
+        flex: true,
+    },
+    ColumnSpec {
+        header: "status",
+        percent: 30,
+        flex: false,
+    },
+];
+
+const HOME_COLUMNS: [ColumnSpec; 4] = [
+    ColumnSpec {
+        header: "id",
+        percent: 15,
+        flex: false,
+    },
+    ColumnSpec {
+        header: "name",
+        percent: 40,
+        flex: true,
+    },
+    ColumnSpec {
+        header: "owner",
+        percent: 20,
+        flex: false,
+    },
+    ColumnSpec {
+        header: "status",
+        percent: 25,
+        flex: false,
+    },
+];
+
+const SEARCH_COLUMNS: [ColumnSpec; 3] = [
+    ColumnSpec {
+        header: "id",
+        percent: 15,
+        flex: false,
+    },
+    ColumnSpec {
+        header: "name",
+        percent: 65,
+        flex: true,
+    },
+    ColumnSpec {
+        header: "type",
+        percent: 20,
+        flex: false,
+    },
+];
+
+const DUE_COLUMNS: [ColumnSpec; 3] = [
+    ColumnSpec {
+        header: "id",
+        percent: 15,
+        flex: false,
+    },
+    ColumnSpec {
+        header: "name",
+        percent: 65,
+        flex: true,
+    },
+    ColumnSpec {
+        header: "due",
+        percent: 20,
+        flex: false,
+    },
+];
+
+const STORY_DETAIL_COLUMNS: [ColumnSpec; 5] = [
+    ColumnSpec {
+        header: "id",
+        percent: 10,
+        flex: false,
+    },
+    ColumnSpec {
+        header: "name",
+        percent: 25,
+        flex: true,
+    },
+    ColumnSpec {
+        header: "description",
+        percent: 35,
+        flex: false,
+    },
+    ColumnSpec {
+        header: "assignee",
+        percent: 15,
+        flex: false,
+    },
+    ColumnSpec {
+        header: "status",
+        percent: 15,
+        flex: false,
+    },
+];
+
+const REFINEMENT_COLUMNS: [ColumnSpec; 3] = [
+    ColumnSpec {
+        header: "epic",
+        percent: 15,
+        flex: false,
+    },
+    ColumnSpec {
+        header: "id",
+        percent: 15,
+        flex: false,
+    },
+    ColumnSpec {
+        header: "name",
+        percent: 70,
+        flex: true,
+    },
+];
+
+const DETAIL_COLUMNS: [ColumnSpec; 4] = [
+    ColumnSpec {
+        header: "id",
+        percent: 10,
+        flex: false,
+    },
+    ColumnSpec {
+        header: "name",
+        percent: 25,
+        flex: true,
+    },
+    ColumnSpec {
+        header: "description",
+        percent: 45,
+        flex: false,
+    },
+    ColumnSpec {
+        header: "status",
+        percent: 20,
+        flex: false,
+    },
+];
 
 pub trait Page {
     fn draw_page(&self) -> Result<()>;
@@ -17,50 +178,213 @@ pub trait Page {
     fn as_any(&self) -> &dyn Any;
 }
 
+// The assignee the epic and story lists are narrowed to, shared by every page
+pub type AssigneeFocus = Rc<RefCell<Option<String>>>;
+
 pub struct HomePage {
     pub db: Rc<JiraDatabase>,
+    pub sort: Cell<SortMode>,
+    pub show_legend: Cell<bool>,
+    // Only epics with this status are listed
+    pub filter: RefCell<Option<Status>>,
+    pub focus: AssigneeFocus,
+    // Leave out epics without stories. They can still be opened by id.
+    pub hide_empty: Cell<bool>,
+    pub display: Rc<DisplayConfig>,
+}
+
+impl HomePage {
+    pub fn new(db: Rc<JiraDatabase>) -> Self {
+        Self {
+            db,
+            sort: Cell::new(SortMode::default()),
+            show_legend: Cell::new(false),
+            filter: RefCell::new(None),
+            focus: AssigneeFocus::default(),
+            hide_empty: Cell::new(false),
+            display: Rc::new(DisplayConfig::default()),
+        }
+    }
+
+    // The epics the table lists, after the status filter, focus and empty toggle
+    fn get_listed_epics<'a>(&self, db: &'a DBState) -> Vec<(&'a String, &'a Epic)> {
+        let filter = self.filter.borrow();
+        let focus = self.focus.borrow();
+        get_home_page_epics(db, self.sort.get())
+            .into_iter()
+            .filter(|(_, epic)| filter.as_ref().is_none_or(|status| *status == epic.status))
+            .filter(|(_, epic)| {
+                focus
+                    .as_deref()
+                    .is_none_or(|assignee| epic_has_assignee(db, epic, assignee))
+            })
+            .filter(|(_, epic)| !(self.hide_empty.get() && epic.is_empty()))
+            .collect()
+    }
 }
+
+// Shown in place of the rows, so an empty list doesn't look broken. An empty database
+// and a list emptied by the filter or focus read differently.
+fn get_home_empty_message(db: &DBState, listed: usize) -> Option<&'static str> {
+    if db.epics.is_empty() {
+        Some("No epics yet — press [c] to create one.")
+    } else if listed == 0 {
+        Some("No epics match the current filter, focus or [e] setting.")
+    } else {
+        None
+    }
+}
+
 impl Page for HomePage {
     fn draw_page(&self) -> Result<()> {
-        println!("----------------------------- EPICS -----------------------------");
-        println!("     id     |               name               |      status     ");
+        let width = get_terminal_width();
+
+        let health = self.db.health()?;
+        println!(
+            "{}",
+            get_health_banner(&health, &HealthThresholds::default())
+        );
+        println!();
 
         // Read epics
         let db = self.db.read_db()?;
 
-        println!();
-        for (epic_id, epic) in db.epics {
-            println!(
-                " {} | {} | {} ",
-                get_column_string(&epic_id, 10),
-                get_column_string(&epic.name, 30),
-                get_column_string(&epic.status.to_string(), 15)
+        let filter = self.filter.borrow();
+        let title = match filter.as_ref() {
+            Some(status) => format!("EPICS: {}", status),
+            None => "EPICS".to_owned(),
+        };
+        let mut table = Table::new(&title, &HOME_COLUMNS).truncation(self.display.truncation);
+        let focus = self.focus.borrow();
+        let listed = self.get_listed_epics(&db);
+        let empty_message = get_home_empty_message(&db, listed.len());
+        for (epic_id, epic) in listed {
+            // Pinned epics are marked in the leading gutter
+            let marker = if epic.pinned { '*' } else { ' ' };
+            table.push(
+                TableRow::new(&[
+                    epic_id,
+                    &epic.name,
+                    epic.owner.as_deref().unwrap_or_default(),
+                    &epic.status.to_string(),
+                ])
+                .marker(marker),
             );
         }
+        table.render(&mut stdout(), TableWidth::Fit(width))?;
+        if let Some(message) = empty_message {
+            println!("{}", message);
+        }
 
         println!();
+
+        // The active filter is marked in the leading gutter, like pins
+        if self.show_legend.get() {
+            println!("{}", get_table_title("LEGEND", width));
+            for (number, (status, count)) in get_status_legend(&db).iter().enumerate() {
+                let marker = if filter.as_ref() == Some(status) {
+                    "*"
+                } else {
+                    " "
+                };
+                println!("{}[#{}] {} ({})", marker, number + 1, status, count);
+            }
+            println!();
+        }
+
         println!();
 
-        println!("[q] quit | [c] create epic | [:id:] navigate to epic");
+        let legend = if self.show_legend.get() {
+            " | [L] hide legend | [#n] filter"
+        } else {
+            " | [L] legend"
+        };
+        let empty = if self.hide_empty.get() {
+            "[e] show empty epics"
+        } else {
+            "[e] hide empty epics"
+        };
+        println!(
+            "[q] quit | [R] refresh | [z] undo | [Z] redo | [c] create epic | [i] capture to inbox | [/] search | [v] saved filters | [w] due this week | [n] refinement queue | [:id:] navigate to epic | [*:id:] pin/unpin epic | [s] sort ({}) | [f] status filter, open/in progress/blocked/resolved/closed ({}){} | {} | {}",
+            self.sort.get(),
+            filter
+                .as_ref()
+                .map_or("all".to_owned(), |status| status.to_string()),
+            legend,
+            empty,
+            get_focus_footer(&focus)
+        );
 
         Ok(())
     }
 
     fn handle_input(&self, input: &str) -> Result<Option<Action>> {
-        // Get epics
-        let epics = self.db.read_db()?.epics;
-
         match input {
             "q" => Ok(Some(Action::Exit)),
+            "R" => Ok(Some(Action::Refresh)),
+            "z" => Ok(Some(Action::Undo)),
+            "Z" => Ok(Some(Action::Redo)),
             "c" => Ok(Some(Action::CreateEpic)),
-            input => {
-                if let Ok(epic_id) = input.parse::<String>() {
-                    if epics.contains_key(&epic_id) {
-                        return Ok(Some(Action::NavigateToEpicDetail { epic_id }));
-                    }
+            "i" => Ok(Some(Action::QuickCapture)),
+            "/" => Ok(Some(Action::Search)),
+            "v" => Ok(Some(Action::PickFilter)),
+            "w" => Ok(Some(Action::NavigateToDueThisWeek)),
+            "n" => Ok(Some(Action::NavigateToRefinementQueue)),
+            "@" => Ok(Some(Action::FocusAssignee)),
+            "F" => Ok(Some(Action::ClearAssigneeFocus)),
+            "!" => Ok(Some(Action::NavigateToRawJson { epic_id: None })),
+            "s" => {
+                // Sorting only changes how the page draws, so no action is needed
+                self.sort.set(self.sort.get().next());
+                Ok(None)
+            }
+            "L" => {
+                // Like sorting, the legend only changes how the page draws
+                self.show_legend.set(!self.show_legend.get());
+                Ok(None)
+            }
+            "e" => {
+                self.hide_empty.set(!self.hide_empty.get());
+                Ok(None)
+            }
+            "f" => {
+                let mut filter = self.filter.borrow_mut();
+                *filter = get_next_status_filter(filter.as_ref());
+                Ok(None)
+            }
+            input if input.starts_with('#') => {
+                // Picking the active filter again clears it
+                let legend = get_status_legend(&self.db.read_db()?);
+                let picked = input[1..]
+                    .trim()
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|number| number.checked_sub(1))
+                    .and_then(|index| legend.into_iter().nth(index))
+                    .map(|(status, _)| status);
+                let mut filter = self.filter.borrow_mut();
+                if let Some(status) = picked {
+                    *filter = if filter.as_ref() == Some(&status) {
+                        None
+                    } else {
+                        Some(status)
+                    };
+                }
+                Ok(None)
+            }
+            input if input.starts_with('*') => {
+                let epic_id = input.trim_start_matches('*').trim().to_owned();
+                if self.db.epic_exists(&epic_id)? {
+                    return Ok(Some(Action::TogglePin { epic_id }));
                 }
                 Ok(None)
             }
+            input => {
+                // A unique prefix is enough, like a short git hash
+                let epics = self.db.read_db()?.epics;
+                let epic_id = get_prefix_match(input, epics.keys())?;
+                Ok(epic_id.map(|epic_id| Action::NavigateToEpicDetail { epic_id }))
+            }
         }
     }
 
@@ -69,89 +393,673 @@ impl Page for HomePage {
     }
 }
 
+// An ambiguous prefix is reported so the user knows to type more of the id
+fn get_prefix_match<'a>(
+    input: &str,
+    ids: impl IntoIterator<Item = &'a String>,
+) -> Result<Option<String>> {
+    match IdMatch::from_ids(input, ids) {
+        IdMatch::Unique(id) => Ok(Some(id)),
+        IdMatch::Ambiguous(count) => Err(anyhow!("{} ids start with '{}'.", count, input)),
+        IdMatch::None => Ok(None),
+    }
+}
+
+// Orders an epic's stories depth-first so subtasks follow their parent, siblings by the sort mode.
+// Stories whose parent is missing from the epic are treated as top-level. Only the epic's own ids
+// are looked up and children are grouped once, so the cost follows the epic's size rather than
+// the database's.
+fn get_story_tree<'a>(
+    db_state: &'a DBState,
+    epic: &'a Epic,
+    sort: SortMode,
+) -> Vec<(usize, &'a String, &'a Story)> {
+    // Dangling ids are skipped
+    let stories = epic
+        .stories
+        .iter()
+        .filter_map(|id| db_state.stories.get(id).map(|story| (id, story)))
+        .collect_vec();
+    let stories = sort_stories(stories, sort);
+    let in_epic: HashSet<&String> = stories.iter().map(|(id, _)| *id).collect();
+
+    // Both keep the sort order
+    let mut roots = Vec::new();
+    let mut children: HashMap<&String, Vec<(&String, &Story)>> = HashMap::new();
+    for (id, story) in &stories {
+        match story
+            .parent
+            .as_ref()
+            .filter(|parent_id| in_epic.contains(parent_id))
+        {
+            Some(parent_id) => children.entry(parent_id).or_default().push((id, story)),
+            None => roots.push((*id, *story)),
+        }
+    }
+
+    let mut tree = Vec::new();
+    let mut seen = HashSet::new();
+    let mut stack = roots
+        .into_iter()
+        .rev()
+        .map(|(id, story)| (0, id, story))
+        .collect_vec();
+    while let Some((depth, id, story)) = stack.pop() {
+        // Guard against cycles written by hand into the database
+        if !seen.insert(id) {
+            continue;
+        }
+        tree.push((depth, id, story));
+        if let Some(subtasks) = children.get(id) {
+            stack.extend(
+                subtasks
+                    .iter()
+                    .rev()
+                    .map(|(child_id, child)| (depth + 1, *child_id, *child)),
+            );
+        }
+    }
+    tree
+}
+
+// Whether any of the epic's stories is assigned to `assignee`
+fn epic_has_assignee(db_state: &DBState, epic: &Epic, assignee: &str) -> bool {
+    epic.stories.iter().any(|story_id| {
+        db_state
+            .stories
+            .get(story_id)
+            .is_some_and(|story| story.assignee.as_deref() == Some(assignee))
+    })
+}
+
+fn get_focus_footer(focus: &Option<String>) -> String {
+    match focus {
+        Some(assignee) => format!("Focus: {} | [F] clear focus", assignee),
+        None => "[@] focus on assignee".to_owned(),
+    }
+}
+
+fn get_health_banner(health: &Health, thresholds: &HealthThresholds) -> String {
+    [
+        ("WIP", health.wip, thresholds.wip),
+        ("Overdue", health.overdue, thresholds.overdue),
+        ("Unassigned", health.unassigned, thresholds.unassigned),
+    ]
+    .iter()
+    .map(|(label, count, thresholds)| {
+        paint(
+            &format!("{}: {}", label, count),
+            thresholds.get_color(*count),
+        )
+    })
+    .join(" | ")
+}
+
+// The statuses in use among epics, each with its epic count. Epics carry no
+// labels or colors, so status is the category the legend groups by.
+fn get_status_legend(db_state: &DBState) -> Vec<(Status, usize)> {
+    HOME_FILTER_STATUSES
+        .into_iter()
+        .map(|status| {
+            let count = db_state
+                .epics
+                .values()
+                .filter(|epic| epic.status == status)
+                .count();
+            (status, count)
+        })
+        .filter(|(_, count)| *count > 0)
+        .collect()
+}
+
+// Workflow order, for the legend and the [t] filter cycle
+const HOME_FILTER_STATUSES: [Status; 5] = [
+    Status::Open,
+    Status::InProgress,
+    Status::Blocked,
+    Status::Resolved,
+    Status::Closed,
+];
+
+// [f] steps through every status, then back to listing all epics
+fn get_next_status_filter(filter: Option<&Status>) -> Option<Status> {
+    match filter {
+        None => HOME_FILTER_STATUSES.first().cloned(),
+        Some(current) => HOME_FILTER_STATUSES
+            .iter()
+            .skip_while(|status| *status != current)
+            .nth(1)
+            .cloned(),
+    }
+}
+
+// Pinned epics come first, each group ordered by the sort mode
+fn get_home_page_epics(db_state: &DBState, sort: SortMode) -> Vec<(&String, &Epic)> {
+    let (pinned, unpinned): (Vec<_>, Vec<_>) =
+        db_state.epics.iter().partition(|(_, epic)| epic.pinned);
+
+    let mut epics = sort_epics(db_state, pinned, sort);
+    epics.extend(sort_epics(db_state, unpinned, sort));
+    epics
+}
+
+// "Created: ... | Updated: ...", leaving out whichever timestamp is missing
+fn get_timestamps_line(
+    created_at: Option<&DateTime<Utc>>,
+    updated_at: Option<&DateTime<Utc>>,
+    tz: &Tz,
+) -> Option<String> {
+    let parts = [("Created", created_at), ("Updated", updated_at)]
+        .into_iter()
+        .filter_map(|(label, timestamp)| {
+            timestamp.map(|timestamp| format!("{}: {}", label, format_timestamp(timestamp, tz)))
+        })
+        .collect_vec();
+
+    if parts.is_empty() {
+        return None;
+    }
+    Some(parts.join(" | "))
+}
+
+// Writes the epic detail and its story list, shared by the TUI page and the `open` command
+pub fn write_epic_detail(
+    out: &mut dyn Write,
+    db_state: &DBState,
+    epic_id: &String,
+    sort: SortMode,
+    width: TableWidth,
+    display: &DisplayConfig,
+    focus: Option<&str>,
+) -> Result<()> {
+    let epic = db_state
+        .epics
+        .get(epic_id)
+        .ok_or_else(|| anyhow!("Could not find epic!"))?;
+
+    // Print epic detail
+    let mut detail = Table::new("EPIC", &DETAIL_COLUMNS).truncation(display.truncation);
+    detail.push(TableRow::new(&[
+        epic_id,
+        &epic.name,
+        &epic.description,
+        &epic.status.to_string(),
+    ]));
+    detail.render(out, width)?;
+
+    // Timestamps are stored in UTC and shown in the display zone
+    if let Some(line) = get_timestamps_line(
+        epic.created_at.as_ref(),
+        epic.updated_at.as_ref(),
+        &display.tz,
+    ) {
+        writeln!(out, "{}", line)?;
+    }
+    if let Some(owner) = &epic.owner {
+        writeln!(out, "Owner: {}", owner)?;
+    }
+
+    writeln!(out)?;
+
+    // Print story rows, subtasks indented under their parent
+    let mut stories = Table::new("STORIES", &LIST_COLUMNS).truncation(display.truncation);
+    let id_length = display.story_id_length;
+    let mut listed = 0;
+    for (depth, story_id, story) in get_story_tree(db_state, epic, sort) {
+        if focus.is_some_and(|assignee| story.assignee.as_deref() != Some(assignee)) {
+            continue;
+        }
+        listed += 1;
+        let name = format!("{}{}", "  ".repeat(depth), story.name);
+        let color = DueUrgency::today(story.due_date, &display.due_soon).get_color();
+        stories.push(
+            TableRow::new(&[
+                get_short_id(story_id, id_length),
+                &name,
+                &story.status.to_string(),
+            ])
+            .color(color),
+        );
+    }
+    stories.render(out, width)?;
+    if epic.is_empty() {
+        writeln!(out, "No stories in this epic yet.")?;
+    } else if let (0, Some(assignee)) = (listed, focus) {
+        writeln!(out, "No stories in this epic are assigned to {}.", assignee)?;
+    }
+
+    // Short ids only help while they still tell the stories apart
+    let collisions = get_short_id_collisions(&epic.stories, id_length);
+    if !collisions.is_empty() {
+        writeln!(
+            out,
+            "Warning: several stories start with {}, type more of the id to open one.",
+            collisions.join(", ")
+        )?;
+    }
+
+    Ok(())
+}
+
+// Writes the story detail with its parent and subtasks, shared by the TUI page and the `open` command
+pub fn write_story_detail(
+    out: &mut dyn Write,
+    db_state: &DBState,
+    story_id: &String,
+    width: TableWidth,
+    display: &DisplayConfig,
+) -> Result<()> {
+    let story = db_state
+        .stories
+        .get(story_id)
+        .ok_or_else(|| anyhow!("could not find story!"))?;
+
+    let color = DueUrgency::today(story.due_date, &display.due_soon).get_color();
+    let mut detail = Table::new("STORY", &STORY_DETAIL_COLUMNS).truncation(display.truncation);
+    detail.push(
+        TableRow::new(&[
+            story_id,
+            &story.name,
+            &story.description,
+            story.assignee.as_deref().unwrap_or("—"),
+            &story.status.to_string(),
+        ])
+        .color(color),
+    );
+    detail.render(out, width)?;
+
+    // Right under the story, so it's the first thing read
+    if story.status == Status::Blocked {
+        let reason = story.blocked_reason.as_deref().unwrap_or("no reason given");
+        writeln!(
+            out,
+            "{}",
+            paint(&format!("BLOCKED: {}", reason), Some(Color::Red))
+        )?;
+    }
+    if story.needs_refinement {
+        writeln!(out, "{}", paint("NEEDS REFINEMENT", Some(Color::Yellow)))?;
+    }
+
+    // Timestamps are stored in UTC and shown in the display zone
+    if let Some(line) = get_timestamps_line(
+        story.created_at.as_ref(),
+        story.updated_at.as_ref(),
+        &display.tz,
+    ) {
+        // Colored by how long the story has gone without an update
+        let age = StoryAge::now(story.updated_at.as_ref(), &display.age);
+        writeln!(out, "{}", paint(&line, age.get_color()))?;
+    }
+    // How long the story has been in its current status, when the change was recorded
+    if let Some(change) = story
+        .status_history
+        .last()
+        .filter(|change| change.status == story.status)
+    {
+        writeln!(
+            out,
+            "{} since {}",
+            story.status,
+            format_time_ago(&change.at, &Utc::now())
+        )?;
+    }
+    // Flow metrics, once the story has been finished
+    let flow = [
+        story
+            .cycle_time()
+            .map(|time| format!("Cycle time: {}", format_duration(&time))),
+        story
+            .lead_time()
+            .map(|time| format!("Lead time: {}", format_duration(&time))),
+    ];
+    let flow = flow.into_iter().flatten().collect::<Vec<_>>();
+    if !flow.is_empty() {
+        writeln!(out, "{}", flow.join(" | "))?;
+    }
+    if let Some(due_date) = story.due_date {
+        let due_line = format!("Due: {}", due_date.format("%Y-%m-%d"));
+        writeln!(out, "{}", paint(&due_line, color))?;
+    }
+
+    writeln!(out)?;
+
+    // Print the parent and subtasks, if any
+    if let Some(parent_id) = &story.parent {
+        let parent_name = db_state
+            .stories
+            .get(parent_id)
+            .map_or("", |parent| parent.name.as_str());
+        writeln!(out, "Parent: {} {}", parent_id, parent_name)?;
+    }
+    let children = db_state
+        .stories
+        .iter()
+        .filter(|(_, child)| child.parent.as_ref() == Some(story_id))
+        .sorted_by(|a, b| a.0.cmp(b.0))
+        .collect_vec();
+    if !children.is_empty() {
+        writeln!(out, "Subtasks:")?;
+        for (child_id, child) in children {
+            writeln!(out, "  {}", child.summary_line(child_id, colors_enabled()))?;
+        }
+    }
+
+    // Print the acceptance criteria, if any, numbered for the tick key
+    if !story.checklist.is_empty() {
+        let done = story.checklist.iter().filter(|item| item.done).count();
+        writeln!(out, "Checklist ({}/{}):", done, story.checklist.len())?;
+        for (number, item) in story.checklist.iter().enumerate() {
+            let mark = if item.done { "x" } else { " " };
+            writeln!(out, "  {}. [{}] {}", number + 1, mark, item.text)?;
+        }
+    }
+
+    // Print attachments, numbered for the open/remove keys
+    if !story.attachments.is_empty() {
+        writeln!(out, "Attachments:")?;
+        for (number, attachment) in story.attachments.iter().enumerate() {
+            writeln!(
+                out,
+                "  {}. {} ({})",
+                number + 1,
+                attachment.label,
+                attachment.target
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
 pub struct EpicDetail {
     pub epic_id: String,
     pub db: Rc<JiraDatabase>,
+    pub display: Rc<DisplayConfig>,
+    pub sort: Cell<SortMode>,
+    pub focus: AssigneeFocus,
+}
+
+impl EpicDetail {
+    pub fn new(epic_id: String, db: Rc<JiraDatabase>, display: Rc<DisplayConfig>) -> Self {
+        Self {
+            epic_id,
+            db,
+            display,
+            sort: Cell::new(SortMode::default()),
+            focus: AssigneeFocus::default(),
+        }
+    }
+
+    // What the footer lists and handle_input accepts for the epic as it is now
+    fn get_keys(&self, epic: &Epic) -> Vec<PageKey> {
+        get_epic_keys(
+            epic,
+            self.db.capabilities().writable,
+            self.sort.get(),
+            self.focus.borrow().as_deref(),
+        )
+    }
 }
 
 impl Page for EpicDetail {
     fn draw_page(&self) -> Result<()> {
         let db_state = self.db.read_db()?;
-        let epic = db_state
-            .epics
-            .get(&self.epic_id)
-            .ok_or_else(|| anyhow!("Could not find epic!"))?;
 
-        println!("------------------------------ EPIC ------------------------------");
-        println!("  id  |     name     |         description         |    status    ");
-
-        // Print epic detail using get_column_string()
-        println!(
-            " {} | {} | {} | {} ",
-            get_column_string(&self.epic_id, 5),
-            get_column_string(&epic.name, 13),
-            get_column_string(&epic.description, 28),
-            get_column_string(&epic.status.to_string(), 13)
-        );
+        write_epic_detail(
+            &mut stdout(),
+            &db_state,
+            &self.epic_id,
+            self.sort.get(),
+            TableWidth::Fit(get_terminal_width()),
+            &self.display,
+            self.focus.borrow().as_deref(),
+        )?;
 
         println!();
+        println!();
+
+        if let Some(epic) = db_state.epics.get(&self.epic_id) {
+            println!("{}", get_footer(&self.get_keys(epic)));
+        }
 
-        println!("---------------------------- STORIES ----------------------------");
-        println!("     id     |               name               |      status      ");
+        Ok(())
+    }
 
-        // Grab all stories
-        let stories = &db_state.stories;
+    fn handle_input(&self, input: &str) -> Result<Option<Action>> {
+        // Get database state
+        let epic = self.db.get_epic(&self.epic_id)?;
+        let keys = self.get_keys(&epic);
 
-        // Keep stories that are present in the epic
-        let epic_stores = stories
-            .iter()
-            .filter(|(id, _)| epic.stories.contains(&id))
-            .collect_vec();
+        // Anything that isn't one of the offered keys may be a story id
+        let Some(key) = keys.iter().find(|key| key.accepts(input)) else {
+            if !keys.contains(&PageKey::OpenStory) {
+                return Ok(None);
+            }
+            let story_id = get_prefix_match(input, &epic.stories)?;
+            return Ok(story_id.map(|story_id| Action::NavigateToStoryDetail {
+                epic_id: self.epic_id.clone(),
+                story_id,
+            }));
+        };
+
+        let epic_id = self.epic_id.clone();
+        Ok(match key {
+            PageKey::Previous => Some(Action::NavigateToPreviousPage),
+            PageKey::Back => parse_navigate_back(input).map(|count| Action::NavigateBack { count }),
+            PageKey::Refresh => Some(Action::Refresh),
+            PageKey::UpdateStatus => Some(Action::UpdateEpicStatus { epic_id }),
+            PageKey::Reopen => Some(Action::ReopenEpic { epic_id }),
+            PageKey::SetOwner => Some(Action::UpdateEpicOwner { epic_id }),
+            PageKey::CopyId => Some(Action::CopyId { id: epic_id }),
+            PageKey::Delete => Some(Action::DeleteEpic { epic_id }),
+            PageKey::CreateStory => Some(Action::CreateStory { epic_id }),
+            PageKey::QuickAddStories => Some(Action::QuickAddStories { epic_id }),
+            PageKey::SelectStories => Some(Action::SelectStories { epic_id }),
+            PageKey::Focus => Some(Action::FocusAssignee),
+            PageKey::ClearFocus(_) => Some(Action::ClearAssigneeFocus),
+            PageKey::Sort(sort) => {
+                self.sort.set(sort.next());
+                None
+            }
+            PageKey::RawJson => Some(Action::NavigateToRawJson {
+                epic_id: Some(epic_id),
+            }),
+            _ => None,
+        })
+    }
 
-        // Print story detail using get_column_string()
-        for (story_id, story) in epic_stores {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+pub struct StoryDetail {
+    pub epic_id: String,
+    pub story_id: String,
+    pub db: Rc<JiraDatabase>,
+    pub display: Rc<DisplayConfig>,
+}
+
+impl Page for StoryDetail {
+    fn draw_page(&self) -> Result<()> {
+        let db_state = self.db.read_db()?;
+
+        write_story_detail(
+            &mut stdout(),
+            &db_state,
+            &self.story_id,
+            TableWidth::Fit(get_terminal_width()),
+            &self.display,
+        )?;
+
+        println!();
+
+        if let Some(story) = db_state.stories.get(&self.story_id) {
             println!(
-                " {} | {} | {} ",
-                get_column_string(&story_id, 10),
-                get_column_string(&story.name, 30),
-                get_column_string(&story.status.to_string(), 16)
+                "{}",
+                get_footer(&get_story_keys(story, self.db.capabilities().writable))
             );
         }
 
+        Ok(())
+    }
+
+    fn handle_input(&self, input: &str) -> Result<Option<Action>> {
+        let story = self.db.get_epic_story(&self.epic_id, &self.story_id)?;
+        let keys = get_story_keys(&story, self.db.capabilities().writable);
+        let Some(key) = keys.iter().find(|key| key.accepts(input)) else {
+            return Ok(None);
+        };
+
+        let epic_id = self.epic_id.clone();
+        let story_id = self.story_id.clone();
+        Ok(match key {
+            PageKey::Previous => Some(Action::NavigateToPreviousPage),
+            PageKey::Back => parse_navigate_back(input).map(|count| Action::NavigateBack { count }),
+            PageKey::Refresh => Some(Action::Refresh),
+            PageKey::UpdateStatus => Some(Action::UpdateStoryStatus { story_id }),
+            PageKey::Reopen => Some(Action::ReopenStory { story_id }),
+            PageKey::QuickStatus => {
+                get_quick_status(input).map(|status| Action::SetStoryStatus { story_id, status })
+            }
+            PageKey::AssignToSelf => Some(Action::AssignToSelf { story_id }),
+            PageKey::Assign => Some(Action::AssignStory { story_id }),
+            PageKey::Unassign => Some(Action::Unassign { story_id }),
+            PageKey::ToggleRefinement(_) => Some(Action::ToggleRefinement { story_id }),
+            PageKey::LinkParent => Some(Action::SetStoryParent { epic_id, story_id }),
+            PageKey::Attach => Some(Action::AddAttachment { story_id }),
+            PageKey::OpenAttachment => parse_indexed_command(input, 'o')
+                .map(|index| Action::OpenAttachment { story_id, index }),
+            PageKey::RemoveAttachment => parse_indexed_command(input, 'x')
+                .map(|index| Action::RemoveAttachment { story_id, index }),
+            PageKey::AddChecklistItem => Some(Action::AddChecklistItem { story_id }),
+            PageKey::ToggleChecklistItem => parse_indexed_command(input, 't')
+                .map(|index| Action::ToggleChecklistItem { story_id, index }),
+            PageKey::Promote => Some(Action::PromoteStory { epic_id, story_id }),
+            PageKey::Delete => Some(Action::DeleteStory { epic_id, story_id }),
+            PageKey::CopyId => Some(Action::CopyId { id: story_id }),
+            PageKey::CycleInProgress => Some(Action::CycleInProgress {
+                story_id,
+                forward: input == "]",
+            }),
+            _ => None,
+        })
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+// Keep huge states from flooding the terminal
+const RAW_JSON_MAX_LINES: usize = 40;
+
+// Read-only debug view of the stored JSON, for the whole state or one epic
+pub struct RawJsonPage {
+    pub epic_id: Option<String>,
+    pub db: Rc<JiraDatabase>,
+}
+
+impl Page for RawJsonPage {
+    fn draw_page(&self) -> Result<()> {
+        let db_state = self.db.read_db()?;
+
+        let json = match &self.epic_id {
+            Some(epic_id) => {
+                let epic = db_state
+                    .epics
+                    .get(epic_id)
+                    .ok_or_else(|| anyhow!("Could not find epic!"))?;
+                serde_json::to_string_pretty(epic)?
+            }
+            None => serde_json::to_string_pretty(&db_state)?,
+        };
+
+        println!("{}", get_table_title("RAW JSON", get_terminal_width()));
+
+        let (shown, hidden) = get_truncated_lines(&json, RAW_JSON_MAX_LINES);
+        println!("{}", shown);
+        if hidden > 0 {
+            println!("... {} more lines not shown", hidden);
+        }
+
         println!();
         println!();
 
-        println!("[p] previous | [u] update epic | [d] delete epic | [c] create story | [:id:] navigate to story");
+        println!("[p] previous | [R] refresh");
 
         Ok(())
     }
 
     fn handle_input(&self, input: &str) -> Result<Option<Action>> {
-        // Get database state
-        let epic = self.db.get_epic(&self.epic_id)?;
+        match input {
+            "p" => Ok(Some(Action::NavigateToPreviousPage)),
+            "R" => Ok(Some(Action::Refresh)),
+            input => Ok(parse_navigate_back(input).map(|count| Action::NavigateBack { count })),
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+// Search hits, best match first
+pub struct SearchResultsPage {
+    pub query: String,
+    pub db: Rc<JiraDatabase>,
+    pub display: Rc<DisplayConfig>,
+}
+
+impl Page for SearchResultsPage {
+    fn draw_page(&self) -> Result<()> {
+        let results = self.db.search(&self.query)?;
+
+        let title = format!("SEARCH: {}", self.query);
+        let mut table = Table::new(&title, &SEARCH_COLUMNS).truncation(self.display.truncation);
+        for result in &results {
+            let (id, kind) = match &result.story_id {
+                Some(story_id) => (story_id, "story"),
+                None => (&result.epic_id, "epic"),
+            };
+            table.push(TableRow::new(&[id, &result.name, kind]));
+        }
+        table.render(&mut stdout(), TableWidth::Fit(get_terminal_width()))?;
+        if results.is_empty() {
+            println!("No matches.");
+        }
+
+        println!();
+        println!();
+
+        println!("[p] previous | [R] refresh | [:id:] open result");
+
+        Ok(())
+    }
 
-        // Match user input
+    fn handle_input(&self, input: &str) -> Result<Option<Action>> {
         match input {
             "p" => Ok(Some(Action::NavigateToPreviousPage)),
-            "u" => Ok(Some(Action::UpdateEpicStatus {
-                epic_id: self.epic_id.clone(),
-            })),
-            "d" => Ok(Some(Action::DeleteEpic {
-                epic_id: self.epic_id.clone(),
-            })),
-            "c" => Ok(Some(Action::CreateStory {
-                epic_id: self.epic_id.clone(),
-            })),
+            "R" => Ok(Some(Action::Refresh)),
             input => {
-                if let Ok(story_id) = input.parse::<String>() {
-                    if epic.stories.contains(&story_id) {
-                        return Ok(Some(Action::NavigateToStoryDetail {
-                            epic_id: self.epic_id.clone(),
-                            story_id,
-                        }));
-                    }
+                if let Some(count) = parse_navigate_back(input) {
+                    return Ok(Some(Action::NavigateBack { count }));
                 }
-                Ok(None)
+                // Only ids shown in the results can be opened from here
+                let result =
+                    self.db.search(&self.query)?.into_iter().find(|result| {
+                        result.story_id.as_deref().unwrap_or(&result.epic_id) == input
+                    });
+                Ok(result.map(|result| match result.story_id {
+                    Some(story_id) => Action::NavigateToStoryDetail {
+                        epic_id: result.epic_id,
+                        story_id,
+                    },
+                    None => Action::NavigateToEpicDetail {
+                        epic_id: result.epic_id,
+                    },
+                }))
             }
         }
     }
@@ -161,51 +1069,201 @@ impl Page for EpicDetail {
     }
 }
 
-pub struct StoryDetail {
-    pub epic_id: String,
-    pub story_id: String,
+// Stories matching a story query as (epic_id, story_id, story), sorted by story id
+pub fn get_query_results(db_state: &DBState, query: &str) -> Result<Vec<(String, String, Story)>> {
+    let predicate = parse_story_query(query)?;
+    Ok(db_state
+        .epics
+        .iter()
+        .flat_map(|(epic_id, epic)| epic.stories.iter().map(move |story_id| (epic_id, story_id)))
+        .filter_map(|(epic_id, story_id)| {
+            let story = db_state.stories.get(story_id)?;
+            predicate(story_id, story).then(|| (epic_id.clone(), story_id.clone(), story.clone()))
+        })
+        .sorted_by(|(_, a, _), (_, b, _)| a.cmp(b))
+        .collect())
+}
+
+// Stories matching a saved filter
+pub struct QueryResultsPage {
+    pub name: String,
+    pub query: String,
     pub db: Rc<JiraDatabase>,
+    pub display: Rc<DisplayConfig>,
 }
 
-impl Page for StoryDetail {
+impl Page for QueryResultsPage {
     fn draw_page(&self) -> Result<()> {
-        let db_state = self.db.read_db()?;
-        let story = db_state
-            .stories
-            .get(&self.story_id)
-            .ok_or_else(|| anyhow!("could not find story!"))?;
+        let results = get_query_results(&self.db.read_db()?, &self.query)?;
 
-        println!("------------------------------ STORY ------------------------------");
-        println!("  id  |     name     |         description         |    status    ");
+        let title = format!("FILTER: {} ({})", self.name, self.query);
+        let mut table = Table::new(&title, &LIST_COLUMNS).truncation(self.display.truncation);
+        for (_, story_id, story) in &results {
+            table.push(TableRow::new(&[
+                story_id,
+                &story.name,
+                &story.status.to_string(),
+            ]));
+        }
+        table.render(&mut stdout(), TableWidth::Fit(get_terminal_width()))?;
+        if results.is_empty() {
+            println!("No matches.");
+        }
 
-        println!(
-            " {} | {} | {} | {} ",
-            get_column_string(&self.story_id, 5),
-            get_column_string(&story.name, 13),
-            get_column_string(&story.description, 28),
-            get_column_string(&story.status.to_string(), 13)
-        );
+        println!();
+        println!();
+
+        println!("[p] previous | [R] refresh | [:id:] open story");
+
+        Ok(())
+    }
+
+    fn handle_input(&self, input: &str) -> Result<Option<Action>> {
+        match input {
+            "p" => Ok(Some(Action::NavigateToPreviousPage)),
+            "R" => Ok(Some(Action::Refresh)),
+            input => {
+                if let Some(count) = parse_navigate_back(input) {
+                    return Ok(Some(Action::NavigateBack { count }));
+                }
+                // Only stories the filter lists can be opened
+                let result = get_query_results(&self.db.read_db()?, &self.query)?
+                    .into_iter()
+                    .find(|(_, story_id, _)| story_id == input);
+                Ok(
+                    result.map(|(epic_id, story_id, _)| Action::NavigateToStoryDetail {
+                        epic_id,
+                        story_id,
+                    }),
+                )
+            }
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+// Monday to Sunday of the week `today` falls in
+pub fn get_week_bounds(today: NaiveDate) -> (NaiveDate, NaiveDate) {
+    let start = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+    (start, start + Duration::days(6))
+}
+
+// Stories flagged for refinement across every epic, grouped by epic
+pub struct RefinementQueuePage {
+    pub db: Rc<JiraDatabase>,
+    pub display: Rc<DisplayConfig>,
+}
+
+impl Page for RefinementQueuePage {
+    fn draw_page(&self) -> Result<()> {
+        let queue = self.db.refinement_queue()?;
+
+        let mut table =
+            Table::new("REFINEMENT QUEUE", &REFINEMENT_COLUMNS).truncation(self.display.truncation);
+        for (epic_id, story_id, story) in &queue {
+            table.push(TableRow::new(&[epic_id, story_id, &story.name]));
+        }
+        table.render(&mut stdout(), TableWidth::Fit(get_terminal_width()))?;
+        if queue.is_empty() {
+            println!("Nothing needs refinement.");
+        }
 
         println!();
         println!();
 
-        println!("[p] previous | [u] update story | [d] delete story");
+        println!("[p] previous | [R] refresh | [:id:] open story");
 
         Ok(())
     }
 
     fn handle_input(&self, input: &str) -> Result<Option<Action>> {
-        // Match for options p, u and d.
         match input {
             "p" => Ok(Some(Action::NavigateToPreviousPage)),
-            "u" => Ok(Some(Action::UpdateStoryStatus {
-                story_id: self.story_id.clone(),
-            })),
-            "d" => Ok(Some(Action::DeleteStory {
-                epic_id: self.epic_id.clone(),
-                story_id: self.story_id.clone(),
-            })),
-            _ => Ok(None),
+            "R" => Ok(Some(Action::Refresh)),
+            input => {
+                if let Some(count) = parse_navigate_back(input) {
+                    return Ok(Some(Action::NavigateBack { count }));
+                }
+                // Only stories listed here can be opened
+                let story = self
+                    .db
+                    .refinement_queue()?
+                    .into_iter()
+                    .find(|(_, story_id, _)| story_id == input);
+                Ok(
+                    story.map(|(epic_id, story_id, _)| Action::NavigateToStoryDetail {
+                        epic_id,
+                        story_id,
+                    }),
+                )
+            }
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+// Stories due within a date range, soonest first
+pub struct DueStoriesPage {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub db: Rc<JiraDatabase>,
+    pub display: Rc<DisplayConfig>,
+}
+
+impl Page for DueStoriesPage {
+    fn draw_page(&self) -> Result<()> {
+        let due = self.db.stories_due_between(self.start, self.end)?;
+
+        let title = format!("DUE: {} - {}", self.start, self.end);
+        let mut table = Table::new(&title, &DUE_COLUMNS).truncation(self.display.truncation);
+        for (_, story_id, story) in &due {
+            let due_date = story.due_date.map(|date| date.to_string());
+            table.push(TableRow::new(&[
+                story_id,
+                &story.name,
+                &due_date.unwrap_or_default(),
+            ]));
+        }
+        table.render(&mut stdout(), TableWidth::Fit(get_terminal_width()))?;
+        if due.is_empty() {
+            println!("Nothing due.");
+        }
+
+        println!();
+        println!();
+
+        println!("[p] previous | [R] refresh | [:id:] open story");
+
+        Ok(())
+    }
+
+    fn handle_input(&self, input: &str) -> Result<Option<Action>> {
+        match input {
+            "p" => Ok(Some(Action::NavigateToPreviousPage)),
+            "R" => Ok(Some(Action::Refresh)),
+            input => {
+                if let Some(count) = parse_navigate_back(input) {
+                    return Ok(Some(Action::NavigateBack { count }));
+                }
+                // Only stories listed here can be opened
+                let story = self
+                    .db
+                    .stories_due_between(self.start, self.end)?
+                    .into_iter()
+                    .find(|(_, story_id, _)| story_id == input);
+                Ok(
+                    story.map(|(epic_id, story_id, _)| Action::NavigateToStoryDetail {
+                        epic_id,
+                        story_id,
+                    }),
+                )
+            }
         }
     }
 
@@ -217,109 +1275,535 @@ impl Page for StoryDetail {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::db::test_utils::MockDB;
-    use crate::models::{Epic, Story};
+    use crate::db::test_utils::{walk_epic_to, walk_story_to, MockDB};
+    use crate::models::{Epic, Status, Story};
+
+    mod home_page {
+        use super::*;
+
+        #[test]
+        fn draw_page_should_not_throw_error() {
+            let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+
+            let page = HomePage::new(db);
+            assert_eq!(page.draw_page().is_ok(), true);
+        }
+
+        #[test]
+        fn handle_input_should_not_throw_error() {
+            let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+
+            let page = HomePage::new(db);
+            assert_eq!(page.handle_input("").is_ok(), true);
+        }
+
+        #[test]
+        fn handle_input_should_return_the_correct_actions() {
+            let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+
+            let epic = Epic::new("".to_owned(), "".to_owned());
+
+            let epic_id = db.create_epic(epic).unwrap();
+
+            let page = HomePage::new(db);
+
+            let q = "q";
+            let c = "c";
+            let invalid_epic_id = "999";
+            let junk_input = "j983f2j";
+
+            assert_eq!(page.handle_input(q).unwrap(), Some(Action::Exit));
+            assert_eq!(page.handle_input("R").unwrap(), Some(Action::Refresh));
+            assert_eq!(page.handle_input("z").unwrap(), Some(Action::Undo));
+            assert_eq!(page.handle_input("Z").unwrap(), Some(Action::Redo));
+            assert_eq!(page.handle_input(c).unwrap(), Some(Action::CreateEpic));
+            assert_eq!(page.handle_input("i").unwrap(), Some(Action::QuickCapture));
+            assert_eq!(page.handle_input("/").unwrap(), Some(Action::Search));
+            assert_eq!(page.handle_input("v").unwrap(), Some(Action::PickFilter));
+            assert_eq!(
+                page.handle_input(&epic_id).unwrap(),
+                Some(Action::NavigateToEpicDetail {
+                    epic_id: epic_id.clone()
+                })
+            );
+            assert_eq!(page.handle_input(invalid_epic_id).unwrap(), None);
+            assert_eq!(page.handle_input(junk_input).unwrap(), None);
+            assert_eq!(
+                page.handle_input(&format!("*{}", epic_id)).unwrap(),
+                Some(Action::TogglePin { epic_id })
+            );
+            assert_eq!(page.handle_input("*999").unwrap(), None);
+            assert_eq!(
+                page.handle_input("!").unwrap(),
+                Some(Action::NavigateToRawJson { epic_id: None })
+            );
+        }
+
+        #[test]
+        fn hide_empty_should_toggle_epics_without_stories() {
+            let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+            let empty_id = db
+                .create_epic(Epic::new("".to_owned(), "".to_owned()))
+                .unwrap();
+            let full_id = db
+                .create_epic(Epic::new("".to_owned(), "".to_owned()))
+                .unwrap();
+            db.create_story(Story::new("".to_owned(), "".to_owned()), &full_id)
+                .unwrap();
+            let page = HomePage::new(Rc::clone(&db));
+            let listed = |page: &HomePage| {
+                let db_state = db.read_db().unwrap();
+                page.get_listed_epics(&db_state)
+                    .into_iter()
+                    .map(|(epic_id, _)| epic_id.clone())
+                    .sorted()
+                    .collect_vec()
+            };
+            let all = [empty_id.clone(), full_id.clone()]
+                .into_iter()
+                .sorted()
+                .collect_vec();
+
+            assert_eq!(listed(&page), all);
+            assert_eq!(page.handle_input("e").unwrap(), None);
+            assert_eq!(listed(&page), vec![full_id.clone()]);
+            // Still reachable by id, and listed again once it has a story
+            assert_eq!(
+                page.handle_input(&empty_id).unwrap(),
+                Some(Action::NavigateToEpicDetail {
+                    epic_id: empty_id.clone()
+                })
+            );
+            db.create_story(Story::new("".to_owned(), "".to_owned()), &empty_id)
+                .unwrap();
+            assert_eq!(listed(&page), all);
+            page.handle_input("e").unwrap();
+            assert!(!page.hide_empty.get());
+        }
+
+        #[test]
+        fn handle_input_should_navigate_by_unique_prefix() {
+            let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+            let mut db_state = db.read_db().unwrap();
+            for id in ["abc123", "abd456"] {
+                db_state
+                    .epics
+                    .insert(id.to_owned(), Epic::new("".to_owned(), "".to_owned()));
+            }
+            db.database.write_db(&db_state).unwrap();
+
+            let page = HomePage::new(db);
+
+            assert_eq!(
+                page.handle_input("abc").unwrap(),
+                Some(Action::NavigateToEpicDetail {
+                    epic_id: "abc123".to_owned()
+                })
+            );
+            assert!(page
+                .handle_input("ab")
+                .unwrap_err()
+                .to_string()
+                .contains("2 ids"));
+            assert_eq!(page.handle_input("zz").unwrap(), None);
+        }
+
+        #[test]
+        fn status_legend_should_count_epics_per_status_in_use() {
+            let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+            for status in [Status::Closed, Status::Open, Status::Closed] {
+                let epic_id = db
+                    .create_epic(Epic::new("".to_owned(), "".to_owned()))
+                    .unwrap();
+                walk_epic_to(&db, &epic_id, status).unwrap();
+            }
+
+            let legend = get_status_legend(&db.read_db().unwrap());
+
+            assert_eq!(legend, vec![(Status::Open, 1), (Status::Closed, 2)]);
+        }
+
+        #[test]
+        fn handle_input_should_toggle_the_legend_and_filter() {
+            let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+            let epic_id = db
+                .create_epic(Epic::new("".to_owned(), "".to_owned()))
+                .unwrap();
+            walk_epic_to(&db, &epic_id, Status::Closed).unwrap();
+            db.create_epic(Epic::new("".to_owned(), "".to_owned()))
+                .unwrap();
+
+            let page = HomePage::new(db);
+
+            assert_eq!(page.handle_input("L").unwrap(), None);
+            assert!(page.show_legend.get());
+            assert_eq!(page.handle_input("#2").unwrap(), None);
+            assert_eq!(*page.filter.borrow(), Some(Status::Closed));
+            assert!(page.draw_page().is_ok());
+            page.handle_input("#2").unwrap();
+            assert_eq!(*page.filter.borrow(), None);
+            page.handle_input("#9").unwrap();
+            assert_eq!(*page.filter.borrow(), None);
+        }
+
+        #[test]
+        fn handle_input_should_cycle_the_status_filter() {
+            let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+            db.create_epic(Epic::new("".to_owned(), "".to_owned()))
+                .unwrap();
+            let page = HomePage::new(db);
+
+            let mut seen = vec![];
+            for _ in 0..6 {
+                assert_eq!(page.handle_input("f").unwrap(), None);
+                seen.push(page.filter.borrow().clone());
+                assert!(page.draw_page().is_ok());
+            }
+            // Focus moved to [@] to leave [f] to the filter
+            assert_eq!(page.handle_input("@").unwrap(), Some(Action::FocusAssignee));
+
+            assert_eq!(
+                seen,
+                vec![
+                    Some(Status::Open),
+                    Some(Status::InProgress),
+                    Some(Status::Blocked),
+                    Some(Status::Resolved),
+                    Some(Status::Closed),
+                    None,
+                ]
+            );
+        }
+
+        #[test]
+        fn pinned_epics_should_be_listed_first() {
+            let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+
+            let mut epic_ids = vec![];
+            for name in ["a", "b", "c", "d"] {
+                epic_ids.push(
+                    db.create_epic(Epic::new(name.to_owned(), "".to_owned()))
+                        .unwrap(),
+                );
+            }
+
+            let unpinned_order = get_home_page_epics(&db.read_db().unwrap(), SortMode::Name)
+                .into_iter()
+                .map(|(id, _)| id.clone())
+                .collect_vec();
+            let last = unpinned_order.last().unwrap().clone();
+
+            db.toggle_pin(&last).unwrap();
+
+            let db_state = db.read_db().unwrap();
+            let rendered = get_home_page_epics(&db_state, SortMode::Name);
+
+            assert_eq!(rendered[0].0, &last);
+            assert!(rendered[0].1.pinned);
+
+            // Everything else keeps its normal order
+            let rest = rendered[1..]
+                .iter()
+                .map(|(id, _)| (*id).clone())
+                .collect_vec();
+            assert_eq!(rest, unpinned_order[..3].to_vec());
+        }
+
+        #[test]
+        fn sort_should_cycle_on_s() {
+            let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+            let page = HomePage::new(db);
+
+            assert_eq!(page.sort.get(), SortMode::Name);
+            assert_eq!(page.handle_input("s").unwrap(), None);
+            assert_eq!(page.sort.get(), SortMode::Status);
+            page.handle_input("s").unwrap();
+            assert_eq!(page.sort.get(), SortMode::Updated);
+        }
+    }
+
+    mod epic_detail_page {
+        use super::*;
+
+        #[test]
+        fn timestamps_line_should_skip_missing_timestamps() {
+            let timestamp = crate::db::test_utils::fixed_time();
+
+            assert_eq!(get_timestamps_line(None, None, &Tz::UTC), None);
+            assert_eq!(
+                get_timestamps_line(Some(&timestamp), None, &Tz::Europe__Madrid).unwrap(),
+                "Created: 2022-01-01 13:00 CET"
+            );
+            assert_eq!(
+                get_timestamps_line(Some(&timestamp), Some(&timestamp), &Tz::UTC).unwrap(),
+                "Created: 2022-01-01 12:00 UTC | Updated: 2022-01-01 12:00 UTC"
+            );
+        }
+
+        #[test]
+        fn focus_should_narrow_both_the_epic_and_the_story_lists() {
+            let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+            let mine = db
+                .create_epic(Epic::new("".to_owned(), "".to_owned()))
+                .unwrap();
+            let theirs = db
+                .create_epic(Epic::new("".to_owned(), "".to_owned()))
+                .unwrap();
+            for (epic_id, name) in [(&mine, "mine"), (&mine, "unassigned"), (&theirs, "theirs")] {
+                let story_id = db
+                    .create_story(Story::new(name.to_owned(), "".to_owned()), epic_id)
+                    .unwrap();
+                let assignee = match name {
+                    "mine" => Some("ada".to_owned()),
+                    "theirs" => Some("bob".to_owned()),
+                    _ => None,
+                };
+                db.set_stories_assignee(&[story_id], assignee).unwrap();
+            }
+            let db_state = db.read_db().unwrap();
+            let render = |focus| {
+                let mut out = Vec::new();
+                write_epic_detail(
+                    &mut out,
+                    &db_state,
+                    &mine,
+                    SortMode::Name,
+                    TableWidth::Fit(100),
+                    &DisplayConfig::default(),
+                    focus,
+                )
+                .unwrap();
+                String::from_utf8(out).unwrap()
+            };
 
-    mod home_page {
-        use super::*;
+            assert!(epic_has_assignee(&db_state, &db_state.epics[&mine], "ada"));
+            assert!(!epic_has_assignee(
+                &db_state,
+                &db_state.epics[&theirs],
+                "ada"
+            ));
+            let focused = render(Some("ada"));
+            assert!(focused.contains("mine"));
+            assert!(!focused.contains("unassigned"));
+            assert!(render(None).contains("unassigned"));
+            assert!(!render(None).contains("No stories"));
+            assert!(render(Some("cy")).contains("No stories in this epic are assigned to cy."));
+        }
 
         #[test]
-        fn draw_page_should_not_throw_error() {
-            let db = Rc::new(JiraDatabase {
-                database: Box::new(MockDB::new()),
-            });
+        fn empty_epics_should_say_so_instead_of_an_empty_table() {
+            let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+            let epic_id = db
+                .create_epic(Epic::new("".to_owned(), "".to_owned()))
+                .unwrap();
+            let render = |db_state: &DBState| {
+                let mut out = Vec::new();
+                write_epic_detail(
+                    &mut out,
+                    db_state,
+                    &epic_id,
+                    SortMode::Name,
+                    TableWidth::Fit(100),
+                    &DisplayConfig::default(),
+                    None,
+                )
+                .unwrap();
+                String::from_utf8(out).unwrap()
+            };
 
-            let page = HomePage { db };
-            assert_eq!(page.draw_page().is_ok(), true);
+            let empty = render(&db.read_db().unwrap());
+            db.create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+                .unwrap();
+            let with_story = render(&db.read_db().unwrap());
+
+            assert!(empty.contains("No stories in this epic yet."));
+            assert!(!with_story.contains("No stories"));
         }
 
         #[test]
-        fn handle_input_should_not_throw_error() {
-            let db = Rc::new(JiraDatabase {
-                database: Box::new(MockDB::new()),
-            });
+        fn home_page_should_tell_an_empty_database_from_an_empty_filter() {
+            let mut db_state = DBState {
+                last_item_id: "0".to_owned(),
+                epics: HashMap::new(),
+                stories: HashMap::new(),
+                revision: 0,
+            };
 
-            let page = HomePage { db };
-            assert_eq!(page.handle_input("").is_ok(), true);
+            assert_eq!(
+                get_home_empty_message(&db_state, 0),
+                Some("No epics yet — press [c] to create one.")
+            );
+            db_state
+                .epics
+                .insert("1".to_owned(), Epic::new("".to_owned(), "".to_owned()));
+            assert_eq!(
+                get_home_empty_message(&db_state, 0),
+                Some("No epics match the current filter, focus or [e] setting.")
+            );
+            assert_eq!(get_home_empty_message(&db_state, 1), None);
         }
 
         #[test]
-        fn handle_input_should_return_the_correct_actions() {
-            let db = Rc::new(JiraDatabase {
-                database: Box::new(MockDB::new()),
-            });
+        fn story_tree_should_list_subtasks_under_their_parent() {
+            let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+            let epic_id = db
+                .create_epic(Epic::new("".to_owned(), "".to_owned()))
+                .unwrap();
+            let mut story_ids = vec![];
+            for name in ["a", "b", "c"] {
+                story_ids.push(
+                    db.create_story(Story::new(name.to_owned(), "".to_owned()), &epic_id)
+                        .unwrap(),
+                );
+            }
+            // c becomes a subtask of a
+            db.set_story_parent(&epic_id, &story_ids[2], Some(story_ids[0].clone()))
+                .unwrap();
 
-            let epic = Epic::new("".to_owned(), "".to_owned());
+            let db_state = db.read_db().unwrap();
+            let epic = db_state.epics.get(&epic_id).unwrap();
+            let tree = get_story_tree(&db_state, epic, SortMode::Name)
+                .into_iter()
+                .map(|(depth, _, story)| (depth, story.name.as_str()))
+                .collect_vec();
 
-            let epic_id = db.create_epic(epic).unwrap();
+            assert_eq!(tree, vec![(0, "a"), (1, "c"), (0, "b")]);
+        }
 
-            let page = HomePage { db };
+        // The previous approach, kept to check the grouped tree against: it scans every
+        // story of the epic for the children of each story
+        fn get_story_tree_by_scanning<'a>(
+            db_state: &'a DBState,
+            epic: &'a Epic,
+            sort: SortMode,
+        ) -> Vec<(usize, &'a String, &'a Story)> {
+            let stories = epic
+                .stories
+                .iter()
+                .filter_map(|id| db_state.stories.get(id).map(|story| (id, story)))
+                .collect_vec();
+            let stories = sort_stories(stories, sort);
+            let mut tree: Vec<(usize, &String, &Story)> = Vec::new();
+            let mut stack = stories
+                .iter()
+                .rev()
+                .filter(|(_, story)| {
+                    !story
+                        .parent
+                        .as_ref()
+                        .is_some_and(|parent_id| epic.stories.contains(parent_id))
+                })
+                .map(|(id, story)| (0, *id, *story))
+                .collect_vec();
+            while let Some((depth, id, story)) = stack.pop() {
+                if tree.iter().any(|(_, seen, _)| *seen == id) {
+                    continue;
+                }
+                tree.push((depth, id, story));
+                stack.extend(
+                    stories
+                        .iter()
+                        .rev()
+                        .filter(|(_, child)| child.parent.as_ref() == Some(id))
+                        .map(|(child_id, child)| (depth + 1, *child_id, *child)),
+                );
+            }
+            tree
+        }
 
-            let q = "q";
-            let c = "c";
-            let invalid_epic_id = "999";
-            let junk_input = "j983f2j";
+        #[test]
+        fn story_tree_should_scale_with_the_epic_not_the_database() {
+            // A large database where one epic owns a few stories and another owns thousands,
+            // each story with two subtasks
+            let mut stories = HashMap::new();
+            for index in 0..100_000 {
+                stories.insert(
+                    format!("other{}", index),
+                    Story::new(format!("other {}", index), "".to_owned()),
+                );
+            }
+            let mut big = Epic::new("big".to_owned(), "".to_owned());
+            for index in 0..3_000 {
+                let parent = (index % 3 != 0).then(|| format!("big{}", index - index % 3));
+                stories.insert(
+                    format!("big{}", index),
+                    Story {
+                        parent,
+                        ..Story::new(format!("story {:05}", index), "".to_owned())
+                    },
+                );
+                big.stories.push(format!("big{}", index));
+            }
+            let mut small = Epic::new("small".to_owned(), "".to_owned());
+            small.stories = vec!["other7".to_owned(), "gone".to_owned(), "other3".to_owned()];
+            let db_state = DBState {
+                last_item_id: "0".to_owned(),
+                epics: HashMap::from([
+                    ("1".to_owned(), big.clone()),
+                    ("2".to_owned(), small.clone()),
+                ]),
+                stories,
+                revision: 0,
+            };
 
-            assert_eq!(page.handle_input(q).unwrap(), Some(Action::Exit));
-            assert_eq!(page.handle_input(c).unwrap(), Some(Action::CreateEpic));
+            let small_tree = get_story_tree(&db_state, &small, SortMode::Name);
+            let started = std::time::Instant::now();
+            let big_tree = get_story_tree(&db_state, &big, SortMode::Name);
+            let grouped = started.elapsed();
+            let started = std::time::Instant::now();
+            let scanned = get_story_tree_by_scanning(&db_state, &big, SortMode::Name);
+            let scanning = started.elapsed();
+
+            // Dangling ids are skipped
             assert_eq!(
-                page.handle_input(&epic_id).unwrap(),
-                Some(Action::NavigateToEpicDetail {
-                    epic_id: epic_id.clone()
-                })
+                small_tree
+                    .iter()
+                    .map(|(_, id, _)| id.as_str())
+                    .collect_vec(),
+                ["other3", "other7"]
+            );
+            assert_eq!(big_tree, scanned);
+            assert_eq!(big_tree.len(), 3_000);
+            assert_eq!(big_tree[1].0, 1);
+            eprintln!(
+                "story tree of 3000: grouped {:?}, scanned {:?}",
+                grouped, scanning
             );
-            assert_eq!(page.handle_input(invalid_epic_id).unwrap(), None);
-            assert_eq!(page.handle_input(junk_input).unwrap(), None);
         }
-    }
-
-    mod epic_detail_page {
-        use super::*;
 
         #[test]
         fn draw_page_should_not_throw_error() {
-            let db = Rc::new(JiraDatabase {
-                database: Box::new(MockDB::new()),
-            });
+            let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
             let epic_id = db
                 .create_epic(Epic::new("".to_owned(), "".to_owned()))
                 .unwrap();
 
-            let page = EpicDetail { epic_id, db };
+            let page = EpicDetail::new(epic_id, db, Rc::new(DisplayConfig::default()));
             assert_eq!(page.draw_page().is_ok(), true);
         }
 
         #[test]
         fn handle_input_should_not_throw_error() {
-            let db = Rc::new(JiraDatabase {
-                database: Box::new(MockDB::new()),
-            });
+            let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
             let epic_id = db
                 .create_epic(Epic::new("".to_owned(), "".to_owned()))
                 .unwrap();
 
-            let page = EpicDetail { epic_id, db };
+            let page = EpicDetail::new(epic_id, db, Rc::new(DisplayConfig::default()));
             assert_eq!(page.handle_input("").is_ok(), true);
         }
 
         #[test]
         fn draw_page_should_throw_error_for_invalid_epic_id() {
-            let db = Rc::new(JiraDatabase {
-                database: Box::new(MockDB::new()),
-            });
+            let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
 
-            let page = EpicDetail {
-                epic_id: "999".to_owned(),
-                db,
-            };
+            let page = EpicDetail::new("999".to_owned(), db, Rc::new(DisplayConfig::default()));
             assert_eq!(page.draw_page().is_err(), true);
         }
 
         #[test]
         fn handle_input_should_return_the_correct_actions() {
-            let db = Rc::new(JiraDatabase {
-                database: Box::new(MockDB::new()),
-            });
+            let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
 
             let epic_id = db
                 .create_epic(Epic::new("".to_owned(), "".to_owned()))
@@ -328,15 +1812,13 @@ mod tests {
                 .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
                 .unwrap();
 
-            let page = EpicDetail {
-                epic_id: epic_id.clone(),
-                db,
-            };
+            let page = EpicDetail::new(epic_id.clone(), db, Rc::new(DisplayConfig::default()));
 
             let p = "p";
             let u = "u";
             let d = "d";
             let c = "c";
+            let m = "m";
             let invalid_story_id = "999";
             let junk_input = "j983f2j";
 
@@ -344,6 +1826,7 @@ mod tests {
                 page.handle_input(p).unwrap(),
                 Some(Action::NavigateToPreviousPage)
             );
+            assert_eq!(page.handle_input("R").unwrap(), Some(Action::Refresh));
             assert_eq!(
                 page.handle_input(u).unwrap(),
                 Some(Action::UpdateEpicStatus {
@@ -362,6 +1845,24 @@ mod tests {
                     epic_id: epic_id.clone()
                 })
             );
+            assert_eq!(
+                page.handle_input("C").unwrap(),
+                Some(Action::QuickAddStories {
+                    epic_id: epic_id.clone()
+                })
+            );
+            assert_eq!(
+                page.handle_input("!").unwrap(),
+                Some(Action::NavigateToRawJson {
+                    epic_id: Some(epic_id.clone())
+                })
+            );
+            assert_eq!(
+                page.handle_input(m).unwrap(),
+                Some(Action::SelectStories {
+                    epic_id: epic_id.clone()
+                })
+            );
             assert_eq!(
                 page.handle_input(&story_id.to_string()).unwrap(),
                 Some(Action::NavigateToStoryDetail {
@@ -369,6 +1870,10 @@ mod tests {
                     story_id: story_id.clone()
                 })
             );
+            assert_eq!(
+                page.handle_input("2p").unwrap(),
+                Some(Action::NavigateBack { count: 2 })
+            );
             assert_eq!(page.handle_input(invalid_story_id).unwrap(), None);
             assert_eq!(page.handle_input(junk_input).unwrap(), None);
         }
@@ -377,11 +1882,36 @@ mod tests {
     mod story_detail_page {
         use super::*;
 
+        #[test]
+        fn write_story_detail_should_show_why_a_story_is_blocked() {
+            let db = JiraDatabase::with_database(Box::new(MockDB::new()));
+            let epic_id = db
+                .create_epic(Epic::new("".to_owned(), "".to_owned()))
+                .unwrap();
+            let story_id = db
+                .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+                .unwrap();
+            db.block_story(&story_id, Some("waiting on legal".to_owned()))
+                .unwrap();
+            let mut out = Vec::new();
+
+            write_story_detail(
+                &mut out,
+                &db.read_db().unwrap(),
+                &story_id,
+                TableWidth::Fit(80),
+                &DisplayConfig::default(),
+            )
+            .unwrap();
+
+            assert!(String::from_utf8(out)
+                .unwrap()
+                .contains("BLOCKED: waiting on legal"));
+        }
+
         #[test]
         fn draw_page_should_not_throw_error() {
-            let db = Rc::new(JiraDatabase {
-                database: Box::new(MockDB::new()),
-            });
+            let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
 
             let epic_id = db
                 .create_epic(Epic::new("".to_owned(), "".to_owned()))
@@ -394,15 +1924,14 @@ mod tests {
                 epic_id,
                 story_id,
                 db,
+                display: Rc::new(DisplayConfig::default()),
             };
             assert_eq!(page.draw_page().is_ok(), true);
         }
 
         #[test]
         fn handle_input_should_not_throw_error() {
-            let db = Rc::new(JiraDatabase {
-                database: Box::new(MockDB::new()),
-            });
+            let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
 
             let epic_id = db
                 .create_epic(Epic::new("".to_owned(), "".to_owned()))
@@ -415,15 +1944,14 @@ mod tests {
                 epic_id,
                 story_id,
                 db,
+                display: Rc::new(DisplayConfig::default()),
             };
             assert_eq!(page.handle_input("").is_ok(), true);
         }
 
         #[test]
         fn draw_page_should_throw_error_for_invalid_story_id() {
-            let db = Rc::new(JiraDatabase {
-                database: Box::new(MockDB::new()),
-            });
+            let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
 
             let epic_id = db
                 .create_epic(Epic::new("".to_owned(), "".to_owned()))
@@ -436,15 +1964,14 @@ mod tests {
                 epic_id,
                 story_id: "999".to_owned(),
                 db,
+                display: Rc::new(DisplayConfig::default()),
             };
             assert_eq!(page.draw_page().is_err(), true);
         }
 
         #[test]
         fn handle_input_should_return_the_correct_actions() {
-            let db = Rc::new(JiraDatabase {
-                database: Box::new(MockDB::new()),
-            });
+            let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
 
             let epic_id = db
                 .create_epic(Epic::new("".to_owned(), "".to_owned()))
@@ -452,35 +1979,338 @@ mod tests {
             let story_id = db
                 .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
                 .unwrap();
+            // The attachment keys are only offered once there is something attached
+            db.add_attachment(
+                &story_id,
+                crate::models::Attachment {
+                    label: "spec".to_owned(),
+                    target: "spec.md".to_owned(),
+                },
+            )
+            .unwrap();
+            db.add_checklist_item(&story_id, "tests pass".to_owned())
+                .unwrap();
 
             let page = StoryDetail {
                 epic_id: epic_id.to_owned(),
                 story_id: story_id.clone(),
                 db,
+                display: Rc::new(DisplayConfig::default()),
             };
 
             let p = "p";
             let u = "u";
             let d = "d";
-            let some_number = "1";
+            // 1 to 5 set the status
+            let some_number = "9";
             let junk_input = "j983f2j";
 
             assert_eq!(
                 page.handle_input(p).unwrap(),
                 Some(Action::NavigateToPreviousPage)
             );
+            assert_eq!(page.handle_input("R").unwrap(), Some(Action::Refresh));
             assert_eq!(
                 page.handle_input(u).unwrap(),
                 Some(Action::UpdateStoryStatus {
                     story_id: story_id.clone()
                 })
             );
+            assert_eq!(
+                page.handle_input("l").unwrap(),
+                Some(Action::SetStoryParent {
+                    epic_id: epic_id.clone(),
+                    story_id: story_id.clone()
+                })
+            );
+            assert_eq!(
+                page.handle_input("+").unwrap(),
+                Some(Action::AddAttachment {
+                    story_id: story_id.clone()
+                })
+            );
+            assert_eq!(
+                page.handle_input("o2").unwrap(),
+                Some(Action::OpenAttachment {
+                    story_id: story_id.clone(),
+                    index: 1
+                })
+            );
+            assert_eq!(
+                page.handle_input("x1").unwrap(),
+                Some(Action::RemoveAttachment {
+                    story_id: story_id.clone(),
+                    index: 0
+                })
+            );
+            assert_eq!(
+                page.handle_input("k").unwrap(),
+                Some(Action::AddChecklistItem {
+                    story_id: story_id.clone()
+                })
+            );
+            assert_eq!(
+                page.handle_input("t1").unwrap(),
+                Some(Action::ToggleChecklistItem {
+                    story_id: story_id.clone(),
+                    index: 0
+                })
+            );
             assert_eq!(
                 page.handle_input(d).unwrap(),
                 Some(Action::DeleteStory { epic_id, story_id })
             );
+            assert_eq!(
+                page.handle_input("3p").unwrap(),
+                Some(Action::NavigateBack { count: 3 })
+            );
             assert_eq!(page.handle_input(some_number).unwrap(), None);
             assert_eq!(page.handle_input(junk_input).unwrap(), None);
         }
+
+        #[test]
+        fn number_keys_should_set_the_status_directly() {
+            let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+            let epic_id = db
+                .create_epic(Epic::new("".to_owned(), "".to_owned()))
+                .unwrap();
+            let story_id = db
+                .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+                .unwrap();
+            let page = StoryDetail {
+                epic_id,
+                story_id: story_id.clone(),
+                db,
+                display: Rc::new(DisplayConfig::default()),
+            };
+
+            for (key, status) in [
+                ("1", Status::Open),
+                ("2", Status::InProgress),
+                ("3", Status::Resolved),
+                ("4", Status::Closed),
+                ("5", Status::Blocked),
+            ] {
+                assert_eq!(
+                    page.handle_input(key).unwrap(),
+                    Some(Action::SetStoryStatus {
+                        story_id: story_id.clone(),
+                        status
+                    })
+                );
+            }
+        }
+
+        #[test]
+        fn reopen_should_only_be_offered_for_finished_stories() {
+            let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+            let epic_id = db
+                .create_epic(Epic::new("".to_owned(), "".to_owned()))
+                .unwrap();
+            let story_id = db
+                .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+                .unwrap();
+            let page = StoryDetail {
+                epic_id,
+                story_id: story_id.clone(),
+                db: Rc::clone(&db),
+                display: Rc::new(DisplayConfig::default()),
+            };
+
+            assert_eq!(page.handle_input("r").unwrap(), None);
+
+            walk_story_to(&db, &story_id, Status::Resolved).unwrap();
+
+            assert_eq!(
+                page.handle_input("r").unwrap(),
+                Some(Action::ReopenStory { story_id })
+            );
+        }
+    }
+    mod raw_json_page {
+        use super::*;
+
+        #[test]
+        fn draw_page_should_not_throw_error() {
+            let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+            let epic_id = db
+                .create_epic(Epic::new("".to_owned(), "".to_owned()))
+                .unwrap();
+
+            let page = RawJsonPage {
+                epic_id: None,
+                db: Rc::clone(&db),
+            };
+            assert!(page.draw_page().is_ok());
+
+            let page = RawJsonPage {
+                epic_id: Some(epic_id),
+                db,
+            };
+            assert!(page.draw_page().is_ok());
+        }
+
+        #[test]
+        fn draw_page_should_throw_error_for_invalid_epic_id() {
+            let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+
+            let page = RawJsonPage {
+                epic_id: Some("999".to_owned()),
+                db,
+            };
+            assert!(page.draw_page().is_err());
+        }
+
+        #[test]
+        fn handle_input_should_return_the_correct_actions() {
+            let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+
+            let page = RawJsonPage { epic_id: None, db };
+
+            assert_eq!(
+                page.handle_input("p").unwrap(),
+                Some(Action::NavigateToPreviousPage)
+            );
+            assert_eq!(page.handle_input("R").unwrap(), Some(Action::Refresh));
+            assert_eq!(page.handle_input("u").unwrap(), None);
+            assert_eq!(page.handle_input("j983f2j").unwrap(), None);
+        }
+    }
+
+    mod search_results_page {
+        use super::*;
+
+        #[test]
+        fn draw_page_should_not_throw_error() {
+            let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+            db.create_epic(Epic::new("Login".to_owned(), "".to_owned()))
+                .unwrap();
+
+            let page = SearchResultsPage {
+                query: "log".to_owned(),
+                db,
+                display: Rc::new(DisplayConfig::default()),
+            };
+            assert!(page.draw_page().is_ok());
+        }
+
+        #[test]
+        fn handle_input_should_return_the_correct_actions() {
+            let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+            let epic_id = db
+                .create_epic(Epic::new("Login".to_owned(), "".to_owned()))
+                .unwrap();
+            let story_id = db
+                .create_story(Story::new("Logout".to_owned(), "".to_owned()), &epic_id)
+                .unwrap();
+            let other_id = db
+                .create_story(Story::new("Billing".to_owned(), "".to_owned()), &epic_id)
+                .unwrap();
+
+            let page = SearchResultsPage {
+                query: "log".to_owned(),
+                db,
+                display: Rc::new(DisplayConfig::default()),
+            };
+
+            assert_eq!(
+                page.handle_input("p").unwrap(),
+                Some(Action::NavigateToPreviousPage)
+            );
+            assert_eq!(page.handle_input("R").unwrap(), Some(Action::Refresh));
+            assert_eq!(
+                page.handle_input(&epic_id).unwrap(),
+                Some(Action::NavigateToEpicDetail {
+                    epic_id: epic_id.clone()
+                })
+            );
+            assert_eq!(
+                page.handle_input(&story_id).unwrap(),
+                Some(Action::NavigateToStoryDetail { epic_id, story_id })
+            );
+            assert_eq!(page.handle_input(&other_id).unwrap(), None);
+            assert_eq!(page.handle_input("j983f2j").unwrap(), None);
+        }
+    }
+
+    mod query_results_page {
+        use super::*;
+
+        #[test]
+        fn handle_input_should_open_matching_stories_only() {
+            let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+            let epic_id = db
+                .create_epic(Epic::new("Login".to_owned(), "".to_owned()))
+                .unwrap();
+            let open_id = db
+                .create_story(Story::new("Form".to_owned(), "".to_owned()), &epic_id)
+                .unwrap();
+            let closed_id = db
+                .create_story(Story::new("Button".to_owned(), "".to_owned()), &epic_id)
+                .unwrap();
+            walk_story_to(&db, &closed_id, Status::Closed).unwrap();
+
+            let page = QueryResultsPage {
+                name: "open".to_owned(),
+                query: "status=open".to_owned(),
+                db,
+                display: Rc::new(DisplayConfig::default()),
+            };
+
+            assert!(page.draw_page().is_ok());
+            assert_eq!(
+                page.handle_input(&open_id).unwrap(),
+                Some(Action::NavigateToStoryDetail {
+                    epic_id,
+                    story_id: open_id,
+                })
+            );
+            assert_eq!(page.handle_input(&closed_id).unwrap(), None);
+        }
+    }
+
+    mod due_stories_page {
+        use super::*;
+
+        #[test]
+        fn week_bounds_should_run_monday_to_sunday() {
+            let day = |day| NaiveDate::from_ymd_opt(2022, 1, day).unwrap();
+
+            // 2022-01-10 is a Monday
+            assert_eq!(get_week_bounds(day(10)), (day(10), day(16)));
+            assert_eq!(get_week_bounds(day(13)), (day(10), day(16)));
+            assert_eq!(get_week_bounds(day(16)), (day(10), day(16)));
+        }
+
+        #[test]
+        fn handle_input_should_open_listed_stories_only() {
+            let db = Rc::new(JiraDatabase::with_database(Box::new(MockDB::new())));
+            let day = |day| NaiveDate::from_ymd_opt(2022, 1, day).unwrap();
+            let epic_id = db
+                .create_epic(Epic::new("".to_owned(), "".to_owned()))
+                .unwrap();
+            let story_id = db
+                .create_story(Story::new("".to_owned(), "".to_owned()), &epic_id)
+                .unwrap();
+            db.set_story_due_date(&story_id, Some(day(12))).unwrap();
+
+            let page = DueStoriesPage {
+                start: day(10),
+                end: day(16),
+                db,
+                display: Rc::new(DisplayConfig::default()),
+            };
+
+            assert!(page.draw_page().is_ok());
+            assert_eq!(
+                page.handle_input(&story_id).unwrap(),
+                Some(Action::NavigateToStoryDetail { epic_id, story_id })
+            );
+            assert_eq!(page.handle_input("999").unwrap(), None);
+            assert_eq!(
+                page.handle_input("p").unwrap(),
+                Some(Action::NavigateToPreviousPage)
+            );
+        }
     }
 }
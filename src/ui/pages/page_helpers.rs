@@ -1,74 +1,589 @@
-use ellipse::Ellipse;
+use crossterm::style::Color;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{self, Write};
 
-pub fn get_column_string(text: &str, width: usize) -> String {
+use crate::ui::paint;
+
+// What marks a cell cut short to fit its column, configured as truncation
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Truncation {
+    // "..."
+    #[default]
+    Dots,
+    // "…", a single character
+    Ellipsis,
+    // Cut without a mark
+    None,
+}
+
+impl Truncation {
+    pub fn get_indicator(&self) -> &'static str {
+        match self {
+            Truncation::Dots => "...",
+            Truncation::Ellipsis => "…",
+            Truncation::None => "",
+        }
+    }
+}
+
+pub fn get_column_string(text: &str, width: usize, truncation: Truncation) -> String {
     // If string is empty, return a padded string of the given width
     if text.is_empty() {
         return " ".repeat(width);
     }
+    // Counted in characters, like the widths and the padding below
+    let length = text.chars().count();
     // If length and with are the same, return the string
-    if text.len() == width {
+    if length == width {
         return text.to_string();
     }
-    // If width is between 1 and 3, return the same number of dots.
-    if width < 4 {
-        return ".".repeat(width);
-    }
     // If width is larger than the string, return the string padded with spaces
-    if text.len() < width {
+    if length < width {
         return format!("{:width$}", text, width = width);
     }
-    // Get string length
-    let truncated_string = text.truncate_ellipse(width - 3).to_string();
-    // Return string
-    return truncated_string;
+    // If the indicator alone fills the width, return as much of it as fits
+    let indicator = truncation.get_indicator();
+    let reserved = indicator.chars().count();
+    if width <= reserved {
+        return indicator.chars().take(width).collect();
+    }
+    // Keep what fits next to the indicator
+    let kept = text.chars().take(width - reserved).collect::<String>();
+    format!("{}{}", kept, indicator)
+}
+
+pub struct ColumnSpec {
+    pub header: &'static str,
+    // Share of the usable width, in percent
+    pub percent: usize,
+    // The flex column absorbs whatever rounding leaves over
+    pub flex: bool,
+}
+
+pub fn get_terminal_width() -> usize {
+    // Fall back to a classic 80 column terminal when the size can't be read
+    crossterm::terminal::size()
+        .map(|(columns, _)| columns as usize)
+        .unwrap_or(80)
+}
+
+pub fn resolve_column_widths(columns: &[ColumnSpec], total_width: usize) -> Vec<usize> {
+    if columns.is_empty() {
+        return Vec::new();
+    }
+    // Rows render as " a | b | c ", so take out the padding and separators
+    let usable_width = total_width.saturating_sub(2 + 3 * (columns.len() - 1));
+    // Round every column down so the sum never overflows
+    let mut widths = columns
+        .iter()
+        .map(|column| usable_width * column.percent / 100)
+        .collect::<Vec<usize>>();
+    // Hand the leftover to the flex column, or the last one if none is flagged
+    let flex = columns
+        .iter()
+        .position(|column| column.flex)
+        .unwrap_or(columns.len() - 1);
+    let used: usize = widths.iter().sum();
+    if used <= usable_width {
+        widths[flex] += usable_width - used;
+    }
+    widths
+}
+
+pub fn get_table_header(columns: &[ColumnSpec], widths: &[usize]) -> String {
+    let cells = columns
+        .iter()
+        .zip(widths)
+        .map(|(column, width)| format!("{:^width$}", column.header, width = width))
+        .collect::<Vec<String>>();
+    format!(" {} ", cells.join(" | "))
+}
+
+pub fn get_table_row(cells: &[&str], widths: &[usize], truncation: Truncation) -> String {
+    let cells = cells
+        .iter()
+        .zip(widths)
+        .map(|(cell, width)| get_column_string(cell, *width, truncation))
+        .collect::<Vec<String>>();
+    format!(" {} ", cells.join(" | "))
+}
+
+pub fn get_table_title(title: &str, total_width: usize) -> String {
+    format!("{:-^width$}", format!(" {} ", title), width = total_width)
+}
+
+// One table row. The marker takes the leading gutter, e.g. `*` for pinned epics,
+// and the color paints the whole row.
+pub struct TableRow {
+    cells: Vec<String>,
+    marker: char,
+    color: Option<Color>,
+}
+
+impl TableRow {
+    pub fn new(cells: &[&str]) -> Self {
+        Self {
+            cells: cells.iter().map(|cell| cell.to_string()).collect(),
+            marker: ' ',
+            color: None,
+        }
+    }
+
+    pub fn marker(mut self, marker: char) -> Self {
+        self.marker = marker;
+        self
+    }
+
+    pub fn color(mut self, color: Option<Color>) -> Self {
+        self.color = color;
+        self
+    }
+}
+
+// How much room a table gets. Fit squeezes it into that many columns, truncating long
+// cells. Full sizes every column to its longest cell, e.g. for piping complete data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableWidth {
+    Fit(usize),
+    Full,
+}
+
+// Lays out a titled table so every page aligns its columns the same way.
+// Pages describe their rows and leave the widths and padding to render.
+pub struct Table<'a> {
+    title: &'a str,
+    columns: &'a [ColumnSpec],
+    rows: Vec<TableRow>,
+    truncation: Truncation,
+}
+
+impl<'a> Table<'a> {
+    pub fn new(title: &'a str, columns: &'a [ColumnSpec]) -> Self {
+        Self {
+            title,
+            columns,
+            rows: Vec::new(),
+            truncation: Truncation::default(),
+        }
+    }
+
+    pub fn truncation(mut self, truncation: Truncation) -> Self {
+        self.truncation = truncation;
+        self
+    }
+
+    pub fn push(&mut self, row: TableRow) {
+        self.rows.push(row);
+    }
+
+    // Writes the title, header and rows sized to `width`
+    pub fn render(&self, out: &mut dyn Write, width: TableWidth) -> io::Result<()> {
+        let (widths, width) = match width {
+            TableWidth::Fit(width) => (resolve_column_widths(self.columns, width), width),
+            TableWidth::Full => {
+                let widths = self.get_full_widths();
+                // The same padding and separators resolve_column_widths takes out
+                let width = widths.iter().sum::<usize>() + 2 + 3 * widths.len().saturating_sub(1);
+                (widths, width)
+            }
+        };
+
+        writeln!(out, "{}", get_table_title(self.title, width))?;
+        writeln!(out, "{}", get_table_header(self.columns, &widths))?;
+
+        for row in &self.rows {
+            let cells = row.cells.iter().map(String::as_str).collect::<Vec<&str>>();
+            let line = get_table_row(&cells, &widths, self.truncation);
+            let line = format!("{}{}", row.marker, &line[1..]);
+            writeln!(out, "{}", paint(&line, row.color))?;
+        }
+
+        Ok(())
+    }
+
+    // Each column as wide as its header or longest cell, so nothing is cut
+    fn get_full_widths(&self) -> Vec<usize> {
+        self.columns
+            .iter()
+            .enumerate()
+            .map(|(index, column)| {
+                self.rows
+                    .iter()
+                    .filter_map(|row| row.cells.get(index))
+                    .map(|cell| cell.chars().count())
+                    .chain([column.header.chars().count()])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect()
+    }
+}
+
+// Parses a repeat-prefixed back command like "3p" into the number of pages to pop
+pub fn parse_navigate_back(input: &str) -> Option<usize> {
+    let count = input.strip_suffix('p')?;
+    if count.is_empty() || !count.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    count.parse::<usize>().ok().filter(|count| *count > 0)
+}
+
+// Parses commands like "o2" into a zero-based index for the numbered item they target
+pub fn parse_indexed_command(input: &str, prefix: char) -> Option<usize> {
+    let number = input.strip_prefix(prefix)?;
+    if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    number.parse::<usize>().ok()?.checked_sub(1)
+}
+
+// The first `length` characters of an id, or the whole id when no length is set
+pub fn get_short_id(id: &str, length: Option<usize>) -> &str {
+    match length {
+        Some(length) => id
+            .char_indices()
+            .nth(length)
+            .map_or(id, |(end, _)| &id[..end]),
+        None => id,
+    }
+}
+
+// Short ids shared by more than one of `ids`, sorted, so the page can warn about them
+pub fn get_short_id_collisions<'a>(
+    ids: impl IntoIterator<Item = &'a String>,
+    length: Option<usize>,
+) -> Vec<String> {
+    let mut seen = HashMap::new();
+    for id in ids {
+        *seen.entry(get_short_id(id, length)).or_insert(0) += 1;
+    }
+    let mut collisions = seen
+        .into_iter()
+        .filter(|(_, count)| *count > 1)
+        .map(|(short_id, _)| short_id.to_owned())
+        .collect::<Vec<_>>();
+    collisions.sort();
+    collisions
+}
+
+// Returns the first `max_lines` lines and how many were left out
+pub fn get_truncated_lines(text: &str, max_lines: usize) -> (String, usize) {
+    let lines = text.lines().collect::<Vec<&str>>();
+    let hidden = lines.len().saturating_sub(max_lines);
+    (lines[..lines.len() - hidden].join("\n"), hidden)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn parse_navigate_back_should_read_count_prefix() {
+        assert_eq!(parse_navigate_back("3p"), Some(3));
+        assert_eq!(parse_navigate_back("12p"), Some(12));
+    }
+
+    #[test]
+    fn parse_navigate_back_should_reject_other_input() {
+        assert_eq!(parse_navigate_back("p"), None);
+        assert_eq!(parse_navigate_back("0p"), None);
+        assert_eq!(parse_navigate_back("-1p"), None);
+        assert_eq!(parse_navigate_back("3q"), None);
+        assert_eq!(parse_navigate_back("a3p"), None);
+    }
+
+    #[test]
+    fn parse_indexed_command_should_read_one_based_numbers() {
+        assert_eq!(parse_indexed_command("o1", 'o'), Some(0));
+        assert_eq!(parse_indexed_command("x12", 'x'), Some(11));
+        assert_eq!(parse_indexed_command("o0", 'o'), None);
+        assert_eq!(parse_indexed_command("o", 'o'), None);
+        assert_eq!(parse_indexed_command("x1", 'o'), None);
+        assert_eq!(parse_indexed_command("o1a", 'o'), None);
+    }
+
+    #[test]
+    fn get_short_id_should_keep_the_leading_characters() {
+        assert_eq!(get_short_id("abc123", Some(4)), "abc1");
+        assert_eq!(get_short_id("abc123", Some(10)), "abc123");
+        assert_eq!(get_short_id("abc123", None), "abc123");
+    }
+
+    #[test]
+    fn get_short_id_collisions_should_find_shared_prefixes() {
+        let ids = ["abc123", "abc456", "xyz789", "xyz000", "qrs111"].map(str::to_owned);
+
+        assert_eq!(
+            get_short_id_collisions(&ids, Some(3)),
+            vec!["abc".to_owned(), "xyz".to_owned()]
+        );
+        assert!(get_short_id_collisions(&ids, Some(4)).is_empty());
+        assert!(get_short_id_collisions(&ids, None).is_empty());
+    }
+
+    #[test]
+    fn get_truncated_lines_should_keep_short_text() {
+        assert_eq!(get_truncated_lines("a\nb", 3), ("a\nb".to_owned(), 0));
+    }
+
+    #[test]
+    fn get_truncated_lines_should_cut_long_text() {
+        assert_eq!(get_truncated_lines("a\nb\nc\nd", 2), ("a\nb".to_owned(), 2));
+    }
+
     #[test]
     fn get_column_width_0_is_empty_string() {
-        assert_eq!(get_column_string("thisisatest", 0), "");
+        assert_eq!(get_column_string("thisisatest", 0, Truncation::Dots), "");
     }
 
     #[test]
     fn get_column_width_1_is_single_dot_string() {
-        assert_eq!(get_column_string("thisisatest", 1), ".");
+        assert_eq!(get_column_string("thisisatest", 1, Truncation::Dots), ".");
     }
 
     #[test]
     fn get_column_width_2_is_double_dot_string() {
-        assert_eq!(get_column_string("thisisatest", 2), "..");
+        assert_eq!(get_column_string("thisisatest", 2, Truncation::Dots), "..");
     }
 
     #[test]
     fn get_column_width_3_is_triple_dot_string() {
-        assert_eq!(get_column_string("thisisatest", 3), "...");
+        assert_eq!(get_column_string("thisisatest", 3, Truncation::Dots), "...");
+    }
+
+    #[test]
+    fn get_column_width_2_pads_shorter_string_instead_of_dots() {
+        assert_eq!(get_column_string("1", 2, Truncation::Dots), "1 ");
     }
 
     #[test]
     fn get_column_width_4_is_first_char_with_triple_dot_string() {
-        assert_eq!(get_column_string("thisisatest", 4), "t...");
+        assert_eq!(
+            get_column_string("thisisatest", 4, Truncation::Dots),
+            "t..."
+        );
     }
 
     #[test]
     fn get_column_width_6_pads_empty_string_to_six_white_spaces() {
-        assert_eq!(get_column_string("", 6), "      ");
+        assert_eq!(get_column_string("", 6, Truncation::Dots), "      ");
     }
 
     #[test]
     fn get_column_width_6_pads_smaller_string_with_white_spaces() {
-        assert_eq!(get_column_string("this", 6), "this  ");
+        assert_eq!(get_column_string("this", 6, Truncation::Dots), "this  ");
     }
 
     #[test]
     fn get_column_string_returns_same_string_if_length_and_width_are_equal() {
-        assert_eq!(get_column_string("thisisatest", 11), "thisisatest");
+        assert_eq!(
+            get_column_string("thisisatest", 11, Truncation::Dots),
+            "thisisatest"
+        );
     }
 
     #[test]
     fn get_column_string_6_truncates_longer_string_with_ellipse() {
-        assert_eq!(get_column_string("thisisatest", 6), "thi...");
+        assert_eq!(
+            get_column_string("thisisatest", 6, Truncation::Dots),
+            "thi..."
+        );
+    }
+
+    #[test]
+    fn get_column_string_should_reserve_room_for_each_indicator() {
+        let text = "thisisatest";
+
+        assert_eq!(get_column_string(text, 6, Truncation::Dots), "thi...");
+        assert_eq!(get_column_string(text, 6, Truncation::Ellipsis), "thisi…");
+        assert_eq!(get_column_string(text, 6, Truncation::None), "thisis");
+        for truncation in [Truncation::Dots, Truncation::Ellipsis, Truncation::None] {
+            for width in 0..=12 {
+                let column = get_column_string(text, width, truncation);
+                assert_eq!(column.chars().count(), width);
+            }
+        }
+    }
+
+    #[test]
+    fn get_column_string_should_count_accented_characters_once() {
+        // 12 characters, 15 bytes
+        let text = "Café résumé!";
+
+        assert_eq!(get_column_string(text, 12, Truncation::Dots), text);
+        assert_eq!(
+            get_column_string(text, 14, Truncation::Dots),
+            "Café résumé!  "
+        );
+        assert_eq!(get_column_string(text, 8, Truncation::Dots), "Café ...");
+        assert_eq!(get_column_string(text, 4, Truncation::None), "Café");
+    }
+
+    #[test]
+    fn get_column_string_should_never_split_cjk_characters() {
+        let text = "東京タワーの改修";
+
+        for width in 0..=10 {
+            for truncation in [Truncation::Dots, Truncation::Ellipsis, Truncation::None] {
+                let column = get_column_string(text, width, truncation);
+                assert_eq!(column.chars().count(), width);
+            }
+        }
+        assert_eq!(get_column_string(text, 5, Truncation::Dots), "東京...");
+        assert_eq!(
+            get_column_string(text, 9, Truncation::Dots),
+            "東京タワーの改修 "
+        );
+    }
+
+    #[test]
+    fn get_column_string_should_cut_the_indicator_on_narrow_columns() {
+        assert_eq!(
+            get_column_string("thisisatest", 1, Truncation::Ellipsis),
+            "…"
+        );
+        assert_eq!(get_column_string("thisisatest", 1, Truncation::None), "t");
+        assert_eq!(
+            get_column_string("thisisatest", 0, Truncation::Ellipsis),
+            ""
+        );
+    }
+
+    #[test]
+    fn table_should_truncate_with_the_configured_indicator() {
+        let mut table = Table::new("EPICS", &COLUMNS).truncation(Truncation::Ellipsis);
+        table.push(TableRow::new(&[
+            "1",
+            "a name too long for its column",
+            "OPEN",
+        ]));
+        let mut out = Vec::new();
+
+        table.render(&mut out, TableWidth::Fit(30)).unwrap();
+
+        let output = String::from_utf8(out).unwrap();
+        let row = output.lines().nth(2).unwrap();
+        assert!(row.contains("…"));
+        assert!(!row.contains("..."));
+        assert_eq!(row.chars().count(), 30);
+    }
+
+    const COLUMNS: [ColumnSpec; 3] = [
+        ColumnSpec {
+            header: "id",
+            percent: 10,
+            flex: false,
+        },
+        ColumnSpec {
+            header: "name",
+            percent: 45,
+            flex: true,
+        },
+        ColumnSpec {
+            header: "status",
+            percent: 20,
+            flex: false,
+        },
+    ];
+
+    #[test]
+    fn table_should_align_rows_of_varying_lengths() {
+        let mut table = Table::new("EPICS", &COLUMNS);
+        table.push(TableRow::new(&["1", "short", "OPEN"]));
+        table.push(TableRow::new(&["22", "a much longer name", "IN PROGRESS"]).marker('*'));
+        table.push(TableRow::new(&["333", "", "CLOSED"]));
+        let mut out = Vec::new();
+
+        table.render(&mut out, TableWidth::Fit(48)).unwrap();
+
+        let output = String::from_utf8(out).unwrap();
+        let lines = output.lines().collect::<Vec<&str>>();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0], format!("{:-^48}", " EPICS "));
+        // Every line is as wide as the table and the separators line up
+        for line in &lines[1..] {
+            assert_eq!(line.len(), 48);
+            let separators = line.match_indices('|').map(|(i, _)| i).collect::<Vec<_>>();
+            assert_eq!(separators, vec![6, 37]);
+        }
+        assert!(lines[3].starts_with("*22 "));
+        assert!(lines[3].contains("a much longer name"));
+    }
+
+    #[test]
+    fn full_width_tables_should_keep_every_cell_whole() {
+        let description = "a description far longer than any column would allow";
+        let mut table = Table::new("EPICS", &COLUMNS);
+        table.push(TableRow::new(&["1", description, "OPEN"]));
+        let mut fit = Vec::new();
+        let mut full = Vec::new();
+
+        table.render(&mut fit, TableWidth::Fit(48)).unwrap();
+        table.render(&mut full, TableWidth::Full).unwrap();
+
+        let fit = String::from_utf8(fit).unwrap();
+        let full = String::from_utf8(full).unwrap();
+        assert!(!fit.contains(description));
+        assert!(full.contains(&format!(" 1  | {} | OPEN   ", description)));
+        // Title, header and row still line up
+        let lengths = full.lines().map(str::len).collect::<Vec<_>>();
+        assert_eq!(lengths, vec![lengths[0]; 3]);
+    }
+
+    #[test]
+    fn full_width_tables_should_size_columns_in_characters() {
+        let mut table = Table::new("EPICS", &COLUMNS);
+        table.push(TableRow::new(&["1", "Café résumé", "OPEN"]));
+        table.push(TableRow::new(&["2", "plain", "OPEN"]));
+        let mut out = Vec::new();
+
+        table.render(&mut out, TableWidth::Full).unwrap();
+
+        let output = String::from_utf8(out).unwrap();
+        let lengths = output
+            .lines()
+            .map(|line| line.chars().count())
+            .collect::<Vec<_>>();
+        assert_eq!(lengths, vec![lengths[0]; 4]);
+        assert!(output.contains(" 1  | Café résumé | OPEN   "));
+    }
+
+    #[test]
+    fn resolve_column_widths_should_fill_usable_width_exactly() {
+        for total_width in [40, 80, 97, 123, 200] {
+            let widths = resolve_column_widths(&COLUMNS, total_width);
+            let usable_width = total_width - 2 - 3 * 2;
+
+            assert_eq!(widths.iter().sum::<usize>(), usable_width);
+        }
+    }
+
+    #[test]
+    fn resolve_column_widths_should_give_leftover_to_flex_column() {
+        let widths = resolve_column_widths(&COLUMNS, 80);
+
+        // 72 usable: 7 for id, 14 for status, the name takes the rest
+        assert_eq!(widths, vec![7, 51, 14]);
+    }
+
+    #[test]
+    fn resolve_column_widths_should_not_overflow_tiny_terminals() {
+        let widths = resolve_column_widths(&COLUMNS, 5);
+
+        assert_eq!(widths.iter().sum::<usize>(), 0);
+    }
+
+    #[test]
+    fn table_row_should_match_resolved_width() {
+        let widths = resolve_column_widths(&COLUMNS, 80);
+
+        assert_eq!(get_table_header(&COLUMNS, &widths).len(), 80);
+        assert_eq!(
+            get_table_row(&["abc", "name", "OPEN"], &widths, Truncation::Dots).len(),
+            80
+        );
+        assert_eq!(get_table_title("EPICS", 80).len(), 80);
     }
 }
@@ -0,0 +1,282 @@
+use crate::models::{Epic, Status, Story};
+use crate::ui::SortMode;
+
+use super::page_helpers::{parse_indexed_command, parse_navigate_back};
+
+// A key a detail page can offer. get_epic_keys and get_story_keys decide which ones
+// apply to the item as it is now, and both the footer and handle_input go through that
+// list, so a key is only ever shown when it works and works only when shown.
+#[derive(Debug, PartialEq, Clone)]
+pub enum PageKey {
+    Previous,
+    Back,
+    Refresh,
+    UpdateStatus,
+    Reopen,
+    SetOwner,
+    // 1 to 5 on stories
+    QuickStatus,
+    AssignToSelf,
+    Assign,
+    Unassign,
+    // Whether the story is flagged now, which decides the hint
+    ToggleRefinement(bool),
+    LinkParent,
+    Attach,
+    OpenAttachment,
+    RemoveAttachment,
+    AddChecklistItem,
+    ToggleChecklistItem,
+    Promote,
+    Delete,
+    CreateStory,
+    QuickAddStories,
+    SelectStories,
+    // The mode in use, shown next to the key
+    Sort(SortMode),
+    // Typing a story id, matched after every other key
+    OpenStory,
+    Focus,
+    // The assignee currently focused on
+    ClearFocus(String),
+    CopyId,
+    // [ and ], through my stories in progress
+    CycleInProgress,
+    // Debug view, left out of the footer
+    RawJson,
+}
+
+impl PageKey {
+    pub fn accepts(&self, input: &str) -> bool {
+        match self {
+            PageKey::Previous => input == "p",
+            PageKey::Back => parse_navigate_back(input).is_some(),
+            PageKey::Refresh => input == "R",
+            PageKey::UpdateStatus => input == "u",
+            PageKey::Reopen => input == "r",
+            PageKey::SetOwner => input == "o",
+            PageKey::QuickStatus => get_quick_status(input).is_some(),
+            PageKey::AssignToSelf => input == "A",
+            PageKey::Assign => input == "a",
+            PageKey::Unassign => input == "-",
+            PageKey::ToggleRefinement(_) => input == "n",
+            PageKey::LinkParent => input == "l",
+            PageKey::Attach => input == "+",
+            PageKey::OpenAttachment => parse_indexed_command(input, 'o').is_some(),
+            PageKey::RemoveAttachment => parse_indexed_command(input, 'x').is_some(),
+            PageKey::AddChecklistItem => input == "k",
+            PageKey::ToggleChecklistItem => parse_indexed_command(input, 't').is_some(),
+            PageKey::Promote => input == "E",
+            PageKey::Delete => input == "d",
+            PageKey::CreateStory => input == "c",
+            PageKey::QuickAddStories => input == "C",
+            PageKey::SelectStories => input == "m",
+            PageKey::Sort(_) => input == "s",
+            PageKey::OpenStory => false,
+            PageKey::Focus => input == "@",
+            PageKey::ClearFocus(_) => input == "F",
+            PageKey::CopyId => input == "y",
+            PageKey::CycleInProgress => input == "[" || input == "]",
+            PageKey::RawJson => input == "!",
+        }
+    }
+
+    pub fn get_hint(&self) -> Option<String> {
+        let hint = match self {
+            PageKey::Previous => "[p] previous".to_owned(),
+            PageKey::Back => "[:n:p] back n pages".to_owned(),
+            PageKey::Refresh => "[R] refresh".to_owned(),
+            PageKey::UpdateStatus => "[u] update status".to_owned(),
+            PageKey::Reopen => "[r] reopen".to_owned(),
+            PageKey::SetOwner => "[o] set owner".to_owned(),
+            PageKey::QuickStatus => {
+                "[1-5] set status: 1 open, 2 in progress, 3 resolved, 4 closed, 5 blocked"
+                    .to_owned()
+            }
+            PageKey::AssignToSelf => "[A] assign to me".to_owned(),
+            PageKey::Assign => "[a] assign".to_owned(),
+            PageKey::Unassign => "[-] unassign".to_owned(),
+            PageKey::ToggleRefinement(true) => "[n] mark refined".to_owned(),
+            PageKey::ToggleRefinement(false) => "[n] needs refinement".to_owned(),
+            PageKey::LinkParent => "[l] link parent".to_owned(),
+            PageKey::Attach => "[+] attach".to_owned(),
+            PageKey::OpenAttachment => "[o:n:] open attachment".to_owned(),
+            PageKey::RemoveAttachment => "[x:n:] remove attachment".to_owned(),
+            PageKey::AddChecklistItem => "[k] add checklist item".to_owned(),
+            PageKey::ToggleChecklistItem => "[t:n:] tick checklist item".to_owned(),
+            PageKey::Promote => "[E] make epic".to_owned(),
+            PageKey::Delete => "[d] delete".to_owned(),
+            PageKey::CreateStory => "[c] create story".to_owned(),
+            PageKey::QuickAddStories => "[C] quick add stories".to_owned(),
+            PageKey::SelectStories => "[m] select stories".to_owned(),
+            PageKey::Sort(sort) => format!("[s] sort ({})", sort),
+            PageKey::OpenStory => "[:id:] navigate to story".to_owned(),
+            PageKey::Focus => "[@] focus on assignee".to_owned(),
+            PageKey::ClearFocus(assignee) => format!("Focus: {} | [F] clear focus", assignee),
+            PageKey::CopyId => "[y] copy id".to_owned(),
+            PageKey::CycleInProgress => "[[ ]] previous/next of my stories in progress".to_owned(),
+            PageKey::RawJson => return None,
+        };
+        Some(hint)
+    }
+}
+
+pub fn get_footer(keys: &[PageKey]) -> String {
+    keys.iter()
+        .filter_map(PageKey::get_hint)
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+// Finished items offer reopening instead of status changes, and read-only databases
+// offer nothing that writes
+pub fn get_epic_keys(
+    epic: &Epic,
+    writable: bool,
+    sort: SortMode,
+    focus: Option<&str>,
+) -> Vec<PageKey> {
+    let mut keys = vec![PageKey::Previous, PageKey::Back, PageKey::Refresh];
+    if writable {
+        if epic.status.is_finished() {
+            keys.push(PageKey::Reopen);
+        } else {
+            keys.push(PageKey::UpdateStatus);
+        }
+        keys.extend([
+            PageKey::SetOwner,
+            PageKey::Delete,
+            PageKey::CreateStory,
+            PageKey::QuickAddStories,
+            PageKey::SelectStories,
+        ]);
+    }
+    keys.extend([PageKey::Sort(sort), PageKey::OpenStory, PageKey::Focus]);
+    if let Some(assignee) = focus {
+        keys.push(PageKey::ClearFocus(assignee.to_owned()));
+    }
+    keys.extend([PageKey::CopyId, PageKey::RawJson]);
+    keys
+}
+
+pub fn get_story_keys(story: &Story, writable: bool) -> Vec<PageKey> {
+    let mut keys = vec![PageKey::Previous, PageKey::Back, PageKey::Refresh];
+    let has_attachments = !story.attachments.is_empty();
+    if writable {
+        if story.is_finished() {
+            keys.push(PageKey::Reopen);
+        } else {
+            keys.extend([PageKey::UpdateStatus, PageKey::QuickStatus]);
+        }
+        keys.extend([PageKey::AssignToSelf, PageKey::Assign]);
+        if story.assignee.is_some() {
+            keys.push(PageKey::Unassign);
+        }
+        keys.extend([
+            PageKey::ToggleRefinement(story.needs_refinement),
+            PageKey::LinkParent,
+            PageKey::Attach,
+        ]);
+    }
+    if has_attachments {
+        keys.push(PageKey::OpenAttachment);
+    }
+    if writable {
+        if has_attachments {
+            keys.push(PageKey::RemoveAttachment);
+        }
+        keys.push(PageKey::AddChecklistItem);
+        if !story.checklist.is_empty() {
+            keys.push(PageKey::ToggleChecklistItem);
+        }
+        keys.extend([PageKey::Promote, PageKey::Delete]);
+    }
+    keys.extend([PageKey::CopyId, PageKey::CycleInProgress]);
+    keys
+}
+
+// Single-key status changes on StoryDetail, numbered like the [u] status prompt so
+// one scheme serves both. Letters would clash with keys the page already uses.
+pub fn get_quick_status(input: &str) -> Option<Status> {
+    match input {
+        "1" => Some(Status::Open),
+        "2" => Some(Status::InProgress),
+        "3" => Some(Status::Resolved),
+        "4" => Some(Status::Closed),
+        "5" => Some(Status::Blocked),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn closed_story_should_offer_reopen_but_not_status_keys() {
+        let story = Story {
+            status: Status::Closed,
+            ..Story::new("".to_owned(), "".to_owned())
+        };
+
+        let keys = get_story_keys(&story, true);
+        let footer = get_footer(&keys);
+
+        assert!(keys.contains(&PageKey::Reopen));
+        assert!(!keys.contains(&PageKey::UpdateStatus));
+        assert!(!keys.contains(&PageKey::QuickStatus));
+        assert!(footer.contains("[r] reopen"));
+        assert!(!footer.contains("[u]"));
+        assert!(!footer.contains("[1-5]"));
+    }
+
+    #[test]
+    fn read_only_pages_should_only_offer_keys_that_read() {
+        let epic = Epic::new("".to_owned(), "".to_owned());
+        let story = Story {
+            assignee: Some("ana".to_owned()),
+            ..Story::new("".to_owned(), "".to_owned())
+        };
+
+        assert_eq!(
+            get_story_keys(&story, false),
+            vec![
+                PageKey::Previous,
+                PageKey::Back,
+                PageKey::Refresh,
+                PageKey::CopyId,
+                PageKey::CycleInProgress
+            ]
+        );
+        assert_eq!(
+            get_epic_keys(&epic, false, SortMode::Name, Some("ana")),
+            vec![
+                PageKey::Previous,
+                PageKey::Back,
+                PageKey::Refresh,
+                PageKey::Sort(SortMode::Name),
+                PageKey::OpenStory,
+                PageKey::Focus,
+                PageKey::ClearFocus("ana".to_owned()),
+                PageKey::CopyId,
+                PageKey::RawJson,
+            ]
+        );
+    }
+
+    #[test]
+    fn open_story_should_offer_status_keys_and_hide_empty_ones() {
+        let story = Story::new("".to_owned(), "".to_owned());
+
+        let keys = get_story_keys(&story, true);
+
+        assert!(keys.contains(&PageKey::UpdateStatus));
+        assert!(keys.contains(&PageKey::QuickStatus));
+        assert!(!keys.contains(&PageKey::Reopen));
+        // Nobody to unassign and nothing attached
+        assert!(!keys.contains(&PageKey::Unassign));
+        assert!(!keys.contains(&PageKey::OpenAttachment));
+        assert!(!keys.contains(&PageKey::ToggleChecklistItem));
+        assert!(!get_footer(&keys).contains("[!]"));
+    }
+}
@@ -1,14 +1,62 @@
+use std::io::{stdout, Write};
+
+use anyhow::Result;
+
+use crossterm::{
+    cursor::MoveTo,
+    event::{self, Event, KeyCode, KeyEventKind},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType},
+};
+
 use crate::{
-    io_utils::get_user_input,
-    models::{Epic, Status, Story},
+    io_utils::{
+        copy_to_clipboard, get_text_input, get_user_input, open_with_system, wait_for_key_press,
+    },
+    models::{Attachment, Epic, Status, Story},
+    ui::{BulkAction, Selection, StorySelection},
 };
 
+// Takes (id, name) rows and returns the chosen bulk action with the checked ids
+type SelectStoriesPrompt = Box<dyn Fn(&[(String, String)]) -> Option<StorySelection>>;
+
+// Shown with the unfinished story ids after an epic was closed under the warn policy
+type CloseWarningPrompt = Box<dyn Fn(&[String])>;
+
+// Takes the entry labels and returns the index of the picked one
+type PickFilterPrompt = Box<dyn Fn(&[String]) -> Option<usize>>;
+
+// Hands a path or URL to the system opener. Not a prompt as such, but swapped out in tests like one.
+type OpenAttachmentPrompt = Box<dyn Fn(&str) -> Result<()>>;
+
+// Likewise, an error means the id is printed instead
+type CopyToClipboardPrompt = Box<dyn Fn(&str) -> Result<()>>;
+
 pub struct Prompts {
     pub create_epic: Box<dyn Fn() -> Epic>,
     pub create_story: Box<dyn Fn() -> Story>,
-    pub delete_epic: Box<dyn Fn() -> bool>,
+    pub quick_add_stories: Box<dyn Fn() -> Vec<String>>,
+    pub quick_capture: Box<dyn Fn() -> Option<String>>,
+    // Takes the preview of the stories the delete takes with it
+    pub delete_epic: Box<dyn Fn(&str) -> bool>,
     pub delete_story: Box<dyn Fn() -> bool>,
+    pub promote_story: Box<dyn Fn() -> Option<bool>>,
     pub update_status: Box<dyn Fn() -> Option<Status>>,
+    // An empty answer clears the owner
+    pub epic_owner: Box<dyn Fn() -> String>,
+    // Likewise, an empty answer unassigns the story
+    pub assignee: Box<dyn Fn() -> String>,
+    pub blocked_reason: Box<dyn Fn() -> Option<String>>,
+    pub set_parent: Box<dyn Fn() -> Option<String>>,
+    pub focus_assignee: Box<dyn Fn() -> Option<String>>,
+    pub search: Box<dyn Fn() -> String>,
+    pub pick_filter: PickFilterPrompt,
+    pub close_warning: CloseWarningPrompt,
+    pub create_attachment: Box<dyn Fn() -> Attachment>,
+    pub checklist_item: Box<dyn Fn() -> String>,
+    pub open_attachment: OpenAttachmentPrompt,
+    pub copy_to_clipboard: CopyToClipboardPrompt,
+    pub select_stories: SelectStoriesPrompt,
 }
 
 impl Prompts {
@@ -16,9 +64,25 @@ impl Prompts {
         Self {
             create_epic: Box::new(create_epic_prompt),
             create_story: Box::new(create_story_prompt),
+            quick_add_stories: Box::new(quick_add_stories_prompt),
+            quick_capture: Box::new(quick_capture_prompt),
             delete_epic: Box::new(delete_epic_prompt),
             delete_story: Box::new(delete_story_prompt),
+            promote_story: Box::new(promote_story_prompt),
             update_status: Box::new(update_status_prompt),
+            epic_owner: Box::new(epic_owner_prompt),
+            assignee: Box::new(assignee_prompt),
+            blocked_reason: Box::new(blocked_reason_prompt),
+            set_parent: Box::new(set_parent_prompt),
+            focus_assignee: Box::new(focus_assignee_prompt),
+            search: Box::new(search_prompt),
+            pick_filter: Box::new(pick_filter_prompt),
+            close_warning: Box::new(close_warning_prompt),
+            create_attachment: Box::new(create_attachment_prompt),
+            checklist_item: Box::new(checklist_item_prompt),
+            open_attachment: Box::new(open_with_system),
+            copy_to_clipboard: Box::new(copy_to_clipboard),
+            select_stories: Box::new(select_stories_prompt),
         }
     }
 }
@@ -28,11 +92,11 @@ fn create_epic_prompt() -> Epic {
 
     println!("Epic Name: ");
 
-    let epic_name = get_user_input();
+    let epic_name = get_text_input();
 
     println!("Epic Description: ");
 
-    let epic_desc = get_user_input();
+    let epic_desc = get_text_input();
 
     let epic = Epic::new(epic_name.trim().to_owned(), epic_desc.trim().to_owned());
 
@@ -44,29 +108,85 @@ fn create_story_prompt() -> Story {
 
     println!("Story Name: ");
 
-    let story_name = get_user_input();
+    let story_name = get_text_input();
 
     println!("Story Description: ");
 
-    let story_desc = get_user_input();
+    let story_desc = get_text_input();
 
     let story = Story::new(story_name.trim().to_owned(), story_desc.trim().to_owned());
 
     story
 }
 
-fn delete_epic_prompt() -> bool {
+// Keeps asking for story names until a blank line
+fn quick_add_stories_prompt() -> Vec<String> {
     println!("----------------------------");
 
-    println!("Are you sure you want to delete this epic? All stories in this epic will also be deleted [Y/n]: ");
+    let mut names = Vec::new();
+    loop {
+        println!("Story name (blank to finish): ");
 
-    let input = get_user_input();
+        let name = get_text_input();
 
-    if input.trim().eq("Y") {
-        return true;
+        if name.trim().is_empty() {
+            return names;
+        }
+        names.push(name.trim().to_owned());
     }
+}
 
-    false
+// A blank name captures nothing
+fn quick_capture_prompt() -> Option<String> {
+    println!("----------------------------");
+
+    println!("Capture to Inbox: ");
+
+    let name = get_text_input().trim().to_owned();
+
+    if name.is_empty() {
+        return None;
+    }
+
+    Some(name)
+}
+
+fn create_attachment_prompt() -> Attachment {
+    println!("----------------------------");
+
+    println!("Attachment Label: ");
+
+    let label = get_text_input();
+
+    println!("Attachment Path or URL: ");
+
+    let target = get_text_input();
+
+    Attachment {
+        label: label.trim().to_owned(),
+        target: target.trim().to_owned(),
+    }
+}
+
+fn checklist_item_prompt() -> String {
+    println!("----------------------------");
+
+    println!("Checklist Item: ");
+
+    get_text_input().trim().to_owned()
+}
+
+fn delete_epic_prompt(preview: &str) -> bool {
+    println!("----------------------------");
+
+    println!("{}", preview);
+
+    println!("Are you sure you want to delete this epic? [y/N]: ");
+
+    let input = get_user_input();
+
+    // Anything but an explicit yes keeps the epic
+    matches!(input.trim(), "y" | "Y")
 }
 
 fn delete_story_prompt() -> bool {
@@ -83,10 +203,108 @@ fn delete_story_prompt() -> bool {
     false
 }
 
+// None cancels, otherwise whether the subtasks move to the new epic
+fn promote_story_prompt() -> Option<bool> {
+    println!("----------------------------");
+
+    println!("Are you sure you want to turn this story into an epic? [Y/n]: ");
+
+    if !get_user_input().trim().eq("Y") {
+        return None;
+    }
+
+    println!("Move its subtasks to the new epic? [Y/n]: ");
+
+    Some(get_user_input().trim().eq("Y"))
+}
+
+fn set_parent_prompt() -> Option<String> {
+    println!("----------------------------");
+
+    println!("Parent Story Id (leave empty to clear): ");
+
+    let parent_id = get_user_input();
+    let parent_id = parent_id.trim();
+
+    if parent_id.is_empty() {
+        return None;
+    }
+
+    Some(parent_id.to_owned())
+}
+
+fn epic_owner_prompt() -> String {
+    println!("----------------------------");
+
+    println!("Epic owner (leave empty to clear): ");
+
+    get_text_input().trim().to_owned()
+}
+
+fn assignee_prompt() -> String {
+    println!("----------------------------");
+
+    println!("Assignee (leave empty to unassign): ");
+
+    get_text_input().trim().to_owned()
+}
+
+// None means the configured user
+fn focus_assignee_prompt() -> Option<String> {
+    println!("----------------------------");
+
+    println!("Focus on assignee (leave empty for yourself): ");
+
+    let assignee = get_text_input();
+    let assignee = assignee.trim();
+
+    if assignee.is_empty() {
+        return None;
+    }
+
+    Some(assignee.to_owned())
+}
+
+fn search_prompt() -> String {
+    println!("----------------------------");
+
+    println!("Search: ");
+
+    get_text_input().trim().to_owned()
+}
+
+// Index of the picked entry, None when left empty or out of range
+fn pick_filter_prompt(labels: &[String]) -> Option<usize> {
+    println!("----------------------------");
+
+    for (number, label) in labels.iter().enumerate() {
+        println!("[{}] {}", number + 1, label);
+    }
+
+    println!("Filter number (leave empty to cancel): ");
+
+    let number = get_text_input().trim().parse::<usize>().ok()?;
+
+    (1..=labels.len()).contains(&number).then(|| number - 1)
+}
+
+fn close_warning_prompt(story_ids: &[String]) {
+    println!("----------------------------");
+
+    println!(
+        "Warning: this epic still has unfinished stories: {}",
+        story_ids.join(", ")
+    );
+
+    println!("Press any key to continue...");
+
+    wait_for_key_press();
+}
+
 fn update_status_prompt() -> Option<Status> {
     println!("----------------------------");
 
-    println!("New Status (1 - OPEN, 2 - IN-PROGRESS, 3 - RESOLVED, 4 - CLOSED): ");
+    println!("New Status (1 - OPEN, 2 - IN-PROGRESS, 3 - RESOLVED, 4 - CLOSED, 5 - BLOCKED): ");
 
     let status = get_user_input();
 
@@ -106,9 +324,88 @@ fn update_status_prompt() -> Option<Status> {
             4 => {
                 return Some(Status::Closed);
             }
+            5 => {
+                return Some(Status::Blocked);
+            }
             _ => return None,
         }
     }
 
     None
 }
+
+// Empty input gives no reason
+fn blocked_reason_prompt() -> Option<String> {
+    println!("----------------------------");
+
+    println!("Blocked because: ");
+
+    let reason = get_text_input().trim().to_owned();
+
+    if reason.is_empty() {
+        return None;
+    }
+
+    Some(reason)
+}
+
+fn select_stories_prompt(stories: &[(String, String)]) -> Option<StorySelection> {
+    let mut selection = Selection::new(stories.iter().map(|(id, _)| id.clone()).collect());
+
+    if enable_raw_mode().is_err() {
+        return None;
+    }
+
+    let result = loop {
+        if draw_selection(stories, &selection).is_err() {
+            break None;
+        }
+
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => match key.code {
+                KeyCode::Up | KeyCode::Char('k') => selection.move_up(),
+                KeyCode::Down | KeyCode::Char('j') => selection.move_down(),
+                KeyCode::Char(' ') => selection.toggle(),
+                KeyCode::Char('u') => break Some((BulkAction::UpdateStatus, selection.take())),
+                KeyCode::Char('d') => break Some((BulkAction::Delete, selection.take())),
+                KeyCode::Char('a') => break Some((BulkAction::AssignToSelf, selection.take())),
+                KeyCode::Esc | KeyCode::Char('q') => break None,
+                _ => {}
+            },
+            Ok(_) => {}
+            Err(_) => break None,
+        }
+    };
+
+    let _ = disable_raw_mode();
+
+    // Nothing checked means nothing to apply
+    result.filter(|(_, story_ids)| !story_ids.is_empty())
+}
+
+fn draw_selection(stories: &[(String, String)], selection: &Selection) -> std::io::Result<()> {
+    let mut out = stdout();
+    execute!(out, Clear(ClearType::All), MoveTo(0, 0))?;
+
+    // Raw mode doesn't translate newlines, so every line ends in \r\n
+    write!(out, "----------------------------\r\n")?;
+    for (index, (story_id, name)) in stories.iter().enumerate() {
+        let cursor = if index == selection.cursor() {
+            ">"
+        } else {
+            " "
+        };
+        let check = if selection.is_checked(story_id) {
+            "x"
+        } else {
+            " "
+        };
+        write!(out, "{} [{}] {} | {}\r\n", cursor, check, story_id, name)?;
+    }
+    write!(
+        out,
+        "\r\n[up/down] move | [space] select | [u] update status | [d] delete | [a] assign to me | [esc] cancel\r\n"
+    )?;
+
+    out.flush()
+}
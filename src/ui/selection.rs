@@ -0,0 +1,124 @@
+use std::collections::HashSet;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BulkAction {
+    UpdateStatus,
+    Delete,
+    AssignToSelf,
+}
+
+// The bulk action picked in the multi-select prompt and the ids it applies to
+pub type StorySelection = (BulkAction, Vec<String>);
+
+// Cursor and checkmarks over a fixed list of ids, used by the multi-select prompt
+pub struct Selection {
+    ids: Vec<String>,
+    cursor: usize,
+    checked: HashSet<String>,
+}
+
+impl Selection {
+    pub fn new(ids: Vec<String>) -> Self {
+        Self {
+            ids,
+            cursor: 0,
+            checked: HashSet::new(),
+        }
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn is_checked(&self, id: &str) -> bool {
+        self.checked.contains(id)
+    }
+
+    pub fn move_up(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn move_down(&mut self) {
+        if self.cursor + 1 < self.ids.len() {
+            self.cursor += 1;
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        if let Some(id) = self.ids.get(self.cursor) {
+            if !self.checked.remove(id) {
+                self.checked.insert(id.clone());
+            }
+        }
+    }
+
+    // Hands out the checked ids in list order and clears the selection
+    pub fn take(&mut self) -> Vec<String> {
+        let selected = self
+            .ids
+            .iter()
+            .filter(|id| self.checked.contains(*id))
+            .cloned()
+            .collect();
+        self.checked.clear();
+        selected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arrange_selection() -> Selection {
+        Selection::new(vec!["a".to_owned(), "b".to_owned(), "c".to_owned()])
+    }
+
+    #[test]
+    fn toggle_should_check_and_uncheck_the_row_under_the_cursor() {
+        let mut selection = arrange_selection();
+
+        selection.toggle();
+        assert!(selection.is_checked("a"));
+
+        selection.toggle();
+        assert!(!selection.is_checked("a"));
+    }
+
+    #[test]
+    fn cursor_should_stay_within_bounds() {
+        let mut selection = arrange_selection();
+
+        selection.move_up();
+        assert_eq!(selection.cursor(), 0);
+
+        selection.move_down();
+        selection.move_down();
+        selection.move_down();
+        assert_eq!(selection.cursor(), 2);
+    }
+
+    #[test]
+    fn take_should_return_checked_ids_in_order_and_clear() {
+        let mut selection = arrange_selection();
+
+        selection.move_down();
+        selection.move_down();
+        selection.toggle();
+        selection.move_up();
+        selection.move_up();
+        selection.toggle();
+
+        assert_eq!(selection.take(), vec!["a".to_owned(), "c".to_owned()]);
+        assert!(!selection.is_checked("a"));
+        assert_eq!(selection.take(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn toggle_on_empty_list_should_do_nothing() {
+        let mut selection = Selection::new(vec![]);
+
+        selection.toggle();
+
+        assert_eq!(selection.take(), Vec::<String>::new());
+    }
+}
@@ -0,0 +1,285 @@
+use std::cmp::Ordering;
+use std::fmt::Display;
+
+use itertools::Itertools;
+
+use crate::models::{DBState, Epic, Status, Story};
+use crate::ordering::compare_keys;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum SortMode {
+    #[default]
+    Name,
+    Status,
+    Updated,
+    Completion,
+    // Lexical by id. Ids are random, so this is a stable order, not creation order.
+    Id,
+    // As placed with the reorder commands, see ordering
+    Manual,
+}
+
+impl SortMode {
+    // The mode the `s` key switches to next
+    pub fn next(self) -> Self {
+        match self {
+            SortMode::Name => SortMode::Status,
+            SortMode::Status => SortMode::Updated,
+            SortMode::Updated => SortMode::Completion,
+            SortMode::Completion => SortMode::Id,
+            SortMode::Id => SortMode::Manual,
+            SortMode::Manual => SortMode::Name,
+        }
+    }
+}
+
+impl Display for SortMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SortMode::Name => write!(f, "name"),
+            SortMode::Status => write!(f, "status"),
+            SortMode::Updated => write!(f, "updated"),
+            SortMode::Completion => write!(f, "completion"),
+            SortMode::Id => write!(f, "id"),
+            SortMode::Manual => write!(f, "manual"),
+        }
+    }
+}
+
+pub fn sort_epics<'a>(
+    db_state: &DBState,
+    epics: Vec<(&'a String, &'a Epic)>,
+    sort: SortMode,
+) -> Vec<(&'a String, &'a Epic)> {
+    epics
+        .into_iter()
+        .sorted_by(|a, b| {
+            let primary = match sort {
+                SortMode::Name => Ordering::Equal,
+                SortMode::Status => get_status_rank(&a.1.status).cmp(&get_status_rank(&b.1.status)),
+                // Most recent first, never-updated last
+                SortMode::Updated => b.1.updated_at.cmp(&a.1.updated_at),
+                SortMode::Completion => {
+                    // Most complete first, compared as fractions without floats.
+                    // An empty epic counts as 0 of 1.
+                    let (a_done, a_total) = get_epic_completion(db_state, a.1);
+                    let (b_done, b_total) = get_epic_completion(db_state, b.1);
+                    (b_done * a_total.max(1)).cmp(&(a_done * b_total.max(1)))
+                }
+                SortMode::Id => a.0.cmp(b.0),
+                SortMode::Manual => compare_keys(a.1.order, b.1.order),
+            };
+            // Name then id break ties so every mode is a total order
+            primary
+                .then_with(|| a.1.name.cmp(&b.1.name))
+                .then_with(|| a.0.cmp(b.0))
+        })
+        .collect()
+}
+
+pub fn sort_stories<'a>(
+    stories: Vec<(&'a String, &'a Story)>,
+    sort: SortMode,
+) -> Vec<(&'a String, &'a Story)> {
+    stories
+        .into_iter()
+        .sorted_by(|a, b| {
+            let primary = match sort {
+                SortMode::Name => Ordering::Equal,
+                SortMode::Status => get_status_rank(&a.1.status).cmp(&get_status_rank(&b.1.status)),
+                SortMode::Updated => b.1.updated_at.cmp(&a.1.updated_at),
+                // A story has no progress of its own, so finished ones come first
+                SortMode::Completion => {
+                    get_status_rank(&b.1.status).cmp(&get_status_rank(&a.1.status))
+                }
+                SortMode::Id => a.0.cmp(b.0),
+                SortMode::Manual => compare_keys(a.1.order, b.1.order),
+            };
+            primary
+                .then_with(|| a.1.name.cmp(&b.1.name))
+                .then_with(|| a.0.cmp(b.0))
+        })
+        .collect()
+}
+
+// Workflow order, so open work sorts ahead of finished work
+fn get_status_rank(status: &Status) -> u8 {
+    match status {
+        Status::Open => 0,
+        Status::InProgress => 1,
+        Status::Blocked => 2,
+        Status::Resolved => 3,
+        Status::Closed => 4,
+    }
+}
+
+// Resolved and closed stories, and all stories, of the epic
+pub fn get_epic_completion(db_state: &DBState, epic: &Epic) -> (usize, usize) {
+    let done = epic
+        .stories
+        .iter()
+        .filter_map(|id| db_state.stories.get(id))
+        .filter(|story| matches!(story.status, Status::Resolved | Status::Closed))
+        .count();
+    (done, epic.stories.len())
+}
+
+// Whole percent, rounded down. Nothing to do counts as 0%.
+pub fn get_completion_percent(done: usize, total: usize) -> usize {
+    if total == 0 {
+        return 0;
+    }
+    done * 100 / total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use std::collections::HashMap;
+
+    fn get_story(name: &str, status: Status, updated_hour: Option<u32>) -> Story {
+        let mut story = Story::new(name.to_owned(), "".to_owned());
+        story.status = status;
+        story.updated_at =
+            updated_hour.map(|hour| Utc.with_ymd_and_hms(2022, 1, 1, hour, 0, 0).unwrap());
+        story
+    }
+
+    // Epics b, c, a, d with 2/2, 1/2, 0/1 and 0/0 stories done
+    fn arrange_state() -> DBState {
+        let mut stories = HashMap::new();
+        let mut epics = HashMap::new();
+        let fixture = [
+            (
+                "1",
+                "b",
+                Status::Closed,
+                Some(3),
+                vec![Status::Closed, Status::Closed],
+            ),
+            (
+                "2",
+                "c",
+                Status::Open,
+                None,
+                vec![Status::Open, Status::Resolved],
+            ),
+            ("3", "a", Status::InProgress, Some(5), vec![Status::Open]),
+            ("4", "d", Status::Open, Some(1), vec![]),
+        ];
+        for (epic_id, name, status, updated_hour, story_statuses) in fixture {
+            let mut epic = Epic::new(name.to_owned(), "".to_owned());
+            epic.status = status;
+            epic.updated_at =
+                updated_hour.map(|hour| Utc.with_ymd_and_hms(2022, 1, 1, hour, 0, 0).unwrap());
+            for (index, story_status) in story_statuses.into_iter().enumerate() {
+                let story_id = format!("{}-{}", epic_id, index);
+                stories.insert(story_id.clone(), get_story("", story_status, None));
+                epic.stories.push(story_id);
+            }
+            epics.insert(epic_id.to_owned(), epic);
+        }
+        DBState {
+            epics,
+            stories,
+            last_item_id: "0".to_owned(),
+            revision: 0,
+        }
+    }
+
+    fn get_sorted_epic_names(db_state: &DBState, sort: SortMode) -> Vec<&str> {
+        sort_epics(db_state, db_state.epics.iter().collect(), sort)
+            .into_iter()
+            .map(|(_, epic)| epic.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn next_should_cycle_through_every_mode() {
+        let mut sort = SortMode::default();
+        let mut seen = vec![];
+        for _ in 0..6 {
+            seen.push(sort);
+            sort = sort.next();
+        }
+
+        assert_eq!(sort, SortMode::Name);
+        assert_eq!(
+            seen,
+            [
+                SortMode::Name,
+                SortMode::Status,
+                SortMode::Updated,
+                SortMode::Completion,
+                SortMode::Id,
+                SortMode::Manual
+            ]
+        );
+    }
+
+    #[test]
+    fn sort_epics_should_order_by_each_mode() {
+        let db_state = arrange_state();
+
+        assert_eq!(
+            get_sorted_epic_names(&db_state, SortMode::Name),
+            ["a", "b", "c", "d"]
+        );
+        // Ties on status fall back to the name
+        assert_eq!(
+            get_sorted_epic_names(&db_state, SortMode::Status),
+            ["c", "d", "a", "b"]
+        );
+        assert_eq!(
+            get_sorted_epic_names(&db_state, SortMode::Updated),
+            ["a", "b", "d", "c"]
+        );
+        assert_eq!(
+            get_sorted_epic_names(&db_state, SortMode::Completion),
+            ["b", "c", "a", "d"]
+        );
+        assert_eq!(
+            get_sorted_epic_names(&db_state, SortMode::Id),
+            ["b", "c", "a", "d"]
+        );
+    }
+
+    #[test]
+    fn manual_sort_should_follow_the_keys_then_the_name() {
+        let mut db_state = arrange_state();
+        db_state.epics.get_mut("4").unwrap().order = Some(1024);
+        db_state.epics.get_mut("2").unwrap().order = Some(2048);
+
+        assert_eq!(
+            get_sorted_epic_names(&db_state, SortMode::Manual),
+            ["d", "c", "a", "b"]
+        );
+    }
+
+    #[test]
+    fn sort_stories_should_order_by_each_mode() {
+        let ids = ["1", "2", "3", "4"].map(|id| id.to_owned());
+        let stories = [
+            get_story("b", Status::Closed, Some(2)),
+            get_story("a", Status::Open, None),
+            get_story("c", Status::InProgress, Some(4)),
+            get_story("a", Status::Resolved, Some(4)),
+        ];
+        let get_sorted_ids = |sort| {
+            sort_stories(ids.iter().zip(stories.iter()).collect(), sort)
+                .into_iter()
+                .map(|(id, _)| id.as_str())
+                .collect_vec()
+        };
+
+        // Equal names fall back to the id
+        assert_eq!(get_sorted_ids(SortMode::Name), ["2", "4", "1", "3"]);
+        assert_eq!(get_sorted_ids(SortMode::Status), ["2", "3", "4", "1"]);
+        assert_eq!(get_sorted_ids(SortMode::Updated), ["4", "3", "1", "2"]);
+        assert_eq!(get_sorted_ids(SortMode::Completion), ["1", "4", "3", "2"]);
+        assert_eq!(get_sorted_ids(SortMode::Id), ["1", "2", "3", "4"]);
+        // Nothing reordered yet, so only the name ties break
+        assert_eq!(get_sorted_ids(SortMode::Manual), ["2", "4", "1", "3"]);
+    }
+}
@@ -0,0 +1,108 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
+
+// An unset zone means UTC; an unknown name is an error so the caller can warn about it
+pub fn parse_display_timezone(name: Option<&str>) -> Result<Tz> {
+    match name.map(str::trim) {
+        None | Some("") => Ok(Tz::UTC),
+        Some(name) => name
+            .parse::<Tz>()
+            .map_err(|_| anyhow!("Unknown time zone '{}'", name)),
+    }
+}
+
+// Timestamps are stored in UTC and converted only for display
+pub fn format_timestamp(timestamp: &DateTime<Utc>, tz: &Tz) -> String {
+    timestamp
+        .with_timezone(tz)
+        .format("%Y-%m-%d %H:%M %Z")
+        .to_string()
+}
+
+// "just now", "5 minutes ago", "1 day ago", using the largest whole unit
+pub fn format_time_ago(then: &DateTime<Utc>, now: &DateTime<Utc>) -> String {
+    let elapsed = *now - *then;
+    let (count, unit) = if elapsed.num_days() > 0 {
+        (elapsed.num_days(), "day")
+    } else if elapsed.num_hours() > 0 {
+        (elapsed.num_hours(), "hour")
+    } else if elapsed.num_minutes() > 0 {
+        (elapsed.num_minutes(), "minute")
+    } else {
+        return "just now".to_owned();
+    };
+    let plural = if count == 1 { "" } else { "s" };
+    format!("{} {}{} ago", count, unit, plural)
+}
+
+// Two largest units, e.g. "2d 3h" or "45m"
+pub fn format_duration(duration: &Duration) -> String {
+    let minutes = duration.num_minutes().max(0);
+    let (days, hours, minutes) = (minutes / 1440, minutes / 60 % 24, minutes % 60);
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_utils::fixed_time;
+
+    #[test]
+    fn format_timestamp_should_convert_to_the_display_zone() {
+        let timestamp = fixed_time();
+
+        assert_eq!(
+            format_timestamp(&timestamp, &Tz::UTC),
+            "2022-01-01 12:00 UTC"
+        );
+        assert_eq!(
+            format_timestamp(&timestamp, &Tz::America__New_York),
+            "2022-01-01 07:00 EST"
+        );
+        assert_eq!(
+            format_timestamp(&timestamp, &Tz::Asia__Tokyo),
+            "2022-01-01 21:00 JST"
+        );
+    }
+
+    #[test]
+    fn parse_display_timezone_should_default_to_utc() {
+        assert_eq!(parse_display_timezone(None).unwrap(), Tz::UTC);
+        assert_eq!(parse_display_timezone(Some("")).unwrap(), Tz::UTC);
+        assert_eq!(
+            parse_display_timezone(Some("Europe/Madrid")).unwrap(),
+            Tz::Europe__Madrid
+        );
+    }
+
+    #[test]
+    fn format_time_ago_should_use_the_largest_unit() {
+        let now = fixed_time();
+        let ago = |duration| format_time_ago(&(now - duration), &now);
+
+        assert_eq!(ago(chrono::Duration::seconds(30)), "just now");
+        assert_eq!(ago(chrono::Duration::minutes(1)), "1 minute ago");
+        assert_eq!(ago(chrono::Duration::hours(5)), "5 hours ago");
+        assert_eq!(ago(chrono::Duration::hours(49)), "2 days ago");
+    }
+
+    #[test]
+    fn format_duration_should_show_the_two_largest_units() {
+        assert_eq!(format_duration(&Duration::minutes(45)), "45m");
+        assert_eq!(format_duration(&Duration::minutes(125)), "2h 5m");
+        assert_eq!(format_duration(&Duration::hours(51)), "2d 3h");
+        assert_eq!(format_duration(&Duration::minutes(-5)), "0m");
+    }
+
+    #[test]
+    fn parse_display_timezone_should_reject_unknown_zones() {
+        assert!(parse_display_timezone(Some("Mars/Olympus")).is_err());
+    }
+}
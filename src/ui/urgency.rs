@@ -0,0 +1,279 @@
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use crossterm::style::{Color, Stylize};
+
+// How close a story is to its due date
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DueUrgency {
+    Overdue,
+    DueSoon,
+    OnTrack,
+    NoDueDate,
+}
+
+// Stories due within `due_soon_days` of today count as due soon
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct UrgencyThresholds {
+    pub due_soon_days: i64,
+}
+
+impl Default for UrgencyThresholds {
+    fn default() -> Self {
+        Self { due_soon_days: 2 }
+    }
+}
+
+impl DueUrgency {
+    pub fn from_due_date(
+        due_date: Option<NaiveDate>,
+        today: NaiveDate,
+        thresholds: &UrgencyThresholds,
+    ) -> Self {
+        let Some(due_date) = due_date else {
+            return DueUrgency::NoDueDate;
+        };
+        let days_left = (due_date - today).num_days();
+        if days_left < 0 {
+            DueUrgency::Overdue
+        } else if days_left <= thresholds.due_soon_days {
+            DueUrgency::DueSoon
+        } else {
+            DueUrgency::OnTrack
+        }
+    }
+
+    // Urgency of a due date as of today
+    pub fn today(due_date: Option<NaiveDate>, thresholds: &UrgencyThresholds) -> Self {
+        Self::from_due_date(due_date, Local::now().date_naive(), thresholds)
+    }
+
+    // None means the neutral terminal style
+    pub fn get_color(&self) -> Option<Color> {
+        match self {
+            DueUrgency::Overdue => Some(Color::Red),
+            DueUrgency::DueSoon => Some(Color::Yellow),
+            DueUrgency::OnTrack | DueUrgency::NoDueDate => None,
+        }
+    }
+}
+
+// How long a story has gone without an update
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum StoryAge {
+    Fresh,
+    Aging,
+    Stale,
+    Unknown,
+}
+
+// Stories untouched for `aging_days` are aging, and for `stale_days` are stale
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct AgeThresholds {
+    pub aging_days: i64,
+    pub stale_days: i64,
+}
+
+impl Default for AgeThresholds {
+    fn default() -> Self {
+        Self {
+            aging_days: 3,
+            stale_days: 7,
+        }
+    }
+}
+
+impl StoryAge {
+    pub fn from_updated_at(
+        updated_at: Option<&DateTime<Utc>>,
+        now: DateTime<Utc>,
+        thresholds: &AgeThresholds,
+    ) -> Self {
+        let Some(updated_at) = updated_at else {
+            return StoryAge::Unknown;
+        };
+        let days = (now - *updated_at).num_days();
+        if days >= thresholds.stale_days {
+            StoryAge::Stale
+        } else if days >= thresholds.aging_days {
+            StoryAge::Aging
+        } else {
+            StoryAge::Fresh
+        }
+    }
+
+    // Age of a story as of now
+    pub fn now(updated_at: Option<&DateTime<Utc>>, thresholds: &AgeThresholds) -> Self {
+        Self::from_updated_at(updated_at, Utc::now(), thresholds)
+    }
+
+    // None means the neutral terminal style
+    pub fn get_color(&self) -> Option<Color> {
+        match self {
+            StoryAge::Fresh => Some(Color::Green),
+            StoryAge::Aging => Some(Color::Yellow),
+            StoryAge::Stale => Some(Color::Red),
+            StoryAge::Unknown => None,
+        }
+    }
+}
+
+// A count turns yellow at `yellow_at` and red at `red_at`
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct CountThresholds {
+    pub yellow_at: usize,
+    pub red_at: usize,
+}
+
+impl CountThresholds {
+    // Zero is neutral rather than green, there is nothing to report
+    pub fn get_color(&self, count: usize) -> Option<Color> {
+        if count == 0 {
+            None
+        } else if count >= self.red_at {
+            Some(Color::Red)
+        } else if count >= self.yellow_at {
+            Some(Color::Yellow)
+        } else {
+            Some(Color::Green)
+        }
+    }
+}
+
+// Thresholds for each count in the home page health banner
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct HealthThresholds {
+    pub wip: CountThresholds,
+    pub overdue: CountThresholds,
+    pub unassigned: CountThresholds,
+}
+
+impl Default for HealthThresholds {
+    fn default() -> Self {
+        Self {
+            wip: CountThresholds {
+                yellow_at: 6,
+                red_at: 11,
+            },
+            overdue: CountThresholds {
+                yellow_at: 1,
+                red_at: 4,
+            },
+            unassigned: CountThresholds {
+                yellow_at: 4,
+                red_at: 8,
+            },
+        }
+    }
+}
+
+// https://no-color.org: any non-empty NO_COLOR turns colors off
+pub fn colors_enabled() -> bool {
+    std::env::var("NO_COLOR").unwrap_or_default().is_empty() && terminal_supports_ansi()
+}
+
+// Older Windows consoles print escape codes as text instead of coloring
+#[cfg(windows)]
+fn terminal_supports_ansi() -> bool {
+    crossterm::ansi_support::supports_ansi()
+}
+
+#[cfg(not(windows))]
+fn terminal_supports_ansi() -> bool {
+    true
+}
+
+pub fn paint(text: &str, color: Option<Color>) -> String {
+    paint_if(text, color, colors_enabled())
+}
+
+fn paint_if(text: &str, color: Option<Color>, enabled: bool) -> String {
+    match color {
+        Some(color) if enabled => text.with(color).to_string(),
+        _ => text.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn get_day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2022, 1, day).unwrap()
+    }
+
+    #[test]
+    fn from_due_date_should_respect_threshold_boundaries() {
+        let today = get_day(10);
+        let thresholds = UrgencyThresholds::default();
+        let urgency = |day| DueUrgency::from_due_date(Some(get_day(day)), today, &thresholds);
+
+        assert_eq!(urgency(9), DueUrgency::Overdue);
+        assert_eq!(urgency(10), DueUrgency::DueSoon);
+        assert_eq!(urgency(12), DueUrgency::DueSoon);
+        assert_eq!(urgency(13), DueUrgency::OnTrack);
+    }
+
+    #[test]
+    fn from_due_date_should_use_configured_threshold() {
+        let today = get_day(10);
+        let thresholds = UrgencyThresholds { due_soon_days: 0 };
+        let urgency = |day| DueUrgency::from_due_date(Some(get_day(day)), today, &thresholds);
+
+        assert_eq!(urgency(10), DueUrgency::DueSoon);
+        assert_eq!(urgency(11), DueUrgency::OnTrack);
+    }
+
+    #[test]
+    fn missing_due_date_should_be_neutral() {
+        let urgency = DueUrgency::from_due_date(None, get_day(10), &UrgencyThresholds::default());
+
+        assert_eq!(urgency, DueUrgency::NoDueDate);
+        assert_eq!(urgency.get_color(), None);
+    }
+
+    #[test]
+    fn from_updated_at_should_map_thresholds_to_colors() {
+        let now = Utc.with_ymd_and_hms(2022, 1, 10, 12, 0, 0).unwrap();
+        let thresholds = AgeThresholds::default();
+        let age = |days| {
+            let updated_at = now - Duration::days(days);
+            StoryAge::from_updated_at(Some(&updated_at), now, &thresholds)
+        };
+
+        assert_eq!(age(0).get_color(), Some(Color::Green));
+        assert_eq!(age(2).get_color(), Some(Color::Green));
+        assert_eq!(age(3).get_color(), Some(Color::Yellow));
+        assert_eq!(age(6).get_color(), Some(Color::Yellow));
+        assert_eq!(age(7).get_color(), Some(Color::Red));
+    }
+
+    #[test]
+    fn missing_updated_at_should_be_neutral() {
+        let now = Utc.with_ymd_and_hms(2022, 1, 10, 12, 0, 0).unwrap();
+        let age = StoryAge::from_updated_at(None, now, &AgeThresholds::default());
+
+        assert_eq!(age, StoryAge::Unknown);
+        assert_eq!(age.get_color(), None);
+    }
+
+    #[test]
+    fn count_thresholds_should_map_counts_to_colors() {
+        let thresholds = CountThresholds {
+            yellow_at: 2,
+            red_at: 4,
+        };
+
+        assert_eq!(thresholds.get_color(0), None);
+        assert_eq!(thresholds.get_color(1), Some(Color::Green));
+        assert_eq!(thresholds.get_color(2), Some(Color::Yellow));
+        assert_eq!(thresholds.get_color(3), Some(Color::Yellow));
+        assert_eq!(thresholds.get_color(4), Some(Color::Red));
+    }
+
+    #[test]
+    fn paint_should_only_style_when_enabled() {
+        assert_eq!(paint_if("late", Some(Color::Red), false), "late");
+        assert_eq!(paint_if("fine", None, true), "fine");
+        assert_ne!(paint_if("late", Some(Color::Red), true), "late");
+    }
+}
@@ -0,0 +1,190 @@
+use anyhow::{anyhow, Context, Result};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::mpsc::{channel, Receiver};
+use std::time::Duration;
+
+use crate::db::JiraDatabase;
+
+// Saves closer together than this are exported once
+const EXPORT_DEBOUNCE: Duration = Duration::from_millis(300);
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ExportFormat {
+    Ics,
+    Ndjson,
+    // The same rows as export-csv
+    Csv,
+}
+
+impl FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ics" => Ok(ExportFormat::Ics),
+            "ndjson" => Ok(ExportFormat::Ndjson),
+            "csv" => Ok(ExportFormat::Csv),
+            other => Err(anyhow!(
+                "Unknown export format '{}'. Expected ics, ndjson or csv.",
+                other
+            )),
+        }
+    }
+}
+
+impl Display for ExportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportFormat::Ics => write!(f, "ics"),
+            ExportFormat::Ndjson => write!(f, "ndjson"),
+            ExportFormat::Csv => write!(f, "csv"),
+        }
+    }
+}
+
+pub fn export_to_file(db: &JiraDatabase, format: ExportFormat, path: &Path) -> Result<()> {
+    let file = File::create(path)
+        .with_context(|| format!("Failed to create export file {}.", path.display()))?;
+    let mut writer = BufWriter::new(file);
+    match format {
+        ExportFormat::Ics => write!(writer, "{}", db.export_ics()?)?,
+        ExportFormat::Ndjson => db.export_ndjson(&mut writer)?,
+        ExportFormat::Csv => db.export_csv(&mut writer)?,
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+// A failed export is logged rather than fatal, the next save may well fix it
+fn export_and_log(db: &JiraDatabase, format: ExportFormat, path: &Path) {
+    match export_to_file(db, format, path) {
+        Ok(()) => eprintln!("Exported {} to {}", format, path.display()),
+        Err(error) => eprintln!("Export to {} failed: {}", path.display(), error),
+    }
+}
+
+// Exports once, then again after every change to the database file. Runs until interrupted.
+pub fn run_export_on_change(db: &JiraDatabase, format: ExportFormat, output: &Path) -> Result<()> {
+    let db_path = db
+        .database
+        .file_path()
+        .ok_or_else(|| anyhow!("Export on change needs a database stored in a file."))?
+        .to_path_buf();
+
+    export_and_log(db, format, output);
+
+    // Watch the directory, since a save may replace the file rather than modify it
+    let (sender, receiver) = channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    let dir = match db_path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    watcher
+        .watch(&dir, RecursiveMode::NonRecursive)
+        .with_context(|| format!("Failed to watch {}.", dir.display()))?;
+
+    wait_for_changes(&receiver, &db_path, EXPORT_DEBOUNCE, || {
+        export_and_log(db, format, output)
+    })
+}
+
+// Calls `on_change` once per burst of changes to `db_path`. Returns when the watcher goes away.
+// Watcher errors are logged and skipped, a passing one shouldn't end the watch.
+fn wait_for_changes(
+    receiver: &Receiver<notify::Result<Event>>,
+    db_path: &Path,
+    debounce: Duration,
+    mut on_change: impl FnMut(),
+) -> Result<()> {
+    while let Ok(event) = receiver.recv() {
+        let event = match event {
+            Ok(event) => event,
+            Err(error) => {
+                eprintln!("Watching {} failed: {}", db_path.display(), error);
+                continue;
+            }
+        };
+        if !is_change_to(&event, db_path) {
+            continue;
+        }
+        // Wait for the burst to settle before exporting
+        while receiver.recv_timeout(debounce).is_ok() {}
+        on_change();
+    }
+    Ok(())
+}
+
+// Event paths are absolute while the configured path may not be, so only names are compared
+fn is_change_to(event: &Event, db_path: &Path) -> bool {
+    matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+        && event
+            .paths
+            .iter()
+            .any(|path| path.file_name() == db_path.file_name())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Epic;
+    use notify::event::ModifyKind;
+
+    fn change_event(path: &Path) -> notify::Result<Event> {
+        Ok(Event::new(EventKind::Modify(ModifyKind::Any)).add_path(path.to_path_buf()))
+    }
+
+    #[test]
+    fn a_change_to_the_database_should_rewrite_the_export() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("db.json");
+        let output = dir.path().join("export.ndjson");
+        std::fs::write(
+            &db_path,
+            r#"{ "last_item_id": "0", "epics": {}, "stories": {} }"#,
+        )
+        .unwrap();
+        let db = JiraDatabase::new(db_path.clone());
+        db.create_epic(Epic::new("Dashboard".to_owned(), "".to_owned()))
+            .unwrap();
+
+        let (sender, receiver) = channel();
+        sender
+            .send(change_event(&dir.path().join("other.json")))
+            .unwrap();
+        sender
+            .send(Err(notify::Error::generic("watch queue overflowed")))
+            .unwrap();
+        sender.send(change_event(&db_path)).unwrap();
+        sender.send(change_event(&db_path)).unwrap();
+        drop(sender);
+
+        let mut exports = 0;
+        wait_for_changes(&receiver, &db_path, Duration::from_millis(1), || {
+            exports += 1;
+            export_and_log(&db, ExportFormat::Ndjson, &output);
+        })
+        .unwrap();
+
+        assert_eq!(exports, 1);
+        assert!(std::fs::read_to_string(&output)
+            .unwrap()
+            .contains("Dashboard"));
+    }
+
+    #[test]
+    fn export_format_should_parse_known_names() {
+        assert_eq!("ics".parse::<ExportFormat>().unwrap(), ExportFormat::Ics);
+        assert_eq!(
+            "ndjson".parse::<ExportFormat>().unwrap(),
+            ExportFormat::Ndjson
+        );
+        assert_eq!("csv".parse::<ExportFormat>().unwrap(), ExportFormat::Csv);
+        assert!("pdf".parse::<ExportFormat>().is_err());
+    }
+}